@@ -11,12 +11,15 @@ use autocorrect::ignorer::Ignorer;
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
     ActiveTheme, IconName, Sizable, WindowExt,
+    breadcrumb::{Breadcrumb, BreadcrumbItem},
     button::{Button, ButtonVariants as _},
     h_flex,
     highlighter::{Diagnostic, DiagnosticSeverity, Language, LanguageConfig, LanguageRegistry},
     input::{
         self, CodeActionProvider, CompletionProvider, DefinitionProvider, DocumentColorProvider,
-        HoverProvider, Input, InputEvent, InputState, Position, Rope, RopeExt, TabSize,
+        HoverProvider, InlayHintProvider, Input, InputEvent, InputState, Position, ProblemList,
+        ReferencesProvider, RenameProvider, Rope, RopeExt, SemanticTokensProvider, SnippetRegistry,
+        TabSize, UserSnippet,
     },
     list::ListItem,
     resizable::{h_resizable, resizable_panel},
@@ -64,6 +67,18 @@ fn init() {
             "",
         ),
     );
+
+    SnippetRegistry::singleton().register(
+        "rust",
+        vec![
+            UserSnippet::new("test", "#[test]\nfn ${1:name}() {\n\t$0\n}")
+                .description("Test function"),
+            UserSnippet::new("for", "for ${1:item} in ${2:items} {\n\t$0\n}")
+                .description("For loop"),
+            UserSnippet::new("derive", "#[derive(${1|Debug,Clone,Default|})]")
+                .description("Derive attribute"),
+        ],
+    );
 }
 
 pub struct Example {
@@ -75,6 +90,10 @@ pub struct Example {
     indent_guides: bool,
     soft_wrap: bool,
     show_whitespaces: bool,
+    show_problems: bool,
+    minimap: bool,
+    sticky_scroll: bool,
+    read_only: bool,
     lsp_store: ExampleLspStore,
     _subscriptions: Vec<Subscription>,
     _lint_task: Task<()>,
@@ -651,6 +670,195 @@ impl DocumentColorProvider for ExampleLspStore {
     }
 }
 
+impl RenameProvider for ExampleLspStore {
+    fn rename(
+        &self,
+        text: &Rope,
+        offset: usize,
+        new_name: String,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Option<WorkspaceEdit>>> {
+        let word = text.word_at(offset);
+        if word.is_empty() {
+            return Task::ready(Ok(None));
+        }
+
+        // Rename all the same words in the document.
+        let source = text.to_string();
+        let mut edits = vec![];
+        for (start, _) in source.match_indices(word.as_str()) {
+            let end = start + word.len();
+            if text.word_range(start) != Some(start..end) {
+                continue;
+            }
+
+            edits.push(TextEdit {
+                range: lsp_types::Range {
+                    start: text.offset_to_position(start),
+                    end: text.offset_to_position(end),
+                },
+                new_text: new_name.clone(),
+            });
+        }
+
+        let document_uri = lsp_types::Uri::from_str("file://example").unwrap();
+        Task::ready(Ok(Some(WorkspaceEdit {
+            changes: Some([(document_uri, edits)].into_iter().collect()),
+            ..Default::default()
+        })))
+    }
+}
+
+impl ReferencesProvider for ExampleLspStore {
+    fn references(
+        &self,
+        text: &Rope,
+        offset: usize,
+        _context: lsp_types::ReferenceContext,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<lsp_types::Location>>> {
+        let word = text.word_at(offset);
+        if word.is_empty() {
+            return Task::ready(Ok(vec![]));
+        }
+
+        // All the same words in the document.
+        let document_uri = lsp_types::Uri::from_str("file://example").unwrap();
+        let source = text.to_string();
+        let locations = source
+            .match_indices(word.as_str())
+            .filter(|(start, _)| text.word_range(*start) == Some(*start..*start + word.len()))
+            .map(|(start, _)| lsp_types::Location {
+                uri: document_uri.clone(),
+                range: lsp_types::Range {
+                    start: text.offset_to_position(start),
+                    end: text.offset_to_position(start + word.len()),
+                },
+            })
+            .collect();
+
+        Task::ready(Ok(locations))
+    }
+}
+
+impl InlayHintProvider for ExampleLspStore {
+    fn inlay_hints(
+        &self,
+        text: &Rope,
+        range: Range<usize>,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<lsp_types::InlayHint>>> {
+        // Show the type hints for the `let` bindings of the literal values.
+        let mut hints = vec![];
+        let start_row = text.offset_to_point(range.start).row;
+        for (ix, line) in text.slice(range).to_string().lines().enumerate() {
+            let Some(rest) = line.trim_start().strip_prefix("let ") else {
+                continue;
+            };
+            let Some((name, value)) = rest.split_once(" = ") else {
+                continue;
+            };
+            let name = name.trim_start_matches("mut ");
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+
+            let value = value.trim_end_matches(';');
+            let ty = if value.starts_with('"') {
+                "&str"
+            } else if value == "true" || value == "false" {
+                "bool"
+            } else if value.parse::<i64>().is_ok() {
+                "i32"
+            } else if value.parse::<f64>().is_ok() {
+                "f64"
+            } else {
+                continue;
+            };
+
+            let column = line.find(" = ").unwrap_or_default();
+            hints.push(lsp_types::InlayHint {
+                position: text.offset_to_position(text.line_start_offset(start_row + ix) + column),
+                label: lsp_types::InlayHintLabel::String(format!(": {}", ty)),
+                kind: Some(lsp_types::InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            });
+        }
+
+        Task::ready(Ok(hints))
+    }
+}
+
+impl SemanticTokensProvider for ExampleLspStore {
+    fn legend(&self) -> lsp_types::SemanticTokensLegend {
+        lsp_types::SemanticTokensLegend {
+            token_types: vec![lsp_types::SemanticTokenType::VARIABLE],
+            token_modifiers: vec![lsp_types::SemanticTokenModifier::new("mutable")],
+        }
+    }
+
+    fn semantic_tokens_full(
+        &self,
+        text: &Rope,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<lsp_types::SemanticTokens>> {
+        // Mark the variables declared by `let mut` as mutable.
+        let source = text.to_string();
+        let mut names = source
+            .match_indices("let mut ")
+            .map(|(ix, prefix)| text.word_at(ix + prefix.len()))
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let mut offsets = names
+            .iter()
+            .flat_map(|name| {
+                source
+                    .match_indices(name.as_str())
+                    .filter(|(start, _)| {
+                        text.word_range(*start) == Some(*start..*start + name.len())
+                    })
+                    .map(|(start, name)| (start, name.chars().count()))
+            })
+            .collect::<Vec<_>>();
+        offsets.sort();
+
+        let mut data = vec![];
+        let mut last = Position::default();
+        for (start, length) in offsets {
+            let position = text.offset_to_position(start);
+            let delta_start = if position.line == last.line {
+                position.character - last.character
+            } else {
+                position.character
+            };
+            data.push(lsp_types::SemanticToken {
+                delta_line: position.line - last.line,
+                delta_start,
+                length: length as u32,
+                token_type: 0,
+                token_modifiers_bitset: 0b1,
+            });
+            last = position;
+        }
+
+        Task::ready(Ok(lsp_types::SemanticTokens {
+            result_id: None,
+            data,
+        }))
+    }
+}
+
 fn build_file_items(ignorer: &Ignorer, root: &PathBuf, path: &PathBuf) -> Vec<TreeItem> {
     let mut items = Vec::new();
 
@@ -695,6 +903,7 @@ impl Example {
                 .code_editor(default_language.name().to_string())
                 .line_number(true)
                 .indent_guides(true)
+                .sticky_scroll(5)
                 .tab_size(TabSize {
                     tab_size: 4,
                     hard_tabs: false,
@@ -709,6 +918,13 @@ impl Example {
             editor.lsp.hover_provider = Some(lsp_store.clone());
             editor.lsp.definition_provider = Some(lsp_store.clone());
             editor.lsp.document_color_provider = Some(lsp_store.clone());
+            editor.lsp.rename_provider = Some(lsp_store.clone());
+            editor.lsp.inlay_hint_provider = Some(lsp_store.clone());
+            editor.lsp.references_provider = Some(lsp_store.clone());
+            editor.lsp.semantic_tokens_provider = Some(lsp_store.clone());
+            editor.lsp.document_uri = Some(lsp_types::Uri::from_str("file://example").unwrap());
+            // Mark the changes against the original fixture in the gutter.
+            editor.set_diff_base(include_str!("./fixtures/test.rs"), window, cx);
 
             editor
         });
@@ -730,6 +946,10 @@ impl Example {
             indent_guides: true,
             soft_wrap: false,
             show_whitespaces: false,
+            show_problems: false,
+            minimap: false,
+            sticky_scroll: true,
+            read_only: false,
             lsp_store,
             _subscriptions,
             _lint_task: Task::ready(()),
@@ -893,7 +1113,8 @@ impl Example {
                 _ = view.update_in(window, |this, window, cx| {
                     _ = this.editor.update(cx, |this, cx| {
                         this.set_highlighter(language.name().to_string(), cx);
-                        this.set_value(content, window, cx);
+                        this.set_value(content.clone(), window, cx);
+                        this.set_diff_base(content, window, cx);
                     });
 
                     this.language = language;
@@ -974,6 +1195,29 @@ impl Example {
             }))
     }
 
+    fn render_problems_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let summary = self
+            .editor
+            .read(cx)
+            .diagnostics()
+            .map(|diagnostics| diagnostics.summary())
+            .unwrap_or_default();
+
+        Button::new("problems")
+            .ghost()
+            .xsmall()
+            .when(self.show_problems, |this| this.icon(IconName::Check))
+            .label(format!(
+                "Errors: {}, Warnings: {}",
+                summary.error_count(),
+                summary.warning_count()
+            ))
+            .on_click(cx.listener(|this, _, _, cx| {
+                this.show_problems = !this.show_problems;
+                cx.notify();
+            }))
+    }
+
     fn render_soft_wrap_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         Button::new("soft-wrap")
             .ghost()
@@ -989,6 +1233,40 @@ impl Example {
             }))
     }
 
+    fn render_minimap_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("minimap")
+            .ghost()
+            .xsmall()
+            .when(self.minimap, |this| this.icon(IconName::Check))
+            .label("Minimap")
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.minimap = !this.minimap;
+                this.editor.update(cx, |state, cx| {
+                    state.set_minimap(this.minimap, window, cx);
+                });
+                cx.notify();
+            }))
+    }
+
+    fn render_sticky_scroll_button(
+        &self,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        Button::new("sticky-scroll")
+            .ghost()
+            .xsmall()
+            .when(self.sticky_scroll, |this| this.icon(IconName::Check))
+            .label("Sticky Scroll")
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.sticky_scroll = !this.sticky_scroll;
+                this.editor.update(cx, |state, cx| {
+                    state.set_sticky_scroll(if this.sticky_scroll { 5 } else { 0 }, window, cx);
+                });
+                cx.notify();
+            }))
+    }
+
     fn render_show_whitespaces_button(
         &self,
         _: &mut Window,
@@ -1027,6 +1305,38 @@ impl Example {
             }))
     }
 
+    fn render_read_only_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("read-only")
+            .ghost()
+            .xsmall()
+            .when(self.read_only, |this| this.icon(IconName::Check))
+            .label("Read-only")
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.read_only = !this.read_only;
+                this.editor.update(cx, |state, cx| {
+                    state.set_read_only(this.read_only, window, cx);
+                });
+                cx.notify();
+            }))
+    }
+
+    fn render_symbol_breadcrumb(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let symbols = self
+            .editor
+            .update(cx, |editor, _| editor.symbol_path_at_cursor());
+
+        Breadcrumb::new()
+            .text_xs()
+            .children(symbols.into_iter().map(|symbol| {
+                let editor = self.editor.clone();
+                BreadcrumbItem::new(symbol.name.clone()).on_click(move |_, window, cx| {
+                    editor.update(cx, |editor, cx| {
+                        editor.go_to_symbol(&symbol, window, cx);
+                    });
+                })
+            }))
+    }
+
     fn render_go_to_line_button(&self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let position = self.editor.read(cx).cursor_position();
         let cursor = self.editor.read(cx).cursor();
@@ -1075,13 +1385,33 @@ impl Render for Example {
                                     .child(self.render_file_tree(window, cx)),
                             )
                             .child(
-                                Input::new(&self.editor)
-                                    .bordered(false)
-                                    .p_0()
-                                    .h_full()
-                                    .font_family(cx.theme().mono_font_family.clone())
-                                    .text_size(cx.theme().mono_font_size)
-                                    .focus_bordered(false)
+                                v_flex()
+                                    .size_full()
+                                    .child(
+                                        h_flex()
+                                            .h_7()
+                                            .px_4()
+                                            .border_b_1()
+                                            .border_color(cx.theme().border)
+                                            .child(self.render_symbol_breadcrumb(window, cx)),
+                                    )
+                                    .child(
+                                        Input::new(&self.editor)
+                                            .bordered(false)
+                                            .p_0()
+                                            .flex_1()
+                                            .font_family(cx.theme().mono_font_family.clone())
+                                            .text_size(cx.theme().mono_font_size)
+                                            .focus_bordered(false),
+                                    )
+                                    .when(self.show_problems, |this| {
+                                        this.child(
+                                            ProblemList::new("problems", &self.editor)
+                                                .h(px(160.))
+                                                .border_t_1()
+                                                .border_color(cx.theme().border),
+                                        )
+                                    })
                                     .into_any_element(),
                             ),
                     )
@@ -1101,7 +1431,11 @@ impl Render for Example {
                                     .child(self.render_line_number_button(window, cx))
                                     .child(self.render_soft_wrap_button(window, cx))
                                     .child(self.render_show_whitespaces_button(window, cx))
-                                    .child(self.render_indent_guides_button(window, cx)),
+                                    .child(self.render_indent_guides_button(window, cx))
+                                    .child(self.render_minimap_button(window, cx))
+                                    .child(self.render_sticky_scroll_button(window, cx))
+                                    .child(self.render_read_only_button(window, cx))
+                                    .child(self.render_problems_button(window, cx)),
                             )
                             .child(self.render_go_to_line_button(window, cx)),
                     ),
@@ -0,0 +1,261 @@
+use gpui::{
+    App, AppContext, Context, Entity, Focusable, IntoElement, ParentElement, Render, Styled,
+    Subscription, Window, px,
+};
+
+use gpui_component::{
+    ActiveTheme as _, IconName, Selectable as _, Sizable as _,
+    button::{Button, ButtonGroup, ButtonVariants as _},
+    diff_view::{DiffView, DiffViewMode, DiffViewState},
+    h_flex, v_flex,
+};
+
+use crate::section;
+
+const OLD_TEXT: &str = r#"use std::collections::HashMap;
+
+/// A simple in-memory cache.
+pub struct Cache {
+    items: HashMap<String, String>,
+    capacity: usize,
+}
+
+impl Cache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: HashMap::new(),
+            capacity,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.items.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, value: String) {
+        if self.items.len() >= self.capacity {
+            return;
+        }
+        self.items.insert(key, value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+"#;
+
+const NEW_TEXT: &str = r#"use std::collections::HashMap;
+
+/// A simple in-memory cache with a fixed capacity.
+pub struct Cache {
+    items: HashMap<String, String>,
+    capacity: usize,
+}
+
+impl Cache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: HashMap::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.items.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, value: String) -> bool {
+        if self.items.len() >= self.capacity && !self.items.contains_key(&key) {
+            return false;
+        }
+        self.items.insert(key, value);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+"#;
+
+const PATCH: &str = r#"--- a/src/main.rs
++++ b/src/main.rs
+@@ -3,5 +3,6 @@ use std::env;
+ fn main() {
+     let args: Vec<String> = env::args().collect();
+-    let name = &args[1];
++    let name = args.get(1).map(|s| s.as_str()).unwrap_or("world");
+     println!("Hello, {}!", name);
++    std::process::exit(0);
+ }
+"#;
+
+pub struct DiffViewStory {
+    focus_handle: gpui::FocusHandle,
+    mode: DiffViewMode,
+    diff_state: Entity<DiffViewState>,
+    patch_state: Entity<DiffViewState>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl super::Story for DiffViewStory {
+    fn title() -> &'static str {
+        "DiffView"
+    }
+
+    fn description() -> &'static str {
+        "Compare two texts side by side or unified, with syntax highlighting."
+    }
+
+    fn new_view(window: &mut Window, cx: &mut App) -> Entity<impl Render> {
+        Self::view(window, cx)
+    }
+}
+
+impl DiffViewStory {
+    pub(crate) fn new(_: &mut Window, cx: &mut Context<Self>) -> Self {
+        let diff_state = cx.new(|cx| {
+            DiffViewState::new(cx)
+                .language("rust")
+                .context_lines(2)
+                .texts(OLD_TEXT, NEW_TEXT)
+        });
+        let patch_state = cx.new(|cx| {
+            let mut state = DiffViewState::new(cx)
+                .language("rust")
+                .mode(DiffViewMode::Unified);
+            if let Err(err) = state.set_patch(PATCH, cx) {
+                tracing::error!("Failed to parse patch: {}", err);
+            }
+            state
+        });
+
+        // Update the selected hunk in the toolbar.
+        let _subscriptions = vec![cx.observe(&diff_state, |_, _, cx| cx.notify())];
+
+        Self {
+            focus_handle: cx.focus_handle(),
+            mode: DiffViewMode::SideBySide,
+            diff_state,
+            patch_state,
+            _subscriptions,
+        }
+    }
+
+    pub fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(window, cx))
+    }
+
+    fn set_mode(&mut self, mode: DiffViewMode, cx: &mut Context<Self>) {
+        self.mode = mode;
+        self.diff_state
+            .update(cx, |state, cx| state.set_mode(mode, cx));
+        cx.notify();
+    }
+}
+
+impl Focusable for DiffViewStory {
+    fn focus_handle(&self, _: &gpui::App) -> gpui::FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for DiffViewStory {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let hunks_count = self.diff_state.read(cx).hunks_count();
+        let selected_hunk = self.diff_state.read(cx).selected_hunk();
+
+        v_flex()
+            .gap_6()
+            .child(
+                section("Compare Texts")
+                    .sub_title("Press `alt-f5` / `shift-alt-f5` to go to the next / previous hunk.")
+                    .v_flex()
+                    .child(
+                        h_flex()
+                            .w_full()
+                            .gap_3()
+                            .justify_between()
+                            .child(
+                                ButtonGroup::new("diff-mode")
+                                    .outline()
+                                    .compact()
+                                    .small()
+                                    .child(
+                                        Button::new("side-by-side")
+                                            .label("Side by Side")
+                                            .selected(self.mode == DiffViewMode::SideBySide),
+                                    )
+                                    .child(
+                                        Button::new("unified")
+                                            .label("Unified")
+                                            .selected(self.mode == DiffViewMode::Unified),
+                                    )
+                                    .on_click(cx.listener(
+                                        |this, selecteds: &Vec<usize>, _, cx| {
+                                            let mode = match selecteds[0] {
+                                                0 => DiffViewMode::SideBySide,
+                                                _ => DiffViewMode::Unified,
+                                            };
+                                            this.set_mode(mode, cx);
+                                        },
+                                    )),
+                            )
+                            .child(
+                                h_flex()
+                                    .gap_1()
+                                    .child(format!(
+                                        "{}/{}",
+                                        selected_hunk.map_or(0, |ix| ix + 1),
+                                        hunks_count
+                                    ))
+                                    .child(
+                                        Button::new("prev-hunk")
+                                            .ghost()
+                                            .small()
+                                            .icon(IconName::ChevronUp)
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.diff_state
+                                                    .update(cx, |state, cx| state.prev_hunk(cx));
+                                            })),
+                                    )
+                                    .child(
+                                        Button::new("next-hunk")
+                                            .ghost()
+                                            .small()
+                                            .icon(IconName::ChevronDown)
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.diff_state
+                                                    .update(cx, |state, cx| state.next_hunk(cx));
+                                            })),
+                                    ),
+                            ),
+                    )
+                    .child(
+                        DiffView::new(&self.diff_state)
+                            .h(px(420.))
+                            .border_1()
+                            .border_color(cx.theme().border)
+                            .rounded(cx.theme().radius),
+                    ),
+            )
+            .child(
+                section("Unified Patch").v_flex().child(
+                    DiffView::new(&self.patch_state)
+                        .h(px(200.))
+                        .border_1()
+                        .border_color(cx.theme().border)
+                        .rounded(cx.theme().radius),
+                ),
+            )
+    }
+}
@@ -0,0 +1,220 @@
+//! A tiny language server for testing the `lsp-client` feature, see `tests/lsp_client.rs`.
+//!
+//! - Publish a warning for each line contains `TODO`.
+//! - Hover returns the word at the position.
+//! - Completion returns a `fake_completion` item.
+//! - Definition returns the start of the document.
+//!
+//! The positions are in UTF-16, the default encoding of the protocol.
+use std::io::{self, BufRead, Read, Write};
+
+use serde_json::{Value, json};
+
+fn main() {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = io::stdout();
+    let mut uri = String::new();
+    let mut lines: Vec<String> = vec![];
+
+    while let Some(message) = read_message(&mut reader) {
+        let id = message.get("id").cloned();
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "positionEncoding": "utf-16",
+                    "textDocumentSync": 2,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "definitionProvider": true,
+                    "codeActionProvider": true,
+                    "colorProvider": true,
+                },
+                "serverInfo": { "name": "fake-lsp-server" },
+            }),
+            "textDocument/didOpen" => {
+                uri = params["textDocument"]["uri"].as_str().unwrap().to_string();
+                lines = split_lines(params["textDocument"]["text"].as_str().unwrap());
+                publish_diagnostics(
+                    &mut stdout,
+                    &uri,
+                    &lines,
+                    &params["textDocument"]["version"],
+                );
+                continue;
+            }
+            "textDocument/didChange" => {
+                for change in params["contentChanges"].as_array().unwrap() {
+                    apply_change(&mut lines, change);
+                }
+                publish_diagnostics(
+                    &mut stdout,
+                    &uri,
+                    &lines,
+                    &params["textDocument"]["version"],
+                );
+                continue;
+            }
+            "textDocument/hover" => {
+                let word = word_at(&lines, &params["position"]);
+                if word.is_empty() {
+                    Value::Null
+                } else {
+                    json!({ "contents": { "kind": "plaintext", "value": word } })
+                }
+            }
+            "textDocument/completion" => json!([{ "label": "fake_completion" }]),
+            "textDocument/definition" => json!({
+                "uri": uri,
+                "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+            }),
+            "textDocument/codeAction" | "textDocument/documentColor" => json!([]),
+            "shutdown" => Value::Null,
+            "exit" => return,
+            _ => {
+                if let Some(id) = id {
+                    write_message(
+                        &mut stdout,
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": -32601, "message": format!("unknown method {}", method) },
+                        }),
+                    );
+                }
+                continue;
+            }
+        };
+
+        if let Some(id) = id {
+            write_message(
+                &mut stdout,
+                json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            );
+        }
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("Content-Length:") {
+            content_length = len.trim().parse().ok()?;
+        }
+    }
+
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn write_message(writer: &mut impl Write, message: Value) {
+    let content = message.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )
+    .unwrap();
+    writer.flush().unwrap();
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split('\n').map(ToString::to_string).collect()
+}
+
+/// Returns the byte offset in the line of the `character` (in UTF-16 code units).
+fn byte_offset(line: &str, character: u64) -> usize {
+    let mut len = 0;
+    for (ix, c) in line.char_indices() {
+        if len >= character as usize {
+            return ix;
+        }
+        len += c.len_utf16();
+    }
+    line.len()
+}
+
+fn apply_change(lines: &mut Vec<String>, change: &Value) {
+    let text = change["text"].as_str().unwrap();
+    let Some(range) = change.get("range") else {
+        *lines = split_lines(text);
+        return;
+    };
+
+    let start_line = range["start"]["line"].as_u64().unwrap() as usize;
+    let end_line = range["end"]["line"].as_u64().unwrap() as usize;
+    let start = byte_offset(
+        &lines[start_line],
+        range["start"]["character"].as_u64().unwrap(),
+    );
+    let end = byte_offset(
+        &lines[end_line],
+        range["end"]["character"].as_u64().unwrap(),
+    );
+
+    let new_text = format!(
+        "{}{}{}",
+        &lines[start_line][..start],
+        text,
+        &lines[end_line][end..]
+    );
+    lines.splice(start_line..=end_line, split_lines(&new_text));
+}
+
+fn word_at(lines: &[String], position: &Value) -> String {
+    let Some(line) = lines.get(position["line"].as_u64().unwrap() as usize) else {
+        return String::new();
+    };
+    let offset = byte_offset(line, position["character"].as_u64().unwrap());
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_word(*c))
+        .map_or(0, |(ix, c)| ix + c.len_utf8());
+    let end = line[offset..]
+        .find(|c| !is_word(c))
+        .map_or(line.len(), |ix| offset + ix);
+    line[start..end].to_string()
+}
+
+fn publish_diagnostics(writer: &mut impl Write, uri: &str, lines: &[String], version: &Value) {
+    let diagnostics = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, line)| {
+            let start = line.find("TODO")?;
+            let character = line[..start].encode_utf16().count();
+            Some(json!({
+                "range": {
+                    "start": { "line": ix, "character": character },
+                    "end": { "line": ix, "character": character + 4 },
+                },
+                "severity": 2,
+                "message": "TODO found",
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    write_message(
+        writer,
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "version": version, "diagnostics": diagnostics },
+        }),
+    );
+}
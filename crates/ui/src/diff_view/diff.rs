@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use ropey::Rope;
+
+use crate::{
+    RopeExt as _,
+    input::diff::{changed_ranges, diff_hunks, hash_lines},
+};
+
+/// The kind of a line in the [`super::DiffView`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    /// The line is unchanged.
+    Unchanged,
+    /// The line is added in the new text.
+    Added,
+    /// The line is removed from the old text.
+    Removed,
+    /// The line is changed, the old and new lines are shown side by side.
+    Modified,
+}
+
+/// A run of lines in the old and new texts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum DiffOp {
+    /// The unchanged lines.
+    Equal {
+        old: Range<usize>,
+        new: Range<usize>,
+    },
+    /// The changed lines, that is a hunk.
+    Change {
+        old: Range<usize>,
+        new: Range<usize>,
+    },
+    /// The unchanged lines that are not included in the patch, the content is unknown.
+    Omitted {
+        old: Range<usize>,
+        new: Range<usize>,
+    },
+}
+
+/// A row to display in the [`super::DiffView`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum DiffRow {
+    /// A line, the `old` and `new` are the 0-based rows in the old and new texts.
+    Line {
+        kind: DiffLineKind,
+        old: Option<usize>,
+        new: Option<usize>,
+    },
+    /// The collapsed unchanged lines of the [`DiffOp`] at `op_ix`.
+    Collapsed {
+        op_ix: usize,
+        lines: usize,
+        expandable: bool,
+    },
+}
+
+/// Returns the diff ops to change the `old` text to the `new` text by lines.
+pub(super) fn diff_ops(old: &Rope, new: &Rope) -> Vec<DiffOp> {
+    let hunks = diff_hunks(
+        &hash_lines(old, 0..old.lines_len()),
+        &hash_lines(new, 0..new.lines_len()),
+    );
+
+    let mut ops = vec![];
+    let (mut old_row, mut new_row) = (0, 0);
+    for hunk in hunks {
+        if hunk.base_rows.start > old_row {
+            ops.push(DiffOp::Equal {
+                old: old_row..hunk.base_rows.start,
+                new: new_row..hunk.rows.start,
+            });
+        }
+        old_row = hunk.base_rows.end;
+        new_row = hunk.rows.end;
+        ops.push(DiffOp::Change {
+            old: hunk.base_rows,
+            new: hunk.rows,
+        });
+    }
+    if old_row < old.lines_len() {
+        ops.push(DiffOp::Equal {
+            old: old_row..old.lines_len(),
+            new: new_row..new.lines_len(),
+        });
+    }
+
+    ops
+}
+
+/// Build the rows to display, returns the rows and the row range of each hunk.
+///
+/// - `side_by_side`: Whether to pair the removed and added lines in one row.
+/// - `context_lines`: The number of unchanged lines to keep around the hunks, the others are collapsed.
+/// - `expanded`: The index of the ops that the collapsed lines are expanded.
+pub(super) fn build_rows(
+    ops: &[DiffOp],
+    side_by_side: bool,
+    context_lines: usize,
+    expanded: &HashSet<usize>,
+) -> (Vec<DiffRow>, Vec<Range<usize>>) {
+    let mut rows = vec![];
+    let mut hunk_rows = vec![];
+
+    let unchanged = |old: usize, new: usize| DiffRow::Line {
+        kind: DiffLineKind::Unchanged,
+        old: Some(old),
+        new: Some(new),
+    };
+
+    for (op_ix, op) in ops.iter().enumerate() {
+        match op {
+            DiffOp::Equal { old, new } => {
+                let head = if op_ix == 0 { 0 } else { context_lines };
+                let tail = if op_ix + 1 == ops.len() {
+                    0
+                } else {
+                    context_lines
+                };
+
+                // Collapse at least 2 lines, otherwise the placeholder is not shorter.
+                if expanded.contains(&op_ix) || old.len() <= head + tail + 1 {
+                    for i in 0..old.len() {
+                        rows.push(unchanged(old.start + i, new.start + i));
+                    }
+                    continue;
+                }
+
+                for i in 0..head {
+                    rows.push(unchanged(old.start + i, new.start + i));
+                }
+                rows.push(DiffRow::Collapsed {
+                    op_ix,
+                    lines: old.len() - head - tail,
+                    expandable: true,
+                });
+                for i in old.len() - tail..old.len() {
+                    rows.push(unchanged(old.start + i, new.start + i));
+                }
+            }
+            DiffOp::Omitted { old, .. } => {
+                if !old.is_empty() {
+                    rows.push(DiffRow::Collapsed {
+                        op_ix,
+                        lines: old.len(),
+                        expandable: false,
+                    });
+                }
+            }
+            DiffOp::Change { old, new } => {
+                let start = rows.len();
+                if side_by_side {
+                    for i in 0..old.len().max(new.len()) {
+                        let old_row = (i < old.len()).then_some(old.start + i);
+                        let new_row = (i < new.len()).then_some(new.start + i);
+                        let kind = match (old_row, new_row) {
+                            (Some(_), Some(_)) => DiffLineKind::Modified,
+                            (Some(_), None) => DiffLineKind::Removed,
+                            _ => DiffLineKind::Added,
+                        };
+                        rows.push(DiffRow::Line {
+                            kind,
+                            old: old_row,
+                            new: new_row,
+                        });
+                    }
+                } else {
+                    for row in old.clone() {
+                        rows.push(DiffRow::Line {
+                            kind: DiffLineKind::Removed,
+                            old: Some(row),
+                            new: None,
+                        });
+                    }
+                    for row in new.clone() {
+                        rows.push(DiffRow::Line {
+                            kind: DiffLineKind::Added,
+                            old: None,
+                            new: Some(row),
+                        });
+                    }
+                }
+                hunk_rows.push(start..rows.len());
+            }
+        }
+    }
+
+    (rows, hunk_rows)
+}
+
+/// The changed byte ranges of the lines by words, the key is the 0-based row.
+#[derive(Debug, Default)]
+pub(super) struct WordChanges {
+    pub(super) old: HashMap<usize, Vec<Range<usize>>>,
+    pub(super) new: HashMap<usize, Vec<Range<usize>>>,
+}
+
+impl WordChanges {
+    /// Compute the word changes of the changed lines, the lines of a hunk are paired in order.
+    pub(super) fn new(ops: &[DiffOp], old_text: &Rope, new_text: &Rope) -> Self {
+        let mut this = Self::default();
+        for op in ops {
+            let DiffOp::Change { old, new } = op else {
+                continue;
+            };
+
+            for (old_row, new_row) in old.clone().zip(new.clone()) {
+                let old_line = old_text.slice_line(old_row).to_string();
+                let new_line = new_text.slice_line(new_row).to_string();
+                if let Some((old_ranges, new_ranges)) = word_changes(&old_line, &new_line) {
+                    this.old.insert(old_row, old_ranges);
+                    this.new.insert(new_row, new_ranges);
+                }
+            }
+        }
+        this
+    }
+}
+
+/// Split the line into words, whitespaces and punctuations, returns the byte ranges.
+fn tokenize(line: &str) -> Vec<Range<usize>> {
+    #[derive(PartialEq)]
+    enum CharKind {
+        Word,
+        Whitespace,
+        Punctuation,
+    }
+
+    let kind = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            CharKind::Word
+        } else if c.is_whitespace() {
+            CharKind::Whitespace
+        } else {
+            CharKind::Punctuation
+        }
+    };
+
+    let mut tokens: Vec<Range<usize>> = vec![];
+    let mut last_kind = None;
+    for (ix, c) in line.char_indices() {
+        let c_kind = kind(c);
+        match tokens.last_mut() {
+            Some(token)
+                if c_kind != CharKind::Punctuation && last_kind.as_ref() == Some(&c_kind) =>
+            {
+                token.end = ix + c.len_utf8();
+            }
+            _ => tokens.push(ix..ix + c.len_utf8()),
+        }
+        last_kind = Some(c_kind);
+    }
+    tokens
+}
+
+/// Returns the changed byte ranges of the old and new lines by words.
+///
+/// Returns `None` if the lines have nothing in common, that the whole lines are changed.
+fn word_changes(old_line: &str, new_line: &str) -> Option<(Vec<Range<usize>>, Vec<Range<usize>>)> {
+    let old_tokens = tokenize(old_line);
+    let new_tokens = tokenize(new_line);
+    let old_words = old_tokens
+        .iter()
+        .map(|range| &old_line[range.clone()])
+        .collect::<Vec<_>>();
+    let new_words = new_tokens
+        .iter()
+        .map(|range| &new_line[range.clone()])
+        .collect::<Vec<_>>();
+
+    let changes = changed_ranges(&old_words, &new_words);
+    if changes.len() == 1
+        && changes[0].0.len() == old_words.len()
+        && changes[0].1.len() == new_words.len()
+    {
+        return None;
+    }
+
+    let to_bytes = |tokens: &[Range<usize>], range: &Range<usize>| {
+        (!range.is_empty()).then(|| tokens[range.start].start..tokens[range.end - 1].end)
+    };
+    let old_ranges = changes
+        .iter()
+        .filter_map(|(range, _)| to_bytes(&old_tokens, range))
+        .collect();
+    let new_ranges = changes
+        .iter()
+        .filter_map(|(_, range)| to_bytes(&new_tokens, range))
+        .collect();
+    Some((old_ranges, new_ranges))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ropey::Rope;
+
+    use super::{DiffLineKind, DiffOp, DiffRow, build_rows, diff_ops, tokenize, word_changes};
+
+    #[test]
+    fn test_diff_ops() {
+        let old = Rope::from("a\nb\nc\nd\ne");
+        let new = Rope::from("a\nB\nc\nd\ne\nf");
+        assert_eq!(
+            diff_ops(&old, &new),
+            vec![
+                DiffOp::Equal {
+                    old: 0..1,
+                    new: 0..1
+                },
+                DiffOp::Change {
+                    old: 1..2,
+                    new: 1..2
+                },
+                DiffOp::Equal {
+                    old: 2..5,
+                    new: 2..5
+                },
+                DiffOp::Change {
+                    old: 5..5,
+                    new: 5..6
+                },
+            ]
+        );
+        assert_eq!(
+            diff_ops(&old, &old),
+            vec![DiffOp::Equal {
+                old: 0..5,
+                new: 0..5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_rows() {
+        let line = |kind, old, new| DiffRow::Line { kind, old, new };
+        let ops = vec![
+            DiffOp::Equal {
+                old: 0..4,
+                new: 0..4,
+            },
+            DiffOp::Change {
+                old: 4..6,
+                new: 4..5,
+            },
+            DiffOp::Equal {
+                old: 6..12,
+                new: 5..11,
+            },
+            DiffOp::Change {
+                old: 12..12,
+                new: 11..12,
+            },
+        ];
+
+        let (rows, hunk_rows) = build_rows(&ops, true, 1, &HashSet::new());
+        assert_eq!(
+            rows,
+            vec![
+                DiffRow::Collapsed {
+                    op_ix: 0,
+                    lines: 3,
+                    expandable: true
+                },
+                line(DiffLineKind::Unchanged, Some(3), Some(3)),
+                line(DiffLineKind::Modified, Some(4), Some(4)),
+                line(DiffLineKind::Removed, Some(5), None),
+                line(DiffLineKind::Unchanged, Some(6), Some(5)),
+                DiffRow::Collapsed {
+                    op_ix: 2,
+                    lines: 4,
+                    expandable: true
+                },
+                line(DiffLineKind::Unchanged, Some(11), Some(10)),
+                line(DiffLineKind::Added, None, Some(11)),
+            ]
+        );
+        assert_eq!(hunk_rows, vec![2..4, 7..8]);
+
+        let (rows, hunk_rows) = build_rows(&ops, false, 1, &HashSet::from([2]));
+        assert_eq!(rows.len(), 1 + 1 + 3 + 6 + 1);
+        assert_eq!(rows[2], line(DiffLineKind::Removed, Some(4), None));
+        assert_eq!(rows[4], line(DiffLineKind::Added, None, Some(4)));
+        assert_eq!(hunk_rows, vec![2..5, 11..12]);
+    }
+
+    #[test]
+    fn test_word_changes() {
+        assert_eq!(
+            tokenize("let foo_1 = a+b;"),
+            vec![
+                0..3,
+                3..4,
+                4..9,
+                9..10,
+                10..11,
+                11..12,
+                12..13,
+                13..14,
+                14..15,
+                15..16
+            ]
+        );
+        assert_eq!(
+            word_changes("let a = 1;", "let b = 1;"),
+            Some((vec![4..5], vec![4..5]))
+        );
+        assert_eq!(
+            word_changes("foo(a, b)", "foo(a)"),
+            Some((vec![5..8], vec![]))
+        );
+        assert_eq!(word_changes("foo", "bar"), None);
+    }
+}
@@ -0,0 +1,555 @@
+//! A diff viewer to compare two texts side by side or unified.
+mod diff;
+mod patch;
+
+pub use diff::DiffLineKind;
+
+use std::{collections::HashSet, ops::Range, rc::Rc};
+
+use anyhow::Result;
+use gpui::{
+    App, Context, ElementId, Entity, FocusHandle, HighlightStyle, Hsla, InteractiveElement as _,
+    IntoElement, KeyBinding, ListSizingBehavior, MouseButton, ParentElement as _, Render,
+    RenderOnce, ScrollStrategy, SharedString, StyleRefinement, Styled, StyledText,
+    UniformListScrollHandle, Window, div, prelude::FluentBuilder as _, px, uniform_list,
+};
+use ropey::Rope;
+use rust_i18n::t;
+
+use crate::{
+    ActiveTheme as _, Icon, IconName, RopeExt as _, Sizable as _, StyledExt as _, h_flex,
+    highlighter::SyntaxHighlighter,
+    input::{GoToNextHunk, GoToPrevHunk},
+    scroll::ScrollableElement as _,
+};
+
+use diff::{DiffOp, DiffRow, WordChanges, build_rows, diff_ops};
+use patch::Patch;
+
+const CONTEXT: &str = "DiffView";
+pub(crate) fn init(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("alt-f5", GoToNextHunk, Some(CONTEXT)),
+        KeyBinding::new("shift-alt-f5", GoToPrevHunk, Some(CONTEXT)),
+    ]);
+}
+
+/// The layout of the [`DiffView`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffViewMode {
+    /// The old and new texts are shown in two columns.
+    #[default]
+    SideBySide,
+    /// The removed and added lines are shown in one column.
+    Unified,
+}
+
+/// The text of one side of the diff.
+struct DiffSide {
+    text: Rope,
+    highlighter: Option<SyntaxHighlighter>,
+}
+
+impl DiffSide {
+    fn new(text: Rope, language: Option<&SharedString>) -> Self {
+        let highlighter = language.map(|language| {
+            let mut highlighter = SyntaxHighlighter::new(language);
+            highlighter.update(None, &text);
+            highlighter
+        });
+
+        Self { text, highlighter }
+    }
+
+    /// Returns the text and the highlight styles of the line.
+    fn line(
+        &self,
+        row: usize,
+        word_changes: Option<&Vec<Range<usize>>>,
+        word_bg: Hsla,
+        cx: &App,
+    ) -> (SharedString, Vec<(Range<usize>, HighlightStyle)>) {
+        let line = self.text.slice_line(row).to_string();
+        let line = line.trim_end_matches('\r').to_string();
+        let line_start = self.text.line_start_offset(row);
+
+        let mut styles = self
+            .highlighter
+            .as_ref()
+            .map(|highlighter| {
+                highlighter.styles(
+                    &(line_start..line_start + line.len()),
+                    &cx.theme().highlight_theme,
+                )
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(range, style)| {
+                let start = range.start.saturating_sub(line_start).min(line.len());
+                let end = range.end.saturating_sub(line_start).min(line.len());
+                (start < end).then_some((start..end, style))
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(word_changes) = word_changes {
+            let word_styles = word_changes.iter().map(|range| {
+                (
+                    range.start.min(line.len())..range.end.min(line.len()),
+                    HighlightStyle {
+                        background_color: Some(word_bg),
+                        ..Default::default()
+                    },
+                )
+            });
+            styles = gpui::combine_highlights(styles, word_styles).collect();
+        }
+
+        (line.into(), styles)
+    }
+}
+
+/// State of the [`DiffView`].
+///
+/// ```ignore
+/// let state = cx.new(|cx| {
+///     DiffViewState::new(cx)
+///         .language("rust")
+///         .texts(old_text, new_text)
+/// });
+///
+/// DiffView::new(&state)
+/// ```
+pub struct DiffViewState {
+    focus_handle: FocusHandle,
+    language: Option<SharedString>,
+    mode: DiffViewMode,
+    context_lines: usize,
+    old: Rc<DiffSide>,
+    new: Rc<DiffSide>,
+    ops: Vec<DiffOp>,
+    word_changes: Rc<WordChanges>,
+    /// The index of the ops that the collapsed lines are expanded.
+    expanded: HashSet<usize>,
+    rows: Rc<Vec<DiffRow>>,
+    /// The row range of each hunk in the `rows`.
+    hunk_rows: Vec<Range<usize>>,
+    selected_hunk: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+}
+
+impl DiffViewState {
+    /// Create a new empty diff view state.
+    pub fn new(cx: &mut App) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            language: None,
+            mode: DiffViewMode::default(),
+            context_lines: 3,
+            old: Rc::new(DiffSide::new(Rope::new(), None)),
+            new: Rc::new(DiffSide::new(Rope::new(), None)),
+            ops: vec![],
+            word_changes: Rc::new(WordChanges::default()),
+            expanded: HashSet::new(),
+            rows: Rc::new(vec![]),
+            hunk_rows: vec![],
+            selected_hunk: None,
+            scroll_handle: UniformListScrollHandle::default(),
+        }
+    }
+
+    /// Set the language to highlight the texts, must be set before the texts.
+    pub fn language(mut self, language: impl Into<SharedString>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Set the layout mode, default is [`DiffViewMode::SideBySide`].
+    pub fn mode(mut self, mode: DiffViewMode) -> Self {
+        self.mode = mode;
+        self.rebuild_rows();
+        self
+    }
+
+    /// Set the number of unchanged lines to show around the hunks, default is 3.
+    ///
+    /// The other unchanged lines are collapsed, click to expand them.
+    pub fn context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self.rebuild_rows();
+        self
+    }
+
+    /// Set the old and new texts to compare.
+    pub fn texts(mut self, old: impl Into<SharedString>, new: impl Into<SharedString>) -> Self {
+        self.update_texts(old.into(), new.into());
+        self
+    }
+
+    /// Set the old and new texts to compare.
+    pub fn set_texts(
+        &mut self,
+        old: impl Into<SharedString>,
+        new: impl Into<SharedString>,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_texts(old.into(), new.into());
+        cx.notify();
+    }
+
+    /// Set the unified diff patch to show, the unchanged lines out of the hunks can't be expanded.
+    ///
+    /// Only the first file of the patch is supported.
+    pub fn set_patch(&mut self, patch: &str, cx: &mut Context<Self>) -> Result<()> {
+        let patch = Patch::parse(patch)?;
+        self.set_sides(
+            Rope::from(patch.old_text.as_str()),
+            Rope::from(patch.new_text.as_str()),
+            patch.ops,
+        );
+        cx.notify();
+        Ok(())
+    }
+
+    /// Set the language to highlight the texts.
+    pub fn set_language(&mut self, language: impl Into<SharedString>, cx: &mut Context<Self>) {
+        self.language = Some(language.into());
+        let ops = std::mem::take(&mut self.ops);
+        self.set_sides(self.old.text.clone(), self.new.text.clone(), ops);
+        cx.notify();
+    }
+
+    /// Set the layout mode.
+    pub fn set_mode(&mut self, mode: DiffViewMode, cx: &mut Context<Self>) {
+        self.mode = mode;
+        self.rebuild_rows();
+        cx.notify();
+    }
+
+    /// Set the number of unchanged lines to show around the hunks.
+    pub fn set_context_lines(&mut self, context_lines: usize, cx: &mut Context<Self>) {
+        self.context_lines = context_lines;
+        self.rebuild_rows();
+        cx.notify();
+    }
+
+    /// Returns the number of hunks.
+    pub fn hunks_count(&self) -> usize {
+        self.hunk_rows.len()
+    }
+
+    /// Returns the index of the selected hunk.
+    pub fn selected_hunk(&self) -> Option<usize> {
+        self.selected_hunk
+    }
+
+    /// Select the hunk and scroll to it.
+    pub fn select_hunk(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let Some(rows) = self.hunk_rows.get(ix) else {
+            return;
+        };
+
+        self.selected_hunk = Some(ix);
+        // Keep the context lines above the hunk visible.
+        let row = rows.start.saturating_sub(self.context_lines.min(3));
+        self.scroll_handle.scroll_to_item(row, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    /// Select the next hunk, wrap around at the end.
+    pub fn next_hunk(&mut self, cx: &mut Context<Self>) {
+        if self.hunk_rows.is_empty() {
+            return;
+        }
+
+        let ix = self
+            .selected_hunk
+            .map_or(0, |ix| (ix + 1) % self.hunk_rows.len());
+        self.select_hunk(ix, cx);
+    }
+
+    /// Select the previous hunk, wrap around at the start.
+    pub fn prev_hunk(&mut self, cx: &mut Context<Self>) {
+        if self.hunk_rows.is_empty() {
+            return;
+        }
+
+        let len = self.hunk_rows.len();
+        let ix = self
+            .selected_hunk
+            .map_or(len - 1, |ix| (ix + len - 1) % len);
+        self.select_hunk(ix, cx);
+    }
+
+    /// Expand all the collapsed unchanged lines.
+    pub fn expand_all(&mut self, cx: &mut Context<Self>) {
+        self.expanded = (0..self.ops.len()).collect();
+        self.rebuild_rows();
+        cx.notify();
+    }
+
+    pub fn focus(&mut self, window: &mut Window, cx: &mut App) {
+        self.focus_handle.focus(window, cx);
+    }
+
+    fn update_texts(&mut self, old: SharedString, new: SharedString) {
+        let old = Rope::from(old.as_str());
+        let new = Rope::from(new.as_str());
+        let ops = diff_ops(&old, &new);
+        self.set_sides(old, new, ops);
+    }
+
+    fn set_sides(&mut self, old: Rope, new: Rope, ops: Vec<DiffOp>) {
+        self.word_changes = Rc::new(WordChanges::new(&ops, &old, &new));
+        self.old = Rc::new(DiffSide::new(old, self.language.as_ref()));
+        self.new = Rc::new(DiffSide::new(new, self.language.as_ref()));
+        self.ops = ops;
+        self.expanded.clear();
+        self.selected_hunk = None;
+        self.rebuild_rows();
+    }
+
+    fn rebuild_rows(&mut self) {
+        let (rows, hunk_rows) = build_rows(
+            &self.ops,
+            self.mode == DiffViewMode::SideBySide,
+            self.context_lines,
+            &self.expanded,
+        );
+        self.rows = Rc::new(rows);
+        self.hunk_rows = hunk_rows;
+        if self
+            .selected_hunk
+            .is_some_and(|ix| ix >= self.hunk_rows.len())
+        {
+            self.selected_hunk = None;
+        }
+    }
+
+    fn expand(&mut self, op_ix: usize, cx: &mut Context<Self>) {
+        self.expanded.insert(op_ix);
+        self.rebuild_rows();
+        cx.notify();
+    }
+
+    fn on_action_next_hunk(&mut self, _: &GoToNextHunk, _: &mut Window, cx: &mut Context<Self>) {
+        self.next_hunk(cx);
+    }
+
+    fn on_action_prev_hunk(&mut self, _: &GoToPrevHunk, _: &mut Window, cx: &mut Context<Self>) {
+        self.prev_hunk(cx);
+    }
+
+    fn render_row(&self, ix: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        let row_height = (cx.theme().mono_font_size * 1.5).round();
+        let number_len = self
+            .old
+            .text
+            .lines_len()
+            .max(self.new.text.lines_len())
+            .to_string()
+            .len();
+        let selected = self
+            .selected_hunk
+            .and_then(|hunk_ix| self.hunk_rows.get(hunk_ix))
+            .is_some_and(|rows| rows.contains(&ix));
+
+        let row = h_flex()
+            .id(ix)
+            .h(row_height)
+            .w_full()
+            .overflow_hidden()
+            .whitespace_nowrap()
+            .border_l_2()
+            .border_color(if selected {
+                cx.theme().primary
+            } else {
+                cx.theme().transparent
+            });
+
+        match &self.rows[ix] {
+            DiffRow::Collapsed {
+                op_ix,
+                lines,
+                expandable,
+            } => {
+                let op_ix = *op_ix;
+                row.gap_2()
+                    .px_2()
+                    .bg(cx.theme().muted)
+                    .text_color(cx.theme().muted_foreground)
+                    .child(Icon::new(IconName::ChevronsUpDown).xsmall())
+                    .child(format!("{} {}", lines, t!("DiffView.Unchanged Lines")))
+                    .when(*expandable, |this| {
+                        this.cursor_pointer()
+                            .hover(|this| this.text_color(cx.theme().foreground))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _, _, cx| this.expand(op_ix, cx)),
+                            )
+                    })
+            }
+            DiffRow::Line { kind, old, new } => match self.mode {
+                DiffViewMode::SideBySide => row
+                    .child(self.render_cell(*kind, *old, None, true, number_len, cx))
+                    .child(div().h_full().border_l_1().border_color(cx.theme().border))
+                    .child(self.render_cell(*kind, None, *new, false, number_len, cx)),
+                DiffViewMode::Unified => row.child(self.render_cell(
+                    *kind,
+                    *old,
+                    *new,
+                    old.is_some() && new.is_none(),
+                    number_len,
+                    cx,
+                )),
+            },
+        }
+    }
+
+    /// Render a line with the line numbers.
+    ///
+    /// - `old_row` and `new_row`: The rows in the old and new texts, both for the unchanged line.
+    /// - `is_old`: Whether to show the line of the old text.
+    fn render_cell(
+        &self,
+        kind: DiffLineKind,
+        old_row: Option<usize>,
+        new_row: Option<usize>,
+        is_old: bool,
+        number_len: usize,
+        cx: &App,
+    ) -> impl IntoElement {
+        let (side, row, word_changes) = if is_old {
+            (&self.old, old_row, &self.word_changes.old)
+        } else {
+            (&self.new, new_row, &self.word_changes.new)
+        };
+
+        let (sign, bg, word_bg) = match (kind, is_old) {
+            (DiffLineKind::Unchanged, _) => (" ", None, cx.theme().transparent),
+            (DiffLineKind::Removed, _) | (DiffLineKind::Modified, true) => (
+                "-",
+                Some(cx.theme().red.opacity(0.1)),
+                cx.theme().red.opacity(0.3),
+            ),
+            (DiffLineKind::Added, _) | (DiffLineKind::Modified, false) => (
+                "+",
+                Some(cx.theme().green.opacity(0.1)),
+                cx.theme().green.opacity(0.3),
+            ),
+        };
+
+        let line_number = |row: Option<usize>| {
+            div()
+                .flex_shrink_0()
+                .px_1()
+                .text_color(cx.theme().muted_foreground)
+                .child(match row {
+                    Some(row) => format!("{:>width$}", row + 1, width = number_len),
+                    None => " ".repeat(number_len),
+                })
+        };
+
+        let Some(row) = row else {
+            // The empty side of the added or removed line.
+            return h_flex()
+                .flex_1()
+                .h_full()
+                .bg(cx.theme().muted.opacity(0.5))
+                .into_any_element();
+        };
+
+        let (line, styles) = side.line(row, word_changes.get(&row), word_bg, cx);
+        h_flex()
+            .flex_1()
+            .h_full()
+            .min_w_0()
+            .overflow_hidden()
+            .when_some(bg, |this, bg| this.bg(bg))
+            .map(|this| match self.mode {
+                DiffViewMode::SideBySide => this.child(line_number(Some(row))),
+                DiffViewMode::Unified => {
+                    this.child(line_number(old_row)).child(line_number(new_row))
+                }
+            })
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .w(px(16.))
+                    .text_color(cx.theme().muted_foreground)
+                    .child(sign),
+            )
+            .child(StyledText::new(line).with_highlights(styles))
+            .into_any_element()
+    }
+}
+
+impl Render for DiffViewState {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .id("diff-view-state")
+            .size_full()
+            .relative()
+            .font_family(cx.theme().mono_font_family.clone())
+            .text_size(cx.theme().mono_font_size)
+            .child(
+                uniform_list("rows", self.rows.len(), {
+                    cx.processor(move |state, visible_range: Range<usize>, _, cx| {
+                        visible_range
+                            .map(|ix| state.render_row(ix, cx))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .flex_grow()
+                .size_full()
+                .track_scroll(&self.scroll_handle)
+                .with_sizing_behavior(ListSizingBehavior::Auto)
+                .into_any_element(),
+            )
+    }
+}
+
+/// A diff viewer of two texts, see [`DiffViewState`].
+///
+/// The lines are compared by lines and words, the unchanged lines far from the hunks are collapsed.
+/// Use `alt-f5` and `shift-alt-f5` to go to the next and previous hunk.
+#[derive(IntoElement)]
+pub struct DiffView {
+    id: ElementId,
+    state: Entity<DiffViewState>,
+    style: StyleRefinement,
+}
+
+impl DiffView {
+    pub fn new(state: &Entity<DiffViewState>) -> Self {
+        Self {
+            id: ElementId::Name(format!("diff-view-{}", state.entity_id()).into()),
+            state: state.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+}
+
+impl Styled for DiffView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl RenderOnce for DiffView {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let focus_handle = self.state.read(cx).focus_handle.clone();
+        let scroll_handle = self.state.read(cx).scroll_handle.clone();
+
+        div()
+            .id(self.id)
+            .key_context(CONTEXT)
+            .track_focus(&focus_handle)
+            .on_action(window.listener_for(&self.state, DiffViewState::on_action_next_hunk))
+            .on_action(window.listener_for(&self.state, DiffViewState::on_action_prev_hunk))
+            .size_full()
+            .bg(cx.theme().editor_background())
+            .child(self.state)
+            .refine_style(&self.style)
+            .vertical_scrollbar(&scroll_handle)
+    }
+}
@@ -0,0 +1,233 @@
+use anyhow::{Context as _, Result, anyhow};
+
+use super::diff::DiffOp;
+
+/// The old and new texts of the hunks in a unified diff patch.
+///
+/// The lines between the hunks are not included in the patch, they are filled with empty lines,
+/// so the rows of the texts are the same as the original files.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct Patch {
+    pub(super) old_text: String,
+    pub(super) new_text: String,
+    pub(super) ops: Vec<DiffOp>,
+}
+
+impl Patch {
+    /// Parse the unified diff patch, the file headers (e.g.: `---`, `+++`, `diff --git`) are ignored.
+    pub(super) fn parse(patch: &str) -> Result<Self> {
+        let mut old_lines: Vec<&str> = vec![];
+        let mut new_lines: Vec<&str> = vec![];
+        let mut ops = vec![];
+        // The remaining lines of the current hunk.
+        let (mut old_remaining, mut new_remaining) = (0, 0);
+
+        for line in patch.lines() {
+            if old_remaining == 0 && new_remaining == 0 {
+                // Stop at the header of the next file.
+                if !ops.is_empty() && (line.starts_with("diff ") || line.starts_with("--- ")) {
+                    break;
+                }
+                if !line.starts_with("@@") {
+                    continue;
+                }
+
+                let (old_start, old_len, new_start, new_len) = parse_hunk_header(line)?;
+                // The start line is the line before the hunk if the range is empty.
+                let old_row = if old_len == 0 {
+                    old_start
+                } else {
+                    old_start - 1
+                };
+                let new_row = if new_len == 0 {
+                    new_start
+                } else {
+                    new_start - 1
+                };
+                if old_row < old_lines.len() || new_row < new_lines.len() {
+                    return Err(anyhow!("Hunks are overlapping or out of order: {}", line));
+                }
+
+                let old_gap = old_lines.len()..old_row;
+                let new_gap = new_lines.len()..new_row;
+                if !old_gap.is_empty() || !new_gap.is_empty() {
+                    old_lines.resize(old_row, "");
+                    new_lines.resize(new_row, "");
+                    ops.push(DiffOp::Omitted {
+                        old: old_gap,
+                        new: new_gap,
+                    });
+                }
+
+                old_remaining = old_len;
+                new_remaining = new_len;
+                continue;
+            }
+
+            // The prefix is ASCII, so the content starts at the byte 1.
+            let content = line.get(1..).unwrap_or_default();
+            match line.as_bytes().first() {
+                Some(b' ') | None => {
+                    push_op(&mut ops, false, old_lines.len(), new_lines.len(), 1, 1);
+                    old_lines.push(content);
+                    new_lines.push(content);
+                    old_remaining = old_remaining.saturating_sub(1);
+                    new_remaining = new_remaining.saturating_sub(1);
+                }
+                Some(b'-') => {
+                    push_op(&mut ops, true, old_lines.len(), new_lines.len(), 1, 0);
+                    old_lines.push(content);
+                    old_remaining = old_remaining.saturating_sub(1);
+                }
+                Some(b'+') => {
+                    push_op(&mut ops, true, old_lines.len(), new_lines.len(), 0, 1);
+                    new_lines.push(content);
+                    new_remaining = new_remaining.saturating_sub(1);
+                }
+                // `\ No newline at end of file`
+                Some(b'\\') => {}
+                _ => return Err(anyhow!("Invalid line in hunk: {}", line)),
+            }
+        }
+
+        if old_remaining > 0 || new_remaining > 0 {
+            return Err(anyhow!("Unexpected end of the patch"));
+        }
+
+        Ok(Self {
+            old_text: old_lines.join("\n"),
+            new_text: new_lines.join("\n"),
+            ops,
+        })
+    }
+}
+
+/// Push the lines to the last op if it's the same kind, otherwise push a new op.
+fn push_op(
+    ops: &mut Vec<DiffOp>,
+    changed: bool,
+    old_row: usize,
+    new_row: usize,
+    old_len: usize,
+    new_len: usize,
+) {
+    match ops.last_mut() {
+        Some(DiffOp::Change { old, new }) if changed => {
+            old.end += old_len;
+            new.end += new_len;
+        }
+        Some(DiffOp::Equal { old, new }) if !changed => {
+            old.end += old_len;
+            new.end += new_len;
+        }
+        _ => {
+            let old = old_row..old_row + old_len;
+            let new = new_row..new_row + new_len;
+            ops.push(if changed {
+                DiffOp::Change { old, new }
+            } else {
+                DiffOp::Equal { old, new }
+            });
+        }
+    }
+}
+
+/// Parse the hunk header, e.g.: `@@ -1,3 +1,4 @@`, returns the 1-based start and length of the old and new ranges.
+fn parse_hunk_header(line: &str) -> Result<(usize, usize, usize, usize)> {
+    let parse_range = |range: &str, prefix: char| -> Result<(usize, usize)> {
+        let range = range
+            .strip_prefix(prefix)
+            .with_context(|| format!("Invalid hunk header: {}", line))?;
+        let (start, len) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((start.parse()?, len.parse()?))
+    };
+
+    let mut parts = line.trim_start_matches("@@").split_whitespace();
+    let (old_start, old_len) = parse_range(parts.next().unwrap_or_default(), '-')?;
+    let (new_start, new_len) = parse_range(parts.next().unwrap_or_default(), '+')?;
+    if (old_len > 0 && old_start == 0) || (new_len > 0 && new_start == 0) {
+        return Err(anyhow!("Invalid hunk header: {}", line));
+    }
+
+    Ok((old_start, old_len, new_start, new_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::{Patch, parse_hunk_header};
+    use crate::diff_view::diff::DiffOp;
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(parse_hunk_header("@@ -1,3 +1,4 @@").unwrap(), (1, 3, 1, 4));
+        assert_eq!(
+            parse_hunk_header("@@ -10 +12,0 @@ fn main() {").unwrap(),
+            (10, 1, 12, 0)
+        );
+        assert!(parse_hunk_header("@@ 1,3 +1,4 @@").is_err());
+        assert!(parse_hunk_header("@@ -0,1 +1 @@").is_err());
+    }
+
+    #[test]
+    fn test_parse_patch() {
+        let patch = Patch::parse(indoc! {r#"
+            diff --git a/foo.rs b/foo.rs
+            --- a/foo.rs
+            +++ b/foo.rs
+            @@ -2,3 +2,3 @@
+             b
+            -c
+            +C
+             d
+            @@ -8,2 +8,3 @@
+             h
+            +i
+             j
+        "#})
+        .unwrap();
+
+        assert_eq!(patch.old_text, "\nb\nc\nd\n\n\n\nh\nj");
+        assert_eq!(patch.new_text, "\nb\nC\nd\n\n\n\nh\ni\nj");
+        assert_eq!(
+            patch.ops,
+            vec![
+                DiffOp::Omitted {
+                    old: 0..1,
+                    new: 0..1
+                },
+                DiffOp::Equal {
+                    old: 1..2,
+                    new: 1..2
+                },
+                DiffOp::Change {
+                    old: 2..3,
+                    new: 2..3
+                },
+                DiffOp::Equal {
+                    old: 3..4,
+                    new: 3..4
+                },
+                DiffOp::Omitted {
+                    old: 4..7,
+                    new: 4..7
+                },
+                DiffOp::Equal {
+                    old: 7..8,
+                    new: 7..8
+                },
+                DiffOp::Change {
+                    old: 8..8,
+                    new: 8..9
+                },
+                DiffOp::Equal {
+                    old: 8..9,
+                    new: 9..10
+                },
+            ]
+        );
+
+        assert!(Patch::parse("@@ -1,2 +1,2 @@\n a\n").is_err());
+    }
+}
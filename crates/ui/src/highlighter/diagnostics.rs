@@ -9,7 +9,7 @@ use ropey::Rope;
 use sum_tree::{Bias, SeekTarget, SumTree};
 
 use crate::{
-    ActiveTheme,
+    ActiveTheme, IconName,
     input::{Position, RopeExt as _},
 };
 
@@ -131,6 +131,25 @@ impl DiagnosticSeverity {
         }
     }
 
+    /// The icon to show in the gutter and the problem list.
+    pub(crate) fn icon(&self) -> IconName {
+        match self {
+            Self::Error => IconName::CircleX,
+            Self::Warning => IconName::TriangleAlert,
+            Self::Info | Self::Hint => IconName::Info,
+        }
+    }
+
+    /// The higher is more severe.
+    pub(crate) fn level(&self) -> u8 {
+        match self {
+            Self::Error => 3,
+            Self::Warning => 2,
+            Self::Info => 1,
+            Self::Hint => 0,
+        }
+    }
+
     pub(crate) fn highlight_style(&self, cx: &App) -> HighlightStyle {
         let theme = &cx.theme().highlight_theme;
 
@@ -192,18 +211,55 @@ impl Deref for DiagnosticEntry {
     }
 }
 
+/// The summary of the diagnostics, e.g.: the count of each severity.
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticSummary {
     count: usize,
+    error_count: usize,
+    warning_count: usize,
+    info_count: usize,
+    hint_count: usize,
     start: usize,
     end: usize,
 }
 
+impl DiagnosticSummary {
+    /// Returns the total count of the diagnostics.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the count of the diagnostics with the severity.
+    pub fn count_of(&self, severity: DiagnosticSeverity) -> usize {
+        match severity {
+            DiagnosticSeverity::Error => self.error_count,
+            DiagnosticSeverity::Warning => self.warning_count,
+            DiagnosticSeverity::Info => self.info_count,
+            DiagnosticSeverity::Hint => self.hint_count,
+        }
+    }
+
+    /// Returns the count of the errors.
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+
+    /// Returns the count of the warnings.
+    pub fn warning_count(&self) -> usize {
+        self.warning_count
+    }
+}
+
 impl sum_tree::Item for DiagnosticEntry {
     type Summary = DiagnosticSummary;
     fn summary(&self, _cx: &()) -> Self::Summary {
+        let severity = self.diagnostic.severity;
         DiagnosticSummary {
             count: 1,
+            error_count: (severity == DiagnosticSeverity::Error) as usize,
+            warning_count: (severity == DiagnosticSeverity::Warning) as usize,
+            info_count: (severity == DiagnosticSeverity::Info) as usize,
+            hint_count: (severity == DiagnosticSeverity::Hint) as usize,
             start: self.range.start,
             end: self.range.end,
         }
@@ -215,6 +271,10 @@ impl sum_tree::Summary for DiagnosticSummary {
     fn zero(_: Self::Context<'_>) -> Self {
         DiagnosticSummary {
             count: 0,
+            error_count: 0,
+            warning_count: 0,
+            info_count: 0,
+            hint_count: 0,
             start: usize::MIN,
             end: usize::MIN,
         }
@@ -224,6 +284,10 @@ impl sum_tree::Summary for DiagnosticSummary {
         self.start = other.start;
         self.end = other.end;
         self.count += other.count;
+        self.error_count += other.error_count;
+        self.warning_count += other.warning_count;
+        self.info_count += other.info_count;
+        self.hint_count += other.hint_count;
     }
 }
 
@@ -287,6 +351,11 @@ impl DiagnosticSet {
         self.diagnostics.summary().count
     }
 
+    /// Returns the summary of the diagnostics, e.g.: the count of errors and warnings.
+    pub fn summary(&self) -> DiagnosticSummary {
+        self.diagnostics.summary().clone()
+    }
+
     pub fn clear(&mut self) {
         self.diagnostics = SumTree::new(&());
     }
@@ -331,10 +400,27 @@ impl DiagnosticSet {
         styles
     }
 
-    #[allow(unused)]
     pub(crate) fn iter(&self) -> impl Iterator<Item = &DiagnosticEntry> {
         self.diagnostics.iter()
     }
+
+    /// Returns the first diagnostic starts after the offset, wraps around to the first one.
+    pub(crate) fn next_after(&self, offset: usize) -> Option<&DiagnosticEntry> {
+        let first = self.iter().min_by_key(|entry| entry.range.start);
+        self.iter()
+            .filter(|entry| entry.range.start > offset)
+            .min_by_key(|entry| entry.range.start)
+            .or(first)
+    }
+
+    /// Returns the last diagnostic starts before the offset, wraps around to the last one.
+    pub(crate) fn prev_before(&self, offset: usize) -> Option<&DiagnosticEntry> {
+        let last = self.iter().max_by_key(|entry| entry.range.start);
+        self.iter()
+            .filter(|entry| entry.range.start < offset)
+            .max_by_key(|entry| entry.range.start)
+            .or(last)
+    }
 }
 
 #[cfg(test)]
@@ -389,7 +475,30 @@ mod tests {
         );
         assert_eq!(diagnostics.len(), 3);
 
+        let summary = diagnostics.summary();
+        assert_eq!(summary.count(), 3);
+        assert_eq!(summary.error_count(), 1);
+        assert_eq!(summary.warning_count(), 1);
+        assert_eq!(summary.count_of(DiagnosticSeverity::Info), 1);
+        assert_eq!(summary.count_of(DiagnosticSeverity::Hint), 0);
+
+        // The pushed order is not the text order.
+        assert_eq!(diagnostics.next_after(0).unwrap().range, 7..19);
+        assert_eq!(
+            diagnostics.next_after(7).unwrap().message.as_str(),
+            "Info message"
+        );
+        assert_eq!(diagnostics.next_after(30).unwrap().range, 45..50);
+        assert_eq!(diagnostics.next_after(45).unwrap().range, 7..19);
+        assert_eq!(
+            diagnostics.prev_before(45).unwrap().message.as_str(),
+            "Info message"
+        );
+        assert_eq!(diagnostics.prev_before(7).unwrap().range, 45..50);
+
         diagnostics.clear();
         assert_eq!(diagnostics.len(), 0);
+        assert_eq!(diagnostics.summary().count(), 0);
+        assert!(diagnostics.next_after(0).is_none());
     }
 }
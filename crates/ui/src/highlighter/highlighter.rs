@@ -1,15 +1,21 @@
 use crate::highlighter::{HighlightTheme, LanguageRegistry};
+use crate::input::RopeExt as _;
 
 use anyhow::{Context, Result, anyhow};
 use gpui::{HighlightStyle, SharedString};
+use lsp_types::{DocumentSymbol, SymbolKind};
 
 use ropey::{ChunkCursor, Rope};
 use std::{
     collections::{BTreeSet, HashMap},
     ops::Range,
+    time::{Duration, Instant},
     usize,
 };
-use tree_sitter::{InputEdit, Parser, Point, Query, QueryCursor, StreamingIterator, Tree};
+use tree_sitter::{
+    InputEdit, Language, Node, ParseOptions, Parser, Point, Query, QueryCursor, StreamingIterator,
+    Tree,
+};
 
 /// A syntax highlighter that supports incremental parsing, multiline text,
 /// and caching of highlight results.
@@ -20,6 +26,10 @@ pub struct SyntaxHighlighter {
     /// A separate query for injection patterns that have `#set! injection.combined`.
     combined_injections_query: Option<Query>,
     injection_queries: HashMap<SharedString, Query>,
+    /// The query to build the document outline.
+    outline_query: Option<Query>,
+    /// The query for the auto-indentation, see [`SyntaxHighlighter::suggested_indent`].
+    indents_query: Option<Query>,
 
     locals_pattern_index: usize,
     highlights_pattern_index: usize,
@@ -42,6 +52,49 @@ pub struct SyntaxHighlighter {
     /// Parsed injection trees (language → tree with ranges).
     /// These are built once in update() and queried multiple times in match_styles().
     injection_layers: HashMap<SharedString, InjectionLayer>,
+    /// Whether the last background parsing is timed out, see [`SyntaxHighlighter::background_parser`].
+    timed_out: bool,
+}
+
+/// A parser to parse the text on the background executor, see [`SyntaxHighlighter::background_parser`].
+pub(crate) struct BackgroundParser {
+    language: Language,
+    old_tree: Option<Tree>,
+    text: Rope,
+}
+
+impl BackgroundParser {
+    /// Parse the text, returns None if the parsing takes longer than the `timeout`.
+    pub(crate) fn parse(self, timeout: Duration) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(&self.language).ok()?;
+
+        let started_at = Instant::now();
+        let mut progress = |_: &tree_sitter::ParseState| started_at.elapsed() > timeout;
+        let text = &self.text;
+        parser.parse_with_options(
+            &mut move |offset, _| {
+                if offset >= text.len() {
+                    ""
+                } else {
+                    let (chunk, chunk_byte_ix) = text.chunk(offset);
+                    &chunk[offset - chunk_byte_ix..]
+                }
+            },
+            self.old_tree.as_ref(),
+            Some(ParseOptions::new().progress_callback(&mut progress)),
+        )
+    }
+}
+
+/// The indentation of a line suggested by the indents query, see [`SyntaxHighlighter::suggested_indent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SuggestedIndent {
+    /// The leading whitespace of the line.
+    pub(crate) indent: String,
+    /// Whether the line starts with an `@outdent` capture (e.g.: `}`),
+    /// so the lines before it are one level deeper than the `indent`.
+    pub(crate) outdent: bool,
 }
 
 /// A parsed injection layer.
@@ -321,6 +374,38 @@ impl SyntaxHighlighter {
             }
         }
 
+        let outline_query = if config.outline.is_empty() {
+            None
+        } else {
+            match Query::new(&config.language, &config.outline) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    tracing::error!(
+                        "failed to build outline query for {:?}: {:?}",
+                        config.name,
+                        e
+                    );
+                    None
+                }
+            }
+        };
+
+        let indents_query = if config.indents.is_empty() {
+            None
+        } else {
+            match Query::new(&config.language, &config.indents) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    tracing::error!(
+                        "failed to build indents query for {:?}: {:?}",
+                        config.name,
+                        e
+                    );
+                    None
+                }
+            }
+        };
+
         // let highlight_indices = vec![None; query.capture_names().len()];
 
         Ok(Self {
@@ -328,6 +413,8 @@ impl SyntaxHighlighter {
             query: Some(query),
             combined_injections_query,
             injection_queries,
+            outline_query,
+            indents_query,
 
             locals_pattern_index,
             highlights_pattern_index,
@@ -343,6 +430,7 @@ impl SyntaxHighlighter {
             parser,
             tree: None,
             injection_layers: HashMap::new(),
+            timed_out: false,
         })
     }
 
@@ -350,6 +438,271 @@ impl SyntaxHighlighter {
         self.text.len() == 0
     }
 
+    /// Returns the last parsed syntax tree.
+    pub fn tree(&self) -> Option<&Tree> {
+        self.tree.as_ref()
+    }
+
+    /// Returns the language at the offset.
+    ///
+    /// This is the injected language if the offset is in an injection (e.g.: JavaScript in the `<script>` of HTML),
+    /// otherwise the language of the highlighter.
+    pub fn language_at(&self, offset: usize) -> SharedString {
+        let Some(tree) = self.tree.as_ref() else {
+            return self.language.clone();
+        };
+
+        // The patterns of `self.query` before the locals are the injections.
+        let queries = [
+            (self.query.as_ref(), self.locals_pattern_index),
+            (self.combined_injections_query.as_ref(), usize::MAX),
+        ];
+        for (query, injection_patterns_end) in queries {
+            let Some(query) = query else {
+                continue;
+            };
+            let content_capture_index = query.capture_index_for_name("injection.content");
+            let language_capture_index = query.capture_index_for_name("injection.language");
+            if content_capture_index.is_none() {
+                continue;
+            }
+
+            let mut cursor = QueryCursor::new();
+            cursor.set_byte_range(offset.saturating_sub(1)..offset + 1);
+            let mut matches = cursor.matches(query, tree.root_node(), TextProvider(&self.text));
+            while let Some(query_match) = matches.next() {
+                if query_match.pattern_index >= injection_patterns_end {
+                    continue;
+                }
+
+                let in_content = query_match.captures.iter().any(|cap| {
+                    Some(cap.index) == content_capture_index
+                        && (cap.node.start_byte()..=cap.node.end_byte()).contains(&offset)
+                });
+                if !in_content {
+                    continue;
+                }
+
+                let language = query
+                    .property_settings(query_match.pattern_index)
+                    .iter()
+                    .find(|prop| prop.key.as_ref() == "injection.language")
+                    .and_then(|prop| prop.value.as_ref().map(|v| v.to_string()))
+                    .or_else(|| {
+                        query_match
+                            .captures
+                            .iter()
+                            .find(|cap| Some(cap.index) == language_capture_index)
+                            .map(|cap| self.text.slice(cap.node.byte_range()).to_string())
+                    });
+                if let Some(language) = language {
+                    return language.into();
+                }
+            }
+        }
+
+        self.language.clone()
+    }
+
+    /// Returns the indentation of the line starting at `offset` by the indents query of the language.
+    ///
+    /// The text after `offset` is the content of the line, e.g.: the text after the cursor on Enter.
+    /// Returns `None` if there is no `@indent` or `@align` capture containing the line.
+    pub(crate) fn suggested_indent(
+        &self,
+        offset: usize,
+        indent_unit: &str,
+    ) -> Option<SuggestedIndent> {
+        let (Some(query), Some(tree)) = (self.indents_query.as_ref(), self.tree.as_ref()) else {
+            return None;
+        };
+        let offset = offset.min(self.text.len());
+        let root = tree.root_node();
+
+        // The line starts with an `@outdent` capture, e.g.: `}` or `</div>`.
+        let content_offset = offset
+            + self
+                .text
+                .slice(offset..)
+                .chars()
+                .take_while(|c| matches!(c, ' ' | '\t'))
+                .count();
+        let mut outdent = false;
+        let mut node = root.descendant_for_byte_range(content_offset, content_offset + 1);
+        while let Some(n) = node.filter(|n| n.start_byte() == content_offset) {
+            if self.indent_captures(query, n).contains(&"outdent") {
+                outdent = true;
+                break;
+            }
+            node = n.parent();
+        }
+
+        // Find the innermost `@indent` or `@align` node containing the line.
+        let mut node = root.descendant_for_byte_range(offset.saturating_sub(1), offset);
+        while let Some(n) = node {
+            node = n.parent();
+            if n.start_byte() >= offset {
+                continue;
+            }
+
+            let captures = self.indent_captures(query, n);
+            let is_indent = captures.contains(&"indent");
+            let is_align = captures.contains(&"align");
+            if !is_indent && !is_align {
+                continue;
+            }
+            // The node ends before the line, unless it's not closed, e.g.: `case 1:` in a switch.
+            if n.end_byte() <= offset && self.is_closed(query, n) {
+                continue;
+            }
+
+            let row = n.start_position().row;
+            let line = self.text.slice_line(row).to_string();
+            let base_indent = &line[..line.len() - line.trim_start().len()];
+
+            if is_align {
+                if let Some(column) = self.align_column(query, n) {
+                    let width = line
+                        .get(base_indent.len()..column)
+                        .map_or(0, |s| s.chars().count());
+                    return Some(SuggestedIndent {
+                        indent: format!("{}{}", base_indent, " ".repeat(width)),
+                        outdent: false,
+                    });
+                }
+            }
+            if is_indent {
+                let indent = if outdent {
+                    base_indent.to_string()
+                } else {
+                    format!("{}{}", base_indent, indent_unit)
+                };
+                return Some(SuggestedIndent { indent, outdent });
+            }
+        }
+
+        None
+    }
+
+    /// Returns the capture names of the indents query that the `node` is captured by.
+    fn indent_captures<'a>(&self, query: &'a Query, node: Node) -> Vec<&'a str> {
+        let mut cursor = QueryCursor::new();
+        cursor.set_max_start_depth(Some(0));
+        let mut matches = cursor.matches(query, node, TextProvider(&self.text));
+
+        let mut names = vec![];
+        while let Some(query_match) = matches.next() {
+            for cap in query_match.captures {
+                if cap.node.id() == node.id() {
+                    if let Some(name) = query.capture_names().get(cap.index as usize) {
+                        names.push(*name);
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Returns true if the last child of the node is an `@outdent` capture, e.g.: `}`.
+    fn is_closed(&self, query: &Query, node: Node) -> bool {
+        let mut cursor = node.walk();
+        let last_child = node.children(&mut cursor).last();
+        last_child.is_some_and(|child| {
+            !child.is_missing() && self.indent_captures(query, child).contains(&"outdent")
+        })
+    }
+
+    /// Returns the column of the first item after the opening bracket of the `@align` node,
+    /// `None` if the item is not on the same line as the bracket.
+    fn align_column(&self, query: &Query, node: Node) -> Option<usize> {
+        let mut cursor = node.walk();
+        let item = node.children(&mut cursor).nth(1)?;
+        if item.start_position().row != node.start_position().row
+            || self.indent_captures(query, item).contains(&"outdent")
+        {
+            return None;
+        }
+        Some(item.start_position().column)
+    }
+
+    /// Returns the symbols matched by the outline query of the language.
+    ///
+    /// The symbols are flattened (without children) and sorted by the position.
+    #[allow(deprecated)]
+    pub fn outline_symbols(&self) -> Vec<DocumentSymbol> {
+        let (Some(query), Some(tree)) = (self.outline_query.as_ref(), self.tree.as_ref()) else {
+            return vec![];
+        };
+        let Some(name_capture_index) = query.capture_index_for_name("name") else {
+            return vec![];
+        };
+
+        // (range, pattern_index, kind, name, name_range)
+        let mut items = vec![];
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), TextProvider(&self.text));
+        while let Some(query_match) = matches.next() {
+            let mut item = None;
+            let mut name = String::new();
+            let mut name_range: Option<Range<usize>> = None;
+            for capture in query_match.captures {
+                let range = capture.node.byte_range();
+                if capture.index == name_capture_index {
+                    if !name.is_empty() {
+                        name.push(' ');
+                    }
+                    name.push_str(&self.text.slice(range.clone()).to_string());
+                    name_range = Some(match name_range {
+                        Some(name_range) => {
+                            name_range.start.min(range.start)..name_range.end.max(range.end)
+                        }
+                        None => range,
+                    });
+                } else if let Some(kind) =
+                    query.capture_names()[capture.index as usize].strip_prefix("definition.")
+                {
+                    item = Some((range, symbol_kind(kind)));
+                }
+            }
+
+            let (Some((range, kind)), Some(name_range)) = (item, name_range) else {
+                continue;
+            };
+            let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+            items.push((range, query_match.pattern_index, kind, name, name_range));
+        }
+
+        // The same node may be matched by multiple patterns, keep the first pattern.
+        items.sort_by(|a, b| {
+            a.0.start
+                .cmp(&b.0.start)
+                .then(b.0.end.cmp(&a.0.end))
+                .then(a.1.cmp(&b.1))
+        });
+        items.dedup_by(|a, b| a.0 == b.0);
+
+        items
+            .into_iter()
+            .map(|(range, _, kind, name, name_range)| {
+                let to_lsp_range = |range: Range<usize>| lsp_types::Range {
+                    start: self.text.offset_to_position(range.start),
+                    end: self.text.offset_to_position(range.end),
+                };
+
+                DocumentSymbol {
+                    name,
+                    detail: None,
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    range: to_lsp_range(range),
+                    selection_range: to_lsp_range(name_range),
+                    children: None,
+                }
+            })
+            .collect()
+    }
+
     /// Highlight the given text, returning a map from byte ranges to highlight captures.
     ///
     /// Uses incremental parsing by `edit` to efficiently update the highlighter's state.
@@ -390,11 +743,51 @@ impl SyntaxHighlighter {
             return;
         };
 
+        // The text is parsed in the foreground (e.g.: it is smaller than the large file size now),
+        // so the background parsing can be tried again.
+        self.timed_out = false;
         self.tree = Some(new_tree.clone());
         self.text = text.clone();
         self.parse_combined_injections(&new_tree);
     }
 
+    /// Apply the `edit` to the last parsed tree without parsing.
+    ///
+    /// This is used for the large text, the text is parsed by the [`BackgroundParser`] later,
+    /// the highlights are shifted by the edit until the new tree is set.
+    pub(crate) fn edit(&mut self, edit: &InputEdit, text: &Rope) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.edit(edit);
+        }
+        self.text = text.clone();
+    }
+
+    /// Returns a parser to parse the current text on the background executor,
+    /// the result should be set by [`Self::set_tree`].
+    ///
+    /// Returns None if the last background parsing is timed out, that the text is too large to highlight.
+    pub(crate) fn background_parser(&self) -> Option<BackgroundParser> {
+        if self.timed_out {
+            return None;
+        }
+
+        let config = LanguageRegistry::singleton().language(&self.language)?;
+        Some(BackgroundParser {
+            language: config.language.clone(),
+            old_tree: self.tree.clone(),
+            text: self.text.clone(),
+        })
+    }
+
+    /// Set the tree parsed by the [`BackgroundParser`], None if the parsing is timed out.
+    ///
+    /// The combined injections are not parsed for the large text.
+    pub(crate) fn set_tree(&mut self, tree: Option<Tree>) {
+        self.timed_out = tree.is_none();
+        self.tree = tree;
+        self.injection_layers.clear();
+    }
+
     /// Parse all combined injections after main tree is updated.
     /// pattern: parse once in update, query many times in render.
     fn parse_combined_injections(&mut self, tree: &Tree) {
@@ -652,6 +1045,28 @@ impl SyntaxHighlighter {
 /// To:
 ///
 /// AABCCDDCEEEE
+/// Returns the [`SymbolKind`] of the `@definition.{kind}` capture in the outline query.
+fn symbol_kind(kind: &str) -> SymbolKind {
+    match kind {
+        "module" => SymbolKind::MODULE,
+        "namespace" => SymbolKind::NAMESPACE,
+        "class" => SymbolKind::CLASS,
+        "struct" => SymbolKind::STRUCT,
+        "interface" => SymbolKind::INTERFACE,
+        "impl" => SymbolKind::OBJECT,
+        "enum" => SymbolKind::ENUM,
+        "enum_member" => SymbolKind::ENUM_MEMBER,
+        "function" | "macro" => SymbolKind::FUNCTION,
+        "method" => SymbolKind::METHOD,
+        "constructor" => SymbolKind::CONSTRUCTOR,
+        "field" => SymbolKind::FIELD,
+        "property" => SymbolKind::PROPERTY,
+        "type" => SymbolKind::TYPE_PARAMETER,
+        "constant" => SymbolKind::CONSTANT,
+        _ => SymbolKind::VARIABLE,
+    }
+}
+
 pub(crate) fn unique_styles(
     total_range: &Range<usize>,
     styles: Vec<(Range<usize>, HighlightStyle)>,
@@ -859,6 +1274,244 @@ $x = 1;
         }
     }
 
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_language_at() {
+        let html = "<div>\n<script>\nlet a = 1;\n</script>\n<style>\n.foo { color: red; }\n</style>\n</div>";
+        let mut highlighter = SyntaxHighlighter::new("html");
+        highlighter.update(None, &Rope::from_str(html));
+
+        assert_eq!(highlighter.language_at(2), "html");
+        assert_eq!(
+            highlighter.language_at(html.find("let a").unwrap()),
+            "javascript"
+        );
+        assert_eq!(highlighter.language_at(html.find(".foo").unwrap()), "css");
+        assert_eq!(
+            highlighter.language_at(html.find("</div>").unwrap()),
+            "html"
+        );
+
+        let php = "<?php\n$x = 1;\n?>\n<div>Hello</div>\n";
+        let mut highlighter = SyntaxHighlighter::new("php");
+        highlighter.update(None, &Rope::from_str(php));
+
+        assert_eq!(highlighter.language_at(php.find("$x").unwrap()), "php");
+        assert_eq!(highlighter.language_at(php.find("Hello").unwrap()), "html");
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_suggested_indent() {
+        let suggested_indent = |highlighter: &SyntaxHighlighter, offset: usize| {
+            highlighter
+                .suggested_indent(offset, "    ")
+                .map(|suggestion| (suggestion.indent, suggestion.outdent))
+        };
+
+        let text = "fn main() {\n    let a = foo(\n        1,\n    );\n}\nfn b() {}";
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &Rope::from_str(text));
+
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('{').unwrap() + 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("1,").unwrap() + 2),
+            Some(("        ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find(");").unwrap()),
+            Some(("    ".into(), true))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("}\n").unwrap() + 1),
+            None
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("{}").unwrap() + 1),
+            Some(("".into(), true))
+        );
+
+        // The block is not closed.
+        let text = "fn main() {\n    if a {";
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, text.len()),
+            Some(("        ".into(), false))
+        );
+
+        let text = "foo(a,\n    b)\nbar(\n    c)";
+        let mut highlighter = SyntaxHighlighter::new("python");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('\n').unwrap()),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("bar(").unwrap() + 4),
+            Some(("    ".into(), false))
+        );
+
+        // The compound statements of Python are indented without the closing brackets.
+        let text = indoc::indoc! {r#"
+            class A:
+                def foo(self):
+                    for a in b:
+                        pass
+                    while a:
+                        pass
+                    with a as b:
+                        pass
+                    try:
+                        pass
+                    except Exception:
+                        pass
+                    finally:
+                        pass
+                    if a:
+                        pass
+                    elif b:
+                        pass
+                    else:
+                        return 1"#};
+        let mut highlighter = SyntaxHighlighter::new("python");
+        highlighter.update(None, &Rope::from_str(text));
+        for (line, indent) in [
+            ("class A:", "    "),
+            ("def foo(self):", "        "),
+            ("for a in b:", "            "),
+            ("while a:", "            "),
+            ("with a as b:", "            "),
+            ("try:", "            "),
+            ("except Exception:", "            "),
+            ("finally:", "            "),
+            ("if a:", "            "),
+            ("elif b:", "            "),
+            ("else:", "            "),
+            ("return 1", "            "),
+        ] {
+            assert_eq!(
+                suggested_indent(&highlighter, text.find(line).unwrap() + line.len()),
+                Some((indent.into(), false)),
+                "{}",
+                line
+            );
+        }
+
+        let text =
+            "package main\n\nimport (\n\t\"fmt\"\n)\n\nfunc main() {\n\tfoo(\n\t\t1,\n\t)\n}";
+        let mut highlighter = SyntaxHighlighter::new("go");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('(').unwrap() + 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('{').unwrap() + 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("1,").unwrap() + 2),
+            Some(("\t    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("\t)").unwrap()),
+            Some(("\t".into(), true))
+        );
+
+        let text = "function a() {\n  foo({\n    b: [1],\n  });\n}";
+        let mut highlighter = SyntaxHighlighter::new("javascript");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('{').unwrap() + 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("({").unwrap() + 2),
+            Some(("      ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("[").unwrap() + 1),
+            Some(("        ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("});").unwrap()),
+            Some(("  ".into(), true))
+        );
+
+        let text = "type A = {\n  b: number;\n};\nfunction a(b: A) {\n}";
+        let mut highlighter = SyntaxHighlighter::new("typescript");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('{').unwrap() + 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find("A) {").unwrap() + 4),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.rfind('}').unwrap()),
+            Some(("".into(), true))
+        );
+
+        let text = "{\n  \"a\": [\n    1\n  ]\n}";
+        let mut highlighter = SyntaxHighlighter::new("json");
+        highlighter.update(None, &Rope::from_str(text));
+        assert_eq!(
+            suggested_indent(&highlighter, 1),
+            Some(("    ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find('[').unwrap() + 1),
+            Some(("      ".into(), false))
+        );
+        assert_eq!(
+            suggested_indent(&highlighter, text.find(']').unwrap()),
+            Some(("  ".into(), true))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_outline_symbols() {
+        let code = r#"mod foo {
+    pub struct Point {
+        x: i32,
+    }
+
+    impl Display for Point {
+        fn fmt(&self) {}
+    }
+}
+
+fn main() {}
+"#;
+
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &Rope::from_str(code));
+
+        let symbols = highlighter
+            .outline_symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind, symbol.range.start.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            vec![
+                ("foo".to_string(), SymbolKind::MODULE, 0),
+                ("Point".to_string(), SymbolKind::STRUCT, 1),
+                ("x".to_string(), SymbolKind::FIELD, 2),
+                ("Display for Point".to_string(), SymbolKind::OBJECT, 5),
+                ("fmt".to_string(), SymbolKind::FUNCTION, 6),
+                ("main".to_string(), SymbolKind::FUNCTION, 10),
+            ]
+        );
+    }
+
     #[test]
     fn test_unique_styles() {
         let red = color_style(gpui::red());
@@ -198,6 +198,84 @@ impl Language {
         .collect()
     }
 
+    /// Return the outline query for the language, empty if the outline is not supported.
+    #[allow(unused)]
+    pub(super) fn outline_query(&self) -> &'static str {
+        #[cfg(not(feature = "tree-sitter-languages"))]
+        return "";
+
+        #[cfg(feature = "tree-sitter-languages")]
+        match self {
+            Self::Rust => include_str!("languages/rust/outline.scm"),
+            Self::Go => include_str!("languages/go/outline.scm"),
+            Self::JavaScript => include_str!("languages/javascript/outline.scm"),
+            Self::TypeScript | Self::Tsx => include_str!("languages/typescript/outline.scm"),
+            Self::Python => include_str!("languages/python/outline.scm"),
+            _ => "",
+        }
+    }
+
+    /// Return the indents query for the language, empty if the auto-indentation is not supported.
+    pub(super) fn indents_query(&self) -> &'static str {
+        #[cfg(not(feature = "tree-sitter-languages"))]
+        return include_str!("languages/json/indents.scm");
+
+        #[cfg(feature = "tree-sitter-languages")]
+        match self {
+            Self::Rust => include_str!("languages/rust/indents.scm"),
+            Self::Go => include_str!("languages/go/indents.scm"),
+            Self::JavaScript => include_str!("languages/javascript/indents.scm"),
+            Self::TypeScript | Self::Tsx => include_str!("languages/typescript/indents.scm"),
+            Self::Json => include_str!("languages/json/indents.scm"),
+            Self::Python => include_str!("languages/python/indents.scm"),
+            _ => "",
+        }
+    }
+
+    /// Return the line comment and block comment tokens of the language.
+    pub(super) fn comment_tokens(
+        &self,
+    ) -> (Option<&'static str>, Option<(&'static str, &'static str)>) {
+        #[cfg(not(feature = "tree-sitter-languages"))]
+        return (None, None);
+
+        #[cfg(feature = "tree-sitter-languages")]
+        match self {
+            Self::C
+            | Self::Cpp
+            | Self::CSharp
+            | Self::Go
+            | Self::Java
+            | Self::JavaScript
+            | Self::Kotlin
+            | Self::Php
+            | Self::Proto
+            | Self::Rust
+            | Self::Scala
+            | Self::Swift
+            | Self::Tsx
+            | Self::TypeScript => (Some("//"), Some(("/*", "*/"))),
+            Self::Zig => (Some("//"), None),
+            Self::Css => (None, Some(("/*", "*/"))),
+            Self::Bash
+            | Self::CMake
+            | Self::Elixir
+            | Self::GraphQL
+            | Self::Make
+            | Self::Python
+            | Self::Ruby
+            | Self::Toml
+            | Self::Yaml => (Some("#"), None),
+            Self::Lua => (Some("--"), Some(("--[[", "]]"))),
+            Self::Sql => (Some("--"), Some(("/*", "*/"))),
+            Self::Astro | Self::Html | Self::Markdown | Self::MarkdownInline | Self::Svelte => {
+                (None, Some(("<!--", "-->")))
+            }
+            Self::Ejs | Self::Erb => (None, Some(("<%#", "%>"))),
+            Self::Plain | Self::Diff | Self::Json | Self::JsDoc => (None, None),
+        }
+    }
+
     /// Return the language info for the language.
     ///
     /// (language, query, injection, locals)
@@ -416,7 +494,7 @@ impl Language {
 
         let language = tree_sitter::Language::new(language);
 
-        LanguageConfig::new(
+        let mut config = LanguageConfig::new(
             self.name(),
             language,
             self.injection_languages(),
@@ -424,6 +502,17 @@ impl Language {
             injection,
             locals,
         )
+        .outline(self.outline_query())
+        .indents(self.indents_query());
+
+        let (line_comment, block_comment) = self.comment_tokens();
+        if let Some(token) = line_comment {
+            config = config.line_comment(token);
+        }
+        if let Some((start, end)) = block_comment {
+            config = config.block_comment(start, end);
+        }
+        config
     }
 }
 
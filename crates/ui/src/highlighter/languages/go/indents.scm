@@ -0,0 +1,18 @@
+[
+  (argument_list)
+  (block)
+  (communication_case)
+  (default_case)
+  (expression_case)
+  (field_declaration_list)
+  (import_spec_list)
+  (interface_type)
+  (literal_value)
+  (parameter_list)
+  (type_case)
+] @indent
+
+[
+  "}"
+  ")"
+] @outdent
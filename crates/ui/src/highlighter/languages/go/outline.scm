@@ -0,0 +1,29 @@
+(function_declaration
+  name: (_) @name) @definition.function
+
+(method_declaration
+  name: (_) @name) @definition.method
+
+(type_spec
+  name: (_) @name
+  type: (struct_type)) @definition.struct
+
+(type_spec
+  name: (_) @name
+  type: (interface_type)) @definition.interface
+
+(type_spec
+  name: (_) @name) @definition.type
+
+(source_file
+  (const_declaration
+    (const_spec
+      name: (_) @name) @definition.constant))
+
+(source_file
+  (var_declaration
+    (var_spec
+      name: (_) @name) @definition.variable))
+
+(field_declaration
+  name: (_) @name) @definition.field
@@ -0,0 +1,24 @@
+[
+  (arguments)
+  (array)
+  (array_pattern)
+  (class_body)
+  (export_clause)
+  (formal_parameters)
+  (jsx_element)
+  (named_imports)
+  (object)
+  (object_pattern)
+  (parenthesized_expression)
+  (statement_block)
+  (switch_body)
+  (switch_case)
+  (switch_default)
+] @indent
+
+[
+  "}"
+  ")"
+  "]"
+  (jsx_closing_element)
+] @outdent
@@ -0,0 +1,29 @@
+(class_declaration
+  name: (_) @name) @definition.class
+
+(method_definition
+  name: (_) @name) @definition.method
+
+(field_definition
+  property: (_) @name) @definition.field
+
+(function_declaration
+  name: (_) @name) @definition.function
+
+(generator_function_declaration
+  name: (_) @name) @definition.function
+
+(variable_declarator
+  name: (identifier) @name
+  value: (arrow_function)) @definition.function
+
+(program
+  (lexical_declaration
+    (variable_declarator
+      name: (identifier) @name) @definition.variable))
+
+(program
+  (export_statement
+    (lexical_declaration
+      (variable_declarator
+        name: (identifier) @name) @definition.variable)))
@@ -0,0 +1,30 @@
+[
+  (argument_list)
+  (class_definition)
+  (dictionary)
+  (elif_clause)
+  (else_clause)
+  (except_clause)
+  (finally_clause)
+  (for_statement)
+  (function_definition)
+  (if_statement)
+  (list)
+  (parameters)
+  (set)
+  (try_statement)
+  (tuple)
+  (while_statement)
+  (with_statement)
+] @indent
+
+[
+  (argument_list)
+  (parameters)
+] @align
+
+[
+  "}"
+  ")"
+  "]"
+] @outdent
@@ -0,0 +1,5 @@
+(class_definition
+  name: (_) @name) @definition.class
+
+(function_definition
+  name: (_) @name) @definition.function
@@ -0,0 +1,23 @@
+[
+  (arguments)
+  (array_expression)
+  (block)
+  (declaration_list)
+  (enum_variant_list)
+  (field_declaration_list)
+  (field_initializer_list)
+  (match_block)
+  (ordered_field_declaration_list)
+  (parameters)
+  (token_tree)
+  (tuple_expression)
+  (type_arguments)
+  (type_parameters)
+  (use_list)
+] @indent
+
+[
+  "}"
+  ")"
+  "]"
+] @outdent
@@ -0,0 +1,43 @@
+(mod_item
+  name: (_) @name) @definition.module
+
+(struct_item
+  name: (_) @name) @definition.struct
+
+(union_item
+  name: (_) @name) @definition.struct
+
+(enum_item
+  name: (_) @name) @definition.enum
+
+(enum_variant
+  name: (_) @name) @definition.enum_member
+
+(trait_item
+  name: (_) @name) @definition.interface
+
+(impl_item
+  trait: (_)? @name
+  "for"? @name
+  type: (_) @name) @definition.impl
+
+(function_item
+  name: (_) @name) @definition.function
+
+(function_signature_item
+  name: (_) @name) @definition.function
+
+(macro_definition
+  name: (_) @name) @definition.macro
+
+(type_item
+  name: (_) @name) @definition.type
+
+(const_item
+  name: (_) @name) @definition.constant
+
+(static_item
+  name: (_) @name) @definition.constant
+
+(field_declaration
+  name: (_) @name) @definition.field
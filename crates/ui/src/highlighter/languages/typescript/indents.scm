@@ -0,0 +1,25 @@
+[
+  (arguments)
+  (array)
+  (array_pattern)
+  (class_body)
+  (export_clause)
+  (formal_parameters)
+  (named_imports)
+  (object)
+  (object_pattern)
+  (object_type)
+  (parenthesized_expression)
+  (statement_block)
+  (switch_body)
+  (switch_case)
+  (switch_default)
+  (type_arguments)
+  (type_parameters)
+] @indent
+
+[
+  "}"
+  ")"
+  "]"
+] @outdent
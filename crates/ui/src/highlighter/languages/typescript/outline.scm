@@ -0,0 +1,44 @@
+(class_declaration
+  name: (_) @name) @definition.class
+
+(abstract_class_declaration
+  name: (_) @name) @definition.class
+
+(interface_declaration
+  name: (_) @name) @definition.interface
+
+(enum_declaration
+  name: (_) @name) @definition.enum
+
+(type_alias_declaration
+  name: (_) @name) @definition.type
+
+(method_definition
+  name: (_) @name) @definition.method
+
+(method_signature
+  name: (_) @name) @definition.method
+
+(public_field_definition
+  name: (_) @name) @definition.field
+
+(property_signature
+  name: (_) @name) @definition.property
+
+(function_declaration
+  name: (_) @name) @definition.function
+
+(variable_declarator
+  name: (identifier) @name
+  value: (arrow_function)) @definition.function
+
+(program
+  (lexical_declaration
+    (variable_declarator
+      name: (identifier) @name) @definition.variable))
+
+(program
+  (export_statement
+    (lexical_declaration
+      (variable_declarator
+        name: (identifier) @name) @definition.variable)))
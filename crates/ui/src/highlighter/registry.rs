@@ -64,6 +64,21 @@ pub struct LanguageConfig {
     pub highlights: SharedString,
     pub injections: SharedString,
     pub locals: SharedString,
+    /// The outline query, the `@definition.*` captures are the symbols and the `@name` captures are their names.
+    ///
+    /// e.g.: `(function_item name: (_) @name) @definition.function`
+    pub outline: SharedString,
+    /// The indents query for the auto-indentation, the captures are:
+    ///
+    /// - `@indent`: The lines inside the node are indented one level more than the line of the node start.
+    /// - `@outdent`: The line starts with the node (e.g.: `}`) is at the same level as the line of the node start.
+    /// - `@align`: The lines inside the node are aligned to the first item after the opening bracket,
+    ///   if the item is on the same line as the bracket.
+    pub indents: SharedString,
+    /// The token to start a line comment, e.g.: `//`.
+    pub line_comment: Option<SharedString>,
+    /// The tokens to start and end a block comment, e.g.: `/*` and `*/`.
+    pub block_comment: Option<(SharedString, SharedString)>,
 }
 
 impl LanguageConfig {
@@ -82,8 +97,39 @@ impl LanguageConfig {
             highlights: SharedString::from(highlights.to_string()),
             injections: SharedString::from(injections.to_string()),
             locals: SharedString::from(locals.to_string()),
+            outline: SharedString::default(),
+            indents: SharedString::default(),
+            line_comment: None,
+            block_comment: None,
         }
     }
+
+    /// Set the outline query to build the document outline without a [`crate::input::DocumentSymbolProvider`].
+    pub fn outline(mut self, outline: &str) -> Self {
+        self.outline = SharedString::from(outline.to_string());
+        self
+    }
+
+    /// Set the indents query for the auto-indentation on Enter, typing a closing bracket and pasting.
+    pub fn indents(mut self, indents: &str) -> Self {
+        self.indents = SharedString::from(indents.to_string());
+        self
+    }
+
+    /// Set the line comment token, used by [`crate::input::ToggleComment`].
+    pub fn line_comment(mut self, token: &str) -> Self {
+        self.line_comment = Some(SharedString::from(token.to_string()));
+        self
+    }
+
+    /// Set the block comment tokens, used by [`crate::input::ToggleComment`] if the language has no line comment.
+    pub fn block_comment(mut self, start: &str, end: &str) -> Self {
+        self.block_comment = Some((
+            SharedString::from(start.to_string()),
+            SharedString::from(end.to_string()),
+        ));
+        self
+    }
 }
 
 /// Theme for Tree-sitter Highlight
@@ -30,6 +30,8 @@ pub struct History<I: HistoryItem> {
     max_undos: usize,
     group_interval: Option<Duration>,
     grouping: bool,
+    /// Force the next change to start a new version, see [`History::break_grouping`].
+    break_grouping: bool,
     unique: bool,
 }
 
@@ -47,6 +49,7 @@ where
             max_undos: 1000,
             group_interval: None,
             grouping: false,
+            break_grouping: false,
             unique: false,
         }
     }
@@ -80,13 +83,24 @@ where
         self.grouping = false;
     }
 
+    /// Make the next change to start a new version, even if it's made within the `group_interval` of the last change.
+    ///
+    /// This is useful to keep a batch of changes (e.g.: format the document) as a separate undo step.
+    pub fn break_grouping(&mut self) {
+        self.grouping = false;
+        self.break_grouping = true;
+    }
+
     /// Increment the version number if the last change was made more than `GROUP_INTERVAL` milliseconds ago.
     fn inc_version(&mut self) -> usize {
         let t = Instant::now();
-        if !self.grouping && Some(self.last_changed_at.elapsed()) > self.group_interval {
+        if self.break_grouping
+            || (!self.grouping && Some(self.last_changed_at.elapsed()) > self.group_interval)
+        {
             self.version += 1;
         }
 
+        self.break_grouping = false;
         self.last_changed_at = t;
         self.version
     }
@@ -124,6 +138,16 @@ where
         &self.redos
     }
 
+    /// Get the mutable undo stack, e.g.: to shift the changes after the text is changed by others.
+    pub(crate) fn undos_mut(&mut self) -> &mut Vec<I> {
+        &mut self.undos
+    }
+
+    /// Get the mutable redo stack.
+    pub(crate) fn redos_mut(&mut self) -> &mut Vec<I> {
+        &mut self.redos
+    }
+
     /// Clear the undo and redo stacks.
     pub fn clear(&mut self) {
         self.undos.clear();
@@ -253,6 +277,29 @@ mod tests {
         assert_eq!(history.undo().is_none(), true);
     }
 
+    #[test]
+    fn test_break_grouping() {
+        let mut history: History<TabIndex> = History::new().group_interval(Duration::from_secs(60));
+        history.push(0.into());
+        history.push(1.into());
+        assert_eq!(history.version(), 0);
+
+        history.break_grouping();
+        history.push(2.into());
+        history.start_grouping();
+        history.push(3.into());
+        history.end_grouping();
+        assert_eq!(history.version(), 1);
+
+        let changes = history.undo().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].tab_index, 3);
+        assert_eq!(changes[1].tab_index, 2);
+
+        let changes = history.undo().unwrap();
+        assert_eq!(changes.len(), 2);
+    }
+
     #[test]
     fn test_unique_history() {
         let mut history: History<TabIndex> = History::new().max_undos(100).unique();
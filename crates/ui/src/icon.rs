@@ -100,6 +100,7 @@ pub enum IconName {
     Plus,
     Redo,
     Redo2,
+    Regex,
     Replace,
     ResizeCorner,
     Search,
@@ -112,12 +113,14 @@ pub enum IconName {
     StarFill,
     StarOff,
     Sun,
+    TextSelect,
     ThumbsDown,
     ThumbsUp,
     TriangleAlert,
     Undo,
     Undo2,
     User,
+    WholeWord,
     WindowClose,
     WindowMaximize,
     WindowMinimize,
@@ -211,6 +214,7 @@ impl IconNamed for IconName {
             Self::Plus => "icons/plus.svg",
             Self::Redo => "icons/redo.svg",
             Self::Redo2 => "icons/redo-2.svg",
+            Self::Regex => "icons/regex.svg",
             Self::Replace => "icons/replace.svg",
             Self::ResizeCorner => "icons/resize-corner.svg",
             Self::Search => "icons/search.svg",
@@ -223,12 +227,14 @@ impl IconNamed for IconName {
             Self::StarFill => "icons/star-fill.svg",
             Self::StarOff => "icons/star-off.svg",
             Self::Sun => "icons/sun.svg",
+            Self::TextSelect => "icons/text-select.svg",
             Self::ThumbsDown => "icons/thumbs-down.svg",
             Self::ThumbsUp => "icons/thumbs-up.svg",
             Self::TriangleAlert => "icons/triangle-alert.svg",
             Self::Undo => "icons/undo.svg",
             Self::Undo2 => "icons/undo-2.svg",
             Self::User => "icons/user.svg",
+            Self::WholeWord => "icons/whole-word.svg",
             Self::WindowClose => "icons/window-close.svg",
             Self::WindowMaximize => "icons/window-maximize.svg",
             Self::WindowMinimize => "icons/window-minimize.svg",
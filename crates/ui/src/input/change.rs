@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range};
 
 use crate::{history::HistoryItem, input::Selection};
 
@@ -26,6 +26,29 @@ impl Change {
             version: 0,
         }
     }
+
+    /// Shift the ranges after the text in `range` is replaced by a text with `new_len`.
+    ///
+    /// The `applied` is true if the change is in the undo stack that the `new_text` is in the text,
+    /// otherwise the `old_text` is in the text.
+    ///
+    /// Returns false if the `range` overlaps the change, then it can't be undone or redone anymore.
+    pub(crate) fn shift(&mut self, range: &Range<usize>, new_len: usize, applied: bool) -> bool {
+        let current = if applied {
+            self.new_range
+        } else {
+            self.old_range
+        };
+
+        if range.end <= current.start {
+            let start = (current.start + new_len).saturating_sub(range.len());
+            self.old_range = (start..start + self.old_text.len()).into();
+            self.new_range = (start..start + self.new_text.len()).into();
+            true
+        } else {
+            range.start >= current.end
+        }
+    }
 }
 
 impl HistoryItem for Change {
@@ -37,3 +60,31 @@ impl HistoryItem for Change {
         self.version = version;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Change;
+
+    #[test]
+    fn test_shift() {
+        // "Hello world" -> "Hello, world"
+        let change = Change::new(5..5, "", 5..6, ",");
+
+        let mut c = change.clone();
+        assert!(c.shift(&(0..5), 2, true));
+        assert_eq!((c.old_range.start, c.old_range.end), (2, 2));
+        assert_eq!((c.new_range.start, c.new_range.end), (2, 3));
+
+        let mut c = change.clone();
+        assert!(c.shift(&(6..11), 0, true));
+        assert_eq!(c, change);
+
+        let mut c = change.clone();
+        assert!(!c.shift(&(4..6), 0, true));
+
+        // The old text is empty in the redo stack, the insertion at the same position is before it.
+        let mut c = change.clone();
+        assert!(c.shift(&(5..5), 3, false));
+        assert_eq!((c.new_range.start, c.new_range.end), (8, 9));
+    }
+}
@@ -0,0 +1,583 @@
+use gpui::{ClipboardItem, Context, MouseMoveEvent, Window};
+use ropey::RopeSlice;
+
+use crate::input::{
+    InputState, RopeExt as _, SelectColumnDown, SelectColumnLeft, SelectColumnRight,
+    SelectColumnUp, Selection, text_wrapper::DisplayPoint,
+};
+
+/// A point in the column (rectangular) selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) struct ColumnPoint {
+    /// The 0-based soft wrapped row index.
+    pub(super) row: usize,
+    /// The 0-based visual column, the `\t` is expanded by the tab size.
+    pub(super) column: usize,
+}
+
+impl ColumnPoint {
+    fn new(row: usize, column: usize) -> Self {
+        Self { row, column }
+    }
+}
+
+/// A rectangular selection, from the `anchor` to the `head`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ColumnSelection {
+    pub(super) anchor: ColumnPoint,
+    pub(super) head: ColumnPoint,
+}
+
+impl InputState {
+    /// Returns the [`ColumnPoint`] of the given offset.
+    pub(super) fn offset_to_column_point(&self, offset: usize) -> ColumnPoint {
+        let display_point = self.text_wrapper.offset_to_display_point(offset);
+        let row_start =
+            self.text_wrapper
+                .display_point_to_offset(DisplayPoint::new(display_point.row, 0, 0));
+        let tab_size = self.mode.tab_size().tab_size;
+
+        ColumnPoint::new(
+            display_point.row,
+            visual_column(self.text.slice(row_start..offset.max(row_start)), tab_size),
+        )
+    }
+
+    /// Returns the offset of the given [`ColumnPoint`], the column is clamped to the end of the row.
+    pub(super) fn column_point_to_offset(&self, point: ColumnPoint) -> usize {
+        let row_start = self
+            .text_wrapper
+            .display_point_to_offset(DisplayPoint::new(point.row, 0, 0));
+        let row_end =
+            self.text_wrapper
+                .display_point_to_offset(DisplayPoint::new(point.row, 0, usize::MAX));
+        let tab_size = self.mode.tab_size().tab_size;
+
+        row_start
+            + offset_for_visual_column(
+                self.text.slice(row_start..row_end.max(row_start)),
+                point.column,
+                tab_size,
+            )
+    }
+
+    fn max_display_row(&self) -> usize {
+        self.text_wrapper.len().saturating_sub(1)
+    }
+
+    /// Select the rectangle from the `anchor` to the `head`.
+    ///
+    /// Each row will be a selection, the `head` row is the primary selection.
+    pub(super) fn select_column(
+        &mut self,
+        anchor: ColumnPoint,
+        head: ColumnPoint,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.mode.is_multi_line() {
+            return;
+        }
+
+        let (start_col, end_col) = if anchor.column <= head.column {
+            (anchor.column, head.column)
+        } else {
+            (head.column, anchor.column)
+        };
+        let rows = anchor.row.min(head.row)..=anchor.row.max(head.row);
+
+        let mut primary = None;
+        let mut extra_selections = vec![];
+        for row in rows {
+            let start = self.column_point_to_offset(ColumnPoint::new(row, start_col));
+            let end = self.column_point_to_offset(ColumnPoint::new(row, end_col));
+            let selection = Selection::new(start, end);
+            if row == head.row {
+                primary = Some(selection);
+            } else {
+                extra_selections.push(selection);
+            }
+        }
+
+        let Some(primary) = primary else {
+            return;
+        };
+
+        self.selected_range = primary;
+        self.selection_reversed = head.column < anchor.column;
+        self.selected_word_range = None;
+        self.extra_selections = extra_selections;
+        self.normalize_extra_selections();
+        self.column_selection = Some(ColumnSelection { anchor, head });
+        self.scroll_to(self.cursor(), None, cx);
+        self.pause_blink_cursor(cx);
+        cx.notify();
+    }
+
+    /// Start a column selection from the mouse down offset (Alt or Middle button drag).
+    pub(super) fn start_column_selection(&mut self, offset: usize) {
+        let point = self.offset_to_column_point(offset);
+        self.column_selection = Some(ColumnSelection {
+            anchor: point,
+            head: point,
+        });
+    }
+
+    /// Update the column selection on mouse drag, returns true if handled.
+    pub(super) fn on_drag_column_selection(
+        &mut self,
+        event: &MouseMoveEvent,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(column_selection) = self.column_selection else {
+            return false;
+        };
+
+        let Some(head) = self.column_point_for_mouse_position(event.position) else {
+            return false;
+        };
+        if head == column_selection.head {
+            return true;
+        }
+
+        self.select_column(column_selection.anchor, head, cx);
+        true
+    }
+
+    /// Returns the [`ColumnPoint`] for the mouse position, the column may be out of the line end.
+    fn column_point_for_mouse_position(
+        &self,
+        position: gpui::Point<gpui::Pixels>,
+    ) -> Option<ColumnPoint> {
+        let last_layout = self.last_layout.as_ref()?;
+        let bounds = self.last_bounds?;
+
+        let offset = self.index_for_mouse_position(position);
+        let mut point = self.offset_to_column_point(offset);
+
+        // Allow the column to go beyond the line end, use the space width to calculate the column.
+        let row_end = self.column_point_to_offset(ColumnPoint::new(point.row, usize::MAX));
+        let x = position.x - bounds.origin.x - last_layout.line_number_width;
+        let space_width = last_layout.space_width;
+        if offset == row_end && space_width > gpui::px(0.) && x > gpui::px(0.) {
+            let column = (x / space_width).round() as usize;
+            point.column = point.column.max(column);
+        }
+
+        Some(point)
+    }
+
+    fn move_column_selection(&mut self, rows: isize, columns: isize, cx: &mut Context<Self>) {
+        if !self.mode.is_multi_line() {
+            return;
+        }
+
+        let column_selection = self.column_selection.unwrap_or_else(|| {
+            let point = self.offset_to_column_point(self.cursor());
+            ColumnSelection {
+                anchor: point,
+                head: point,
+            }
+        });
+
+        let mut head = column_selection.head;
+        head.row = head
+            .row
+            .saturating_add_signed(rows)
+            .min(self.max_display_row());
+        head.column = head.column.saturating_add_signed(columns);
+
+        self.select_column(column_selection.anchor, head, cx);
+    }
+
+    pub(super) fn select_column_up(
+        &mut self,
+        _: &SelectColumnUp,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_column_selection(-1, 0, cx);
+    }
+
+    pub(super) fn select_column_down(
+        &mut self,
+        _: &SelectColumnDown,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_column_selection(1, 0, cx);
+    }
+
+    pub(super) fn select_column_left(
+        &mut self,
+        _: &SelectColumnLeft,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_column_selection(0, -1, cx);
+    }
+
+    pub(super) fn select_column_right(
+        &mut self,
+        _: &SelectColumnRight,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_column_selection(0, 1, cx);
+    }
+
+    /// Copy the column selection as a block, returns false if not in column selection.
+    pub(super) fn copy_column_selection(&mut self, cx: &mut Context<Self>) -> bool {
+        if !matches!(self.column_selection, Some(selection) if selection.anchor != selection.head) {
+            return false;
+        }
+
+        // Keep the empty lines to preserve the block shape.
+        let text = self
+            .selections()
+            .into_iter()
+            .map(|selection| self.text.slice(selection).to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        cx.write_to_clipboard(ClipboardItem::new_string(text.clone()));
+        self.column_clipboard = Some(text.into());
+        true
+    }
+
+    /// Paste the block (copied from a column selection) at the cursor, each line is
+    /// inserted at the same column of the following rows.
+    ///
+    /// Returns false if the text is not a block.
+    pub(super) fn paste_column_block(
+        &mut self,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.has_multiple_cursors() || !self.mode.is_multi_line() {
+            return false;
+        }
+        if self.column_clipboard.as_deref() != Some(new_text) {
+            return false;
+        }
+
+        let lines = new_text.split('\n').collect::<Vec<_>>();
+        if lines.len() < 2 {
+            return false;
+        }
+
+        let start = self.offset_to_column_point(self.selected_range.start);
+        let max_row = self.max_display_row();
+
+        // The (offset, text) to insert, sorted by offset.
+        let mut inserts: Vec<(usize, String)> = vec![];
+        let mut trailing = String::new();
+        for (ix, line) in lines.iter().enumerate() {
+            let row = start.row + ix;
+            if row > max_row {
+                // Append new lines at the end of the text.
+                trailing.push('\n');
+                trailing.push_str(&" ".repeat(start.column));
+                trailing.push_str(line);
+                continue;
+            }
+
+            let point = ColumnPoint::new(row, start.column);
+            let offset = self.column_point_to_offset(point);
+            let padding = start
+                .column
+                .saturating_sub(self.offset_to_column_point(offset).column);
+            let text = if ix == 0 {
+                line.to_string()
+            } else {
+                format!("{}{}", " ".repeat(padding), line)
+            };
+            inserts.push((offset, text));
+        }
+
+        if !trailing.is_empty() {
+            let text_len = self.text.len();
+            match inserts.last_mut() {
+                Some((offset, text)) if *offset == text_len => text.push_str(&trailing),
+                _ => inserts.push((text_len, trailing)),
+            }
+        }
+
+        let first = self.selected_range;
+        let mut selections = inserts
+            .iter()
+            .enumerate()
+            .map(|(ix, (offset, _))| {
+                if ix == 0 {
+                    first
+                } else {
+                    Selection::new(*offset, *offset)
+                }
+            })
+            .collect::<Vec<_>>();
+        self.selected_range = selections.remove(0);
+        self.extra_selections = selections;
+
+        let texts = inserts
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>();
+        self.replace_text_in_selections(&texts, window, cx);
+        // Keep only the cursor at the end of the first line, like a normal paste.
+        self.clear_extra_cursors(cx);
+        true
+    }
+}
+
+/// Returns the visual column of the end of the `text`, `\t` is expanded to the next tab stop.
+fn visual_column(text: RopeSlice, tab_size: usize) -> usize {
+    let tab_size = tab_size.max(1);
+    text.chars().fold(0, |column, c| {
+        if c == '\t' {
+            column + tab_size - column % tab_size
+        } else {
+            column + 1
+        }
+    })
+}
+
+/// Returns the byte offset in the `text` of the visual column, clamped to the text length.
+fn offset_for_visual_column(text: RopeSlice, target: usize, tab_size: usize) -> usize {
+    let tab_size = tab_size.max(1);
+    let mut column = 0;
+    let mut offset = 0;
+    for c in text.chars() {
+        if column >= target || c == '\n' {
+            break;
+        }
+
+        column = if c == '\t' {
+            column + tab_size - column % tab_size
+        } else {
+            column + 1
+        };
+        offset += c.len_utf8();
+    }
+
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{AppContext as _, ClipboardItem, MouseUpEvent, TestAppContext};
+    use ropey::Rope;
+
+    use super::{ColumnPoint, offset_for_visual_column, visual_column};
+    use crate::input::{
+        Copy, InputState, Paste, SelectColumnDown, SelectColumnRight, Selection, TabSize,
+    };
+
+    #[test]
+    fn test_visual_column() {
+        let text = Rope::from("\tfoo\t b");
+        assert_eq!(visual_column(text.slice(0..0), 4), 0);
+        assert_eq!(visual_column(text.slice(0..1), 4), 4);
+        assert_eq!(visual_column(text.slice(0..4), 4), 7);
+        assert_eq!(visual_column(text.slice(0..5), 4), 8);
+        assert_eq!(visual_column(text.slice(0..7), 4), 10);
+
+        let text = Rope::from("你好");
+        assert_eq!(visual_column(text.slice(0..6), 4), 2);
+    }
+
+    #[test]
+    fn test_offset_for_visual_column() {
+        let text = Rope::from("\tfoo\t b");
+        let slice = text.slice(..);
+        assert_eq!(offset_for_visual_column(slice, 0, 4), 0);
+        assert_eq!(offset_for_visual_column(slice, 2, 4), 1);
+        assert_eq!(offset_for_visual_column(slice, 4, 4), 1);
+        assert_eq!(offset_for_visual_column(slice, 6, 4), 3);
+        assert_eq!(offset_for_visual_column(slice, 8, 4), 5);
+        assert_eq!(offset_for_visual_column(slice, 100, 4), 7);
+
+        let text = Rope::from("你好\nabc");
+        assert_eq!(offset_for_visual_column(text.slice(..), 1, 4), 3);
+        assert_eq!(offset_for_visual_column(text.slice(..), 10, 4), 6);
+    }
+
+    #[gpui::test]
+    fn test_select_column(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .tab_size(TabSize {
+                        tab_size: 4,
+                        hard_tabs: false,
+                    })
+                    .default_value("abcdef\nab\n\tcd\nabcdefgh")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.text_wrapper.prepare_if_need(&state.text.clone(), cx);
+
+                // The short line is clamped to the line end, the tab is selected as a whole.
+                state.select_column(ColumnPoint::new(0, 1), ColumnPoint::new(3, 5), cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![
+                        Selection::new(1, 5),
+                        Selection::new(8, 9),
+                        Selection::new(11, 12),
+                        Selection::new(15, 19),
+                    ]
+                );
+                assert_eq!(state.selected_range, Selection::new(15, 19));
+                assert!(!state.selection_reversed);
+
+                // The block is copied with a line for each row.
+                state.copy(&Copy, window, cx);
+                assert_eq!(
+                    cx.read_from_clipboard().and_then(|item| item.text()),
+                    Some("bcde\nb\nc\nbcde".into())
+                );
+
+                // The head is the primary selection.
+                state.select_column(ColumnPoint::new(3, 5), ColumnPoint::new(0, 1), cx);
+                assert_eq!(state.selected_range, Selection::new(1, 5));
+                assert!(state.selection_reversed);
+
+                // Select the column by the keyboard from the cursor.
+                state.clear_extra_cursors(cx);
+                state.selected_range = (1..1).into();
+                state.select_column_right(&SelectColumnRight, window, cx);
+                state.select_column_right(&SelectColumnRight, window, cx);
+                state.select_column_down(&SelectColumnDown, window, cx);
+                state.select_column_down(&SelectColumnDown, window, cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![
+                        Selection::new(1, 3),
+                        Selection::new(8, 9),
+                        Selection::new(11, 11),
+                    ]
+                );
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_copy_column_selection(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("foo\n\nbar")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.text_wrapper.prepare_if_need(&state.text.clone(), cx);
+                cx.write_to_clipboard(ClipboardItem::new_string("none".into()));
+
+                // Not a column selection.
+                state.select_column(ColumnPoint::new(0, 1), ColumnPoint::new(0, 1), cx);
+                assert!(!state.copy_column_selection(cx));
+                assert_eq!(
+                    cx.read_from_clipboard().and_then(|item| item.text()),
+                    Some("none".into())
+                );
+
+                // The empty line is kept to preserve the block shape.
+                state.select_column(ColumnPoint::new(0, 1), ColumnPoint::new(2, 3), cx);
+                assert!(state.copy_column_selection(cx));
+                assert_eq!(
+                    cx.read_from_clipboard().and_then(|item| item.text()),
+                    Some("oo\n\nar".into())
+                );
+                assert_eq!(state.column_clipboard.as_deref(), Some("oo\n\nar"));
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_paste_column_block(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("abc\na\nabc")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.text_wrapper.prepare_if_need(&state.text.clone(), cx);
+                state.select_column(ColumnPoint::new(0, 0), ColumnPoint::new(1, 1), cx);
+                state.copy(&Copy, window, cx);
+                assert_eq!(state.column_clipboard.as_deref(), Some("a\na"));
+
+                // Fewer lines than the rows, the short line is padded to the column.
+                state.clear_extra_cursors(cx);
+                state.selected_range = (3..3).into();
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "abca\na  a\nabc");
+                assert_eq!(state.selections(), vec![Selection::new(4, 4)]);
+
+                // More lines than the rows, the new lines are appended.
+                state.selected_range = (12..12).into();
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "abca\na  a\nabac\n  a");
+                assert_eq!(state.selections(), vec![Selection::new(13, 13)]);
+
+                // The block is pasted line by line to the same number of cursors,
+                // otherwise as a whole to each cursor.
+                state.selected_range = (0..0).into();
+                state.add_cursor(5, cx);
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "aabca\naa  a\nabac\n  a");
+                state.add_cursor(20, cx);
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "aa\naabca\naa\naa  a\nabac\n  aa\na");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_alt_click_without_drag(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("foo\nbar\nbaz")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.text_wrapper.prepare_if_need(&state.text.clone(), cx);
+
+                // Alt-click at 5 as the mouse down does, then release the mouse.
+                state.selected_range = (1..1).into();
+                state.add_cursor(5, cx);
+                state.start_column_selection(5);
+                state.on_mouse_up(&MouseUpEvent::default(), window, cx);
+                assert!(state.column_selection.is_none());
+                assert_eq!(
+                    state.selections(),
+                    vec![Selection::new(1, 1), Selection::new(5, 5)]
+                );
+
+                // The column selection starts from the primary cursor.
+                state.select_column_down(&SelectColumnDown, window, cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![Selection::new(5, 5), Selection::new(9, 9)]
+                );
+            });
+        });
+    }
+}
@@ -0,0 +1,307 @@
+use std::ops::Range;
+
+use gpui::{Context, SharedString, Window};
+use ropey::Rope;
+
+use crate::{
+    RopeExt as _,
+    highlighter::LanguageRegistry,
+    input::{InputState, Selection, ToggleComment, mode::InputMode, multi_cursor::shift_offset},
+};
+
+/// The line and block comment tokens of a language, e.g.: `//` and (`/*`, `*/`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CommentTokens {
+    line: Option<SharedString>,
+    block: Option<(SharedString, SharedString)>,
+}
+
+impl InputState {
+    /// Comment or uncomment the lines of all the selections.
+    ///
+    /// The line comment token is used if the language has one, otherwise each group of
+    /// contiguous lines is wrapped with the block comment tokens.
+    pub(super) fn toggle_comment(
+        &mut self,
+        _: &ToggleComment,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tokens) = self.comment_tokens() else {
+            cx.propagate();
+            return;
+        };
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let selections = self.selections();
+        let mut rows: Vec<Range<usize>> = vec![];
+        for selection in selections.iter() {
+            let start_row = self.text.offset_to_point(selection.start).row;
+            let end = self.text.offset_to_point(selection.end);
+            // Skip the last line if the selection ends at the start of it.
+            let end_row = if end.row > start_row && end.column == 0 {
+                end.row - 1
+            } else {
+                end.row
+            };
+
+            match rows.last_mut() {
+                Some(last) if start_row <= last.end => last.end = last.end.max(end_row + 1),
+                _ => rows.push(start_row..end_row + 1),
+            }
+        }
+
+        let edits = toggle_comment_edits(&self.text, &rows, &tokens);
+        if edits.is_empty() {
+            return;
+        }
+
+        let was_silent = self.silent_replace_text;
+        self.silent_replace_text = true;
+        let mut primary = self.selected_range;
+        let mut extra_selections = std::mem::take(&mut self.extra_selections);
+
+        // Replace from the last edit to the first, so the earlier offsets are not changed.
+        for (ix, (range, new_text)) in edits.iter().enumerate().rev() {
+            if ix + 1 < edits.len() {
+                self.history.start_grouping();
+            }
+            self.replace_text_in_range(Some(self.range_to_utf16(range)), new_text, window, cx);
+
+            for selection in std::iter::once(&mut primary).chain(extra_selections.iter_mut()) {
+                *selection = shift_selection(*selection, range, new_text.len());
+            }
+        }
+        self.history.end_grouping();
+        self.silent_replace_text = was_silent;
+
+        self.selected_range = primary;
+        self.extra_selections = extra_selections;
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        cx.notify();
+    }
+
+    /// Returns the comment tokens of the language at the cursor.
+    ///
+    /// The injected language (e.g.: JavaScript in the `<script>` of HTML) is preferred,
+    /// fallback to the language of the editor if the injected language has no comment tokens.
+    fn comment_tokens(&self) -> Option<CommentTokens> {
+        let InputMode::CodeEditor {
+            highlighter,
+            language,
+            ..
+        } = &self.mode
+        else {
+            return None;
+        };
+
+        let injected_language = highlighter
+            .borrow()
+            .as_ref()
+            .map(|highlighter| highlighter.language_at(self.cursor()));
+
+        let registry = LanguageRegistry::singleton();
+        injected_language
+            .into_iter()
+            .chain(Some(language.clone()))
+            .filter_map(|name| registry.language(&name))
+            .map(|config| CommentTokens {
+                line: config.line_comment,
+                block: config.block_comment,
+            })
+            .find(|tokens| tokens.line.is_some() || tokens.block.is_some())
+    }
+}
+
+/// Shift the selection after the text in `range` is replaced by a text with `new_len`.
+///
+/// The start of a non-empty selection is kept if the text is inserted at it,
+/// so the inserted comment token is included in the selection.
+fn shift_selection(selection: Selection, range: &Range<usize>, new_len: usize) -> Selection {
+    let start = if range.is_empty() && selection.start == range.start && !selection.is_empty() {
+        selection.start
+    } else {
+        shift_offset(selection.start, range, new_len)
+    };
+    let end = shift_offset(selection.end, range, new_len);
+    (start..end).into()
+}
+
+/// Returns the edits (sorted by the offset) to toggle the comment of the `rows` in the `text`.
+///
+/// The lines are uncommented if all the non-blank lines are commented, otherwise they are commented.
+fn toggle_comment_edits(
+    text: &Rope,
+    rows: &[Range<usize>],
+    tokens: &CommentTokens,
+) -> Vec<(Range<usize>, String)> {
+    let lines = |rows: Range<usize>| {
+        rows.filter_map(|row| {
+            let line = text.slice_line(row).to_string();
+            if line.trim().is_empty() {
+                return None;
+            }
+            Some((text.line_start_offset(row), line))
+        })
+        .collect::<Vec<_>>()
+    };
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+
+    if let Some(token) = tokens.line.as_ref() {
+        let token: &str = token.as_ref();
+        let lines = rows.iter().cloned().flat_map(lines).collect::<Vec<_>>();
+        let commented = !lines.is_empty()
+            && lines
+                .iter()
+                .all(|(_, line)| line.trim_start().starts_with(token));
+
+        if commented {
+            return lines
+                .iter()
+                .map(|(offset, line)| {
+                    let start = offset + indent_of(line);
+                    let mut end = start + token.len();
+                    if line[end - offset..].starts_with(' ') {
+                        end += 1;
+                    }
+                    (start..end, String::new())
+                })
+                .collect();
+        }
+
+        // Insert the token at the same column to keep the indentation aligned.
+        let column = lines
+            .iter()
+            .map(|(_, line)| indent_of(line))
+            .min()
+            .unwrap_or_default();
+        return lines
+            .iter()
+            .map(|(offset, _)| (offset + column..offset + column, format!("{} ", token)))
+            .collect();
+    }
+
+    let Some((start_token, end_token)) = tokens.block.as_ref() else {
+        return vec![];
+    };
+    let (start_token, end_token): (&str, &str) = (start_token.as_ref(), end_token.as_ref());
+
+    // The trimmed range of each group of contiguous lines.
+    let blocks = rows
+        .iter()
+        .cloned()
+        .filter_map(|rows| {
+            let lines = lines(rows);
+            let (first_offset, first_line) = lines.first()?;
+            let (last_offset, last_line) = lines.last()?;
+            let start = first_offset + indent_of(first_line);
+            let end = last_offset + last_line.trim_end().len();
+            Some((start..end, text.slice(start..end).to_string()))
+        })
+        .collect::<Vec<_>>();
+    let commented = !blocks.is_empty()
+        && blocks.iter().all(|(_, block)| {
+            block.len() >= start_token.len() + end_token.len()
+                && block.starts_with(start_token)
+                && block.ends_with(end_token)
+        });
+
+    let mut edits = vec![];
+    for (range, block) in blocks {
+        if commented {
+            let mut start_len = start_token.len();
+            let mut end_len = end_token.len();
+            if block[start_len..].starts_with(' ') {
+                start_len += 1;
+            }
+            if block.len() >= start_len + end_len + 1
+                && block[..block.len() - end_len].ends_with(' ')
+            {
+                end_len += 1;
+            }
+            edits.push((range.start..range.start + start_len, String::new()));
+            edits.push((range.end - end_len..range.end, String::new()));
+        } else {
+            edits.push((range.start..range.start, format!("{} ", start_token)));
+            edits.push((range.end..range.end, format!(" {}", end_token)));
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::{CommentTokens, toggle_comment_edits};
+
+    #[track_caller]
+    fn assert_toggle(
+        text: &str,
+        rows: &[std::ops::Range<usize>],
+        tokens: &CommentTokens,
+        expected: &str,
+    ) {
+        let mut new_text = text.to_string();
+        for (range, new) in toggle_comment_edits(&Rope::from(text), rows, tokens)
+            .into_iter()
+            .rev()
+        {
+            new_text.replace_range(range, &new);
+        }
+        assert_eq!(new_text, expected);
+    }
+
+    #[test]
+    fn test_toggle_line_comment() {
+        let tokens = CommentTokens {
+            line: Some("//".into()),
+            block: Some(("/*".into(), "*/".into())),
+        };
+
+        assert_toggle(
+            "fn main() {\n    let a = 1;\n\n  let b = 2;\n}",
+            &[1..4],
+            &tokens,
+            "fn main() {\n  //   let a = 1;\n\n  // let b = 2;\n}",
+        );
+        assert_toggle(
+            "fn main() {\n  //   let a = 1;\n\n  //let b = 2;\n}",
+            &[1..4],
+            &tokens,
+            "fn main() {\n    let a = 1;\n\n  let b = 2;\n}",
+        );
+        // Comment all if some lines are not commented.
+        assert_toggle("// a\nb\n", &[0..2], &tokens, "// // a\n// b\n");
+        // Multiple groups of lines.
+        assert_toggle("a\nb\nc", &[0..1, 2..3], &tokens, "// a\nb\n// c");
+        // Blank lines only.
+        assert_toggle("  \n\n", &[0..2], &tokens, "  \n\n");
+    }
+
+    #[test]
+    fn test_toggle_block_comment() {
+        let tokens = CommentTokens {
+            line: None,
+            block: Some(("<!--".into(), "-->".into())),
+        };
+
+        assert_toggle(
+            "<div>\n  <p>Hello</p>\n  <p>World</p>\n</div>",
+            &[1..3],
+            &tokens,
+            "<div>\n  <!-- <p>Hello</p>\n  <p>World</p> -->\n</div>",
+        );
+        assert_toggle(
+            "<div>\n  <!-- <p>Hello</p>\n  <p>World</p> -->\n</div>",
+            &[1..3],
+            &tokens,
+            "<div>\n  <p>Hello</p>\n  <p>World</p>\n</div>",
+        );
+        assert_toggle("<!--a-->", &[0..1], &tokens, "a");
+        assert_toggle("a\nb", &[0..1, 1..2], &tokens, "<!-- a -->\n<!-- b -->");
+    }
+}
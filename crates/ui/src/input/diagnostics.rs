@@ -0,0 +1,60 @@
+use gpui::{Context, Window};
+
+use crate::{
+    highlighter::DiagnosticEntry,
+    input::{GoToNextDiagnostic, GoToPrevDiagnostic, InputState, popovers::DiagnosticPopover},
+};
+
+impl InputState {
+    pub(crate) fn on_action_go_to_next_diagnostic(
+        &mut self,
+        _: &GoToNextDiagnostic,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = self.cursor();
+        let Some(entry) = self
+            .mode
+            .diagnostics()
+            .and_then(|diagnostics| diagnostics.next_after(offset))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.go_to_diagnostic_entry(&entry, window, cx);
+    }
+
+    pub(crate) fn on_action_go_to_prev_diagnostic(
+        &mut self,
+        _: &GoToPrevDiagnostic,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = self.cursor();
+        let Some(entry) = self
+            .mode
+            .diagnostics()
+            .and_then(|diagnostics| diagnostics.prev_before(offset))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.go_to_diagnostic_entry(&entry, window, cx);
+    }
+
+    /// Move the cursor to the start of the diagnostic and show the diagnostic popover.
+    pub(crate) fn go_to_diagnostic_entry(
+        &mut self,
+        entry: &DiagnosticEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.unfold_at_row(entry.diagnostic.range.start.line as usize, cx);
+        self.move_to(entry.range.start, None, cx);
+        self.diagnostic_popover = Some(DiagnosticPopover::new(entry, cx.entity(), cx));
+        self.focus(window, cx);
+        cx.notify();
+    }
+}
@@ -0,0 +1,685 @@
+use std::{
+    hash::{DefaultHasher, Hasher as _},
+    ops::Range,
+    rc::Rc,
+};
+
+use gpui::{Context, MouseDownEvent, SharedString, Window, px};
+use ropey::Rope;
+
+use crate::input::{
+    GoToNextHunk, GoToPrevHunk, InputState, RevertHunk, RopeExt as _, ToggleHunkDiff,
+    element::{DIFF_MARKER_WIDTH, LINE_NUMBER_RIGHT_MARGIN},
+};
+
+/// The max edit distance to find the minimal diff, the changed lines are treated as one hunk if exceeded.
+const MAX_EDIT_DISTANCE: isize = 1000;
+
+/// The status of a [`DiffHunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffHunkStatus {
+    /// The lines are added.
+    Added,
+    /// The lines are modified.
+    Modified,
+    /// The lines are deleted.
+    Deleted,
+}
+
+/// A changed range of lines between the diff base and the current text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// The 0-based rows in the current text, empty if the lines are deleted.
+    pub rows: Range<usize>,
+    /// The 0-based rows in the diff base text, empty if the lines are added.
+    pub base_rows: Range<usize>,
+}
+
+impl DiffHunk {
+    pub fn status(&self) -> DiffHunkStatus {
+        if self.rows.is_empty() {
+            DiffHunkStatus::Deleted
+        } else if self.base_rows.is_empty() {
+            DiffHunkStatus::Added
+        } else {
+            DiffHunkStatus::Modified
+        }
+    }
+}
+
+/// The diff marker of a row in the gutter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct DiffRowMarker {
+    /// The status of the row if it is added or modified.
+    pub(super) status: Option<DiffHunkStatus>,
+    /// Whether there are deleted lines before the row.
+    pub(super) deleted_before: bool,
+    /// Whether there are deleted lines after the row, only for the last row.
+    pub(super) deleted_after: bool,
+}
+
+/// The diff base text of the editor, the hunks are updated on each edit.
+pub(super) struct DiffBase {
+    pub(super) text: Rope,
+    /// The hash of each line in the diff base text.
+    base_lines: Vec<u64>,
+    /// The hash of each line in the current text.
+    lines: Vec<u64>,
+    pub(super) hunks: Rc<Vec<DiffHunk>>,
+    /// The `base_rows` of the hunk that the deleted lines are displayed inline.
+    expanded: Option<Range<usize>>,
+}
+
+impl DiffBase {
+    fn new(base: Rope, text: &Rope) -> Self {
+        let mut this = Self {
+            base_lines: hash_lines(&base, 0..base.lines_len()),
+            lines: hash_lines(text, 0..text.lines_len()),
+            text: base,
+            hunks: Rc::new(vec![]),
+            expanded: None,
+        };
+        this.update_hunks();
+        this
+    }
+
+    /// Update the hunks after the `old_rows` are replaced by the `new_rows` of the `text`.
+    fn update(&mut self, text: &Rope, old_rows: Range<usize>, new_rows: Range<usize>) {
+        let end = old_rows.end.min(self.lines.len());
+        let start = old_rows.start.min(end);
+        self.lines
+            .splice(start..end, hash_lines(text, new_rows).into_iter());
+        self.update_hunks();
+    }
+
+    fn update_hunks(&mut self) {
+        self.hunks = Rc::new(diff_hunks(&self.base_lines, &self.lines));
+        // Keep the hunk expanded if its deleted lines are not changed.
+        if self
+            .expanded
+            .as_ref()
+            .is_some_and(|expanded| !self.hunks.iter().any(|hunk| &hunk.base_rows == expanded))
+        {
+            self.expanded = None;
+        }
+    }
+
+    /// Returns the expanded hunk.
+    fn expanded_hunk(&self) -> Option<&DiffHunk> {
+        let expanded = self.expanded.as_ref()?;
+        self.hunks.iter().find(|hunk| &hunk.base_rows == expanded)
+    }
+
+    /// Returns the deleted lines of the hunk in the diff base text, without the last line break.
+    fn deleted_text(&self, hunk: &DiffHunk) -> String {
+        let base_range = rows_byte_range(&self.text, &hunk.base_rows);
+        let deleted_text = self.text.slice(base_range).to_string();
+        // Remove the line break out of the deleted lines, see `rows_byte_range`.
+        if hunk.base_rows.end < self.text.lines_len() {
+            deleted_text.strip_suffix('\n')
+        } else {
+            deleted_text.strip_prefix('\n')
+        }
+        .unwrap_or(&deleted_text)
+        .to_string()
+    }
+}
+
+/// Returns the hash of each line in the `rows`.
+pub(crate) fn hash_lines(text: &Rope, rows: Range<usize>) -> Vec<u64> {
+    rows.map(|row| {
+        let mut hasher = DefaultHasher::new();
+        // Write the bytes without separators, the chunks of the same line may be split differently.
+        for chunk in text.slice_line(row).chunks() {
+            hasher.write(chunk.as_bytes());
+        }
+        hasher.finish()
+    })
+    .collect()
+}
+
+/// Returns the hunks to change the `base` lines to the `lines`.
+pub(crate) fn diff_hunks(base: &[u64], lines: &[u64]) -> Vec<DiffHunk> {
+    let prefix = base
+        .iter()
+        .zip(lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    changed_ranges(
+        &base[prefix..base.len() - suffix],
+        &lines[prefix..lines.len() - suffix],
+    )
+    .into_iter()
+    .map(|(base_rows, rows)| DiffHunk {
+        rows: rows.start + prefix..rows.end + prefix,
+        base_rows: base_rows.start + prefix..base_rows.end + prefix,
+    })
+    .collect()
+}
+
+/// Returns the changed `(old ranges, new ranges)` of the items by the Myers' diff algorithm.
+pub(crate) fn changed_ranges<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+) -> Vec<(Range<usize>, Range<usize>)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    if n == 0 && m == 0 {
+        return vec![];
+    }
+
+    let max_d = (n + m).min(MAX_EDIT_DISTANCE);
+    let offset = max_d + 1;
+    // The furthest `x` of each diagonal `k = x - y`, the index is `k + offset`.
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // The `v` of `-d..=d` diagonals before each step `d`.
+    let mut trace: Vec<Vec<isize>> = vec![];
+
+    for d in 0..=max_d {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d
+                || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize])
+            {
+                v[(offset + k + 1) as usize]
+            } else {
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+
+            if x >= n && y >= m {
+                return backtrack(&trace, n, m);
+            }
+        }
+    }
+
+    vec![(0..old.len(), 0..new.len())]
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<(Range<usize>, Range<usize>)> {
+    // The matched (old row, new row) pairs, in reverse order.
+    let mut matches = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let get = |k: isize| v[(k + d) as usize];
+            let k = x - y;
+            let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (get(prev_k), get(prev_k) - prev_k)
+        };
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    let mut ranges = vec![];
+    let (mut i, mut j) = (0, 0);
+    for (x, y) in matches
+        .into_iter()
+        .rev()
+        .chain(std::iter::once((n as usize, m as usize)))
+    {
+        if x > i || y > j {
+            ranges.push((i..x, j..y));
+        }
+        i = x + 1;
+        j = y + 1;
+    }
+    ranges
+}
+
+/// Returns the byte range of the `rows` in the `text`.
+///
+/// If the rows reach the end of the text, the range includes the line break before the rows,
+/// so that the ranges of a hunk in the diff base and the current text can be replaced by each other.
+fn rows_byte_range(text: &Rope, rows: &Range<usize>) -> Range<usize> {
+    if rows.end < text.lines_len() {
+        return text.line_start_offset(rows.start)..text.line_start_offset(rows.end);
+    }
+
+    if rows.is_empty() {
+        text.len()..text.len()
+    } else if rows.start > 0 {
+        text.line_start_offset(rows.start).saturating_sub(1)..text.len()
+    } else {
+        0..text.len()
+    }
+}
+
+impl InputState {
+    /// Set the diff base text, the changed lines against it are marked in the gutter.
+    ///
+    /// Only for [`super::InputMode::CodeEditor`] mode with line number.
+    pub fn set_diff_base(
+        &mut self,
+        text: impl Into<SharedString>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let text: SharedString = text.into();
+        self.diff_base = Some(DiffBase::new(Rope::from(text.as_str()), &self.text));
+        self.update_diff_block_rows();
+        cx.notify();
+    }
+
+    /// Clear the diff base text.
+    pub fn clear_diff_base(&mut self, _: &mut Window, cx: &mut Context<Self>) {
+        self.diff_base = None;
+        self.update_diff_block_rows();
+        cx.notify();
+    }
+
+    /// Returns the diff hunks against the diff base, sorted by the rows.
+    pub fn diff_hunks(&self) -> Rc<Vec<DiffHunk>> {
+        self.diff_base
+            .as_ref()
+            .map(|diff_base| diff_base.hunks.clone())
+            .unwrap_or_default()
+    }
+
+    /// Update the diff hunks after the text changed.
+    ///
+    /// - `old_text`: The text before change.
+    /// - `range`: The replaced range in the `old_text`.
+    /// - `new_text`: The inserted text.
+    pub(super) fn update_diff_for_edit(
+        &mut self,
+        old_text: &Rope,
+        range: &Range<usize>,
+        new_text: &str,
+    ) {
+        let Some(diff_base) = self.diff_base.as_mut() else {
+            return;
+        };
+
+        let start_row = old_text.offset_to_point(range.start).row;
+        let old_end_row = old_text.offset_to_point(range.end).row;
+        let new_end_row = self
+            .text
+            .offset_to_point((range.start + new_text.len()).min(self.text.len()))
+            .row;
+        diff_base.update(
+            &self.text,
+            start_row..old_end_row + 1,
+            start_row..new_end_row + 1,
+        );
+        self.update_diff_block_rows();
+    }
+
+    /// Display the deleted lines of the expanded hunk as the block rows above the hunk.
+    fn update_diff_block_rows(&mut self) {
+        let block_rows = self
+            .diff_base
+            .as_ref()
+            .and_then(|diff_base| diff_base.expanded_hunk())
+            .filter(|hunk| !hunk.base_rows.is_empty())
+            .map(|hunk| (hunk.rows.start, hunk.base_rows.len()));
+        self.text_wrapper.set_block_rows(block_rows);
+    }
+
+    /// Returns the row of the expanded hunk and its deleted lines, the lines are displayed above the row.
+    pub(super) fn expanded_diff_hunk_text(&self) -> Option<(usize, String)> {
+        let diff_base = self.diff_base.as_ref()?;
+        let hunk = diff_base.expanded_hunk()?;
+        if hunk.base_rows.is_empty() {
+            return None;
+        }
+
+        Some((hunk.rows.start, diff_base.deleted_text(hunk)))
+    }
+
+    /// Collapse the expanded hunk, returns true if a hunk is collapsed.
+    pub(super) fn collapse_diff_hunk(&mut self, cx: &mut Context<Self>) -> bool {
+        let Some(diff_base) = self.diff_base.as_mut() else {
+            return false;
+        };
+        if diff_base.expanded.take().is_none() {
+            return false;
+        }
+
+        self.update_diff_block_rows();
+        cx.notify();
+        true
+    }
+
+    /// Returns the diff markers of the rows for the gutter.
+    pub(super) fn diff_row_markers(&self, rows: &Range<usize>) -> Vec<DiffRowMarker> {
+        let mut markers = vec![DiffRowMarker::default(); rows.len()];
+        let Some(diff_base) = self.diff_base.as_ref() else {
+            return markers;
+        };
+
+        let last_row = self.text.lines_len().saturating_sub(1);
+        for hunk in diff_base.hunks.iter() {
+            match hunk.status() {
+                DiffHunkStatus::Deleted => {
+                    if hunk.rows.start > last_row {
+                        if rows.contains(&last_row) {
+                            markers[last_row - rows.start].deleted_after = true;
+                        }
+                    } else if rows.contains(&hunk.rows.start) {
+                        markers[hunk.rows.start - rows.start].deleted_before = true;
+                    }
+                }
+                status => {
+                    let start = hunk.rows.start.max(rows.start);
+                    let end = hunk.rows.end.min(rows.end);
+                    for row in start..end {
+                        markers[row - rows.start].status = Some(status);
+                    }
+                }
+            }
+        }
+
+        markers
+    }
+
+    /// Returns the index of the hunk at the `row`.
+    ///
+    /// The deleted hunk is at the rows before and after the deleted lines.
+    pub(super) fn diff_hunk_ix_at_row(&self, row: usize) -> Option<usize> {
+        let hunks = self.diff_base.as_ref()?.hunks.clone();
+        hunks
+            .iter()
+            .position(|hunk| hunk.rows.contains(&row))
+            .or_else(|| {
+                hunks.iter().position(|hunk| {
+                    hunk.rows.is_empty() && (hunk.rows.start == row || hunk.rows.start == row + 1)
+                })
+            })
+    }
+
+    /// Move the cursor to the start of the hunk, and expand it if a hunk is expanded.
+    pub(crate) fn go_to_diff_hunk(
+        &mut self,
+        ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let hunks = self.diff_hunks();
+        let Some(hunk) = hunks.get(ix) else {
+            return;
+        };
+
+        let row = hunk.rows.start.min(self.text.lines_len().saturating_sub(1));
+        self.unfold_at_row(row, cx);
+        if self
+            .diff_base
+            .as_ref()
+            .is_some_and(|diff_base| diff_base.expanded.is_some())
+        {
+            self.expand_diff_hunk(ix, cx);
+        }
+        self.move_to(self.text.line_start_offset(row), None, cx);
+        self.focus(window, cx);
+    }
+
+    /// Show the deleted lines of the hunk inline, above the lines of the hunk.
+    fn expand_diff_hunk(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let Some(diff_base) = self.diff_base.as_mut() else {
+            return;
+        };
+        let Some(hunk) = diff_base.hunks.get(ix) else {
+            return;
+        };
+
+        diff_base.expanded = Some(hunk.base_rows.clone());
+        self.update_diff_block_rows();
+        cx.notify();
+    }
+
+    /// Toggle to show the deleted lines of the hunk inline.
+    fn toggle_diff_hunk(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let is_expanded = self.diff_base.as_ref().is_some_and(|diff_base| {
+            diff_base
+                .hunks
+                .get(ix)
+                .is_some_and(|hunk| diff_base.expanded.as_ref() == Some(&hunk.base_rows))
+        });
+        if is_expanded {
+            self.collapse_diff_hunk(cx);
+            return;
+        }
+
+        self.expand_diff_hunk(ix, cx);
+    }
+
+    /// Revert the hunk to the diff base text.
+    pub(crate) fn revert_diff_hunk(
+        &mut self,
+        ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(diff_base) = self.diff_base.as_ref() else {
+            return;
+        };
+        let Some(hunk) = diff_base.hunks.get(ix).cloned() else {
+            return;
+        };
+
+        let base_range = rows_byte_range(&diff_base.text, &hunk.base_rows);
+        let base_text = diff_base.text.slice(base_range).to_string();
+        let range = rows_byte_range(&self.text, &hunk.rows);
+        self.replace_text_in_range_silent(
+            Some(self.range_to_utf16(&range)),
+            &base_text,
+            window,
+            cx,
+        );
+
+        let row = hunk.rows.start.min(self.text.lines_len().saturating_sub(1));
+        self.move_to(self.text.line_start_offset(row), None, cx);
+    }
+
+    pub(super) fn on_action_go_to_next_hunk(
+        &mut self,
+        _: &GoToNextHunk,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let hunks = self.diff_hunks();
+        if hunks.is_empty() {
+            return;
+        }
+
+        let row = self.text.offset_to_point(self.cursor()).row;
+        let ix = hunks
+            .iter()
+            .position(|hunk| hunk.rows.start > row)
+            .unwrap_or(0);
+        self.go_to_diff_hunk(ix, window, cx);
+    }
+
+    pub(super) fn on_action_go_to_prev_hunk(
+        &mut self,
+        _: &GoToPrevHunk,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let hunks = self.diff_hunks();
+        if hunks.is_empty() {
+            return;
+        }
+
+        let row = self.text.offset_to_point(self.cursor()).row;
+        let ix = hunks
+            .iter()
+            .rposition(|hunk| hunk.rows.start < row)
+            .unwrap_or(hunks.len() - 1);
+        self.go_to_diff_hunk(ix, window, cx);
+    }
+
+    pub(super) fn on_action_revert_hunk(
+        &mut self,
+        _: &RevertHunk,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let row = self.text.offset_to_point(self.cursor()).row;
+        if let Some(ix) = self.diff_hunk_ix_at_row(row) {
+            self.revert_diff_hunk(ix, window, cx);
+        }
+    }
+
+    pub(super) fn on_action_toggle_hunk_diff(
+        &mut self,
+        _: &ToggleHunkDiff,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let row = self.text.offset_to_point(self.cursor()).row;
+        if let Some(ix) = self.diff_hunk_ix_at_row(row) {
+            self.toggle_diff_hunk(ix, cx);
+        }
+    }
+
+    /// Toggle the hunk diff if the mouse down on the diff marker in the gutter, returns true if handled.
+    pub(super) fn on_mouse_down_diff_marker(
+        &mut self,
+        event: &MouseDownEvent,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.diff_base.is_none() || !self.mode.line_number() {
+            return false;
+        }
+        let Some(last_layout) = self.last_layout.as_ref() else {
+            return false;
+        };
+
+        let marker_left =
+            self.input_bounds.origin.x + last_layout.line_number_width - LINE_NUMBER_RIGHT_MARGIN;
+        let marker_right = marker_left + DIFF_MARKER_WIDTH + px(4.);
+        if event.position.x < marker_left || event.position.x > marker_right {
+            return false;
+        }
+
+        let offset = self.index_for_mouse_position(event.position);
+        let row = self.text.offset_to_point(offset).row;
+        let Some(ix) = self.diff_hunk_ix_at_row(row) else {
+            return false;
+        };
+
+        self.toggle_diff_hunk(ix, cx);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::{DiffBase, DiffHunk, DiffHunkStatus, diff_hunks, rows_byte_range};
+    use crate::input::RopeExt as _;
+
+    fn hunk(rows: std::ops::Range<usize>, base_rows: std::ops::Range<usize>) -> DiffHunk {
+        DiffHunk { rows, base_rows }
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        assert_eq!(diff_hunks(&[1, 2, 3], &[1, 2, 3]), vec![]);
+        assert_eq!(
+            diff_hunks(&[1, 2, 3, 4], &[1, 3, 4, 5]),
+            vec![hunk(1..1, 1..2), hunk(3..4, 4..4)]
+        );
+        assert_eq!(
+            diff_hunks(&[1, 2, 3, 4], &[1, 6, 7, 4]),
+            vec![hunk(1..3, 1..3)]
+        );
+        assert_eq!(diff_hunks(&[], &[1, 2]), vec![hunk(0..2, 0..0)]);
+        assert_eq!(
+            diff_hunks(&[1, 2, 3, 4, 5], &[0, 1, 3, 4, 6, 5]),
+            vec![hunk(0..1, 0..0), hunk(2..2, 1..2), hunk(4..5, 4..4)]
+        );
+
+        assert_eq!(hunk(1..1, 1..2).status(), DiffHunkStatus::Deleted);
+        assert_eq!(hunk(1..2, 1..1).status(), DiffHunkStatus::Added);
+        assert_eq!(hunk(1..2, 1..3).status(), DiffHunkStatus::Modified);
+    }
+
+    #[test]
+    fn test_diff_base_update() {
+        let mut text = Rope::from("a\nb\nc\nd");
+        let mut diff_base = DiffBase::new(text.clone(), &text);
+        assert_eq!(diff_base.hunks.as_ref(), &vec![]);
+
+        // Replace `b` with `x\ny`
+        text.replace(2..3, "x\ny");
+        diff_base.update(&text, 1..2, 1..3);
+        assert_eq!(diff_base.hunks.as_ref(), &vec![hunk(1..3, 1..2)]);
+
+        // Delete `x\ny\n`
+        text.replace(2..6, "");
+        diff_base.update(&text, 1..4, 1..2);
+        assert_eq!(diff_base.hunks.as_ref(), &vec![hunk(1..1, 1..2)]);
+    }
+
+    #[test]
+    fn test_diff_base_expanded_hunk() {
+        let mut text = Rope::from("a\nx\nc\nd");
+        let mut diff_base = DiffBase::new(Rope::from("a\nb\nc\nd"), &text);
+        diff_base.expanded = Some(1..2);
+        assert_eq!(diff_base.expanded_hunk(), Some(&hunk(1..2, 1..2)));
+        assert_eq!(diff_base.deleted_text(&hunk(1..2, 1..2)), "b");
+
+        // Keep expanded if the deleted lines are not changed.
+        text.replace(2..3, "y");
+        diff_base.update(&text, 1..2, 1..2);
+        assert_eq!(diff_base.expanded, Some(1..2));
+
+        // Collapse after the hunk is reverted.
+        text.replace(2..3, "b");
+        diff_base.update(&text, 1..2, 1..2);
+        assert_eq!(diff_base.expanded, None);
+        assert_eq!(diff_base.expanded_hunk(), None);
+
+        // The deleted last lines.
+        let text = Rope::from("a");
+        let diff_base = DiffBase::new(Rope::from("a\nb\nc"), &text);
+        assert_eq!(diff_base.deleted_text(&hunk(1..1, 1..3)), "b\nc");
+    }
+
+    #[test]
+    fn test_rows_byte_range() {
+        let base = Rope::from("a\nb");
+        let text = Rope::from("a");
+        // The deleted last line.
+        assert_eq!(rows_byte_range(&text, &(1..1)), 1..1);
+        assert_eq!(rows_byte_range(&base, &(1..2)), 1..3);
+
+        let mut text = Rope::from("a\nc\nd");
+        assert_eq!(rows_byte_range(&text, &(1..2)), 2..4);
+        assert_eq!(rows_byte_range(&text, &(0..3)), 0..5);
+
+        // Revert the modified line.
+        let range = rows_byte_range(&text, &(1..2));
+        text.replace(range, "b\n");
+        assert_eq!(text.to_string(), "a\nb\nd");
+        assert_eq!(text.lines_len(), 3);
+    }
+}
@@ -0,0 +1,435 @@
+use std::ops::Range;
+
+use gpui::{Context, SharedString, Window};
+use ropey::Rope;
+use sum_tree::Bias;
+use tree_sitter::Point;
+
+use crate::input::{
+    InputEvent, InputState, RopeExt as _, change::Change, multi_cursor::shift_offset,
+};
+
+/// An edit applied to the text of the [`InputState`], emitted by the [`InputEvent::Edit`] event.
+///
+/// The text in `range` of the old text is replaced by the `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputEdit {
+    /// The replaced byte range in the old text.
+    pub range: Range<usize>,
+    /// The start point (row, column in bytes) of the `range`.
+    pub start: Point,
+    /// The end point of the `range` in the old text.
+    pub old_end: Point,
+    /// The end point of the inserted `text` in the new text.
+    pub new_end: Point,
+    /// The inserted text.
+    pub text: SharedString,
+    /// The version of the text before the edit, see [`InputState::version`].
+    pub old_version: usize,
+    /// The version of the text after the edit.
+    pub new_version: usize,
+    /// The origin of the edit, None for the local edits.
+    ///
+    /// See also [`InputState::apply_remote_edits`].
+    pub origin: Option<SharedString>,
+}
+
+impl InputEdit {
+    /// The byte range of the inserted text in the new text.
+    pub fn new_range(&self) -> Range<usize> {
+        self.range.start..self.range.start + self.text.len()
+    }
+}
+
+impl InputState {
+    /// Returns the version of the text, it's increased by 1 for each edit.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Apply the edits from the other origin (e.g.: a collaborator or a file sync), in order.
+    ///
+    /// Each edit replaces the byte `range` of the text after the previous edits are applied.
+    ///
+    /// - The cursors and selections are kept at the same positions of the text around the edits.
+    /// - The edits are not added to the local undo history, the undo only reverts the local changes,
+    ///   the local changes overlapped by the edits are removed from the history.
+    /// - The edits are kept as an undo step of the `origin`, see [`Self::undo_remote_edits`].
+    /// - The [`InputEvent::Edit`] events are emitted with the `origin`.
+    /// - The edits are applied in the read-only mode.
+    pub fn apply_remote_edits<T: AsRef<str>>(
+        &mut self,
+        origin: impl Into<SharedString>,
+        edits: impl IntoIterator<Item = (Range<usize>, T)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let origin: SharedString = origin.into();
+        let changes = self.apply_edits_of_origin(origin.clone(), edits, window, cx);
+        if !changes.is_empty() {
+            self.remote_undos.entry(origin).or_default().push(changes);
+        }
+    }
+
+    /// Undo the last [`Self::apply_remote_edits`] of the `origin`, returns false if nothing to undo.
+    ///
+    /// The reverted edits are applied as the edits of the `origin`, the changes made after them
+    /// (local or the other origins) are kept. The edits overlapped by the later changes can't be undone.
+    pub fn undo_remote_edits(
+        &mut self,
+        origin: impl Into<SharedString>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let origin: SharedString = origin.into();
+        let Some(changes) = self
+            .remote_undos
+            .get_mut(&origin)
+            .and_then(|undos| undos.pop())
+        else {
+            return false;
+        };
+
+        let edits = changes
+            .into_iter()
+            .rev()
+            .map(|change| (change.new_range.into(), change.old_text))
+            .collect::<Vec<(Range<usize>, String)>>();
+        self.apply_edits_of_origin(origin, edits, window, cx);
+        true
+    }
+
+    /// Apply the `edits` of the `origin`, returns the applied changes.
+    fn apply_edits_of_origin<T: AsRef<str>>(
+        &mut self,
+        origin: SharedString,
+        edits: impl IntoIterator<Item = (Range<usize>, T)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Vec<Change> {
+        let was_disabled = self.disabled;
+        let was_read_only = self.read_only;
+        self.disabled = false;
+        self.read_only = false;
+        self.history.ignore = true;
+        self.edit_origin = Some(origin);
+
+        let mut changes = vec![];
+        for (range, new_text) in edits {
+            let new_text = new_text.as_ref();
+            let start = self.text.clip_offset(range.start, Bias::Left);
+            let end = self.text.clip_offset(range.end, Bias::Right).max(start);
+            let range = start..end;
+
+            let version = self.version;
+            let selected_range = self.selected_range;
+            let ime_marked_range = self.ime_marked_range;
+            let old_text = self.text.slice(range.clone()).to_string();
+            self.replace_text_in_range_silent(
+                Some(self.range_to_utf16(&range)),
+                new_text,
+                window,
+                cx,
+            );
+            // The edit is rejected by the validation.
+            if self.version == version {
+                self.selected_range = selected_range;
+                self.ime_marked_range = ime_marked_range;
+                continue;
+            }
+
+            let new_len = new_text.len();
+            changes.push(Change::new(
+                range.clone(),
+                &old_text,
+                range.start..range.start + new_len,
+                new_text,
+            ));
+            self.selected_range.start = shift_offset(selected_range.start, &range, new_len);
+            self.selected_range.end = shift_offset(selected_range.end, &range, new_len);
+            self.ime_marked_range = ime_marked_range.map(|marked_range| {
+                (shift_offset(marked_range.start, &range, new_len)
+                    ..shift_offset(marked_range.end, &range, new_len))
+                    .into()
+            });
+            self.history
+                .undos_mut()
+                .retain_mut(|change| change.shift(&range, new_len, true));
+            self.history
+                .redos_mut()
+                .retain_mut(|change| change.shift(&range, new_len, false));
+        }
+
+        self.edit_origin = None;
+        self.history.ignore = false;
+        self.disabled = was_disabled;
+        self.read_only = was_read_only;
+        self.update_preferred_column();
+        cx.notify();
+        changes
+    }
+
+    /// Shift the undo steps of the remote edits after the text in `range` is replaced by a text with `new_len`.
+    ///
+    /// The steps of the origin of the edit are not shifted, they are undone in the reverse order.
+    pub(super) fn shift_remote_undos(&mut self, range: &Range<usize>, new_len: usize) {
+        for (origin, undos) in self.remote_undos.iter_mut() {
+            if self.edit_origin.as_ref() == Some(origin) {
+                continue;
+            }
+
+            for changes in undos.iter_mut() {
+                changes.retain_mut(|change| change.shift(range, new_len, true));
+            }
+            undos.retain(|changes| !changes.is_empty());
+        }
+    }
+
+    /// Increase the version and emit the [`InputEvent::Edit`] for the text in `range` of the
+    /// `old_text` replaced by the `new_text`, must be called after the `self.text` is updated.
+    pub(super) fn emit_edit(
+        &mut self,
+        old_text: &Rope,
+        range: &Range<usize>,
+        new_text: &str,
+        cx: &mut Context<Self>,
+    ) {
+        let old_version = self.version;
+        self.version += 1;
+
+        let edit = InputEdit {
+            range: range.clone(),
+            start: old_text.offset_to_point(range.start),
+            old_end: old_text.offset_to_point(range.end),
+            new_end: self.text.offset_to_point(range.start + new_text.len()),
+            text: new_text.to_string().into(),
+            old_version,
+            new_version: self.version,
+            origin: self.edit_origin.clone(),
+        };
+        cx.emit(InputEvent::Edit { edit });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use gpui::{
+        AppContext as _, Entity, EntityInputHandler as _, TestAppContext, VisualTestContext,
+    };
+    use tree_sitter::Point;
+
+    use super::InputEdit;
+    use crate::{
+        history::History,
+        input::{InputEvent, InputState, Undo},
+    };
+
+    fn subscribe_edits(
+        state: &Entity<InputState>,
+        cx: &mut VisualTestContext,
+    ) -> Rc<RefCell<Vec<InputEdit>>> {
+        let edits = Rc::new(RefCell::new(vec![]));
+        cx.update(|_, cx| {
+            let edits = edits.clone();
+            cx.subscribe(state, move |_, event: &InputEvent, _| {
+                if let InputEvent::Edit { edit } = event {
+                    edits.borrow_mut().push(edit.clone());
+                }
+            })
+            .detach();
+        });
+        edits
+    }
+
+    #[gpui::test]
+    fn test_apply_remote_edits_selections(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| InputState::new(window, cx).default_value("hello world"))
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // Select `world` in the IME composition.
+                state.selected_range = (6..11).into();
+                state.ime_marked_range = Some((6..11).into());
+
+                state.apply_remote_edits(
+                    "remote",
+                    [(0..5, "hi"), (3..3, "big "), (8..9, "")],
+                    window,
+                    cx,
+                );
+                assert_eq!(state.value(), "hi big wrld");
+                assert_eq!(state.selected_range, (7..11).into());
+                assert_eq!(state.ime_marked_range, Some((7..11).into()));
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_apply_remote_edits_history(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| InputState::new(window, cx).default_value("hello world"))
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // Every change is a new undo step without the grouping.
+                state.history = History::new().group_interval(std::time::Duration::ZERO);
+                state.replace_text_in_range(Some(0..0), "A", window, cx);
+                state.replace_text_in_range(Some(12..12), "B", window, cx);
+                assert_eq!(state.value(), "Ahello worldB");
+                assert_eq!(state.history.undos().len(), 2);
+
+                // The local change of `A` is overlapped and dropped, the change of `B` is shifted.
+                state.apply_remote_edits("remote", [(0..1, "X"), (0..0, "123")], window, cx);
+                assert_eq!(state.value(), "123Xhello worldB");
+                assert_eq!(state.history.undos().len(), 1);
+                assert_eq!(state.history.undos()[0].new_text, "B");
+                assert_eq!(state.history.undos()[0].new_range, (15..16).into());
+
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "123Xhello world");
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "123Xhello world");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_undo_remote_edits(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| InputState::new(window, cx).default_value("hello world"))
+        });
+        let edits = subscribe_edits(&state, cx);
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.history = History::new().group_interval(std::time::Duration::ZERO);
+                state.apply_remote_edits("a", [(0..5, "hi")], window, cx);
+                state.apply_remote_edits("b", [(8..8, "!")], window, cx);
+                state.replace_text_in_range(Some(0..0), "> ", window, cx);
+                assert_eq!(state.value(), "> hi world!");
+
+                // Only the edits of the origin are undone, shifted by the later changes.
+                assert!(state.undo_remote_edits("a", window, cx));
+                assert_eq!(state.value(), "> hello world!");
+                assert!(!state.undo_remote_edits("a", window, cx));
+
+                // The local undo only reverts the local change.
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "hello world!");
+
+                assert!(state.undo_remote_edits("b", window, cx));
+                assert_eq!(state.value(), "hello world");
+            });
+        });
+        assert_eq!(edits.borrow()[3].origin, Some("a".into()));
+        assert_eq!(edits.borrow()[5].origin, Some("b".into()));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // The remote edit overlapped by a local change can't be undone.
+                state.apply_remote_edits("a", [(0..5, "hi")], window, cx);
+                state.replace_text_in_range(Some(0..2), "yo", window, cx);
+                assert!(!state.undo_remote_edits("a", window, cx));
+                assert_eq!(state.value(), "yo world");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_apply_remote_edits_events(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state =
+            cx.update(|window, cx| cx.new(|cx| InputState::new(window, cx).default_value("hello")));
+        let edits = subscribe_edits(&state, cx);
+        let version = state.read_with(cx, |state, _| state.version());
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.apply_remote_edits("remote", [(5..5, " world"), (0..1, "H")], window, cx);
+                state.replace_text_in_range(Some(0..0), "!", window, cx);
+            });
+        });
+
+        assert_eq!(
+            edits.borrow().as_slice(),
+            &[
+                InputEdit {
+                    range: 5..5,
+                    start: Point::new(0, 5),
+                    old_end: Point::new(0, 5),
+                    new_end: Point::new(0, 11),
+                    text: " world".into(),
+                    old_version: version,
+                    new_version: version + 1,
+                    origin: Some("remote".into()),
+                },
+                InputEdit {
+                    range: 0..1,
+                    start: Point::new(0, 0),
+                    old_end: Point::new(0, 1),
+                    new_end: Point::new(0, 1),
+                    text: "H".into(),
+                    old_version: version + 1,
+                    new_version: version + 2,
+                    origin: Some("remote".into()),
+                },
+                InputEdit {
+                    range: 0..0,
+                    start: Point::new(0, 0),
+                    old_end: Point::new(0, 0),
+                    new_end: Point::new(0, 1),
+                    text: "!".into(),
+                    old_version: version + 2,
+                    new_version: version + 3,
+                    origin: None,
+                },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn test_apply_remote_edits_masked(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .mask_pattern("(AA)999-999")
+                    .default_value("(AB)123-")
+            })
+        });
+        let edits = subscribe_edits(&state, cx);
+        let version = state.read_with(cx, |state, _| state.version());
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // The `x` is rejected by the mask pattern.
+                state.apply_remote_edits("remote", [(8..8, "x"), (8..8, "4")], window, cx);
+                assert_eq!(state.value(), "(AB)123-4");
+                assert_eq!(state.version(), version + 1);
+            });
+        });
+
+        // The whole text is replaced by the masked text.
+        assert_eq!(
+            edits.borrow().as_slice(),
+            &[InputEdit {
+                range: 0..8,
+                start: Point::new(0, 0),
+                old_end: Point::new(0, 8),
+                new_end: Point::new(0, 9),
+                text: "(AB)123-4".into(),
+                old_version: version,
+                new_version: version + 1,
+                origin: Some("remote".into()),
+            }]
+        );
+    }
+}
@@ -10,8 +10,14 @@ use ropey::Rope;
 use smallvec::SmallVec;
 
 use crate::{
-    ActiveTheme as _, Colorize, Root,
-    input::{RopeExt as _, blink_cursor::CURSOR_WIDTH, text_wrapper::LineLayout},
+    ActiveTheme as _, Colorize, IconName, IconNamed as _, Root,
+    highlighter::DiagnosticSeverity,
+    input::{
+        RopeExt as _,
+        blink_cursor::CURSOR_WIDTH,
+        diff::{DiffHunkStatus, DiffRowMarker},
+        text_wrapper::LineLayout,
+    },
 };
 
 use super::{InlineBadge, InputState, LastLayout, mode::InputMode};
@@ -19,6 +25,12 @@ use super::{InlineBadge, InputState, LastLayout, mode::InputMode};
 const BOTTOM_MARGIN_ROWS: usize = 3;
 pub(super) const RIGHT_MARGIN: Pixels = px(10.);
 pub(super) const LINE_NUMBER_RIGHT_MARGIN: Pixels = px(10.);
+/// The width of the fold marker in the line number area.
+pub(super) const FOLD_MARKER_WIDTH: Pixels = px(12.);
+/// The width of the diagnostic marker in the line number area.
+pub(super) const DIAGNOSTIC_MARKER_WIDTH: Pixels = px(14.);
+/// The width of the diff marker, painted at the right of the line number area.
+pub(super) const DIFF_MARKER_WIDTH: Pixels = px(3.);
 
 pub(super) struct TextElement {
     pub(crate) state: Entity<InputState>,
@@ -44,7 +56,10 @@ impl TextElement {
             let state = self.state.clone();
 
             move |event: &MouseMoveEvent, _, window, cx| {
-                if event.pressed_button == Some(MouseButton::Left) {
+                if matches!(
+                    event.pressed_button,
+                    Some(MouseButton::Left) | Some(MouseButton::Middle)
+                ) {
                     state.update(cx, |state, cx| {
                         state.on_drag_move(event, window, cx);
                     });
@@ -239,18 +254,20 @@ impl TextElement {
             }
 
             // cursor bounds
-            let cursor_height = match state.size {
-                crate::Size::Large => 1.,
-                crate::Size::Small => 0.75,
-                _ => 0.85,
-            } * line_height;
+            let cursor_height = cursor_height(state.size, line_height);
+            // The block cursor for the Vim normal mode.
+            let cursor_width = if state.vim_block_cursor() {
+                last_layout.space_width.max(CURSOR_WIDTH)
+            } else {
+                CURSOR_WIDTH
+            };
 
             cursor_bounds = Some(Bounds::new(
                 point(
                     bounds.left() + cursor_pos.x + line_number_width + scroll_offset.x,
                     bounds.top() + cursor_pos.y + ((line_height - cursor_height) / 2.),
                 ),
-                size(CURSOR_WIDTH, cursor_height),
+                size(cursor_width, cursor_height),
             ));
         }
 
@@ -465,6 +482,29 @@ impl TextElement {
         Self::layout_match_range(symbol_range, last_layout, bounds)
     }
 
+    fn layout_rename_preview(
+        &self,
+        last_layout: &LastLayout,
+        bounds: &Bounds<Pixels>,
+        cx: &mut App,
+    ) -> Vec<Path<Pixels>> {
+        let rename_popover = self.state.read(cx).rename_popover.clone();
+        let Some(ranges) = rename_popover.and_then(|popover| {
+            popover
+                .read(cx)
+                .preview
+                .as_ref()
+                .map(|preview| preview.ranges.clone())
+        }) else {
+            return vec![];
+        };
+
+        ranges
+            .into_iter()
+            .filter_map(|range| Self::layout_match_range(range, last_layout, bounds))
+            .collect()
+    }
+
     fn layout_document_colors(
         &self,
         document_colors: &[(Range<usize>, Hsla)],
@@ -521,6 +561,84 @@ impl TextElement {
         Self::layout_match_range(range, &last_layout, bounds)
     }
 
+    /// Layout the extra selections of multiple cursors.
+    fn layout_extra_selections(
+        &self,
+        last_layout: &LastLayout,
+        bounds: &Bounds<Pixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<Path<Pixels>> {
+        let state = self.state.read(cx);
+        if !state.focus_handle.is_focused(window) {
+            return vec![];
+        }
+
+        let visible_range_offset = &last_layout.visible_range_offset;
+        state
+            .extra_selections
+            .iter()
+            .filter(|selection| !selection.is_empty())
+            .filter_map(|selection| {
+                let range = selection.start.max(visible_range_offset.start)
+                    ..selection.end.min(visible_range_offset.end);
+                Self::layout_match_range(range, last_layout, bounds)
+            })
+            .collect()
+    }
+
+    /// Layout the extra cursors of multiple cursors.
+    ///
+    /// The `bounds` must have applied the scroll offset.
+    fn layout_extra_cursors(
+        &self,
+        last_layout: &LastLayout,
+        bounds: &Bounds<Pixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Vec<Bounds<Pixels>> {
+        let state = self.state.read(cx);
+        if state.extra_selections.is_empty() || !state.focus_handle.is_focused(window) {
+            return vec![];
+        }
+
+        let line_height = last_layout.line_height;
+        let cursor_height = cursor_height(state.size, line_height);
+        let visible_range_offset = &last_layout.visible_range_offset;
+
+        let mut cursors = vec![];
+        for selection in state.extra_selections.iter() {
+            let offset = selection.end;
+            if offset < visible_range_offset.start || offset > visible_range_offset.end {
+                continue;
+            }
+
+            let mut prev_lines_offset = visible_range_offset.start;
+            let mut offset_y = last_layout.visible_top;
+            for line in last_layout.lines.iter() {
+                if let Some(pos) =
+                    line.position_for_index(offset.saturating_sub(prev_lines_offset), last_layout)
+                {
+                    cursors.push(Bounds::new(
+                        bounds.origin
+                            + point(
+                                last_layout.line_number_width + pos.x,
+                                offset_y + pos.y + (line_height - cursor_height) / 2.,
+                            ),
+                        size(CURSOR_WIDTH, cursor_height),
+                    ));
+                    break;
+                }
+
+                offset_y += line.size(line_height).height;
+                // +1 for skip the last `\n`
+                prev_lines_offset += line.len() + 1;
+            }
+        }
+
+        cursors
+    }
+
     /// Calculate the visible range of lines in the viewport.
     ///
     /// Returns
@@ -575,13 +693,7 @@ impl TextElement {
         style: &TextStyle,
         window: &mut Window,
     ) -> (Pixels, usize) {
-        let total_lines = text.lines_len();
-        let line_number_len = match total_lines {
-            0..=9999 => 5,
-            10000..=99999 => 6,
-            100000..=999999 => 7,
-            _ => 8,
-        };
+        let line_number_len = line_number_len(text.lines_len());
 
         let line_number_width = if state.mode.line_number() {
             let empty_line_number = window.text_system().shape_line(
@@ -598,7 +710,23 @@ impl TextElement {
                 None,
             );
 
-            empty_line_number.width + px(6.) + LINE_NUMBER_RIGHT_MARGIN
+            let fold_marker_width = if state.mode.has_folding() {
+                FOLD_MARKER_WIDTH
+            } else {
+                px(0.)
+            };
+
+            let diagnostic_marker_width = if state.mode.has_diagnostic_markers() {
+                DIAGNOSTIC_MARKER_WIDTH
+            } else {
+                px(0.)
+            };
+
+            diagnostic_marker_width
+                + empty_line_number.width
+                + px(6.)
+                + fold_marker_width
+                + LINE_NUMBER_RIGHT_MARGIN
         } else {
             px(0.)
         };
@@ -691,6 +819,75 @@ impl TextElement {
         (first_line, ghost_lines)
     }
 
+    /// Layout the deleted lines of the expanded diff hunk, returns the row that they are displayed above.
+    fn layout_diff_block(
+        state: &InputState,
+        visible_range: &Range<usize>,
+        font_size: Pixels,
+        window: &mut Window,
+        cx: &App,
+    ) -> Option<(usize, Vec<ShapedLine>)> {
+        let (row, deleted_text) = state.expanded_diff_hunk_text()?;
+        // The block of the row after the visible range is displayed after the last visible line.
+        if row < visible_range.start || row > visible_range.end {
+            return None;
+        }
+        if state.text_wrapper.is_row_folded(row) {
+            return None;
+        }
+
+        let font = window.text_style().font();
+        let lines = deleted_text
+            .split('\n')
+            .map(|line| {
+                let text: SharedString = line.to_string().into();
+                let run = TextRun {
+                    len: text.len(),
+                    font: font.clone(),
+                    color: cx.theme().foreground,
+                    background_color: None,
+                    underline: None,
+                    strikethrough: None,
+                };
+                window
+                    .text_system()
+                    .shape_line(text, font_size, &[run], None)
+            })
+            .collect();
+
+        Some((row, lines))
+    }
+
+    /// Paint the deleted lines of the expanded diff hunk at the `origin` of the text.
+    fn paint_diff_block(
+        lines: &[ShapedLine],
+        origin: Point<Pixels>,
+        bounds: &Bounds<Pixels>,
+        line_height: Pixels,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        window.paint_quad(fill(
+            Bounds::from_corners(
+                origin,
+                point(bounds.right(), origin.y + line_height * lines.len() as f32),
+            ),
+            cx.theme().red.opacity(0.15),
+        ));
+
+        for (ix, line) in lines.iter().enumerate() {
+            _ = line.paint(
+                point(origin.x, origin.y + line_height * ix as f32),
+                line_height,
+                TextAlign::Left,
+                None,
+                window,
+                cx,
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn layout_lines(
         state: &InputState,
         display_text: &Rope,
@@ -698,6 +895,7 @@ impl TextElement {
         font_size: Pixels,
         runs: &[TextRun],
         bg_segments: &[(Range<usize>, Hsla)],
+        inlay_hints: &[(usize, SharedString, TextRun)],
         window: &mut Window,
     ) -> Vec<LineLayout> {
         let is_single_line = state.mode.is_single_line();
@@ -757,10 +955,20 @@ impl TextElement {
 
             debug_assert_eq!(line_item.len(), line.len());
 
+            if line_item.folded {
+                lines.push(LineLayout::folded(line.len()));
+                // +1 for the `\n`
+                offset += line.len() + 1;
+                continue;
+            }
+
             let mut line_layout = LineLayout::new();
             let mut wrapped_lines = SmallVec::with_capacity(1);
+            let mut line_inlays = SmallVec::with_capacity(1);
+            let line_start = visible_range_offset.start + offset;
 
-            for range in &line_item.wrapped_lines {
+            for (i, range) in line_item.wrapped_lines.iter().enumerate() {
+                let is_last = i + 1 == line_item.wrapped_lines.len();
                 let line_runs = runs_for_range(runs, offset, &range);
                 let line_runs = if bg_segments.is_empty() {
                     line_runs
@@ -781,15 +989,59 @@ impl TextElement {
                     )
                 };
 
-                let sub_line: SharedString = line[range.clone()].to_string().into();
+                // The inlay hints at the end of soft wrapped line is displayed at the start of next line.
+                let range_inlays = inlay_hints
+                    .iter()
+                    .filter(|(ix, _, _)| {
+                        *ix >= line_start + range.start
+                            && (*ix < line_start + range.end
+                                || (is_last && *ix == line_start + range.end))
+                    })
+                    .map(|(ix, label, run)| (ix - line_start - range.start, label, run))
+                    .collect::<Vec<_>>();
+
+                let sub_line: SharedString = if range_inlays.is_empty() {
+                    line[range.clone()].to_string().into()
+                } else {
+                    let sub_line = &line[range.clone()];
+                    let mut text = String::with_capacity(sub_line.len());
+                    let mut last_ix = 0;
+                    for (ix, label, _) in &range_inlays {
+                        text.push_str(&sub_line[last_ix..*ix]);
+                        text.push_str(label);
+                        last_ix = *ix;
+                    }
+                    text.push_str(&sub_line[last_ix..]);
+                    text.into()
+                };
+                let line_runs = if range_inlays.is_empty() {
+                    line_runs
+                } else {
+                    insert_inlay_runs(
+                        &line_runs,
+                        &range_inlays
+                            .iter()
+                            .map(|(ix, _, run)| (*ix, (*run).clone()))
+                            .collect::<Vec<_>>(),
+                    )
+                };
+
                 let shaped_line = window
                     .text_system()
                     .shape_line(sub_line, font_size, &line_runs, None);
 
                 wrapped_lines.push(shaped_line);
+                line_inlays.push(
+                    range_inlays
+                        .iter()
+                        .map(|(ix, label, _)| (*ix, label.len()))
+                        .collect(),
+                );
             }
 
+            line_layout.set_inlays(line_inlays);
             line_layout.set_wrapped_lines(wrapped_lines);
+            line_layout.set_block_rows(line_item.block_rows);
             lines.push(line_layout);
 
             // +1 for the `\n`
@@ -843,6 +1095,14 @@ impl TextElement {
             offset = range.end;
         }
 
+        // The semantic tokens are layered over the syntax highlighting.
+        let semantic_styles = state
+            .lsp
+            .semantic_token_styles(&visible_byte_range, &cx.theme().highlight_theme);
+        if !semantic_styles.is_empty() {
+            styles = gpui::combine_highlights(styles, semantic_styles).collect();
+        }
+
         let diagnostic_styles = diagnostics.styles_for_range(&visible_byte_range, cx);
 
         // hover definition style
@@ -857,6 +1117,16 @@ impl TextElement {
     }
 }
 
+/// Returns the chars length of the line numbers for the total lines.
+pub(super) fn line_number_len(total_lines: usize) -> usize {
+    match total_lines {
+        0..=9999 => 5,
+        10000..=99999 => 6,
+        100000..=999999 => 7,
+        _ => 8,
+    }
+}
+
 pub(super) struct PrepaintState {
     /// The lines of entire lines.
     last_layout: LastLayout,
@@ -864,6 +1134,16 @@ pub(super) struct PrepaintState {
     ///
     /// The child is the soft lines.
     line_numbers: Option<Vec<SmallVec<[ShapedLine; 1]>>>,
+    /// The fold markers of the visible lines, `Some(true)` means the line is folded.
+    fold_markers: Vec<Option<bool>>,
+    /// The placeholder to paint after the folded line.
+    fold_placeholder: Option<ShapedLine>,
+    /// The most severe diagnostic of the visible lines.
+    diagnostic_markers: Vec<Option<DiagnosticSeverity>>,
+    /// The diff markers of the visible lines against the diff base.
+    diff_markers: Vec<DiffRowMarker>,
+    /// The row and the shaped deleted lines of the expanded diff hunk, painted above the row.
+    diff_block: Option<(usize, Vec<ShapedLine>)>,
     /// Size of the scrollable area by entire lines.
     scroll_size: Size<Pixels>,
     cursor_bounds: Option<Bounds<Pixels>>,
@@ -871,8 +1151,13 @@ pub(super) struct PrepaintState {
     /// row index (zero based), no wrap, same line as the cursor.
     current_row: Option<usize>,
     selection_path: Option<Path<Pixels>>,
+    /// The selection paths of the extra cursors.
+    extra_selection_paths: Vec<Path<Pixels>>,
+    /// The bounds of the extra cursors, already applied scroll offset.
+    extra_cursor_bounds: Vec<Bounds<Pixels>>,
     hover_highlight_path: Option<Path<Pixels>>,
     search_match_paths: Vec<(Path<Pixels>, bool)>,
+    rename_preview_paths: Vec<Path<Pixels>>,
     document_color_paths: Vec<(Path<Pixels>, Hsla)>,
     hover_definition_hitbox: Option<Hitbox>,
     indent_guides_path: Option<Path<Pixels>>,
@@ -962,13 +1247,26 @@ impl Element for TextElement {
         self.state.update(cx, |state, cx| {
             state.text_wrapper.set_font(font, text_size, cx);
             state.text_wrapper.prepare_if_need(&state.text, cx);
+            if state.mode.has_folding() && state.mode.line_number() {
+                state.foldable_ranges();
+            }
         });
 
-        let state = self.state.read(cx);
         let line_height = window.line_height();
-
-        let (visible_range, visible_top) =
-            self.calculate_visible_range(&state, line_height, bounds.size.height);
+        let (mut visible_range, mut visible_top) =
+            self.calculate_visible_range(self.state.read(cx), line_height, bounds.size.height);
+
+        // In the large file mode, the soft wrap is only computed for the visible lines (and a page around them).
+        let wrapped = self.state.update(cx, |state, cx| {
+            let margin = visible_range.len();
+            let rows = visible_range.start.saturating_sub(margin)..visible_range.end + margin;
+            state.text_wrapper.wrap_rows(rows, cx)
+        });
+        let state = self.state.read(cx);
+        if wrapped {
+            (visible_range, visible_top) =
+                self.calculate_visible_range(&state, line_height, bounds.size.height);
+        }
         let visible_start_offset = state.text.line_start_offset(visible_range.start);
         let visible_end_offset = state
             .text
@@ -1026,6 +1324,15 @@ impl Element for TextElement {
             cursor_bounds: None,
             text_align: state.text_align,
             content_width: bounds.size.width,
+            space_width: window
+                .text_system()
+                .advance(
+                    window.text_system().resolve_font(&style.font()),
+                    text_size,
+                    ' ',
+                )
+                .map(|size| size.width)
+                .unwrap_or_default(),
         };
 
         let run = TextRun {
@@ -1099,6 +1406,26 @@ impl Element for TextElement {
         let document_colors = state
             .lsp
             .document_colors_for_range(&text, &last_layout.visible_range);
+        let inlay_hints = if is_empty || state.masked {
+            vec![]
+        } else {
+            state
+                .lsp
+                .inlay_hints_for_range(&text, &last_layout.visible_range)
+                .into_iter()
+                .map(|(ix, label)| {
+                    let run = TextRun {
+                        len: label.len(),
+                        font: style.font(),
+                        color: cx.theme().muted_foreground,
+                        background_color: Some(cx.theme().muted),
+                        underline: None,
+                        strikethrough: None,
+                    };
+                    (ix, label, run)
+                })
+                .collect::<Vec<_>>()
+        };
         let lines = Self::layout_lines(
             &state,
             &display_text,
@@ -1106,6 +1433,7 @@ impl Element for TextElement {
             text_size,
             &runs,
             &document_colors,
+            &inlay_hints,
             window,
         );
 
@@ -1144,7 +1472,9 @@ impl Element for TextElement {
         let ghost_line_count = ghost_lines.len();
         let ghost_lines_height = ghost_line_count as f32 * line_height;
 
-        let total_wrapped_lines = state.text_wrapper.len();
+        let diff_block =
+            Self::layout_diff_block(state, &last_layout.visible_range, text_size, window, cx);
+
         let empty_bottom_height = if state.mode.is_code_editor() {
             bounds
                 .size
@@ -1161,7 +1491,7 @@ impl Element for TextElement {
             } else {
                 longest_line_width
             },
-            (total_wrapped_lines as f32 * line_height + empty_bottom_height + ghost_lines_height)
+            (state.text_wrapper.height(line_height) + empty_bottom_height + ghost_lines_height)
                 .max(bounds.size.height),
         );
 
@@ -1208,7 +1538,10 @@ impl Element for TextElement {
 
         let search_match_paths = self.layout_search_matches(&last_layout, &mut bounds, cx);
         let selection_path = self.layout_selections(&last_layout, &mut bounds, window, cx);
+        let extra_selection_paths = self.layout_extra_selections(&last_layout, &bounds, window, cx);
+        let extra_cursor_bounds = self.layout_extra_cursors(&last_layout, &bounds, window, cx);
         let hover_highlight_path = self.layout_hover_highlight(&last_layout, &mut bounds, cx);
+        let rename_preview_paths = self.layout_rename_preview(&last_layout, &bounds, cx);
         let document_color_paths =
             self.layout_document_colors(&document_colors, &last_layout, &bounds);
 
@@ -1246,6 +1579,11 @@ impl Element for TextElement {
             // build line numbers
             for (ix, line) in last_layout.lines.iter().enumerate() {
                 let ix = last_layout.visible_range.start + ix;
+                if state.text_wrapper.is_row_folded(ix) {
+                    line_numbers.push(SmallVec::new());
+                    continue;
+                }
+
                 let line_no = format!("{:>width$}", ix + 1, width = line_number_len).into();
 
                 let runs = if current_row == Some(ix) {
@@ -1270,6 +1608,64 @@ impl Element for TextElement {
             None
         };
 
+        let fold_markers = if state.mode.has_folding() {
+            (last_layout.visible_range.start..last_layout.visible_range.end)
+                .map(|row| {
+                    if state.is_folded_at_row(row) {
+                        Some(true)
+                    } else if !state.text_wrapper.is_row_folded(row)
+                        && state.foldable_range_at(row).is_some()
+                    {
+                        Some(false)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let diagnostic_markers = match state.mode.diagnostics() {
+            Some(diagnostics) if state.mode.line_number() && !diagnostics.is_empty() => {
+                let visible_range = last_layout.visible_range.clone();
+                let mut markers: Vec<Option<DiagnosticSeverity>> = vec![None; visible_range.len()];
+                for entry in diagnostics.iter() {
+                    let row = entry.diagnostic.range.start.line as usize;
+                    if !visible_range.contains(&row) {
+                        continue;
+                    }
+
+                    let marker = &mut markers[row - visible_range.start];
+                    if marker.is_none_or(|severity| severity.level() < entry.severity.level()) {
+                        *marker = Some(entry.severity);
+                    }
+                }
+                markers
+            }
+            _ => vec![],
+        };
+        let diff_markers = if state.diff_base.is_some() && state.mode.line_number() {
+            state.diff_row_markers(&last_layout.visible_range)
+        } else {
+            vec![]
+        };
+        let fold_placeholder = (!state.folded_ranges.is_empty()).then(|| {
+            let text: SharedString = " ⋯ ".into();
+            window.text_system().shape_line(
+                text.clone(),
+                text_size,
+                &[TextRun {
+                    len: text.len(),
+                    font: style.font(),
+                    color: cx.theme().muted_foreground,
+                    background_color: Some(cx.theme().muted),
+                    underline: None,
+                    strikethrough: None,
+                }],
+                None,
+            )
+        });
+
         let hover_definition_hitbox = self.layout_hover_definition_hitbox(state, window, cx);
         let indent_guides_path =
             self.layout_indent_guides(state, &bounds, &last_layout, &text_style, window);
@@ -1279,11 +1675,19 @@ impl Element for TextElement {
             last_layout,
             scroll_size,
             line_numbers,
+            fold_markers,
+            fold_placeholder,
+            diagnostic_markers,
+            diff_markers,
+            diff_block,
             cursor_bounds,
             cursor_scroll_offset,
             current_row,
             selection_path,
+            extra_selection_paths,
+            extra_cursor_bounds,
             search_match_paths,
+            rename_preview_paths,
             hover_highlight_path,
             hover_definition_hitbox,
             document_color_paths,
@@ -1366,6 +1770,12 @@ impl Element for TextElement {
             }
         }
         let active_line_color = cx.theme().highlight_theme.style.editor_active_line;
+        // The line numbers are after the diagnostic markers.
+        let line_number_left = if self.state.read(cx).mode.has_diagnostic_markers() {
+            DIAGNOSTIC_MARKER_WIDTH
+        } else {
+            px(0.)
+        };
 
         window.with_content_mask(
             Some(ContentMask {
@@ -1381,6 +1791,9 @@ impl Element for TextElement {
                     for (ix, lines) in line_numbers.iter().enumerate() {
                         let row = visible_range_start + ix;
                         let is_active = prepaint.current_row == Some(row);
+                        if let Some(line) = prepaint.last_layout.lines.get(ix) {
+                            offset_y += line.block_height(line_height);
+                        }
                         let p = point(input_bounds.origin.x, origin.y + offset_y);
                         let height = line_height * lines.len() as f32;
                         // Paint the current line background
@@ -1412,9 +1825,16 @@ impl Element for TextElement {
                         }
                     }
 
+                    for path in prepaint.rename_preview_paths.drain(..) {
+                        window.paint_path(path, secondary_selection);
+                    }
+
                     if let Some(path) = prepaint.selection_path.take() {
                         window.paint_path(path, cx.theme().selection);
                     }
+                    for path in prepaint.extra_selection_paths.drain(..) {
+                        window.paint_path(path, cx.theme().selection);
+                    }
 
                     // Paint hover highlight
                     if let Some(path) = prepaint.hover_highlight_path.take() {
@@ -1449,6 +1869,12 @@ impl Element for TextElement {
                         origin.x + prepaint.last_layout.line_number_width + (scroll_offset),
                         origin.y + offset_y,
                     );
+                    if line.block_rows > 0
+                        && let Some((block_row, block_lines)) = prepaint.diff_block.as_ref()
+                        && *block_row == row
+                    {
+                        Self::paint_diff_block(block_lines, p, &bounds, line_height, window, cx);
+                    }
                     if let Some(badges) = prepaint.inline_badges_by_line.get(ix) {
                         for (range, badge) in badges {
                             let Some(start_pos) =
@@ -1532,6 +1958,22 @@ impl Element for TextElement {
                         window,
                         cx,
                     );
+
+                    // Paint the placeholder after the folded line
+                    if prepaint.fold_markers.get(ix) == Some(&Some(true))
+                        && let Some(placeholder) = prepaint.fold_placeholder.as_ref()
+                        && let Some(pos) =
+                            line.position_for_index(line.len(), &prepaint.last_layout)
+                    {
+                        _ = placeholder.paint(
+                            p + pos + point(px(4.), px(0.)),
+                            line_height,
+                            TextAlign::Left,
+                            None,
+                            window,
+                            cx,
+                        );
+                    }
                     offset_y += line.size(line_height).height;
 
                     // After the cursor row, paint ghost lines (which shifts subsequent content down)
@@ -1565,6 +2007,17 @@ impl Element for TextElement {
                     }
                 }
 
+                // Paint the deleted lines after the last line
+                if let Some((block_row, block_lines)) = prepaint.diff_block.as_ref()
+                    && *block_row == prepaint.last_layout.visible_range.end
+                {
+                    let p = point(
+                        origin.x + prepaint.last_layout.line_number_width + scroll_offset,
+                        origin.y + offset_y,
+                    );
+                    Self::paint_diff_block(block_lines, p, &bounds, line_height, window, cx);
+                }
+
                 // Paint blinking cursor
                 if focused && show_cursor {
                     if let Some(cursor_bounds) = prepaint.cursor_bounds_with_scroll() {
@@ -1574,7 +2027,18 @@ impl Element for TextElement {
                             && cursor_bounds.bottom() > input_bounds.top()
                             && cursor_bounds.top() < input_bounds.bottom();
                         if cursor_intersects_viewport {
-                            window.paint_quad(fill(cursor_bounds, cx.theme().caret));
+                            let caret = if self.state.read(cx).vim_block_cursor() {
+                                cx.theme().caret.opacity(0.5)
+                            } else {
+                                cx.theme().caret
+                            };
+                            window.paint_quad(fill(cursor_bounds, caret));
+                        }
+                    }
+
+                    for cursor_bounds in prepaint.extra_cursor_bounds.iter() {
+                        if cursor_bounds.intersects(&input_bounds) {
+                            window.paint_quad(fill(*cursor_bounds, cx.theme().caret));
                         }
                     }
                 }
@@ -1596,9 +2060,30 @@ impl Element for TextElement {
                     ));
 
                     // Each item is the normal lines.
+                    let diff_marker_x = input_bounds.origin.x
+                        + prepaint.last_layout.line_number_width
+                        - LINE_NUMBER_RIGHT_MARGIN
+                        + px(2.);
                     for (ix, lines) in line_numbers.iter().enumerate() {
                         let row = visible_range_start + ix;
 
+                        // Paint the diff marker of the deleted lines above the line
+                        let block_height = prepaint
+                            .last_layout
+                            .lines
+                            .get(ix)
+                            .map_or(px(0.), |line| line.block_height(line_height));
+                        if block_height > px(0.) {
+                            window.paint_quad(fill(
+                                Bounds::new(
+                                    point(diff_marker_x, origin.y + offset_y),
+                                    size(DIFF_MARKER_WIDTH, block_height),
+                                ),
+                                cx.theme().red,
+                            ));
+                            offset_y += block_height;
+                        }
+
                         let p = point(input_bounds.origin.x, origin.y + offset_y);
                         let is_active = prepaint.current_row == Some(row);
 
@@ -1616,8 +2101,98 @@ impl Element for TextElement {
                             }
                         }
 
+                        // Paint the fold marker
+                        if let Some(folded) = prepaint.fold_markers.get(ix).copied().flatten() {
+                            let icon = if folded {
+                                IconName::ChevronRight
+                            } else {
+                                IconName::ChevronDown
+                            };
+                            let icon_size = FOLD_MARKER_WIDTH;
+                            let icon_bounds = Bounds::new(
+                                point(
+                                    input_bounds.origin.x + prepaint.last_layout.line_number_width
+                                        - LINE_NUMBER_RIGHT_MARGIN
+                                        - FOLD_MARKER_WIDTH,
+                                    origin.y + offset_y + (line_height - icon_size) / 2.,
+                                ),
+                                size(icon_size, icon_size),
+                            );
+                            let _ = window.paint_svg(
+                                icon_bounds,
+                                icon.path(),
+                                None,
+                                Default::default(),
+                                cx.theme().muted_foreground,
+                                cx,
+                            );
+                        }
+
+                        // Paint the diagnostic marker
+                        if let Some(severity) =
+                            prepaint.diagnostic_markers.get(ix).copied().flatten()
+                        {
+                            let icon_size = px(12.);
+                            let icon_bounds = Bounds::new(
+                                point(
+                                    input_bounds.origin.x + px(2.),
+                                    origin.y + offset_y + (line_height - icon_size) / 2.,
+                                ),
+                                size(icon_size, icon_size),
+                            );
+                            let _ = window.paint_svg(
+                                icon_bounds,
+                                severity.icon().path(),
+                                None,
+                                Default::default(),
+                                severity.fg(cx),
+                                cx,
+                            );
+                        }
+
+                        // Paint the diff marker
+                        if let Some(marker) = prepaint.diff_markers.get(ix) {
+                            let x = diff_marker_x;
+                            if let Some(status) = marker.status {
+                                let color = match status {
+                                    DiffHunkStatus::Added => cx.theme().green,
+                                    _ => cx.theme().blue,
+                                };
+                                window.paint_quad(fill(
+                                    Bounds::new(
+                                        point(x, origin.y + offset_y),
+                                        size(DIFF_MARKER_WIDTH, height),
+                                    ),
+                                    color,
+                                ));
+                            }
+
+                            let deleted_marker_size = size(DIFF_MARKER_WIDTH * 2., px(3.));
+                            if marker.deleted_before && block_height == px(0.) {
+                                window.paint_quad(fill(
+                                    Bounds::new(
+                                        point(x, origin.y + offset_y - px(1.5)),
+                                        deleted_marker_size,
+                                    ),
+                                    cx.theme().red,
+                                ));
+                            }
+                            if marker.deleted_after {
+                                window.paint_quad(fill(
+                                    Bounds::new(
+                                        point(x, origin.y + offset_y + height - px(1.5)),
+                                        deleted_marker_size,
+                                    ),
+                                    cx.theme().red,
+                                ));
+                            }
+                        }
+
                         for line in lines {
-                            let line_point = point(input_bounds.origin.x, origin.y + offset_y);
+                            let line_point = point(
+                                input_bounds.origin.x + line_number_left,
+                                origin.y + offset_y,
+                            );
                             _ = line.paint(
                                 line_point,
                                 line_height,
@@ -1634,6 +2209,18 @@ impl Element for TextElement {
                             offset_y += prepaint.ghost_lines_height;
                         }
                     }
+
+                    if let Some((block_row, block_lines)) = prepaint.diff_block.as_ref()
+                        && *block_row == prepaint.last_layout.visible_range.end
+                    {
+                        window.paint_quad(fill(
+                            Bounds::new(
+                                point(diff_marker_x, origin.y + offset_y),
+                                size(DIFF_MARKER_WIDTH, line_height * block_lines.len() as f32),
+                            ),
+                            cx.theme().red,
+                        ));
+                    }
                 }
             },
         );
@@ -1647,6 +2234,10 @@ impl Element for TextElement {
             state.scroll_size = prepaint.scroll_size;
             state.update_scroll_offset(Some(prepaint.cursor_scroll_offset), cx);
             state.deferred_scroll_offset = None;
+            let visible_range = prepaint.last_layout.visible_range.clone();
+            state
+                .lsp
+                .update_inlay_hints(&state.text, &visible_range, window, cx);
         });
 
         if let Some(hitbox) = prepaint.hover_definition_hitbox.as_ref() {
@@ -1682,6 +2273,16 @@ impl Element for TextElement {
     }
 }
 
+/// Returns the cursor height by the input size.
+fn cursor_height(size: crate::Size, line_height: Pixels) -> Pixels {
+    let ratio = match size {
+        crate::Size::Large => 1.,
+        crate::Size::Small => 0.75,
+        _ => 0.85,
+    };
+    line_height * ratio
+}
+
 /// Get the runs for the given range.
 ///
 /// The range is the byte range of the wrapped line.
@@ -1837,6 +2438,47 @@ fn split_runs_by_inline_badges(
     result
 }
 
+/// Insert the runs of the inlay hints into the `runs` of the text.
+///
+/// The `inlays` are the (byte index in the text, run of the label) sorted by index.
+fn insert_inlay_runs(runs: &[TextRun], inlays: &[(usize, TextRun)]) -> Vec<TextRun> {
+    if inlays.is_empty() {
+        return runs.to_vec();
+    }
+
+    let mut result = vec![];
+    let mut inlays = inlays.iter().peekable();
+    let mut cursor = 0;
+    for run in runs {
+        let run_end = cursor + run.len;
+        let mut run_start = cursor;
+
+        while let Some((ix, inlay_run)) = inlays.next_if(|(ix, _)| *ix < run_end) {
+            if *ix > run_start {
+                result.push(TextRun {
+                    len: ix - run_start,
+                    ..run.clone()
+                });
+                run_start = *ix;
+            }
+            result.push(inlay_run.clone());
+        }
+
+        if run_end > run_start {
+            result.push(TextRun {
+                len: run_end - run_start,
+                ..run.clone()
+            });
+        }
+
+        cursor = run_end;
+    }
+
+    // The inlay hints at the end of the text.
+    result.extend(inlays.map(|(_, run)| run.clone()));
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1938,4 +2580,74 @@ mod tests {
         assert_eq!(result[4].color, gpui::black());
         assert_eq!(result[5].color, gpui::blue());
     }
+
+    #[test]
+    fn test_insert_inlay_runs() {
+        let run = TextRun {
+            len: 0,
+            font: gpui::font(".SystemUIFont"),
+            color: gpui::black(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        };
+        let inlay_run = TextRun {
+            color: gpui::red(),
+            ..run.clone()
+        };
+
+        // "let a = foo(1)"
+        let runs = vec![
+            TextRun {
+                len: 3,
+                ..run.clone()
+            },
+            TextRun {
+                len: 8,
+                ..run.clone()
+            },
+            TextRun {
+                len: 3,
+                ..run.clone()
+            },
+        ];
+
+        let inlays = vec![
+            (
+                5,
+                TextRun {
+                    len: 5,
+                    ..inlay_run.clone()
+                },
+            ),
+            (
+                11,
+                TextRun {
+                    len: 3,
+                    ..inlay_run.clone()
+                },
+            ),
+            (
+                14,
+                TextRun {
+                    len: 4,
+                    ..inlay_run.clone()
+                },
+            ),
+        ];
+        let result = insert_inlay_runs(&runs, &inlays);
+        assert_eq!(
+            result.iter().map(|run| run.len).collect::<Vec<_>>(),
+            vec![3, 2, 5, 6, 3, 3, 4]
+        );
+        assert_eq!(
+            result
+                .iter()
+                .map(|run| run.color == gpui::red())
+                .collect::<Vec<_>>(),
+            vec![false, false, true, false, true, false, true]
+        );
+
+        assert_eq!(insert_inlay_runs(&runs, &[]).len(), 3);
+    }
 }
@@ -0,0 +1,552 @@
+use std::{ops::Range, rc::Rc};
+
+use gpui::{Context, MouseDownEvent, Window, px};
+use ropey::{Rope, RopeSlice};
+use tree_sitter::{Node, Tree};
+
+use crate::input::{
+    Fold, FoldAll, InputState, RopeExt as _, Unfold, UnfoldAll,
+    element::{FOLD_MARKER_WIDTH, LINE_NUMBER_RIGHT_MARGIN},
+    mode::InputMode,
+};
+
+/// A range of rows that can be folded.
+///
+/// The `start_row` is kept visible when folded, the rows after it until the `end_row` (inclusive) are hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FoldRange {
+    /// The 0-based row of the fold start line.
+    pub start_row: usize,
+    /// The 0-based row of the last hidden line (inclusive).
+    pub end_row: usize,
+}
+
+impl FoldRange {
+    pub fn new(start_row: usize, end_row: usize) -> Self {
+        Self { start_row, end_row }
+    }
+
+    /// Returns true if the `row` is hidden when this range is folded.
+    #[inline]
+    pub fn hides_row(&self, row: usize) -> bool {
+        row > self.start_row && row <= self.end_row
+    }
+}
+
+impl InputMode {
+    #[inline]
+    pub(super) fn has_folding(&self) -> bool {
+        match self {
+            InputMode::CodeEditor {
+                folding,
+                multi_line,
+                ..
+            } => *folding && *multi_line,
+            _ => false,
+        }
+    }
+}
+
+impl InputState {
+    /// Set whether to enable code folding in code editor mode, default is true.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn folding(mut self, folding: bool) -> Self {
+        debug_assert!(self.mode.is_code_editor() && self.mode.is_multi_line());
+        if let InputMode::CodeEditor { folding: f, .. } = &mut self.mode {
+            *f = folding;
+        }
+        self
+    }
+
+    /// Set code folding in code editor mode, all folded ranges will be unfolded if disabled.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn set_folding(&mut self, folding: bool, _: &mut Window, cx: &mut Context<Self>) {
+        debug_assert!(self.mode.is_code_editor());
+        if let InputMode::CodeEditor { folding: f, .. } = &mut self.mode {
+            *f = folding;
+        }
+        if !folding {
+            self.unfold_all(cx);
+        }
+        cx.notify();
+    }
+
+    /// Returns the folded ranges, sorted by the start row.
+    pub fn folded_ranges(&self) -> &[FoldRange] {
+        &self.folded_ranges
+    }
+
+    /// Returns all the ranges can be folded, sorted by the start row.
+    ///
+    /// The ranges are from the [`super::FoldingRangeProvider`] if it has,
+    /// otherwise from the syntax tree, or the indentation for plain text.
+    pub fn foldable_ranges(&mut self) -> Rc<Vec<FoldRange>> {
+        if let Some(ranges) = self.foldable_ranges.as_ref() {
+            return ranges.clone();
+        }
+
+        let ranges = if !self.mode.has_folding() || self.is_large_file() {
+            vec![]
+        } else if !self.lsp.folding_ranges.is_empty() {
+            normalize_fold_ranges(self.lsp.folding_ranges.clone(), self.text.lines_len())
+        } else {
+            let syntax_ranges = match &self.mode {
+                InputMode::CodeEditor { highlighter, .. } => highlighter
+                    .borrow()
+                    .as_ref()
+                    .and_then(|highlighter| highlighter.tree())
+                    .map(|tree| syntax_fold_ranges(tree, &self.text)),
+                _ => None,
+            };
+
+            match syntax_ranges {
+                Some(ranges) if !ranges.is_empty() => ranges,
+                _ => indent_fold_ranges(&self.text, self.mode.tab_size().tab_size),
+            }
+        };
+
+        let ranges = Rc::new(ranges);
+        self.foldable_ranges = Some(ranges.clone());
+        ranges
+    }
+
+    /// Returns the foldable range start at the given row, this only valid after [`Self::foldable_ranges`] called.
+    pub(super) fn foldable_range_at(&self, row: usize) -> Option<FoldRange> {
+        let ranges = self.foldable_ranges.as_ref()?;
+        let ix = ranges
+            .binary_search_by_key(&row, |range| range.start_row)
+            .ok()?;
+        ranges.get(ix).copied()
+    }
+
+    /// Returns true if the given row is the start row of a folded range.
+    pub fn is_folded_at_row(&self, row: usize) -> bool {
+        self.folded_ranges
+            .iter()
+            .any(|range| range.start_row == row)
+    }
+
+    /// Fold the innermost foldable range that contains the given row.
+    pub fn fold_at_row(&mut self, row: usize, cx: &mut Context<Self>) {
+        let ranges = self.foldable_ranges();
+        let Some(range) = ranges
+            .iter()
+            .filter(|range| range.start_row == row || range.hides_row(row))
+            .filter(|range| !self.folded_ranges.contains(range))
+            .max_by_key(|range| range.start_row)
+            .copied()
+        else {
+            return;
+        };
+
+        self.folded_ranges.push(range);
+        self.update_folded_ranges(cx);
+    }
+
+    /// Unfold the folded ranges that start at or contain the given row.
+    pub fn unfold_at_row(&mut self, row: usize, cx: &mut Context<Self>) {
+        let len = self.folded_ranges.len();
+        self.folded_ranges
+            .retain(|range| range.start_row != row && !range.hides_row(row));
+        if self.folded_ranges.len() != len {
+            self.update_folded_ranges(cx);
+        }
+    }
+
+    /// Toggle the fold of the foldable range start at the given row.
+    pub fn toggle_fold_at_row(&mut self, row: usize, cx: &mut Context<Self>) {
+        if self.is_folded_at_row(row) {
+            self.unfold_at_row(row, cx);
+            return;
+        }
+
+        self.foldable_ranges();
+        if let Some(range) = self.foldable_range_at(row) {
+            self.folded_ranges.push(range);
+            self.update_folded_ranges(cx);
+        }
+    }
+
+    /// Fold all the foldable ranges.
+    pub fn fold_all(&mut self, cx: &mut Context<Self>) {
+        self.folded_ranges = self.foldable_ranges().as_ref().clone();
+        self.update_folded_ranges(cx);
+    }
+
+    /// Unfold all the folded ranges.
+    pub fn unfold_all(&mut self, cx: &mut Context<Self>) {
+        if self.folded_ranges.is_empty() {
+            return;
+        }
+
+        self.folded_ranges.clear();
+        self.update_folded_ranges(cx);
+    }
+
+    pub(super) fn on_action_fold(&mut self, _: &Fold, _: &mut Window, cx: &mut Context<Self>) {
+        let row = self.text.offset_to_point(self.cursor()).row;
+        self.fold_at_row(row, cx);
+    }
+
+    pub(super) fn on_action_unfold(&mut self, _: &Unfold, _: &mut Window, cx: &mut Context<Self>) {
+        let row = self.text.offset_to_point(self.cursor()).row;
+        self.unfold_at_row(row, cx);
+    }
+
+    pub(super) fn on_action_fold_all(
+        &mut self,
+        _: &FoldAll,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.fold_all(cx);
+    }
+
+    pub(super) fn on_action_unfold_all(
+        &mut self,
+        _: &UnfoldAll,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.unfold_all(cx);
+    }
+
+    /// Apply the `folded_ranges` to the text wrapper, and move the cursors out of the folded lines.
+    fn update_folded_ranges(&mut self, cx: &mut Context<Self>) {
+        self.folded_ranges.sort();
+        self.folded_ranges.dedup();
+        self.text_wrapper.set_folded_ranges(&self.folded_ranges);
+        self.mode.update_auto_grow(&self.text_wrapper);
+
+        let cursor_row = self.text.offset_to_point(self.cursor()).row;
+        if self.text_wrapper.is_row_folded(cursor_row) {
+            let offset = self.clip_offset_to_folds(self.cursor(), false);
+            self.clear_extra_cursors(cx);
+            self.move_to(offset, None, cx);
+        } else {
+            let text_wrapper = &self.text_wrapper;
+            let text = &self.text;
+            self.extra_selections.retain(|selection| {
+                !text_wrapper.is_row_folded(text.offset_to_point(selection.end).row)
+            });
+        }
+
+        cx.notify();
+    }
+
+    /// Move the offset out of the folded lines.
+    ///
+    /// If `forward` is true, move to the start of the next visible line,
+    /// otherwise move to the end of the fold start line.
+    pub(super) fn clip_offset_to_folds(&self, offset: usize, forward: bool) -> usize {
+        if self.folded_ranges.is_empty() {
+            return offset;
+        }
+
+        let row = self.text.offset_to_point(offset).row;
+        if !self.text_wrapper.is_row_folded(row) {
+            return offset;
+        }
+
+        if forward && let Some(row) = self.text_wrapper.visible_row(row, true) {
+            return self.text.line_start_offset(row);
+        }
+
+        let row = self.text_wrapper.visible_row(row, false).unwrap_or(0);
+        self.text.line_end_offset(row)
+    }
+
+    /// Update the folded ranges after the text changed, must be called after the text wrapper updated.
+    ///
+    /// - `old_text`: The text before change.
+    /// - `range`: The replaced range in the `old_text`.
+    /// - `new_text`: The inserted text.
+    pub(super) fn update_folds_for_edit(
+        &mut self,
+        old_text: &Rope,
+        range: &Range<usize>,
+        new_text: &str,
+    ) {
+        self.foldable_ranges = None;
+        if self.folded_ranges.is_empty() {
+            return;
+        }
+
+        let start_row = old_text.offset_to_point(range.start).row;
+        let old_end_row = old_text.offset_to_point(range.end).row;
+        let new_end_row = self
+            .text
+            .offset_to_point((range.start + new_text.len()).min(self.text.len()))
+            .row;
+
+        shift_fold_ranges(&mut self.folded_ranges, start_row, old_end_row, new_end_row);
+        self.text_wrapper.set_folded_ranges(&self.folded_ranges);
+    }
+
+    /// Toggle the fold if the mouse down on the fold marker in the gutter, returns true if handled.
+    pub(super) fn on_mouse_down_fold_marker(
+        &mut self,
+        event: &MouseDownEvent,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if !self.mode.has_folding() || !self.mode.line_number() {
+            return false;
+        }
+        let Some(last_layout) = self.last_layout.as_ref() else {
+            return false;
+        };
+
+        let marker_right = self.input_bounds.origin.x + last_layout.line_number_width
+            - LINE_NUMBER_RIGHT_MARGIN
+            + px(2.);
+        let marker_left = marker_right - FOLD_MARKER_WIDTH - px(4.);
+        if event.position.x < marker_left || event.position.x > marker_right {
+            return false;
+        }
+
+        let offset = self.index_for_mouse_position(event.position);
+        let row = self.text.offset_to_point(offset).row;
+        self.foldable_ranges();
+        if !self.is_folded_at_row(row) && self.foldable_range_at(row).is_none() {
+            return false;
+        }
+
+        self.toggle_fold_at_row(row, cx);
+        true
+    }
+}
+
+/// Sort the ranges by start row, and keep the largest range for the same start row.
+fn normalize_fold_ranges(mut ranges: Vec<FoldRange>, total_rows: usize) -> Vec<FoldRange> {
+    ranges.retain(|range| range.end_row > range.start_row && range.start_row < total_rows);
+    ranges.sort_by(|a, b| {
+        a.start_row
+            .cmp(&b.start_row)
+            .then(b.end_row.cmp(&a.end_row))
+    });
+    ranges.dedup_by_key(|range| range.start_row);
+    ranges
+}
+
+/// Returns the fold ranges of the multi-line nodes in the syntax tree.
+fn syntax_fold_ranges(tree: &Tree, text: &Rope) -> Vec<FoldRange> {
+    let mut ranges = vec![];
+    let mut cursor = tree.walk();
+    let mut visited_children = false;
+    loop {
+        if !visited_children {
+            let node = cursor.node();
+            if let Some(range) = fold_range_for_node(node, text) {
+                ranges.push(range);
+            }
+
+            // The single line node has no multi-line children.
+            let is_multi_line = node.end_position().row > node.start_position().row;
+            if is_multi_line && cursor.goto_first_child() {
+                continue;
+            }
+        }
+
+        if cursor.goto_next_sibling() {
+            visited_children = false;
+        } else if cursor.goto_parent() {
+            visited_children = true;
+        } else {
+            break;
+        }
+    }
+
+    normalize_fold_ranges(ranges, text.lines_len())
+}
+
+fn fold_range_for_node(node: Node, text: &Rope) -> Option<FoldRange> {
+    if !node.is_named() || node.parent().is_none() {
+        return None;
+    }
+
+    let start_row = node.start_position().row;
+    let mut end_row = node.end_position().row;
+    if node.end_position().column == 0 {
+        // The node ends with a `\n`.
+        end_row = end_row.saturating_sub(1);
+    } else {
+        // Keep the closing line (e.g.: `}`, `</div>`) visible.
+        let mut cursor = node.walk();
+        if let Some(last_child) = node.children(&mut cursor).last() {
+            let position = last_child.start_position();
+            if position.row == end_row
+                && position.column == leading_whitespace_len(text.slice_line(end_row))
+            {
+                end_row = end_row.saturating_sub(1);
+            }
+        }
+    }
+
+    if end_row <= start_row {
+        return None;
+    }
+
+    Some(FoldRange::new(start_row, end_row))
+}
+
+fn leading_whitespace_len(line: RopeSlice) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| c.len_utf8())
+        .sum()
+}
+
+/// Returns the indent width of the line, `\t` is counted as `tab_size`, or None for blank line.
+fn line_indent(line: RopeSlice, tab_size: usize) -> Option<usize> {
+    let mut indent = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => indent += 1,
+            '\t' => indent += tab_size,
+            '\r' | '\n' => return None,
+            _ => return Some(indent),
+        }
+    }
+
+    None
+}
+
+/// Returns the fold ranges by the indentation, the lines after a line with more indentation can be folded.
+fn indent_fold_ranges(text: &Rope, tab_size: usize) -> Vec<FoldRange> {
+    let mut ranges = vec![];
+    // The (row, indent) of the possible fold start lines.
+    let mut stack: Vec<(usize, usize)> = vec![];
+    let mut last_row = 0;
+
+    for row in 0..text.lines_len() {
+        let Some(indent) = line_indent(text.slice_line(row), tab_size) else {
+            continue;
+        };
+
+        while let Some(&(start_row, start_indent)) = stack.last() {
+            if start_indent < indent {
+                break;
+            }
+
+            stack.pop();
+            if last_row > start_row {
+                ranges.push(FoldRange::new(start_row, last_row));
+            }
+        }
+
+        stack.push((row, indent));
+        last_row = row;
+    }
+
+    for (start_row, _) in stack {
+        if last_row > start_row {
+            ranges.push(FoldRange::new(start_row, last_row));
+        }
+    }
+
+    normalize_fold_ranges(ranges, text.lines_len())
+}
+
+/// Update the folded ranges after the rows `start_row..=old_end_row` are replaced by `start_row..=new_end_row`.
+///
+/// The folded ranges intersecting with the changed rows will be unfolded,
+/// except the change is only in the fold start line.
+fn shift_fold_ranges(
+    folded_ranges: &mut Vec<FoldRange>,
+    start_row: usize,
+    old_end_row: usize,
+    new_end_row: usize,
+) {
+    let in_single_row = start_row == old_end_row && start_row == new_end_row;
+    folded_ranges.retain_mut(|range| {
+        if range.end_row < start_row {
+            return true;
+        }
+
+        if range.start_row > old_end_row {
+            range.start_row = range.start_row + new_end_row - old_end_row;
+            range.end_row = range.end_row + new_end_row - old_end_row;
+            return true;
+        }
+
+        in_single_row && range.start_row == start_row
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::{FoldRange, indent_fold_ranges, normalize_fold_ranges, shift_fold_ranges};
+
+    #[test]
+    fn test_indent_fold_ranges() {
+        let text = Rope::from(
+            "def foo():\n    a = 1\n\n    if a:\n        b = 2\n    return a\n\nclass Bar:\n\tpass\n",
+        );
+        assert_eq!(
+            indent_fold_ranges(&text, 4),
+            vec![
+                FoldRange::new(0, 5),
+                FoldRange::new(3, 4),
+                FoldRange::new(7, 8),
+            ]
+        );
+
+        let text = Rope::from("foo\nbar\n  \nbaz");
+        assert_eq!(indent_fold_ranges(&text, 4), vec![]);
+    }
+
+    #[test]
+    fn test_normalize_fold_ranges() {
+        let ranges = vec![
+            FoldRange::new(3, 5),
+            FoldRange::new(0, 10),
+            FoldRange::new(3, 8),
+            FoldRange::new(4, 4),
+            FoldRange::new(20, 30),
+        ];
+        assert_eq!(
+            normalize_fold_ranges(ranges, 12),
+            vec![FoldRange::new(0, 10), FoldRange::new(3, 8)]
+        );
+    }
+
+    #[test]
+    fn test_shift_fold_ranges() {
+        let folded = vec![
+            FoldRange::new(1, 3),
+            FoldRange::new(5, 8),
+            FoldRange::new(10, 12),
+        ];
+
+        // Insert a new line before the folds.
+        let mut ranges = folded.clone();
+        shift_fold_ranges(&mut ranges, 0, 0, 1);
+        assert_eq!(
+            ranges,
+            vec![
+                FoldRange::new(2, 4),
+                FoldRange::new(6, 9),
+                FoldRange::new(11, 13),
+            ]
+        );
+
+        // Type in the fold start line.
+        let mut ranges = folded.clone();
+        shift_fold_ranges(&mut ranges, 5, 5, 5);
+        assert_eq!(ranges, folded);
+
+        // Delete the lines in a folded range.
+        let mut ranges = folded.clone();
+        shift_fold_ranges(&mut ranges, 6, 8, 6);
+        assert_eq!(ranges, vec![FoldRange::new(1, 3), FoldRange::new(8, 10)]);
+
+        // Insert a new line at the fold start line.
+        let mut ranges = folded.clone();
+        shift_fold_ranges(&mut ranges, 10, 10, 11);
+        assert_eq!(ranges, vec![FoldRange::new(1, 3), FoldRange::new(5, 8)]);
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use gpui::{
     Bounds, Context, EntityInputHandler as _, Hsla, Path, PathBuilder, Pixels, SharedString,
     TextRun, TextStyle, Window, point, px,
@@ -6,6 +8,7 @@ use ropey::RopeSlice;
 
 use crate::{
     RopeExt,
+    highlighter::SuggestedIndent,
     input::{
         Indent, IndentInline, InputState, LastLayout, Outdent, OutdentInline, element::TextElement,
         mode::InputMode,
@@ -51,6 +54,19 @@ impl TabSize {
 
         count
     }
+
+    /// Returns the indent string of the width in spaces, the tabs are used for [`TabSize::hard_tabs`].
+    pub(super) fn indent_string(&self, width: usize) -> String {
+        if self.hard_tabs && self.tab_size > 0 {
+            format!(
+                "{}{}",
+                "\t".repeat(width / self.tab_size),
+                " ".repeat(width % self.tab_size)
+            )
+        } else {
+            " ".repeat(width)
+        }
+    }
 }
 
 impl InputMode {
@@ -133,6 +149,11 @@ impl TextElement {
             let Some(line_layout) = last_layout.line(ix) else {
                 continue;
             };
+            // Skip the folded line.
+            if line_layout.wrapped_lines.is_empty() {
+                continue;
+            }
+            offset_y += line_layout.block_height(line_height);
 
             let mut current_indents = vec![];
             if line.len() > 0 {
@@ -226,6 +247,9 @@ impl InputState {
         if self.accept_inline_completion(window, cx) {
             return;
         }
+        if self.next_tabstop(window, cx) {
+            return;
+        }
         self.indent(false, window, cx);
     }
 
@@ -239,6 +263,9 @@ impl InputState {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.prev_tabstop(window, cx) {
+            return;
+        }
         self.outdent(false, window, cx);
     }
 
@@ -251,11 +278,158 @@ impl InputState {
         self.outdent(true, window, cx);
     }
 
+    /// Returns the indentation of the line starting at `offset` by the indents query of the language.
+    pub(super) fn suggested_indent(&self, offset: usize) -> Option<SuggestedIndent> {
+        let InputMode::CodeEditor {
+            highlighter, tab, ..
+        } = &self.mode
+        else {
+            return None;
+        };
+
+        let highlighter = highlighter.borrow();
+        highlighter
+            .as_ref()?
+            .suggested_indent(offset, &tab.to_string())
+    }
+
+    /// Returns the text to insert for a new line with the indentation, and the cursor offset in the text.
+    ///
+    /// If the cursor is before a closing bracket, e.g.: `{|}`, the bracket is moved to the next line as well.
+    pub(super) fn new_line_text(&mut self) -> (String, usize) {
+        if !self.mode.is_code_editor() {
+            return ("\n".into(), 1);
+        }
+
+        let cursor = self.cursor();
+        let suggestion = if self.selected_range.is_empty() && !self.has_multiple_cursors() {
+            self.suggested_indent(cursor)
+        } else {
+            None
+        };
+        let Some(suggestion) = suggestion else {
+            let text = format!("\n{}", self.indent_of_next_line());
+            let len = text.len();
+            return (text, len);
+        };
+
+        let line_start = self
+            .text
+            .line_start_offset(self.text.offset_to_point(cursor).row);
+        let is_line_start = self
+            .text
+            .slice(line_start..cursor)
+            .chars()
+            .all(|c| c.is_whitespace());
+        if suggestion.outdent && !is_line_start {
+            let inner_line = format!(
+                "\n{}{}",
+                suggestion.indent,
+                self.mode.tab_size().to_string()
+            );
+            let len = inner_line.len();
+            return (format!("{}\n{}", inner_line, suggestion.indent), len);
+        }
+
+        let text = format!("\n{}", suggestion.indent);
+        let len = text.len();
+        (text, len)
+    }
+
+    /// Reindent the line if the typed text is an `@outdent` capture at the start of the line, e.g.: `}`.
+    pub(super) fn auto_outdent(
+        &mut self,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if new_text.chars().count() != 1
+            || new_text.trim().is_empty()
+            || self.has_multiple_cursors()
+            || !self.mode.is_code_editor()
+        {
+            return;
+        }
+
+        let cursor = self.cursor();
+        let offset = cursor.saturating_sub(new_text.len());
+        let line_start = self
+            .text
+            .line_start_offset(self.text.offset_to_point(offset).row);
+        let indent = self.text.slice(line_start..offset).to_string();
+        if !indent.chars().all(|c| c == ' ' || c == '\t') {
+            return;
+        }
+
+        let Some(suggestion) = self.suggested_indent(offset) else {
+            return;
+        };
+        if !suggestion.outdent || suggestion.indent == indent {
+            return;
+        }
+
+        self.replace_text_in_range_silent(
+            Some(self.range_to_utf16(&(line_start..offset))),
+            &suggestion.indent,
+            window,
+            cx,
+        );
+        let cursor = line_start + suggestion.indent.len() + new_text.len();
+        self.selected_range = (cursor..cursor).into();
+    }
+
+    /// Reindent the pasted text to the indentation suggested by the indents query at the cursor.
+    ///
+    /// Returns the range to replace (from the start of the line) and the reindented text,
+    /// `None` if the text is a single line or is pasted after the start of the line.
+    pub(super) fn reindent_pasted_text(&self, text: &str) -> Option<(Range<usize>, String)> {
+        if !text.contains('\n') {
+            return None;
+        }
+
+        let range: Range<usize> = self.selected_range.into();
+        let line_start = self
+            .text
+            .line_start_offset(self.text.offset_to_point(range.start).row);
+        let is_line_start = self
+            .text
+            .slice(line_start..range.start)
+            .chars()
+            .all(|c| c == ' ' || c == '\t');
+        if !is_line_start {
+            return None;
+        }
+
+        let suggestion = self.suggested_indent(range.start)?;
+        let tab = self.mode.tab_size();
+        // Paste before a closing bracket, the text is inside the brackets.
+        let indent = if suggestion.outdent {
+            format!("{}{}", suggestion.indent, tab.to_string())
+        } else {
+            suggestion.indent
+        };
+
+        let mut new_text = reindent_lines(text, &indent, &tab);
+        // The rest of the line is moved after the pasted text, keep its original indentation.
+        let line_end = self
+            .text
+            .line_end_offset(self.text.offset_to_point(range.end).row);
+        let rest_of_line = self.text.slice(range.end..line_end).to_string();
+        if new_text.ends_with('\n') && !rest_of_line.trim().is_empty() {
+            new_text.push_str(&self.text.slice(line_start..range.start).to_string());
+        }
+
+        Some((line_start..range.end, new_text))
+    }
+
     pub(super) fn indent(&mut self, block: bool, window: &mut Window, cx: &mut Context<Self>) {
         if !self.mode.is_indentable() {
             cx.propagate();
             return;
         };
+        if self.block_read_only_edit(cx) {
+            return;
+        }
 
         let tab_indent = self.mode.tab_size().to_string();
         let selected_range = self.selected_range;
@@ -314,6 +488,9 @@ impl InputState {
             cx.propagate();
             return;
         };
+        if self.block_read_only_edit(cx) {
+            return;
+        }
 
         let tab_indent = self.mode.tab_size().to_string();
         let selected_range = self.selected_range;
@@ -384,11 +561,46 @@ impl InputState {
     }
 }
 
+/// Reindent the lines of the `text` to the `indent`, the relative indentation of the lines is kept.
+///
+/// The indentation of the first line is the reference, or the minimum indentation of the other lines
+/// if the first line has no indentation (e.g.: it's copied from the middle of a line).
+fn reindent_lines(text: &str, indent: &str, tab: &TabSize) -> String {
+    let width_of = |line: &str| tab.indent_count(&RopeSlice::from(line));
+    let is_blank = |line: &&str| line.trim().is_empty();
+
+    let mut lines = text.split('\n');
+    let first_line = lines.next().unwrap_or_default();
+    let reference = if first_line.starts_with([' ', '\t']) {
+        width_of(first_line)
+    } else {
+        lines
+            .clone()
+            .filter(|line| !is_blank(line))
+            .map(width_of)
+            .min()
+            .unwrap_or_default()
+    };
+    let target = width_of(indent);
+
+    let mut new_text = format!("{}{}", indent, first_line.trim_start_matches([' ', '\t']));
+    for line in lines {
+        new_text.push('\n');
+        let content = line.trim_start_matches([' ', '\t']);
+        if !is_blank(&line) {
+            let width = (width_of(line) + target).saturating_sub(reference);
+            new_text.push_str(&tab.indent_string(width));
+        }
+        new_text.push_str(content);
+    }
+    new_text
+}
+
 #[cfg(test)]
 mod tests {
     use ropey::RopeSlice;
 
-    use super::TabSize;
+    use super::{TabSize, reindent_lines};
 
     #[test]
     fn test_tab_size() {
@@ -429,4 +641,92 @@ mod tests {
         assert_eq!(tab.indent_count(&RopeSlice::from(" \t abc  ")), 6);
         assert_eq!(tab.indent_count(&RopeSlice::from("abc")), 0);
     }
+
+    #[test]
+    fn test_tab_size_indent_string() {
+        let tab = TabSize {
+            tab_size: 4,
+            hard_tabs: false,
+        };
+        assert_eq!(tab.indent_string(6), "      ");
+        let tab = TabSize {
+            tab_size: 4,
+            hard_tabs: true,
+        };
+        assert_eq!(tab.indent_string(6), "\t  ");
+    }
+
+    #[test]
+    fn test_reindent_lines() {
+        let tab = TabSize {
+            tab_size: 4,
+            hard_tabs: false,
+        };
+        assert_eq!(
+            reindent_lines("fn a() {\n    b();\n\n}", "    ", &tab),
+            "    fn a() {\n        b();\n\n    }"
+        );
+        assert_eq!(
+            reindent_lines("        if a {\n            b();\n        }", "    ", &tab),
+            "    if a {\n        b();\n    }"
+        );
+        // Copied from the middle of a line.
+        assert_eq!(
+            reindent_lines("if a {\n            b();\n        }", "", &tab),
+            "if a {\n    b();\n}"
+        );
+        assert_eq!(
+            reindent_lines("a\r\n  b\r\n", "\t", &tab),
+            "\ta\r\n    b\r\n"
+        );
+    }
+
+    #[gpui::test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_reindent_pasted_text(cx: &mut gpui::TestAppContext) {
+        use gpui::AppContext as _;
+
+        use crate::input::InputState;
+
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .code_editor("rust")
+                    .tab_size(TabSize {
+                        tab_size: 4,
+                        hard_tabs: false,
+                    })
+                    .default_value("fn main() {\n    let x = 1;\n    \n}")
+            })
+        });
+
+        cx.update(|_, cx| {
+            state.update(cx, |state, cx| {
+                state.update_highlighter(&(0..0), "", false, cx);
+
+                // Paste after the indentation, the rest of the line keeps the indentation.
+                state.selected_range = (16..16).into();
+                assert_eq!(
+                    state.reindent_pasted_text("let a = 1;\nlet b = 2;\n"),
+                    Some((12..16, "    let a = 1;\n    let b = 2;\n    ".to_string()))
+                );
+                assert_eq!(
+                    state.reindent_pasted_text("let a = 1;\nlet b = 2;"),
+                    Some((12..16, "    let a = 1;\n    let b = 2;".to_string()))
+                );
+
+                // Paste in a blank line.
+                state.selected_range = (31..31).into();
+                assert_eq!(
+                    state.reindent_pasted_text("let a = 1;\n"),
+                    Some((27..31, "    let a = 1;\n".to_string()))
+                );
+
+                // Paste after the start of the line.
+                state.selected_range = (20..20).into();
+                assert_eq!(state.reindent_pasted_text("a\nb"), None);
+            });
+        });
+    }
 }
@@ -1,13 +1,14 @@
 use gpui::prelude::FluentBuilder as _;
 use gpui::{
     AnyElement, App, DefiniteLength, Edges, EdgesRefinement, Entity, InteractiveElement as _,
-    IntoElement, IsZero, MouseButton, ParentElement as _, Rems, RenderOnce, StyleRefinement,
-    Styled, TextAlign, Window, div, px, relative,
+    IntoElement, IsZero, MouseButton, ParentElement as _, Pixels, Rems, RenderOnce,
+    StyleRefinement, Styled, TextAlign, Window, div, px, relative,
 };
 
 use crate::button::{Button, ButtonVariants as _};
 use crate::input::clear_button;
 use crate::input::element::{LINE_NUMBER_RIGHT_MARGIN, RIGHT_MARGIN};
+use crate::input::minimap::MinimapElement;
 use crate::scroll::Scrollbar;
 use crate::spinner::Spinner;
 use crate::{ActiveTheme, v_flex};
@@ -193,10 +194,43 @@ impl Input {
                 .unwrap_or(px(0.)),
         };
 
+        let has_minimap = state.mode.has_minimap();
+
         v_flex()
             .size_full()
             .children(state.search_panel.clone())
-            .child(div().flex_1().child(input_state.clone()).map(|this| {
+            .child(
+                div()
+                    .flex()
+                    .flex_1()
+                    .child(Self::render_text_area(
+                        paddings,
+                        has_minimap,
+                        input_state,
+                        state,
+                    ))
+                    .when(has_minimap, |this| {
+                        this.child(
+                            div()
+                                .flex_shrink_0()
+                                .w(state.minimap_width)
+                                .child(MinimapElement::new(input_state.clone())),
+                        )
+                    }),
+            )
+    }
+
+    fn render_text_area(
+        paddings: Edges<Pixels>,
+        has_minimap: bool,
+        input_state: &Entity<InputState>,
+        state: &InputState,
+    ) -> impl IntoElement {
+        div()
+            .flex_1()
+            .min_w(px(0.))
+            .child(input_state.clone())
+            .map(|this| {
                 if let Some(last_layout) = state.last_layout.as_ref() {
                     let left = if last_layout.line_number_width.is_zero() {
                         px(0.)
@@ -221,14 +255,15 @@ impl Input {
                             .absolute()
                             .top(-paddings.top)
                             .left(left)
-                            .right(-paddings.right)
+                            .when(has_minimap, |this| this.right_0())
+                            .when(!has_minimap, |this| this.right(-paddings.right))
                             .bottom(-paddings.bottom)
                             .child(scrollbar.scroll_size(scroll_size)),
                     )
                 } else {
                     this
                 }
-            }))
+            })
     }
 }
 
@@ -282,7 +317,7 @@ impl RenderOnce for Input {
         div()
             .id(("input", self.state.entity_id()))
             .flex()
-            .key_context(crate::input::CONTEXT)
+            .key_context(state.key_context())
             .track_focus(&state.focus_handle.clone())
             .tab_index(self.tab_index)
             .when(!state.disabled, |this| {
@@ -305,6 +340,7 @@ impl RenderOnce for Input {
                             .on_action(window.listener_for(&self.state, InputState::outdent_inline))
                             .on_action(window.listener_for(&self.state, InputState::indent_block))
                             .on_action(window.listener_for(&self.state, InputState::outdent_block))
+                            .on_action(window.listener_for(&self.state, InputState::toggle_comment))
                     })
                     .on_action(
                         window.listener_for(&self.state, InputState::on_action_toggle_code_actions),
@@ -321,11 +357,60 @@ impl RenderOnce for Input {
                     .on_action(window.listener_for(&self.state, InputState::select_down))
                     .on_action(window.listener_for(&self.state, InputState::page_up))
                     .on_action(window.listener_for(&self.state, InputState::page_down))
+                    .on_action(window.listener_for(&self.state, InputState::add_cursor_above))
+                    .on_action(window.listener_for(&self.state, InputState::add_cursor_below))
+                    .on_action(window.listener_for(&self.state, InputState::select_column_up))
+                    .on_action(window.listener_for(&self.state, InputState::select_column_down))
+                    .on_action(window.listener_for(&self.state, InputState::select_column_left))
+                    .on_action(window.listener_for(&self.state, InputState::select_column_right))
+                    .when(state.mode.has_folding(), |this| {
+                        this.on_action(window.listener_for(&self.state, InputState::on_action_fold))
+                            .on_action(
+                                window.listener_for(&self.state, InputState::on_action_unfold),
+                            )
+                            .on_action(
+                                window.listener_for(&self.state, InputState::on_action_fold_all),
+                            )
+                            .on_action(
+                                window.listener_for(&self.state, InputState::on_action_unfold_all),
+                            )
+                    })
                     .on_action(
                         window.listener_for(&self.state, InputState::on_action_go_to_definition),
                     )
+                    .on_action(window.listener_for(&self.state, InputState::on_action_rename))
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_find_all_references),
+                    )
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_format_document),
+                    )
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_format_selection),
+                    )
+                    .on_action(
+                        window
+                            .listener_for(&self.state, InputState::on_action_go_to_next_diagnostic),
+                    )
+                    .on_action(
+                        window
+                            .listener_for(&self.state, InputState::on_action_go_to_prev_diagnostic),
+                    )
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_go_to_next_hunk),
+                    )
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_go_to_prev_hunk),
+                    )
+                    .on_action(window.listener_for(&self.state, InputState::on_action_revert_hunk))
+                    .on_action(
+                        window.listener_for(&self.state, InputState::on_action_toggle_hunk_diff),
+                    )
             })
             .on_action(window.listener_for(&self.state, InputState::select_all))
+            .on_action(window.listener_for(&self.state, InputState::select_next_occurrence))
+            .on_action(window.listener_for(&self.state, InputState::expand_selection))
+            .on_action(window.listener_for(&self.state, InputState::shrink_selection))
             .on_action(window.listener_for(&self.state, InputState::select_to_start_of_line))
             .on_action(window.listener_for(&self.state, InputState::select_to_end_of_line))
             .on_action(window.listener_for(&self.state, InputState::select_to_previous_word))
@@ -350,6 +435,10 @@ impl RenderOnce for Input {
                 MouseButton::Right,
                 window.listener_for(&self.state, InputState::on_mouse_down),
             )
+            .on_mouse_down(
+                MouseButton::Middle,
+                window.listener_for(&self.state, InputState::on_mouse_down),
+            )
             .on_mouse_up(
                 MouseButton::Left,
                 window.listener_for(&self.state, InputState::on_mouse_up),
@@ -358,6 +447,10 @@ impl RenderOnce for Input {
                 MouseButton::Right,
                 window.listener_for(&self.state, InputState::on_mouse_up),
             )
+            .on_mouse_up(
+                MouseButton::Middle,
+                window.listener_for(&self.state, InputState::on_mouse_up),
+            )
             .on_mouse_move(window.listener_for(&self.state, InputState::on_mouse_move))
             .on_scroll_wheel(window.listener_for(&self.state, InputState::on_scroll_wheel))
             .size_full()
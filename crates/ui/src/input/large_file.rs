@@ -0,0 +1,174 @@
+use std::{ops::Range, time::Duration};
+
+use gpui::{Context, Task};
+
+use crate::input::{InputState, mode::InputMode};
+
+/// The default text size in bytes to enable the large file mode, see [`InputState::large_file_size`].
+pub(super) const LARGE_FILE_SIZE: usize = 4 * 1024 * 1024;
+/// The max duration to parse the large text, the syntax highlighting is disabled if it's exceeded.
+const PARSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl InputState {
+    /// Set the text size in bytes to enable the large file mode, default is 4 MB.
+    ///
+    /// In the large file mode:
+    ///
+    /// - The text is parsed on the background executor, the syntax highlighting is disabled
+    ///   if the parsing takes longer than 2 seconds.
+    /// - The soft wrap is computed only for the visible lines.
+    /// - The code folding is disabled.
+    pub fn large_file_size(mut self, size: usize) -> Self {
+        self.text_wrapper.large_file_size = size;
+        self
+    }
+
+    /// Returns true if the text is larger than the [`Self::large_file_size`].
+    pub fn is_large_file(&self) -> bool {
+        self.text.len() >= self.text_wrapper.large_file_size
+    }
+
+    /// Update the syntax highlighter after the text in `range` is replaced by the `new_text`.
+    ///
+    /// If `force` is false, the highlighter is only created if it doesn't exist.
+    pub(super) fn update_highlighter(
+        &mut self,
+        range: &Range<usize>,
+        new_text: &str,
+        force: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let large_file = self.is_large_file();
+        self.mode
+            .update_highlighter(range, &self.text, new_text, force, large_file, cx);
+        if large_file {
+            self.parse_in_background(cx);
+        } else {
+            self._parse_task = Task::ready(());
+        }
+    }
+
+    /// Parse the text on the background executor, the previous parsing is cancelled.
+    fn parse_in_background(&mut self, cx: &mut Context<Self>) {
+        let InputMode::CodeEditor { highlighter, .. } = &self.mode else {
+            return;
+        };
+        let Some(parser) = highlighter
+            .borrow()
+            .as_ref()
+            .and_then(|highlighter| highlighter.background_parser())
+        else {
+            return;
+        };
+
+        self._parse_task = cx.spawn(async move |this, cx| {
+            let tree = cx
+                .background_spawn(async move { parser.parse(PARSE_TIMEOUT) })
+                .await;
+
+            // The task is replaced when the text is changed, so the tree is for the current text.
+            _ = this.update(cx, |this, cx| {
+                let InputMode::CodeEditor { highlighter, .. } = &this.mode else {
+                    return;
+                };
+                if let Some(highlighter) = highlighter.borrow_mut().as_mut() {
+                    if tree.is_none() {
+                        tracing::warn!(
+                            "Parsing the large text timed out, the syntax highlighting is disabled."
+                        );
+                    }
+                    highlighter.set_tree(tree);
+                }
+                this.outline = None;
+                cx.notify();
+            });
+        });
+    }
+}
+
+#[cfg(all(test, feature = "tree-sitter-languages"))]
+mod tests {
+    use gpui::{
+        AppContext as _, Entity, EntityInputHandler as _, TestAppContext, VisualTestContext,
+    };
+
+    use crate::input::{InputState, mode::InputMode};
+
+    /// Time out the background parsing of the highlighter.
+    fn time_out(state: &Entity<InputState>, cx: &mut VisualTestContext) {
+        cx.update(|_, cx| {
+            state.update(cx, |state, cx| {
+                state.update_highlighter(&(0..0), "", false, cx)
+            });
+        });
+        cx.run_until_parked();
+        state.update(cx, |state, _| {
+            let InputMode::CodeEditor { highlighter, .. } = &state.mode else {
+                unreachable!();
+            };
+            highlighter.borrow_mut().as_mut().unwrap().set_tree(None);
+        });
+    }
+
+    /// Returns true if the background parsing is disabled by the timeout.
+    fn is_timed_out(state: &Entity<InputState>, cx: &mut VisualTestContext) -> bool {
+        cx.update(|_, cx| {
+            state.update(cx, |state, cx| {
+                state.update_highlighter(&(0..0), "", false, cx);
+                let InputMode::CodeEditor { highlighter, .. } = &state.mode else {
+                    unreachable!();
+                };
+                highlighter
+                    .borrow()
+                    .as_ref()
+                    .unwrap()
+                    .background_parser()
+                    .is_none()
+            })
+        })
+    }
+
+    #[gpui::test]
+    fn test_reset_parse_timeout(cx: &mut TestAppContext) {
+        const TEXT: &str = "fn main() {\n    let a = 1;\n}";
+
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .code_editor("rust")
+                    .large_file_size(16)
+                    .default_value(TEXT)
+            })
+        });
+
+        time_out(&state, cx);
+        assert!(is_timed_out(&state, cx));
+
+        // The text is smaller than the large file size.
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.replace_text_in_range(Some(0..TEXT.len()), "fn a() {}", window, cx);
+                assert!(!state.is_large_file());
+            });
+        });
+        assert!(!is_timed_out(&state, cx));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| state.set_value(TEXT, window, cx));
+        });
+        time_out(&state, cx);
+        assert!(is_timed_out(&state, cx));
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| state.set_value(TEXT, window, cx));
+        });
+        assert!(!is_timed_out(&state, cx));
+
+        time_out(&state, cx);
+        assert!(is_timed_out(&state, cx));
+        cx.update(|_, cx| {
+            state.update(cx, |state, cx| state.set_highlighter("rust", cx));
+        });
+        assert!(!is_timed_out(&state, cx));
+    }
+}
@@ -0,0 +1,682 @@
+use std::{
+    cell::{Cell, RefCell},
+    ops::Range,
+    rc::Rc,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use gpui::{App, Context, Entity, SharedString, Task, Window};
+use lsp_types::{
+    CodeAction, CodeActionContext, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CodeActionTriggerKind, ColorInformation, ColorProviderCapability,
+    CompletionContext, CompletionList, CompletionParams, CompletionResponse, CompletionTextEdit,
+    CompletionTriggerKind, Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentColorParams, ExecuteCommandParams, GotoDefinitionParams,
+    GotoDefinitionResponse, HoverParams, HoverProviderCapability, Location, LocationLink, OneOf,
+    Position, PositionEncodingKind, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+    TextDocumentItem, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Uri, VersionedTextDocumentIdentifier,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, PublishDiagnostics,
+    },
+    request::{
+        CodeActionRequest, CodeActionResolveRequest, Completion, DocumentColor, ExecuteCommand,
+        GotoDefinition, HoverRequest,
+    },
+};
+use ropey::Rope;
+
+use super::{LanguageServer, NotificationSubscription};
+use crate::input::{
+    CodeActionProvider, CompletionProvider, DefinitionProvider, DocumentColorProvider,
+    DocumentSyncProvider, HoverProvider, InputEvent, InputState, Lsp, RopeExt as _,
+    split_workspace_edit,
+};
+
+/// A document opened in the [`LanguageServer`], implements the providers of the [`Lsp`] by the server requests.
+///
+/// Created by [`LanguageServer::open_document`], the `textDocument/didClose` is sent when dropped.
+pub struct LanguageServerDocument {
+    server: Arc<LanguageServer>,
+    uri: Uri,
+    version: Rc<Cell<i32>>,
+    sync_kind: TextDocumentSyncKind,
+    encoding: PositionEncoding,
+    /// The text last synced to the server, to encode the positions of the incremental changes.
+    text: RefCell<Rope>,
+    trigger_characters: Vec<String>,
+    /// The last published diagnostics, to send with the code action requests.
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    _diagnostics_subscription: NotificationSubscription,
+    _diagnostics_task: Task<()>,
+}
+
+impl LanguageServer {
+    /// Open the text of the `editor` as the document of `uri` in the server, returns the [`Lsp`] wired to it.
+    ///
+    /// - The providers are only set for the capabilities of the server, call it after [`LanguageServer::initialize`].
+    /// - The `textDocument/publishDiagnostics` of the document are set to the diagnostics of the `editor`.
+    ///
+    /// The returned [`Lsp`] should be set to the `editor`, e.g.: `editor.lsp = lsp`.
+    pub fn open_document(
+        self: &Arc<Self>,
+        uri: Uri,
+        language_id: impl Into<String>,
+        editor: &Entity<InputState>,
+        cx: &mut App,
+    ) -> Lsp {
+        let capabilities = self.capabilities();
+        let encoding = PositionEncoding::new(capabilities.position_encoding.as_ref());
+        let text = editor.read(cx).text.clone();
+
+        let (diagnostics_tx, diagnostics_rx) = smol::channel::unbounded();
+        let _diagnostics_subscription = self.on_notification::<PublishDiagnostics>({
+            let uri = uri.clone();
+            move |params| {
+                if params.uri == uri {
+                    _ = diagnostics_tx.try_send(params);
+                }
+            }
+        });
+
+        let version = Rc::new(Cell::new(0));
+        let diagnostics = Rc::new(RefCell::new(vec![]));
+        let _diagnostics_task = cx.spawn({
+            let editor = editor.downgrade();
+            let version = version.clone();
+            let diagnostics = diagnostics.clone();
+            async move |cx| {
+                while let Ok(params) = diagnostics_rx.recv().await {
+                    // Ignore the diagnostics of the outdated text, the positions may not match.
+                    if params.version.is_some_and(|v| v != version.get()) {
+                        continue;
+                    }
+
+                    *diagnostics.borrow_mut() = params.diagnostics.clone();
+                    let result = editor.update(cx, |editor, cx| {
+                        let text = editor.text.clone();
+                        let Some(set) = editor.diagnostics_mut() else {
+                            return;
+                        };
+                        set.reset(&text);
+                        set.extend(params.diagnostics.into_iter().map(|mut diagnostic| {
+                            diagnostic.range = encoding.decode_range(&text, diagnostic.range);
+                            diagnostic
+                        }));
+                        cx.notify();
+                    });
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        if let Err(err) = self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: uri.clone(),
+                language_id: language_id.into(),
+                version: version.get(),
+                text: text.to_string(),
+            },
+        }) {
+            tracing::error!("failed to open document: {:?}", err);
+        }
+
+        let sync_kind = match &capabilities.text_document_sync {
+            Some(TextDocumentSyncCapability::Kind(kind)) => *kind,
+            Some(TextDocumentSyncCapability::Options(options)) => {
+                options.change.unwrap_or(TextDocumentSyncKind::NONE)
+            }
+            None => TextDocumentSyncKind::NONE,
+        };
+
+        let document = Rc::new(LanguageServerDocument {
+            server: self.clone(),
+            uri: uri.clone(),
+            version,
+            sync_kind,
+            encoding,
+            text: RefCell::new(text),
+            trigger_characters: capabilities
+                .completion_provider
+                .as_ref()
+                .and_then(|options| options.trigger_characters.clone())
+                .unwrap_or_default(),
+            diagnostics,
+            _diagnostics_subscription,
+            _diagnostics_task,
+        });
+
+        let mut lsp = Lsp {
+            document_uri: Some(uri),
+            document_sync_provider: Some(document.clone()),
+            ..Default::default()
+        };
+
+        if capabilities.completion_provider.is_some() {
+            lsp.completion_provider = Some(document.clone());
+        }
+        if matches!(
+            capabilities.hover_provider,
+            Some(HoverProviderCapability::Simple(true) | HoverProviderCapability::Options(_))
+        ) {
+            lsp.hover_provider = Some(document.clone());
+        }
+        if matches!(
+            capabilities.definition_provider,
+            Some(OneOf::Left(true) | OneOf::Right(_))
+        ) {
+            lsp.definition_provider = Some(document.clone());
+        }
+        if matches!(
+            capabilities.code_action_provider,
+            Some(
+                CodeActionProviderCapability::Simple(true)
+                    | CodeActionProviderCapability::Options(_)
+            )
+        ) {
+            lsp.code_action_providers.push(document.clone());
+        }
+        if matches!(
+            capabilities.color_provider,
+            Some(
+                ColorProviderCapability::Simple(true)
+                    | ColorProviderCapability::ColorProvider(_)
+                    | ColorProviderCapability::Options(_)
+            )
+        ) {
+            lsp.document_color_provider = Some(document);
+        }
+
+        lsp
+    }
+}
+
+impl LanguageServerDocument {
+    /// Returns the URI of the document.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Returns the version of the document, increased by each change.
+    pub fn version(&self) -> i32 {
+        self.version.get()
+    }
+
+    fn text_document(&self) -> TextDocumentIdentifier {
+        TextDocumentIdentifier::new(self.uri.clone())
+    }
+
+    fn text_document_position(&self, text: &Rope, offset: usize) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            self.text_document(),
+            self.encoding.encode(text, text.offset_to_position(offset)),
+        )
+    }
+}
+
+/// The encoding of the `character` of the positions in the server, negotiated in the `initialize`.
+///
+/// The positions of the editor are in chars (UTF-32), so they are converted at the boundary of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PositionEncoding {
+    Utf8,
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// The server without `positionEncoding` in the capabilities uses UTF-16.
+    fn new(kind: Option<&PositionEncodingKind>) -> Self {
+        match kind.map(|kind| kind.as_str()) {
+            Some("utf-8") => Self::Utf8,
+            Some("utf-32") => Self::Utf32,
+            _ => Self::Utf16,
+        }
+    }
+
+    fn char_len(self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Convert the `position` in chars of the `text` to the position of the server.
+    fn encode(self, text: &Rope, position: Position) -> Position {
+        if self == Self::Utf32 {
+            return position;
+        }
+
+        let character = text
+            .slice_line(position.line as usize)
+            .chars()
+            .take(position.character as usize)
+            .map(|c| self.char_len(c))
+            .sum::<usize>();
+        Position::new(position.line, character as u32)
+    }
+
+    /// Convert the `position` of the server to the position in chars of the `text`.
+    ///
+    /// The position inside a char (e.g. between the surrogates) is moved to the start of the char.
+    fn decode(self, text: &Rope, position: Position) -> Position {
+        if self == Self::Utf32 {
+            return position;
+        }
+
+        let mut len = 0;
+        let mut character = 0;
+        for c in text.slice_line(position.line as usize).chars() {
+            len += self.char_len(c);
+            if len > position.character as usize {
+                break;
+            }
+            character += 1;
+        }
+        Position::new(position.line, character)
+    }
+
+    fn encode_range(self, text: &Rope, range: lsp_types::Range) -> lsp_types::Range {
+        lsp_types::Range::new(self.encode(text, range.start), self.encode(text, range.end))
+    }
+
+    fn decode_range(self, text: &Rope, range: lsp_types::Range) -> lsp_types::Range {
+        lsp_types::Range::new(self.decode(text, range.start), self.decode(text, range.end))
+    }
+}
+
+impl Drop for LanguageServerDocument {
+    fn drop(&mut self) {
+        _ = self
+            .server
+            .notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+                text_document: self.text_document(),
+            });
+    }
+}
+
+impl DocumentSyncProvider for LanguageServerDocument {
+    fn did_change(&self, changes: Vec<TextDocumentContentChangeEvent>, text: &Rope, _cx: &mut App) {
+        let old_text = self.text.replace(text.clone());
+        let content_changes = match self.sync_kind {
+            TextDocumentSyncKind::INCREMENTAL => self.encode_changes(old_text, changes),
+            TextDocumentSyncKind::FULL => vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_string(),
+            }],
+            _ => return,
+        };
+
+        self.version.set(self.version.get() + 1);
+        if let Err(err) = self
+            .server
+            .notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(
+                    self.uri.clone(),
+                    self.version.get(),
+                ),
+                content_changes,
+            })
+        {
+            tracing::error!("failed to sync document: {:?}", err);
+        }
+    }
+}
+
+impl LanguageServerDocument {
+    /// Encode the ranges of the incremental `changes`, each range is in the text after the previous changes.
+    fn encode_changes(
+        &self,
+        mut text: Rope,
+        mut changes: Vec<TextDocumentContentChangeEvent>,
+    ) -> Vec<TextDocumentContentChangeEvent> {
+        if self.encoding == PositionEncoding::Utf32 {
+            return changes;
+        }
+
+        for change in changes.iter_mut() {
+            let Some(range) = change.range else {
+                text = Rope::from(change.text.as_str());
+                continue;
+            };
+
+            change.range = Some(self.encoding.encode_range(&text, range));
+            let start = text.position_to_offset(&range.start);
+            let end = text.position_to_offset(&range.end);
+            text.replace(start..end, &change.text);
+        }
+        changes
+    }
+}
+
+impl CompletionProvider for LanguageServerDocument {
+    fn completions(
+        &self,
+        text: &Rope,
+        offset: usize,
+        trigger: CompletionContext,
+        _window: &mut Window,
+        cx: &mut Context<InputState>,
+    ) -> Task<Result<CompletionResponse>> {
+        // Only the trigger characters of the server are sent as the trigger character.
+        let context = match trigger.trigger_character {
+            Some(ch) if self.trigger_characters.contains(&ch) => CompletionContext {
+                trigger_kind: CompletionTriggerKind::TRIGGER_CHARACTER,
+                trigger_character: Some(ch),
+            },
+            _ => CompletionContext {
+                trigger_kind: CompletionTriggerKind::INVOKED,
+                trigger_character: None,
+            },
+        };
+
+        let request = self.server.request::<Completion>(CompletionParams {
+            text_document_position: self.text_document_position(text, offset),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: Some(context),
+        });
+
+        let text = text.clone();
+        let encoding = self.encoding;
+        cx.background_spawn(async move {
+            let mut response = request.await?.unwrap_or(CompletionResponse::Array(vec![]));
+            let items = match &mut response {
+                CompletionResponse::Array(items) => items,
+                CompletionResponse::List(CompletionList { items, .. }) => items,
+            };
+            for item in items.iter_mut() {
+                match &mut item.text_edit {
+                    Some(CompletionTextEdit::Edit(edit)) => {
+                        edit.range = encoding.decode_range(&text, edit.range);
+                    }
+                    Some(CompletionTextEdit::InsertAndReplace(edit)) => {
+                        edit.insert = encoding.decode_range(&text, edit.insert);
+                        edit.replace = encoding.decode_range(&text, edit.replace);
+                    }
+                    None => {}
+                }
+                for edit in item.additional_text_edits.iter_mut().flatten() {
+                    edit.range = encoding.decode_range(&text, edit.range);
+                }
+            }
+            Ok(response)
+        })
+    }
+
+    fn is_completion_trigger(
+        &self,
+        _offset: usize,
+        new_text: &str,
+        _cx: &mut Context<InputState>,
+    ) -> bool {
+        if self.trigger_characters.iter().any(|ch| ch == new_text) {
+            return true;
+        }
+
+        !new_text.is_empty() && new_text.chars().all(|c| c.is_alphanumeric() || c == '_')
+    }
+}
+
+impl HoverProvider for LanguageServerDocument {
+    fn hover(
+        &self,
+        text: &Rope,
+        offset: usize,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Option<lsp_types::Hover>>> {
+        let request = self.server.request::<HoverRequest>(HoverParams {
+            text_document_position_params: self.text_document_position(text, offset),
+            work_done_progress_params: Default::default(),
+        });
+
+        let text = text.clone();
+        let encoding = self.encoding;
+        cx.background_spawn(async move {
+            let mut hover = request.await?;
+            if let Some(range) = hover.as_mut().and_then(|hover| hover.range.as_mut()) {
+                *range = encoding.decode_range(&text, *range);
+            }
+            Ok(hover)
+        })
+    }
+}
+
+impl DefinitionProvider for LanguageServerDocument {
+    fn definitions(
+        &self,
+        text: &Rope,
+        offset: usize,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<LocationLink>>> {
+        let request = self.server.request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: self.text_document_position(text, offset),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+
+        let text = text.clone();
+        let uri = self.uri.clone();
+        let encoding = self.encoding;
+        cx.background_spawn(async move {
+            let mut links = match request.await? {
+                None => vec![],
+                Some(GotoDefinitionResponse::Scalar(location)) => vec![location_link(location)],
+                Some(GotoDefinitionResponse::Array(locations)) => {
+                    locations.into_iter().map(location_link).collect()
+                }
+                Some(GotoDefinitionResponse::Link(links)) => links,
+            };
+
+            // Only the ranges of the current document can be decoded by the text.
+            for link in links.iter_mut() {
+                if let Some(range) = link.origin_selection_range.as_mut() {
+                    *range = encoding.decode_range(&text, *range);
+                }
+                if link.target_uri == uri {
+                    link.target_range = encoding.decode_range(&text, link.target_range);
+                    link.target_selection_range =
+                        encoding.decode_range(&text, link.target_selection_range);
+                }
+            }
+            Ok(links)
+        })
+    }
+}
+
+fn location_link(location: Location) -> LocationLink {
+    LocationLink {
+        origin_selection_range: None,
+        target_uri: location.uri,
+        target_range: location.range,
+        target_selection_range: location.range,
+    }
+}
+
+impl CodeActionProvider for LanguageServerDocument {
+    fn id(&self) -> SharedString {
+        self.server.name()
+    }
+
+    fn code_actions(
+        &self,
+        state: Entity<InputState>,
+        range: Range<usize>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<CodeAction>>> {
+        let text = &state.read(cx).text;
+        let range = self.encoding.encode_range(
+            text,
+            lsp_types::Range::new(
+                text.offset_to_position(range.start),
+                text.offset_to_position(range.end),
+            ),
+        );
+        let diagnostics = self
+            .diagnostics
+            .borrow()
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.range.start <= range.end && range.start <= diagnostic.range.end
+            })
+            .cloned()
+            .collect();
+
+        let request = self.server.request::<CodeActionRequest>(CodeActionParams {
+            text_document: self.text_document(),
+            range,
+            context: CodeActionContext {
+                diagnostics,
+                only: None,
+                trigger_kind: Some(CodeActionTriggerKind::INVOKED),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+
+        cx.background_spawn(async move {
+            Ok(request
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => action,
+                    CodeActionOrCommand::Command(command) => CodeAction {
+                        title: command.title.clone(),
+                        command: Some(command),
+                        ..Default::default()
+                    },
+                })
+                .collect())
+        })
+    }
+
+    /// Apply the edit of the action (resolve it if needed), then execute the command.
+    ///
+    /// The edits of other documents will emit [`InputEvent::WorkspaceEdit`].
+    fn perform_code_action(
+        &self,
+        state: Entity<InputState>,
+        action: CodeAction,
+        _push_to_history: bool,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<()>> {
+        let server = self.server.clone();
+        let encoding = self.encoding;
+        let state = state.downgrade();
+        window.spawn(cx, async move |cx| {
+            let resolve_provider = matches!(
+                server.capabilities().code_action_provider,
+                Some(CodeActionProviderCapability::Options(options)) if options.resolve_provider == Some(true)
+            );
+            let action = if resolve_provider && action.edit.is_none() {
+                server.request::<CodeActionResolveRequest>(action).await?
+            } else {
+                action
+            };
+
+            if let Some(edit) = action.edit {
+                state.update_in(cx, |state, window, cx| {
+                    let (mut edits, changes) =
+                        split_workspace_edit(edit, state.lsp.document_uri.as_ref());
+                    for edit in edits.iter_mut() {
+                        edit.range = encoding.decode_range(&state.text, edit.range);
+                    }
+                    state.apply_formatting_edits(edits, window, cx);
+                    if !changes.is_empty() {
+                        cx.emit(InputEvent::WorkspaceEdit { changes });
+                    }
+                })?;
+            }
+
+            if let Some(command) = action.command {
+                server
+                    .request::<ExecuteCommand>(ExecuteCommandParams {
+                        command: command.command,
+                        arguments: command.arguments.unwrap_or_default(),
+                        work_done_progress_params: Default::default(),
+                    })
+                    .await?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl DocumentColorProvider for LanguageServerDocument {
+    fn document_colors(
+        &self,
+        text: &Rope,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<ColorInformation>>> {
+        let request = self.server.request::<DocumentColor>(DocumentColorParams {
+            text_document: self.text_document(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+
+        let text = text.clone();
+        let encoding = self.encoding;
+        cx.background_spawn(async move {
+            let mut colors = request.await?;
+            for color in colors.iter_mut() {
+                color.range = encoding.decode_range(&text, color.range);
+            }
+            Ok(colors)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+    use ropey::Rope;
+
+    use super::PositionEncoding;
+
+    #[test]
+    fn test_position_encoding() {
+        let text = Rope::from("fn main() {\n    // 😀 你好 TODO\n}");
+
+        let position = Position::new(1, 11);
+        assert_eq!(PositionEncoding::Utf32.encode(&text, position), position);
+        assert_eq!(
+            PositionEncoding::Utf16.encode(&text, position),
+            Position::new(1, 12)
+        );
+        assert_eq!(
+            PositionEncoding::Utf8.encode(&text, position),
+            Position::new(1, 18)
+        );
+
+        assert_eq!(
+            PositionEncoding::Utf16.decode(&text, Position::new(1, 12)),
+            position
+        );
+        assert_eq!(
+            PositionEncoding::Utf8.decode(&text, Position::new(1, 18)),
+            position
+        );
+        // Inside the surrogate pair of the emoji.
+        assert_eq!(
+            PositionEncoding::Utf16.decode(&text, Position::new(1, 8)),
+            Position::new(1, 7)
+        );
+        // Out of the line.
+        assert_eq!(
+            PositionEncoding::Utf16.decode(&text, Position::new(2, 10)),
+            Position::new(2, 1)
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::HashMap,
+    process::{Command, Stdio},
+    sync::{
+        Arc, Mutex, RwLock, Weak,
+        atomic::{AtomicI32, AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use gpui::SharedString;
+use lsp_types::{
+    ClientCapabilities, CodeActionClientCapabilities, CodeActionKind, CodeActionKindLiteralSupport,
+    CodeActionLiteralSupport, CompletionClientCapabilities, CompletionItemCapability,
+    DocumentColorClientCapabilities, GeneralClientCapabilities, GotoCapability,
+    HoverClientCapabilities, InitializeParams, InitializeResult, InitializedParams, MarkupKind,
+    PositionEncodingKind, PublishDiagnosticsClientCapabilities, ServerCapabilities,
+    TextDocumentClientCapabilities, TextDocumentSyncClientCapabilities, Uri, WorkspaceFolder,
+    notification::{Exit, Initialized, Notification},
+    request::{Initialize, Request, Shutdown},
+};
+use serde_json::{Value, json};
+use smol::{
+    channel::{Receiver, Sender},
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+};
+
+mod document;
+mod transport;
+
+pub use document::*;
+
+type NotificationHandler = Arc<dyn Fn(Value) + Send + Sync>;
+type NotificationHandlers = Arc<Mutex<HashMap<usize, (&'static str, NotificationHandler)>>>;
+type PendingRequests = Arc<Mutex<HashMap<i32, Sender<Result<Value>>>>>;
+
+/// A language server process, speaks the Language Server Protocol over stdio.
+///
+/// ```ignore
+/// let server = LanguageServer::start("rust-analyzer", Command::new("rust-analyzer"))?;
+/// server.initialize(Some(root_uri), None).await?;
+///
+/// let lsp = server.open_document(uri, "rust", &editor, cx);
+/// editor.update(cx, |editor, _| editor.lsp = lsp);
+/// ```
+///
+/// The process is killed when the server is dropped, use [`LanguageServer::shutdown`] to exit gracefully.
+pub struct LanguageServer {
+    name: SharedString,
+    next_id: AtomicI32,
+    outgoing: Sender<String>,
+    pending_requests: PendingRequests,
+    notification_handlers: NotificationHandlers,
+    next_handler_id: AtomicUsize,
+    capabilities: RwLock<ServerCapabilities>,
+    _process: smol::process::Child,
+    _io_tasks: Vec<smol::Task<()>>,
+}
+
+/// The subscription of [`LanguageServer::on_notification`], the handler is removed when dropped.
+#[must_use]
+pub struct NotificationSubscription {
+    id: usize,
+    handlers: Weak<Mutex<HashMap<usize, (&'static str, NotificationHandler)>>>,
+}
+
+impl Drop for NotificationSubscription {
+    fn drop(&mut self) {
+        if let Some(handlers) = self.handlers.upgrade() {
+            handlers.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+impl LanguageServer {
+    /// Spawn the language server process by the `command`, the stdin and stdout are used for the protocol.
+    pub fn start(name: impl Into<SharedString>, command: Command) -> Result<Arc<Self>> {
+        let name: SharedString = name.into();
+        let mut command = smol::process::Command::from(command);
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to start language server {:?}", name))?;
+
+        let stdin = process.stdin.take().context("no stdin")?;
+        let stdout = process.stdout.take().context("no stdout")?;
+        let stderr = process.stderr.take().context("no stderr")?;
+
+        let (outgoing, outgoing_rx) = smol::channel::unbounded::<String>();
+        let pending_requests = PendingRequests::default();
+        let notification_handlers = NotificationHandlers::default();
+
+        let write_task = smol::spawn(write_messages(stdin, outgoing_rx));
+        let read_task = smol::spawn(read_messages(
+            stdout,
+            outgoing.clone(),
+            pending_requests.clone(),
+            notification_handlers.clone(),
+        ));
+        let stderr_task = smol::spawn({
+            let name = name.clone();
+            async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Some(Ok(line)) = smol::stream::StreamExt::next(&mut lines).await {
+                    tracing::debug!("[{}] {}", name, line);
+                }
+            }
+        });
+
+        Ok(Arc::new(Self {
+            name,
+            next_id: AtomicI32::new(1),
+            outgoing,
+            pending_requests,
+            notification_handlers,
+            next_handler_id: AtomicUsize::new(0),
+            capabilities: RwLock::new(ServerCapabilities::default()),
+            _process: process,
+            _io_tasks: vec![write_task, read_task, stderr_task],
+        }))
+    }
+
+    /// Returns the name of the language server.
+    pub fn name(&self) -> SharedString {
+        self.name.clone()
+    }
+
+    /// Returns the capabilities of the server, empty before [`LanguageServer::initialize`].
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities.read().unwrap().clone()
+    }
+
+    /// Send the `initialize` request, then the `initialized` notification.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#initialize
+    #[allow(deprecated)]
+    pub async fn initialize(
+        &self,
+        root_uri: Option<Uri>,
+        initialization_options: Option<Value>,
+    ) -> Result<InitializeResult> {
+        let workspace_folders = root_uri.as_ref().map(|uri| {
+            vec![WorkspaceFolder {
+                uri: uri.clone(),
+                name: uri
+                    .path()
+                    .as_str()
+                    .rsplit('/')
+                    .find(|name| !name.is_empty())
+                    .unwrap_or_default()
+                    .to_string(),
+            }]
+        });
+
+        let params = InitializeParams {
+            process_id: Some(std::process::id()),
+            root_uri,
+            initialization_options,
+            capabilities: client_capabilities(),
+            workspace_folders,
+            ..Default::default()
+        };
+
+        let result = self.request::<Initialize>(params).await?;
+        *self.capabilities.write().unwrap() = result.capabilities.clone();
+        self.notify::<Initialized>(InitializedParams {})?;
+        Ok(result)
+    }
+
+    /// Send the `shutdown` request, then the `exit` notification.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#shutdown
+    pub async fn shutdown(&self) -> Result<()> {
+        self.request::<Shutdown>(()).await?;
+        self.notify::<Exit>(())
+    }
+
+    /// Send a request, the returned future resolves with the response of the server.
+    pub fn request<R: Request>(
+        &self,
+        params: R::Params,
+    ) -> impl Future<Output = Result<R::Result>> + use<R> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = smol::channel::bounded(1);
+        self.pending_requests.lock().unwrap().insert(id, tx);
+
+        let sent = serde_json::to_value(params)
+            .map_err(anyhow::Error::from)
+            .and_then(|params| self.send(encode_jsonrpc(Some(id), R::METHOD, params)));
+        if sent.is_err() {
+            self.pending_requests.lock().unwrap().remove(&id);
+        }
+
+        async move {
+            sent?;
+            let result = rx
+                .recv()
+                .await
+                .map_err(|_| anyhow!("language server exited"))??;
+            Ok(serde_json::from_value(result)?)
+        }
+    }
+
+    /// Send a notification.
+    pub fn notify<N: Notification>(&self, params: N::Params) -> Result<()> {
+        let params = serde_json::to_value(params)?;
+        self.send(encode_jsonrpc(None, N::METHOD, params))
+    }
+
+    /// Handle the notifications from the server, the `handler` is called in the background thread.
+    ///
+    /// The `handler` is called without the lock of the handlers, so it can subscribe or unsubscribe the notifications.
+    pub fn on_notification<N: Notification>(
+        &self,
+        handler: impl Fn(N::Params) + Send + Sync + 'static,
+    ) -> NotificationSubscription {
+        let id = self.next_handler_id.fetch_add(1, Ordering::SeqCst);
+        let handler: NotificationHandler =
+            Arc::new(
+                move |params| match serde_json::from_value::<N::Params>(params) {
+                    Ok(params) => handler(params),
+                    Err(err) => tracing::error!("invalid params of {}: {:?}", N::METHOD, err),
+                },
+            );
+        self.notification_handlers
+            .lock()
+            .unwrap()
+            .insert(id, (N::METHOD, handler));
+
+        NotificationSubscription {
+            id,
+            handlers: Arc::downgrade(&self.notification_handlers),
+        }
+    }
+
+    fn send(&self, message: String) -> Result<()> {
+        self.outgoing
+            .try_send(message)
+            .map_err(|_| anyhow!("language server {:?} is not running", self.name))
+    }
+}
+
+/// Encode the JSON-RPC request (with `id`) or notification.
+fn encode_jsonrpc(id: Option<i32>, method: &str, params: Value) -> String {
+    let mut message = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+    });
+    if let Some(id) = id {
+        message["id"] = json!(id);
+    }
+    if !params.is_null() {
+        message["params"] = params;
+    }
+    message.to_string()
+}
+
+async fn write_messages(mut stdin: smol::process::ChildStdin, outgoing: Receiver<String>) {
+    while let Ok(content) = outgoing.recv().await {
+        let message = transport::encode_message(&content);
+        if stdin.write_all(&message).await.is_err() || stdin.flush().await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn read_messages(
+    stdout: smol::process::ChildStdout,
+    outgoing: Sender<String>,
+    pending_requests: PendingRequests,
+    notification_handlers: NotificationHandlers,
+) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let content = match transport::read_message(&mut reader).await {
+            Ok(Some(content)) => content,
+            Ok(None) => break,
+            Err(err) => {
+                tracing::error!("failed to read language server message: {:?}", err);
+                break;
+            }
+        };
+
+        let message = match serde_json::from_str::<Value>(&content) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::error!("invalid language server message: {:?}", err);
+                continue;
+            }
+        };
+
+        let id = message.get("id");
+        let method = message.get("method").and_then(Value::as_str);
+        match (id, method) {
+            // Response
+            (Some(id), None) => {
+                let Some(id) = id.as_i64() else {
+                    continue;
+                };
+                let Some(tx) = pending_requests.lock().unwrap().remove(&(id as i32)) else {
+                    continue;
+                };
+
+                let result = match message.get("error") {
+                    Some(error) => Err(anyhow!(
+                        "{}",
+                        error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or_default()),
+                };
+                _ = tx.try_send(result);
+            }
+            // The request from server, reply with the default result.
+            (Some(id), Some(method)) => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let len = message
+                            .pointer("/params/items")
+                            .and_then(Value::as_array)
+                            .map_or(0, |items| items.len());
+                        Value::Array(vec![Value::Null; len])
+                    }
+                    _ => Value::Null,
+                };
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": result,
+                });
+                _ = outgoing.try_send(response.to_string());
+            }
+            // Notification
+            (None, Some(method)) => {
+                let params = message.get("params").cloned().unwrap_or_default();
+                // Release the lock before calling, the handlers may drop their subscriptions.
+                let handlers = notification_handlers
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .filter(|(handler_method, _)| *handler_method == method)
+                    .map(|(_, handler)| handler.clone())
+                    .collect::<Vec<_>>();
+                for handler in handlers {
+                    handler(params.clone());
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Fail all the pending requests.
+    for (_, tx) in pending_requests.lock().unwrap().drain() {
+        _ = tx.try_send(Err(anyhow!("language server exited")));
+    }
+}
+
+/// The capabilities of the providers implemented by the [`LanguageServerDocument`].
+fn client_capabilities() -> ClientCapabilities {
+    ClientCapabilities {
+        text_document: Some(TextDocumentClientCapabilities {
+            synchronization: Some(TextDocumentSyncClientCapabilities {
+                dynamic_registration: Some(false),
+                will_save: Some(false),
+                will_save_wait_until: Some(false),
+                did_save: Some(false),
+            }),
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    snippet_support: Some(true),
+                    documentation_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            hover: Some(HoverClientCapabilities {
+                content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                ..Default::default()
+            }),
+            definition: Some(GotoCapability {
+                link_support: Some(true),
+                ..Default::default()
+            }),
+            code_action: Some(CodeActionClientCapabilities {
+                code_action_literal_support: Some(CodeActionLiteralSupport {
+                    code_action_kind: CodeActionKindLiteralSupport {
+                        value_set: [
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR,
+                            CodeActionKind::REFACTOR_EXTRACT,
+                            CodeActionKind::REFACTOR_INLINE,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::SOURCE,
+                            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                        ]
+                        .iter()
+                        .map(|kind| kind.as_str().to_string())
+                        .collect(),
+                    },
+                }),
+                ..Default::default()
+            }),
+            color_provider: Some(DocumentColorClientCapabilities::default()),
+            publish_diagnostics: Some(PublishDiagnosticsClientCapabilities::default()),
+            ..Default::default()
+        }),
+        // The editor positions are in chars, prefer UTF-32 to skip the conversion.
+        general: Some(GeneralClientCapabilities {
+            position_encodings: Some(vec![
+                PositionEncodingKind::UTF32,
+                PositionEncodingKind::UTF16,
+            ]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::encode_jsonrpc;
+
+    #[test]
+    fn test_encode_jsonrpc() {
+        let message: serde_json::Value =
+            serde_json::from_str(&encode_jsonrpc(Some(1), "shutdown", json!(null))).unwrap();
+        assert_eq!(
+            message,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"})
+        );
+
+        let message: serde_json::Value =
+            serde_json::from_str(&encode_jsonrpc(None, "exit", json!({"a": 1}))).unwrap();
+        assert_eq!(
+            message,
+            json!({"jsonrpc": "2.0", "method": "exit", "params": {"a": 1}})
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::{Context as _, Result};
+use smol::io::{AsyncBufRead, AsyncBufReadExt as _, AsyncReadExt as _};
+
+/// Encode the JSON-RPC message with the `Content-Length` header.
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#headerPart
+pub(super) fn encode_message(content: &str) -> Vec<u8> {
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content).into_bytes()
+}
+
+/// Read the content of the next JSON-RPC message, returns `None` at the end of the stream.
+pub(super) async fn read_message<R>(reader: &mut R) -> Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        // The other headers (e.g.: `Content-Type`) are ignored.
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let len = value
+                    .trim()
+                    .parse::<usize>()
+                    .context("invalid Content-Length")?;
+                content_length = Some(len);
+            }
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut content).await?;
+    Ok(Some(String::from_utf8(content)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_message, read_message};
+
+    #[test]
+    fn test_read_message() {
+        let mut data = encode_message(r#"{"id":1}"#);
+        data.extend(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n");
+        data.extend(encode_message(r#"{"text":"你好"}"#));
+
+        smol::block_on(async {
+            let mut reader = data.as_slice();
+            assert_eq!(
+                read_message(&mut reader).await.unwrap().as_deref(),
+                Some(r#"{"id":1}"#)
+            );
+            assert_eq!(
+                read_message(&mut reader).await.unwrap().as_deref(),
+                Some(r#"{"text":"你好"}"#)
+            );
+            assert_eq!(read_message(&mut reader).await.unwrap(), None);
+
+            let mut reader = b"Content-Length: abc\r\n\r\n".as_slice();
+            assert!(read_message(&mut reader).await.is_err());
+        });
+    }
+}
@@ -115,9 +115,16 @@ impl InputState {
             return;
         }
 
-        let Some(provider) = self.lsp.completion_provider.clone() else {
-            return;
+        let provider = self.lsp.completion_provider.clone();
+        // The user snippets are shown when typing a prefix of them.
+        let snippets = if new_text.is_empty() {
+            vec![]
+        } else {
+            self.snippet_completions(self.cursor())
         };
+        if provider.is_none() && snippets.is_empty() {
+            return;
+        }
 
         // Always schedule inline completion (debounced).
         // It will check if menu is open before showing the suggestion.
@@ -126,7 +133,10 @@ impl InputState {
         let start = range.end;
         let new_offset = self.cursor();
 
-        if !provider.is_completion_trigger(start, new_text, cx) {
+        let is_trigger = provider
+            .as_ref()
+            .is_some_and(|provider| provider.is_completion_trigger(start, new_text, cx));
+        if !is_trigger && snippets.is_empty() {
             return;
         }
 
@@ -168,16 +178,28 @@ impl InputState {
             trigger_character: Some(query),
         };
 
-        let provider_responses =
-            provider.completions(&self.text, new_offset, completion_context, window, cx);
+        let provider_responses = provider.filter(|_| is_trigger).map(|provider| {
+            provider.completions(&self.text, new_offset, completion_context, window, cx)
+        });
+        // Keep the provider items of the open menu if the provider is not queried,
+        // otherwise they are replaced by the snippets only.
+        let provider_items = if provider_responses.is_none() && menu.read(cx).is_open() {
+            menu.read(cx).provider_items.clone()
+        } else {
+            vec![]
+        };
         self._context_menu_task = cx.spawn_in(window, async move |editor, cx| {
-            let mut completions: Vec<CompletionItem> = vec![];
-            if let Some(provider_responses) = provider_responses.await.ok() {
-                match provider_responses {
-                    CompletionResponse::Array(items) => completions.extend(items),
-                    CompletionResponse::List(list) => completions.extend(list.items),
+            let mut completions: Vec<CompletionItem> = provider_items;
+            if let Some(provider_responses) = provider_responses {
+                if let Some(provider_responses) = provider_responses.await.ok() {
+                    match provider_responses {
+                        CompletionResponse::Array(items) => completions.extend(items),
+                        CompletionResponse::List(list) => completions.extend(list.items),
+                    }
                 }
             }
+            let provider_items = completions.clone();
+            completions.extend(snippets);
 
             if completions.is_empty() {
                 _ = menu.update(cx, |menu, cx| {
@@ -195,6 +217,7 @@ impl InputState {
                     }
 
                     _ = menu.update(cx, |menu, cx| {
+                        menu.provider_items = provider_items;
                         menu.show(new_offset, completions, window, cx);
                     });
 
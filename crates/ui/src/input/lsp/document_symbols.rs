@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use gpui::{App, Context, Task, Window};
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse, SymbolInformation};
+use ropey::Rope;
+
+use crate::input::{InputState, Lsp};
+
+/// Document symbol provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentSymbol
+pub trait DocumentSymbolProvider {
+    /// textDocument/documentSymbol
+    ///
+    /// The [`DocumentSymbolResponse::Flat`] symbols are nested by their ranges.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentSymbol
+    fn document_symbols(
+        &self,
+        _text: &Rope,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<DocumentSymbolResponse>>;
+}
+
+impl Lsp {
+    pub(crate) fn update_document_symbols(
+        &mut self,
+        text: &Rope,
+        window: &mut Window,
+        cx: &mut Context<InputState>,
+    ) {
+        let Some(provider) = self.document_symbol_provider.clone() else {
+            return;
+        };
+
+        let text = text.clone();
+
+        // debounce timer 100ms
+        self._document_symbol_task = cx.spawn_in(window, async move |input_state, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(100))
+                .await;
+
+            let task_result = cx
+                .update(|window, cx| provider.document_symbols(&text, window, cx))
+                .ok();
+
+            if let Some(task) = task_result {
+                if let Ok(response) = task.await {
+                    let symbols = match response {
+                        DocumentSymbolResponse::Nested(symbols) => symbols,
+                        DocumentSymbolResponse::Flat(symbols) => {
+                            symbols.into_iter().map(flat_document_symbol).collect()
+                        }
+                    };
+
+                    let _ = input_state.update(cx, |input_state, cx| {
+                        if symbols != input_state.lsp.document_symbols {
+                            input_state.lsp.document_symbols = symbols;
+                            input_state.outline = None;
+                            cx.notify();
+                        }
+                    });
+                }
+            }
+        });
+    }
+}
+
+/// Convert the flat [`SymbolInformation`] to a [`DocumentSymbol`] without children.
+#[allow(deprecated)]
+fn flat_document_symbol(symbol: SymbolInformation) -> DocumentSymbol {
+    DocumentSymbol {
+        name: symbol.name,
+        detail: None,
+        kind: symbol.kind,
+        tags: symbol.tags,
+        deprecated: symbol.deprecated,
+        range: symbol.location.range,
+        selection_range: symbol.location.range,
+        children: None,
+    }
+}
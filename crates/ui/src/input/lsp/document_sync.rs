@@ -0,0 +1,105 @@
+use std::ops::Range;
+
+use gpui::App;
+use lsp_types::TextDocumentContentChangeEvent;
+use ropey::Rope;
+
+use crate::input::{Lsp, RopeExt as _};
+
+/// Document synchronization provider, to keep the document of the language server in sync with the editor.
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_synchronization
+pub trait DocumentSyncProvider {
+    /// textDocument/didChange
+    ///
+    /// Called after each edit with the incremental change, the `text` is the text after the change.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_didChange
+    fn did_change(
+        &self,
+        _changes: Vec<TextDocumentContentChangeEvent>,
+        _text: &Rope,
+        _cx: &mut App,
+    );
+}
+
+impl Lsp {
+    /// Notify the [`DocumentSyncProvider`] that the `range` (byte offsets of the `old_text`) is replaced by the `new_text`.
+    pub(crate) fn did_change(
+        &self,
+        old_text: &Rope,
+        range: &Range<usize>,
+        new_text: &str,
+        text: &Rope,
+        cx: &mut App,
+    ) {
+        let Some(provider) = self.document_sync_provider.as_ref() else {
+            return;
+        };
+
+        provider.did_change(vec![text_change_event(old_text, range, new_text)], text, cx);
+    }
+
+    /// Notify the [`DocumentSyncProvider`] that the ranges of the `edits` (sorted and not overlapped
+    /// byte offsets of the `old_text`) are replaced, the changes are sent from the last edit in one notification.
+    pub(crate) fn did_change_edits(
+        &self,
+        old_text: &Rope,
+        edits: &[(Range<usize>, String)],
+        text: &Rope,
+        cx: &mut App,
+    ) {
+        let Some(provider) = self.document_sync_provider.as_ref() else {
+            return;
+        };
+
+        // Each range is still in the text after the previous changes, as the changes are in reverse order.
+        let changes = edits
+            .iter()
+            .rev()
+            .map(|(range, new_text)| text_change_event(old_text, range, new_text))
+            .collect();
+        provider.did_change(changes, text, cx);
+    }
+}
+
+/// Build the incremental [`TextDocumentContentChangeEvent`] for the edit.
+fn text_change_event(
+    old_text: &Rope,
+    range: &Range<usize>,
+    new_text: &str,
+) -> TextDocumentContentChangeEvent {
+    let start = old_text.offset_to_position(range.start);
+    let end = old_text.offset_to_position(range.end);
+
+    TextDocumentContentChangeEvent {
+        range: Some(lsp_types::Range::new(start, end)),
+        range_length: None,
+        text: new_text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+    use ropey::Rope;
+
+    use super::text_change_event;
+
+    #[test]
+    fn test_text_change_event() {
+        let text = Rope::from("fn main() {\n    println!(\"你好\");\n}");
+        let event = text_change_event(&text, &(16..24), "print");
+        assert_eq!(
+            event.range,
+            Some(Range::new(Position::new(1, 4), Position::new(1, 12)))
+        );
+        assert_eq!(event.text, "print");
+
+        let event = text_change_event(&text, &(26..32), "");
+        assert_eq!(
+            event.range,
+            Some(Range::new(Position::new(1, 14), Position::new(1, 16)))
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use gpui::{App, Context, Task, Window};
+use lsp_types::FoldingRange;
+use ropey::Rope;
+
+use crate::input::{FoldRange, InputState, Lsp};
+
+pub trait FoldingRangeProvider {
+    /// Fetches the folding ranges of the document.
+    ///
+    /// textDocument/foldingRange
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_foldingRange
+    fn folding_ranges(
+        &self,
+        _text: &Rope,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<FoldingRange>>>;
+}
+
+impl Lsp {
+    pub(crate) fn update_folding_ranges(
+        &mut self,
+        text: &Rope,
+        window: &mut Window,
+        cx: &mut Context<InputState>,
+    ) {
+        let Some(provider) = self.folding_range_provider.as_ref() else {
+            return;
+        };
+
+        let provider = provider.clone();
+        let text = text.clone();
+        let input_state = cx.entity();
+
+        // debounce timer 100ms
+        self._folding_range_task = cx.spawn_in(window, async move |_, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(100))
+                .await;
+
+            let task_result = cx
+                .update(|window, cx| provider.folding_ranges(&text, window, cx))
+                .ok();
+
+            if let Some(task) = task_result {
+                if let Ok(ranges) = task.await {
+                    let _ = input_state.update(cx, |input_state, cx| {
+                        let folding_ranges: Vec<FoldRange> = ranges
+                            .iter()
+                            .map(|range| {
+                                FoldRange::new(range.start_line as usize, range.end_line as usize)
+                            })
+                            .collect();
+
+                        if folding_ranges != input_state.lsp.folding_ranges {
+                            input_state.lsp.folding_ranges = folding_ranges;
+                            input_state.foldable_ranges = None;
+                            cx.notify();
+                        }
+                    });
+                }
+            }
+        });
+    }
+}
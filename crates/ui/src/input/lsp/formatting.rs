@@ -0,0 +1,390 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use gpui::{App, Context, SharedString, Task, Window};
+use lsp_types::{FormattingOptions, TextEdit};
+use ropey::Rope;
+
+use crate::input::{FormatDocument, FormatSelection, InputState, RopeExt, Selection};
+
+/// Formatting provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_formatting
+pub trait FormattingProvider {
+    /// textDocument/formatting
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_formatting
+    fn formatting(
+        &self,
+        _text: &Rope,
+        _options: FormattingOptions,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<TextEdit>>>;
+
+    /// textDocument/rangeFormatting
+    ///
+    /// - The `range` is in bytes of the text.
+    ///
+    /// Default to format the whole document.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rangeFormatting
+    fn range_formatting(
+        &self,
+        text: &Rope,
+        _range: Range<usize>,
+        options: FormattingOptions,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<TextEdit>>> {
+        self.formatting(text, options, window, cx)
+    }
+
+    /// textDocument/onTypeFormatting
+    ///
+    /// - The `offset` is in bytes of current cursor, after the `ch` typed.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_onTypeFormatting
+    fn on_type_formatting(
+        &self,
+        _text: &Rope,
+        _offset: usize,
+        _ch: &str,
+        _options: FormattingOptions,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<TextEdit>>> {
+        Task::ready(Ok(vec![]))
+    }
+
+    /// The characters that trigger the on type formatting, e.g.: `}`, `;`, `\n`.
+    ///
+    /// Default: empty, the on type formatting is disabled.
+    fn on_type_formatting_trigger_characters(&self) -> Vec<SharedString> {
+        vec![]
+    }
+}
+
+impl InputState {
+    pub(crate) fn on_action_format_document(
+        &mut self,
+        _: &FormatDocument,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let task = self.format_document(window, cx);
+        self.lsp._formatting_task = task;
+    }
+
+    pub(crate) fn on_action_format_selection(
+        &mut self,
+        _: &FormatSelection,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let task = self.format_selection(window, cx);
+        self.lsp._formatting_task = task;
+    }
+
+    fn formatting_options(&self) -> FormattingOptions {
+        let tab_size = self.mode.tab_size();
+        FormattingOptions {
+            tab_size: tab_size.tab_size as u32,
+            insert_spaces: !tab_size.hard_tabs,
+            ..Default::default()
+        }
+    }
+
+    /// Format the whole document by the [`FormattingProvider`].
+    ///
+    /// Await the returned task to format before saving (format on save), the formatting is canceled if the task is dropped.
+    pub fn format_document(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(provider) = self.lsp.formatting_provider.clone() else {
+            return Task::ready(Ok(()));
+        };
+
+        let task = provider.formatting(&self.text, self.formatting_options(), window, cx);
+        self.spawn_formatting(task, window, cx)
+    }
+
+    /// Format the selected lines by the [`FormattingProvider`], the current line is formatted if no selection.
+    pub fn format_selection(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(provider) = self.lsp.formatting_provider.clone() else {
+            return Task::ready(Ok(()));
+        };
+
+        let range = if self.selected_range.is_empty() {
+            let row = self.text.offset_to_point(self.cursor()).row;
+            self.text.line_start_offset(row)..self.text.line_end_offset(row)
+        } else {
+            self.selected_range.start..self.selected_range.end
+        };
+
+        let task =
+            provider.range_formatting(&self.text, range, self.formatting_options(), window, cx);
+        self.spawn_formatting(task, window, cx)
+    }
+
+    /// Request the on type formatting if the `new_text` is a trigger character.
+    pub(crate) fn handle_on_type_formatting(
+        &mut self,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.mode.is_code_editor() || self.has_multiple_cursors() {
+            return;
+        }
+
+        let Some(provider) = self.lsp.formatting_provider.clone() else {
+            return;
+        };
+
+        // The new line is inserted with the indent, use the `\n` as the trigger character.
+        let ch =
+            if new_text.starts_with('\n') && new_text.trim_start_matches('\n').trim().is_empty() {
+                "\n"
+            } else {
+                new_text
+            };
+        if !provider
+            .on_type_formatting_trigger_characters()
+            .iter()
+            .any(|c| c.as_ref() == ch)
+        {
+            return;
+        }
+
+        let task = provider.on_type_formatting(
+            &self.text,
+            self.cursor(),
+            ch,
+            self.formatting_options(),
+            window,
+            cx,
+        );
+        self.lsp._formatting_task = self.spawn_formatting(task, window, cx);
+    }
+
+    fn spawn_formatting(
+        &mut self,
+        task: Task<Result<Vec<TextEdit>>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let text = self.text.clone();
+        cx.spawn_in(window, async move |editor, cx| {
+            let edits = task.await?;
+
+            editor.update_in(cx, |editor, window, cx| {
+                // The edits are outdated if the text has been changed.
+                if editor.text != text {
+                    return;
+                }
+
+                editor.apply_formatting_edits(edits, window, cx);
+            })
+        })
+    }
+
+    /// Apply the [`TextEdit`]s (based on the current text) in one batch as one undo step,
+    /// and keep the cursors and selections at the same positions of the formatted text.
+    pub(crate) fn apply_formatting_edits(
+        &mut self,
+        edits: Vec<TextEdit>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut edits = edits
+            .into_iter()
+            .enumerate()
+            .map(|(ix, edit)| {
+                let range = self.text.position_to_offset(&edit.range.start)
+                    ..self.text.position_to_offset(&edit.range.end);
+                (ix, range, edit.new_text)
+            })
+            .filter(|(_, range, new_text)| self.text.slice(range.clone()).to_string() != *new_text)
+            .collect::<Vec<_>>();
+        if edits.is_empty() {
+            return;
+        }
+
+        // The edits at the same position are applied in the given order.
+        edits.sort_by_key(|(ix, range, _)| (range.start, *ix));
+        let changes = edits
+            .iter()
+            .map(|(_, range, new_text)| (range.clone(), new_text.len()))
+            .collect::<Vec<_>>();
+
+        let map_selection = |selection: Selection| -> Selection {
+            (map_offset(selection.start, &changes)..map_offset(selection.end, &changes)).into()
+        };
+        let selected_range = map_selection(self.selected_range);
+        let extra_selections = self
+            .extra_selections
+            .iter()
+            .copied()
+            .map(map_selection)
+            .collect::<Vec<_>>();
+        let selection_reversed = self.selection_reversed;
+
+        let edits = edits
+            .into_iter()
+            .map(|(_, range, new_text)| (range, new_text))
+            .collect::<Vec<_>>();
+        let version = self.version;
+        self.replace_texts_in_ranges(&edits, window, cx);
+        // The edits are blocked (e.g.: in the read-only mode).
+        if self.version == version {
+            return;
+        }
+
+        self.selected_range = selected_range;
+        self.selection_reversed = selection_reversed;
+        self.extra_selections = extra_selections;
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        cx.notify();
+    }
+}
+
+/// Map the offset of the text before the edits to the offset after the edits.
+///
+/// The `changes` are the replaced ranges (sorted and not overlapped) and the new text length,
+/// the offset inside a replaced range keeps the relative position in the new text.
+fn map_offset(offset: usize, changes: &[(Range<usize>, usize)]) -> usize {
+    let mut delta = 0isize;
+    for (range, new_len) in changes {
+        if range.end <= offset {
+            delta += *new_len as isize - range.len() as isize;
+        } else if range.start < offset {
+            let start = range.start.saturating_add_signed(delta);
+            return start + (offset - range.start).min(*new_len);
+        } else {
+            break;
+        }
+    }
+
+    offset.saturating_add_signed(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use gpui::{App, AppContext as _, TestAppContext};
+    use lsp_types::{Position, Range, TextDocumentContentChangeEvent, TextEdit};
+    use ropey::Rope;
+
+    use super::map_offset;
+    use crate::{
+        history::History,
+        input::{DocumentSyncProvider, InputState, Undo},
+    };
+
+    struct FakeDocumentSync(Rc<RefCell<Vec<Vec<TextDocumentContentChangeEvent>>>>);
+
+    impl DocumentSyncProvider for FakeDocumentSync {
+        fn did_change(&self, changes: Vec<TextDocumentContentChangeEvent>, _: &Rope, _: &mut App) {
+            self.0.borrow_mut().push(changes);
+        }
+    }
+
+    fn edit(start: u32, end: u32, new_text: &str) -> TextEdit {
+        TextEdit::new(
+            Range::new(Position::new(0, start), Position::new(0, end)),
+            new_text.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_map_offset() {
+        // "fn  foo( a )" -> "fn foo(a)"
+        let changes = vec![(2..4, 1), (8..9, 0), (10..11, 0)];
+        assert_eq!(map_offset(0, &changes), 0);
+        assert_eq!(map_offset(2, &changes), 2);
+        assert_eq!(map_offset(4, &changes), 3);
+        assert_eq!(map_offset(6, &changes), 5);
+        assert_eq!(map_offset(9, &changes), 7);
+        assert_eq!(map_offset(10, &changes), 8);
+        assert_eq!(map_offset(12, &changes), 9);
+
+        // Inside the replaced range.
+        let changes = vec![(2..8, 3)];
+        assert_eq!(map_offset(3, &changes), 3);
+        assert_eq!(map_offset(7, &changes), 5);
+        assert_eq!(map_offset(8, &changes), 5);
+
+        // Insert at the offset.
+        let changes = vec![(4..4, 2)];
+        assert_eq!(map_offset(3, &changes), 3);
+        assert_eq!(map_offset(4, &changes), 6);
+    }
+
+    #[gpui::test]
+    fn test_apply_formatting_edits(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .code_editor("text")
+                    .default_value("fn  foo( a )")
+            })
+        });
+        let changes = Rc::new(RefCell::new(vec![]));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // Every change is a new undo step without the grouping.
+                state.history = History::new().group_interval(Duration::ZERO);
+                state.lsp.document_sync_provider = Some(Rc::new(FakeDocumentSync(changes.clone())));
+                state.selected_range = (10..12).into();
+
+                let version = state.version();
+                state.apply_formatting_edits(
+                    vec![edit(8, 9, ""), edit(2, 4, " "), edit(10, 11, "")],
+                    window,
+                    cx,
+                );
+                assert_eq!(state.value(), "fn foo(a)");
+                assert_eq!(state.selected_range, (8..9).into());
+                assert_eq!(state.version(), version + 3);
+
+                // The language server is notified once, the changes are from the last edit.
+                let changes = changes.borrow().clone();
+                assert_eq!(changes.len(), 1);
+                assert_eq!(
+                    changes[0]
+                        .iter()
+                        .map(|change| (change.range.unwrap(), change.text.as_str()))
+                        .collect::<Vec<_>>(),
+                    vec![
+                        (Range::new(Position::new(0, 10), Position::new(0, 11)), ""),
+                        (Range::new(Position::new(0, 8), Position::new(0, 9)), ""),
+                        (Range::new(Position::new(0, 2), Position::new(0, 4)), " "),
+                    ]
+                );
+
+                // The edits are undone as one step.
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "fn  foo( a )");
+            });
+        });
+    }
+}
@@ -0,0 +1,162 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::Result;
+use gpui::{App, Context, SharedString, Task, Window};
+use lsp_types::{InlayHint, InlayHintLabel};
+use ropey::Rope;
+
+use crate::input::{InputState, Lsp, RopeExt};
+
+/// Inlay hint provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_inlayHint
+pub trait InlayHintProvider {
+    /// textDocument/inlayHint
+    ///
+    /// - The `range` is in bytes of the visible text.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_inlayHint
+    fn inlay_hints(
+        &self,
+        _text: &Rope,
+        _range: Range<usize>,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<InlayHint>>>;
+}
+
+impl Lsp {
+    /// Get the inlay hints in the visible range (0-based row).
+    ///
+    /// Returns the byte offsets and labels, sorted by offset.
+    pub(crate) fn inlay_hints_for_range(
+        &self,
+        text: &Rope,
+        visible_range: &Range<usize>,
+    ) -> Vec<(usize, SharedString)> {
+        self.inlay_hints
+            .iter()
+            .filter(|hint| {
+                let line = hint.position.line as usize;
+                line >= visible_range.start && line < visible_range.end
+            })
+            .map(|hint| {
+                let offset = text.position_to_offset(&hint.position);
+                (offset, inlay_hint_label(hint))
+            })
+            .filter(|(_, label)| !label.is_empty())
+            .collect()
+    }
+
+    /// Request the inlay hints of the visible rows, skip if they are already requested.
+    pub(crate) fn update_inlay_hints(
+        &mut self,
+        text: &Rope,
+        visible_range: &Range<usize>,
+        window: &mut Window,
+        cx: &mut Context<InputState>,
+    ) {
+        let Some(provider) = self.inlay_hint_provider.clone() else {
+            return;
+        };
+
+        if self.inlay_hints_range.as_ref() == Some(visible_range) {
+            return;
+        }
+        self.inlay_hints_range = Some(visible_range.clone());
+
+        let text = text.clone();
+        let range = text.line_start_offset(visible_range.start)
+            ..text.line_end_offset(visible_range.end.saturating_sub(1));
+
+        // debounce timer 100ms, to avoid request on every scroll.
+        self._inlay_hint_task = cx.spawn_in(window, async move |input_state, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(100))
+                .await;
+
+            let task_result = cx
+                .update(|window, cx| provider.inlay_hints(&text, range, window, cx))
+                .ok();
+
+            if let Some(task) = task_result {
+                if let Ok(mut hints) = task.await {
+                    let _ = input_state.update(cx, |input_state, cx| {
+                        // The hints are outdated if the text has been changed.
+                        if input_state.text != text {
+                            return;
+                        }
+
+                        hints.sort_by_key(|hint| hint.position);
+                        if hints != input_state.lsp.inlay_hints {
+                            input_state.lsp.inlay_hints = hints;
+                            cx.notify();
+                        }
+                    });
+                }
+            }
+        });
+    }
+}
+
+/// Get the display text of the inlay hint, include the paddings.
+fn inlay_hint_label(hint: &InlayHint) -> SharedString {
+    let label = match &hint.label {
+        InlayHintLabel::String(label) => label.clone(),
+        InlayHintLabel::LabelParts(parts) => parts.iter().map(|part| part.value.as_str()).collect(),
+    };
+    if label.is_empty() {
+        return SharedString::default();
+    }
+
+    let mut text = String::with_capacity(label.len() + 2);
+    if hint.padding_left == Some(true) {
+        text.push(' ');
+    }
+    text.push_str(&label);
+    if hint.padding_right == Some(true) {
+        text.push(' ');
+    }
+    text.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintLabelPart, Position};
+
+    use super::inlay_hint_label;
+
+    #[test]
+    fn test_inlay_hint_label() {
+        let mut hint = InlayHint {
+            position: Position::new(0, 5),
+            label: InlayHintLabel::String(": i32".to_string()),
+            kind: Some(InlayHintKind::TYPE),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: None,
+            data: None,
+        };
+        assert_eq!(inlay_hint_label(&hint).as_ref(), ": i32");
+
+        hint.kind = Some(InlayHintKind::PARAMETER);
+        hint.label = InlayHintLabel::LabelParts(vec![
+            InlayHintLabelPart {
+                value: "name".to_string(),
+                ..Default::default()
+            },
+            InlayHintLabelPart {
+                value: ":".to_string(),
+                ..Default::default()
+            },
+        ]);
+        hint.padding_right = Some(true);
+        assert_eq!(inlay_hint_label(&hint).as_ref(), "name: ");
+
+        hint.padding_left = Some(true);
+        hint.label = InlayHintLabel::String("".to_string());
+        assert_eq!(inlay_hint_label(&hint).as_ref(), "");
+    }
+}
@@ -1,21 +1,43 @@
 use anyhow::Result;
 use gpui::{App, Context, Hsla, MouseMoveEvent, Task, Window};
 use ropey::Rope;
-use std::rc::Rc;
+use std::{ops::Range, rc::Rc};
 
-use crate::input::{InputState, RopeExt, popovers::ContextMenu};
+use crate::input::{FoldRange, InputState, RopeExt, popovers::ContextMenu};
 
+#[cfg(feature = "lsp-client")]
+mod client;
 mod code_actions;
 mod completions;
 mod definitions;
 mod document_colors;
+mod document_symbols;
+mod document_sync;
+mod folding_ranges;
+mod formatting;
 mod hover;
+mod inlay_hints;
+mod references;
+mod rename;
+mod semantic_tokens;
+mod signature_help;
 
+#[cfg(feature = "lsp-client")]
+pub use client::*;
 pub use code_actions::*;
 pub use completions::*;
 pub use definitions::*;
 pub use document_colors::*;
+pub use document_symbols::*;
+pub use document_sync::*;
+pub use folding_ranges::*;
+pub use formatting::*;
 pub use hover::*;
+pub use inlay_hints::*;
+pub use references::*;
+pub use rename::*;
+pub use semantic_tokens::*;
+pub use signature_help::*;
 
 /// LSP ServerCapabilities
 ///
@@ -31,10 +53,49 @@ pub struct Lsp {
     pub definition_provider: Option<Rc<dyn DefinitionProvider>>,
     /// The document color provider.
     pub document_color_provider: Option<Rc<dyn DocumentColorProvider>>,
+    /// The folding range provider.
+    pub folding_range_provider: Option<Rc<dyn FoldingRangeProvider>>,
+    /// The rename provider.
+    pub rename_provider: Option<Rc<dyn RenameProvider>>,
+    /// The signature help provider.
+    pub signature_help_provider: Option<Rc<dyn SignatureHelpProvider>>,
+    /// The formatting provider.
+    pub formatting_provider: Option<Rc<dyn FormattingProvider>>,
+    /// The inlay hint provider.
+    pub inlay_hint_provider: Option<Rc<dyn InlayHintProvider>>,
+    /// The references provider.
+    pub references_provider: Option<Rc<dyn ReferencesProvider>>,
+    /// The document symbol provider.
+    pub document_symbol_provider: Option<Rc<dyn DocumentSymbolProvider>>,
+    /// The semantic tokens provider.
+    pub semantic_tokens_provider: Option<Rc<dyn SemanticTokensProvider>>,
+    /// The document sync provider.
+    pub document_sync_provider: Option<Rc<dyn DocumentSyncProvider>>,
+    /// The URI of the current document.
+    ///
+    /// Used to pick the edits of the current document from a [`lsp_types::WorkspaceEdit`],
+    /// if `None`, all the edits will be applied to the current document.
+    pub document_uri: Option<lsp_types::Uri>,
 
     document_colors: Vec<(lsp_types::Range, Hsla)>,
+    pub(super) folding_ranges: Vec<FoldRange>,
+    pub(super) document_symbols: Vec<lsp_types::DocumentSymbol>,
+    /// The last semantic tokens response, to apply the delta edits.
+    semantic_tokens: lsp_types::SemanticTokens,
+    semantic_token_ranges: Vec<SemanticTokenRange>,
+    inlay_hints: Vec<lsp_types::InlayHint>,
+    /// The visible rows of the last inlay hints request.
+    inlay_hints_range: Option<Range<usize>>,
     _hover_task: Task<Result<()>>,
     _document_color_task: Task<()>,
+    _folding_range_task: Task<()>,
+    _rename_task: Task<Result<()>>,
+    _signature_help_task: Task<Result<()>>,
+    _formatting_task: Task<Result<()>>,
+    _inlay_hint_task: Task<()>,
+    _references_task: Task<Result<()>>,
+    _document_symbol_task: Task<()>,
+    _semantic_tokens_task: Task<()>,
 }
 
 impl Default for Lsp {
@@ -45,9 +106,33 @@ impl Default for Lsp {
             hover_provider: None,
             definition_provider: None,
             document_color_provider: None,
+            folding_range_provider: None,
+            rename_provider: None,
+            signature_help_provider: None,
+            formatting_provider: None,
+            inlay_hint_provider: None,
+            references_provider: None,
+            document_symbol_provider: None,
+            semantic_tokens_provider: None,
+            document_sync_provider: None,
+            document_uri: None,
             document_colors: vec![],
+            folding_ranges: vec![],
+            document_symbols: vec![],
+            semantic_tokens: Default::default(),
+            semantic_token_ranges: vec![],
+            inlay_hints: vec![],
+            inlay_hints_range: None,
             _hover_task: Task::ready(Ok(())),
             _document_color_task: Task::ready(()),
+            _folding_range_task: Task::ready(()),
+            _rename_task: Task::ready(Ok(())),
+            _signature_help_task: Task::ready(Ok(())),
+            _formatting_task: Task::ready(Ok(())),
+            _inlay_hint_task: Task::ready(()),
+            _references_task: Task::ready(Ok(())),
+            _document_symbol_task: Task::ready(()),
+            _semantic_tokens_task: Task::ready(()),
         }
     }
 }
@@ -61,13 +146,32 @@ impl Lsp {
         cx: &mut Context<InputState>,
     ) {
         self.update_document_colors(text, window, cx);
+        self.update_folding_ranges(text, window, cx);
+        self.update_document_symbols(text, window, cx);
+        self.update_semantic_tokens(text, window, cx);
+        // Request the inlay hints again on next paint.
+        self.inlay_hints_range = None;
     }
 
     /// Reset all LSP states.
     pub(crate) fn reset(&mut self) {
         self.document_colors.clear();
+        self.folding_ranges.clear();
+        self.document_symbols.clear();
+        self.semantic_tokens = Default::default();
+        self.semantic_token_ranges.clear();
+        self.inlay_hints.clear();
+        self.inlay_hints_range = None;
         self._hover_task = Task::ready(Ok(()));
         self._document_color_task = Task::ready(());
+        self._folding_range_task = Task::ready(());
+        self._rename_task = Task::ready(Ok(()));
+        self._signature_help_task = Task::ready(Ok(()));
+        self._formatting_task = Task::ready(Ok(()));
+        self._inlay_hint_task = Task::ready(());
+        self._references_task = Task::ready(Ok(()));
+        self._document_symbol_task = Task::ready(());
+        self._semantic_tokens_task = Task::ready(());
     }
 }
 
@@ -125,13 +229,20 @@ impl InputState {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        for edit in text_edits {
+        // Group all the edits into one undo step.
+        self.history.break_grouping();
+        for (ix, edit) in text_edits.iter().enumerate() {
+            if ix > 0 {
+                self.history.start_grouping();
+            }
+
             let start = self.text.position_to_offset(&edit.range.start);
             let end = self.text.position_to_offset(&edit.range.end);
 
             let range_utf16 = self.range_to_utf16(&(start..end));
             self.replace_text_in_range_silent(Some(range_utf16), &edit.new_text, window, cx);
         }
+        self.history.break_grouping();
     }
 
     pub(super) fn handle_mouse_move(
@@ -0,0 +1,392 @@
+use anyhow::Result;
+use gpui::{App, Context, Task, Window};
+use lsp_types::{Location, ReferenceContext, Uri};
+use ropey::Rope;
+
+use crate::input::{
+    FindAllReferences, InputEvent, InputState, RopeExt,
+    mode::InputMode,
+    popovers::{ReferenceItem, ReferencesPopover},
+};
+
+/// References provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references
+pub trait ReferencesProvider {
+    /// textDocument/references
+    ///
+    /// - The `offset` is in bytes of current cursor.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references
+    fn references(
+        &self,
+        _text: &Rope,
+        _offset: usize,
+        _context: ReferenceContext,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Vec<Location>>>;
+
+    /// Return the text of the other document (not the [`crate::input::Lsp::document_uri`]) to preview the references.
+    ///
+    /// Default: `None`, the references of other documents are listed without preview.
+    fn document_text(&self, _uri: &Uri, _cx: &mut App) -> Option<Rope> {
+        None
+    }
+}
+
+impl InputState {
+    pub(crate) fn on_action_find_all_references(
+        &mut self,
+        _: &FindAllReferences,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.find_all_references(window, cx);
+    }
+
+    /// Show the references of the symbol at the cursor in a peek view.
+    pub(crate) fn find_all_references(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let InputMode::CodeEditor { language, .. } = &self.mode else {
+            return;
+        };
+        let language = language.clone();
+
+        let Some(provider) = self.lsp.references_provider.clone() else {
+            return;
+        };
+
+        let offset = self.cursor();
+        let symbol_range = self.text.word_range(offset).unwrap_or(offset..offset);
+        let context = ReferenceContext {
+            include_declaration: true,
+        };
+        let version = self.version;
+        let task = provider.references(&self.text, offset, context, window, cx);
+        self.lsp._references_task = cx.spawn_in(window, async move |editor, cx| {
+            let locations = task.await?;
+
+            editor.update_in(cx, |editor, window, cx| {
+                // The text has been changed, the locations may be outdated.
+                if editor.version != version {
+                    return;
+                }
+
+                let document_uri = editor.lsp.document_uri.clone();
+                let mut items = locations
+                    .into_iter()
+                    .map(|location| {
+                        let is_current = document_uri
+                            .as_ref()
+                            .map_or(false, |uri| *uri == location.uri);
+                        let text = if is_current {
+                            Some(editor.text.clone())
+                        } else {
+                            provider.document_text(&location.uri, cx)
+                        };
+
+                        ReferenceItem::new(location, is_current, text)
+                    })
+                    .collect::<Vec<_>>();
+                if items.is_empty() {
+                    editor.references_popover = None;
+                    cx.notify();
+                    return;
+                }
+
+                // The references of current document first.
+                items.sort_by(|a, b| {
+                    b.is_current
+                        .cmp(&a.is_current)
+                        .then_with(|| a.location.uri.as_str().cmp(b.location.uri.as_str()))
+                        .then_with(|| a.location.range.start.cmp(&b.location.range.start))
+                });
+
+                editor.hover_popover = None;
+                editor.signature_help_popover = None;
+                editor.references_popover = Some(ReferencesPopover::new(
+                    cx.entity(),
+                    symbol_range,
+                    version,
+                    items,
+                    language,
+                    window,
+                    cx,
+                ));
+                cx.notify();
+            })
+        });
+    }
+
+    /// Select the previous or next reference in the peek view, return true if handled.
+    pub(crate) fn select_reference(
+        &mut self,
+        delta: isize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(popover) = self.references_popover.as_ref() else {
+            return false;
+        };
+
+        popover.update(cx, |popover, cx| popover.select_delta(delta, window, cx));
+        true
+    }
+
+    /// Open the selected reference in the peek view, return true if handled.
+    pub(crate) fn confirm_reference(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(popover) = self.references_popover.as_ref() else {
+            return false;
+        };
+
+        let popover = popover.read(cx);
+        let version = popover.version;
+        let Some(item) = popover.selected_item() else {
+            return false;
+        };
+        let (location, is_current) = (item.location.clone(), item.is_current);
+        self.open_reference(location, is_current, version, window, cx);
+        true
+    }
+
+    /// Move to the reference in current document,
+    /// or emit [`InputEvent::OpenLocation`] for the reference in other document.
+    ///
+    /// The `version` is the text version of the request, the reference in current document
+    /// is dropped if the text has been changed since then.
+    pub(crate) fn open_reference(
+        &mut self,
+        location: Location,
+        is_current: bool,
+        version: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.hide_references(cx);
+        self.focus(window, cx);
+
+        if is_current {
+            if self.version != version {
+                return;
+            }
+
+            let start = self.text.position_to_offset(&location.range.start);
+            let end = self.text.position_to_offset(&location.range.end);
+            self.move_to(start, None, cx);
+            self.select_to(end, cx);
+        } else {
+            cx.emit(InputEvent::OpenLocation { location });
+        }
+    }
+
+    /// Hide the references peek view, return true if it was showing.
+    pub(crate) fn hide_references(&mut self, cx: &mut Context<Self>) -> bool {
+        if self.references_popover.take().is_none() {
+            return false;
+        }
+
+        self.lsp._references_task = Task::ready(Ok(()));
+        cx.notify();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, str::FromStr as _};
+
+    use anyhow::Result;
+    use gpui::{
+        App, AppContext as _, Entity, EntityInputHandler as _, Task, TestAppContext,
+        VisualTestContext, Window,
+    };
+    use lsp_types::{Location, Position, Range, ReferenceContext, Uri};
+    use ropey::Rope;
+
+    use super::ReferencesProvider;
+    use crate::input::{InputEvent, InputState};
+
+    const MAIN: &str = "file:///main.rs";
+
+    struct FakeReferencesProvider(Vec<Location>);
+
+    impl ReferencesProvider for FakeReferencesProvider {
+        fn references(
+            &self,
+            _: &Rope,
+            _: usize,
+            _: ReferenceContext,
+            _: &mut Window,
+            _: &mut App,
+        ) -> Task<Result<Vec<Location>>> {
+            Task::ready(Ok(self.0.clone()))
+        }
+    }
+
+    fn location(uri: &str, line: u32, character: u32) -> Location {
+        Location::new(
+            Uri::from_str(uri).unwrap(),
+            Range::new(
+                Position::new(line, character),
+                Position::new(line, character + 3),
+            ),
+        )
+    }
+
+    fn find_all_references(
+        state: &Entity<InputState>,
+        locations: Vec<Location>,
+        cx: &mut VisualTestContext,
+    ) {
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.lsp.references_provider = Some(Rc::new(FakeReferencesProvider(locations)));
+                state.find_all_references(window, cx);
+            });
+        });
+        cx.run_until_parked();
+    }
+
+    fn selected_location(state: &Entity<InputState>, cx: &mut VisualTestContext) -> Location {
+        state.read_with(cx, |state, cx| {
+            let popover = state.references_popover.as_ref().unwrap().read(cx);
+            popover.selected_item().unwrap().location.clone()
+        })
+    }
+
+    fn select_reference(state: &Entity<InputState>, delta: isize, cx: &mut VisualTestContext) {
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                assert!(state.select_reference(delta, window, cx));
+            });
+        });
+    }
+
+    fn setup(cx: &mut TestAppContext) -> (Entity<InputState>, &mut VisualTestContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                let mut state = InputState::new(window, cx)
+                    .code_editor("text")
+                    .default_value("foo bar\nfoo baz\nfoo");
+                state.lsp.document_uri = Some(Uri::from_str(MAIN).unwrap());
+                state
+            })
+        });
+        (state, cx)
+    }
+
+    #[gpui::test]
+    fn test_find_all_references(cx: &mut TestAppContext) {
+        let (state, cx) = setup(cx);
+        find_all_references(
+            &state,
+            vec![
+                location("file:///b.rs", 0, 0),
+                location(MAIN, 1, 0),
+                location("file:///a.rs", 1, 0),
+                location(MAIN, 0, 0),
+            ],
+            cx,
+        );
+
+        // The references of current document first, then sorted by the URI and position.
+        assert_eq!(selected_location(&state, cx), location(MAIN, 0, 0));
+        select_reference(&state, 1, cx);
+        assert_eq!(selected_location(&state, cx), location(MAIN, 1, 0));
+        select_reference(&state, 1, cx);
+        assert_eq!(
+            selected_location(&state, cx),
+            location("file:///a.rs", 1, 0)
+        );
+        select_reference(&state, 1, cx);
+        assert_eq!(
+            selected_location(&state, cx),
+            location("file:///b.rs", 0, 0)
+        );
+
+        // Wrap around at the ends.
+        select_reference(&state, 1, cx);
+        assert_eq!(selected_location(&state, cx), location(MAIN, 0, 0));
+        select_reference(&state, -1, cx);
+        assert_eq!(
+            selected_location(&state, cx),
+            location("file:///b.rs", 0, 0)
+        );
+
+        // No references, the peek view is hidden.
+        find_all_references(&state, vec![], cx);
+        state.read_with(cx, |state, _| assert!(state.references_popover.is_none()));
+    }
+
+    #[gpui::test]
+    fn test_confirm_reference(cx: &mut TestAppContext) {
+        let (state, cx) = setup(cx);
+        let opened = Rc::new(RefCell::new(vec![]));
+        cx.update(|_, cx| {
+            let opened = opened.clone();
+            cx.subscribe(&state, move |_, event: &InputEvent, _| {
+                if let InputEvent::OpenLocation { location } = event {
+                    opened.borrow_mut().push(location.clone());
+                }
+            })
+            .detach();
+        });
+
+        // The reference in current document is selected.
+        let locations = vec![location(MAIN, 1, 0), location("file:///a.rs", 1, 0)];
+        find_all_references(&state, locations.clone(), cx);
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                assert!(state.confirm_reference(window, cx));
+                assert_eq!(state.selected_range, (8..11).into());
+                assert!(state.references_popover.is_none());
+            });
+        });
+        assert!(opened.borrow().is_empty());
+
+        // The reference in other document is opened by the host.
+        find_all_references(&state, locations.clone(), cx);
+        select_reference(&state, 1, cx);
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                assert!(state.confirm_reference(window, cx));
+                assert_eq!(state.selected_range, (8..11).into());
+                assert!(state.references_popover.is_none());
+            });
+        });
+        assert_eq!(*opened.borrow(), vec![location("file:///a.rs", 1, 0)]);
+    }
+
+    #[gpui::test]
+    fn test_outdated_references(cx: &mut TestAppContext) {
+        let (state, cx) = setup(cx);
+
+        // The text is changed before the response.
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.lsp.references_provider =
+                    Some(Rc::new(FakeReferencesProvider(vec![location(MAIN, 1, 0)])));
+                state.find_all_references(window, cx);
+                state.replace_text_in_range(Some(0..0), "\n", window, cx);
+            });
+        });
+        cx.run_until_parked();
+        state.read_with(cx, |state, _| assert!(state.references_popover.is_none()));
+
+        // The text is changed after the response.
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.selected_range = (0..0).into();
+                let version = state.version;
+                state.replace_text_in_range(Some(0..1), "", window, cx);
+                state.open_reference(location(MAIN, 1, 0), true, version, window, cx);
+                assert_eq!(state.selected_range, (0..0).into());
+            });
+        });
+    }
+}
@@ -0,0 +1,339 @@
+use std::{collections::HashMap, ops::Range};
+
+use anyhow::Result;
+use gpui::{App, Context, Task, Window};
+use lsp_types::{DocumentChangeOperation, DocumentChanges, OneOf, TextEdit, Uri, WorkspaceEdit};
+use ropey::Rope;
+
+use crate::input::{InputEvent, InputState, Rename, RopeExt, popovers::RenamePopover};
+
+/// Rename provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rename
+pub trait RenameProvider {
+    /// textDocument/prepareRename
+    ///
+    /// Return the range (and the placeholder) of the symbol to rename,
+    /// or `None` if the symbol at the offset can not be renamed.
+    ///
+    /// Default to use the word at the offset.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareRename
+    fn prepare_rename(
+        &self,
+        _text: &Rope,
+        _offset: usize,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Option<lsp_types::PrepareRenameResponse>>> {
+        Task::ready(Ok(Some(
+            lsp_types::PrepareRenameResponse::DefaultBehavior {
+                default_behavior: true,
+            },
+        )))
+    }
+
+    /// textDocument/rename
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rename
+    fn rename(
+        &self,
+        _text: &Rope,
+        _offset: usize,
+        _new_name: String,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Option<WorkspaceEdit>>>;
+}
+
+/// The edits of a rename, to preview before apply.
+#[derive(Clone)]
+pub(crate) struct RenamePreview {
+    /// The edits of the current document.
+    pub(crate) edits: Vec<TextEdit>,
+    /// The byte ranges of the `edits` in the current document.
+    pub(crate) ranges: Vec<Range<usize>>,
+    /// The edits of the other documents.
+    pub(crate) changes: HashMap<Uri, Vec<TextEdit>>,
+}
+
+impl RenamePreview {
+    pub(crate) fn edits_count(&self) -> usize {
+        self.edits.len()
+            + self
+                .changes
+                .values()
+                .map(|edits| edits.len())
+                .sum::<usize>()
+    }
+}
+
+impl InputState {
+    pub(crate) fn on_action_rename(
+        &mut self,
+        _: &Rename,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.start_rename(window, cx);
+    }
+
+    /// Show the rename input at the symbol of the cursor.
+    pub(crate) fn start_rename(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.mode.is_code_editor() || self.disabled || self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let Some(provider) = self.lsp.rename_provider.clone() else {
+            return;
+        };
+
+        let offset = self.cursor();
+        let task = provider.prepare_rename(&self.text, offset, window, cx);
+        self.lsp._rename_task = cx.spawn_in(window, async move |editor, cx| {
+            let Some(response) = task.await? else {
+                return Ok(());
+            };
+
+            editor.update_in(cx, |editor, window, cx| {
+                let (range, placeholder) = match response {
+                    lsp_types::PrepareRenameResponse::Range(range) => {
+                        let range = editor.text.position_to_offset(&range.start)
+                            ..editor.text.position_to_offset(&range.end);
+                        let placeholder = editor.text.slice(range.clone()).to_string();
+                        (range, placeholder)
+                    }
+                    lsp_types::PrepareRenameResponse::RangeWithPlaceholder {
+                        range,
+                        placeholder,
+                    } => (
+                        editor.text.position_to_offset(&range.start)
+                            ..editor.text.position_to_offset(&range.end),
+                        placeholder,
+                    ),
+                    lsp_types::PrepareRenameResponse::DefaultBehavior { .. } => {
+                        let Some(range) = editor.text.word_range(offset) else {
+                            return;
+                        };
+                        let placeholder = editor.text.slice(range.clone()).to_string();
+                        (range, placeholder)
+                    }
+                };
+
+                editor.hover_popover = None;
+                editor.rename_popover = Some(RenamePopover::new(
+                    cx.entity(),
+                    range,
+                    &placeholder,
+                    window,
+                    cx,
+                ));
+                cx.notify();
+            })
+        });
+    }
+
+    /// Request the rename edits of the `new_name` for the symbol at the `offset` to preview.
+    pub(crate) fn request_rename(
+        &mut self,
+        offset: usize,
+        new_name: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(provider) = self.lsp.rename_provider.clone() else {
+            return;
+        };
+        let Some(popover) = self.rename_popover.clone() else {
+            return;
+        };
+
+        let task = provider.rename(&self.text, offset, new_name, window, cx);
+        self.lsp._rename_task = cx.spawn_in(window, async move |editor, cx| {
+            let Some(workspace_edit) = task.await? else {
+                return Ok(());
+            };
+
+            editor.update(cx, |editor, cx| {
+                let (edits, changes) =
+                    split_workspace_edit(workspace_edit, editor.lsp.document_uri.as_ref());
+                let ranges = edits
+                    .iter()
+                    .map(|edit| {
+                        editor.text.position_to_offset(&edit.range.start)
+                            ..editor.text.position_to_offset(&edit.range.end)
+                    })
+                    .collect();
+
+                popover.update(cx, |popover, cx| {
+                    popover.set_preview(
+                        RenamePreview {
+                            edits,
+                            ranges,
+                            changes,
+                        },
+                        cx,
+                    );
+                });
+                cx.notify();
+            })
+        });
+    }
+
+    /// Apply the previewed rename edits, the edits of other documents will emit [`InputEvent::WorkspaceEdit`].
+    pub(crate) fn confirm_rename(
+        &mut self,
+        preview: RenamePreview,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.rename_popover.take().is_none() {
+            return;
+        }
+        self.focus_handle.clone().focus(window, cx);
+
+        // The LSP edits are based on the original text, so apply them from the end.
+        let mut edits = preview.edits;
+        edits.sort_by(|a, b| b.range.start.cmp(&a.range.start));
+        self.apply_lsp_edits(&edits, window, cx);
+
+        if !preview.changes.is_empty() {
+            cx.emit(InputEvent::WorkspaceEdit {
+                changes: preview.changes,
+            });
+        }
+        cx.notify();
+    }
+
+    /// Hide the rename input without apply.
+    pub(crate) fn cancel_rename(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.rename_popover.take().is_none() {
+            return;
+        }
+
+        self.lsp._rename_task = Task::ready(Ok(()));
+        self.focus_handle.clone().focus(window, cx);
+        cx.notify();
+    }
+}
+
+/// Split the [`WorkspaceEdit`] into the edits of the current document and the other documents.
+///
+/// The resource operations (create, rename or delete file) are ignored.
+pub(crate) fn split_workspace_edit(
+    workspace_edit: WorkspaceEdit,
+    document_uri: Option<&Uri>,
+) -> (Vec<TextEdit>, HashMap<Uri, Vec<TextEdit>>) {
+    let mut all_changes: Vec<(Uri, Vec<TextEdit>)> = vec![];
+    if let Some(document_changes) = workspace_edit.document_changes {
+        let document_edits = match document_changes {
+            DocumentChanges::Edits(edits) => edits,
+            DocumentChanges::Operations(operations) => operations
+                .into_iter()
+                .filter_map(|operation| match operation {
+                    DocumentChangeOperation::Edit(edit) => Some(edit),
+                    DocumentChangeOperation::Op(_) => None,
+                })
+                .collect(),
+        };
+
+        for document_edit in document_edits {
+            let edits = document_edit
+                .edits
+                .into_iter()
+                .map(|edit| match edit {
+                    OneOf::Left(edit) => edit,
+                    OneOf::Right(edit) => edit.text_edit,
+                })
+                .collect();
+            all_changes.push((document_edit.text_document.uri, edits));
+        }
+    } else if let Some(changes) = workspace_edit.changes {
+        all_changes.extend(changes);
+    }
+
+    let mut edits = vec![];
+    let mut changes: HashMap<Uri, Vec<TextEdit>> = HashMap::new();
+    for (uri, text_edits) in all_changes {
+        if document_uri.map_or(true, |document_uri| *document_uri == uri) {
+            edits.extend(text_edits);
+        } else {
+            changes.entry(uri).or_default().extend(text_edits);
+        }
+    }
+
+    (edits, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr as _};
+
+    use lsp_types::{
+        AnnotatedTextEdit, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+        Position, Range, TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+    };
+
+    use super::split_workspace_edit;
+
+    fn edit(line: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(line, 0), Position::new(line, 3)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_split_workspace_edit() {
+        let a = Uri::from_str("file:///a.rs").unwrap();
+        let b = Uri::from_str("file:///b.rs").unwrap();
+
+        let workspace_edit = WorkspaceEdit {
+            changes: Some(HashMap::from_iter([
+                (a.clone(), vec![edit(0, "foo"), edit(2, "foo")]),
+                (b.clone(), vec![edit(1, "foo")]),
+            ])),
+            ..Default::default()
+        };
+        let (edits, changes) = split_workspace_edit(workspace_edit.clone(), Some(&a));
+        assert_eq!(edits, vec![edit(0, "foo"), edit(2, "foo")]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.get(&b), Some(&vec![edit(1, "foo")]));
+
+        // Without document uri, all edits are for the current document.
+        let (edits, changes) = split_workspace_edit(workspace_edit, None);
+        assert_eq!(edits.len(), 3);
+        assert!(changes.is_empty());
+
+        // The `document_changes` is preferred to the `changes`.
+        let workspace_edit = WorkspaceEdit {
+            changes: Some(HashMap::from_iter([(a.clone(), vec![edit(5, "bar")])])),
+            document_changes: Some(DocumentChanges::Edits(vec![
+                TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: a.clone(),
+                        version: Some(1),
+                    },
+                    edits: vec![
+                        OneOf::Left(edit(0, "foo")),
+                        OneOf::Right(AnnotatedTextEdit {
+                            text_edit: edit(3, "foo"),
+                            annotation_id: "rename".to_string(),
+                        }),
+                    ],
+                },
+                TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: b.clone(),
+                        version: None,
+                    },
+                    edits: vec![OneOf::Left(edit(1, "foo"))],
+                },
+            ])),
+            ..Default::default()
+        };
+        let (edits, changes) = split_workspace_edit(workspace_edit, Some(&a));
+        assert_eq!(edits, vec![edit(0, "foo"), edit(3, "foo")]);
+        assert_eq!(changes.get(&b), Some(&vec![edit(1, "foo")]));
+    }
+}
@@ -0,0 +1,410 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::Result;
+use gpui::{App, Context, HighlightStyle, StrikethroughStyle, Task, UnderlineStyle, Window, px};
+use lsp_types::{
+    Position, SemanticToken, SemanticTokens, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensLegend,
+};
+use ropey::Rope;
+
+use crate::highlighter::HighlightTheme;
+use crate::input::{InputState, Lsp, RopeExt};
+
+/// Semantic tokens provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_semanticTokens
+pub trait SemanticTokensProvider {
+    /// The token types and modifiers of the tokens,
+    /// the `legend` of the `semanticTokensProvider` in the server capabilities.
+    fn legend(&self) -> SemanticTokensLegend;
+
+    /// textDocument/semanticTokens/full
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_fullRequest
+    fn semantic_tokens_full(
+        &self,
+        _text: &Rope,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<SemanticTokens>>;
+
+    /// textDocument/semanticTokens/full/delta
+    ///
+    /// - The `previous_result_id` is the `result_id` of the last response.
+    ///
+    /// Default to request the full tokens.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_deltaRequest
+    fn semantic_tokens_full_delta(
+        &self,
+        text: &Rope,
+        _previous_result_id: &str,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<SemanticTokensFullDeltaResult>> {
+        let task = self.semantic_tokens_full(text, window, cx);
+        cx.spawn(async move |_| Ok(SemanticTokensFullDeltaResult::Tokens(task.await?)))
+    }
+}
+
+/// A semantic token decoded to the byte range of the text.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SemanticTokenRange {
+    range: Range<usize>,
+    /// The highlight name of the token type, e.g.: `variant`.
+    name: Option<&'static str>,
+    /// The `mutable` modifier, the token is underlined.
+    mutable: bool,
+    /// The `deprecated` modifier, the token is struck through.
+    deprecated: bool,
+}
+
+impl SemanticTokenRange {
+    fn style(&self, theme: &HighlightTheme) -> Option<HighlightStyle> {
+        let mut style = self.name.and_then(|name| theme.style(name));
+        if self.mutable {
+            style.get_or_insert_default().underline = Some(UnderlineStyle {
+                thickness: px(1.),
+                ..UnderlineStyle::default()
+            });
+        }
+        if self.deprecated {
+            style.get_or_insert_default().strikethrough = Some(StrikethroughStyle {
+                thickness: px(1.),
+                ..StrikethroughStyle::default()
+            });
+        }
+        style
+    }
+}
+
+impl Lsp {
+    pub(crate) fn update_semantic_tokens(
+        &mut self,
+        text: &Rope,
+        window: &mut Window,
+        cx: &mut Context<InputState>,
+    ) {
+        let Some(provider) = self.semantic_tokens_provider.clone() else {
+            return;
+        };
+
+        let text = text.clone();
+        let previous_result_id = self.semantic_tokens.result_id.clone();
+
+        // debounce timer 100ms
+        self._semantic_tokens_task = cx.spawn_in(window, async move |input_state, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(100))
+                .await;
+
+            let task_result = cx
+                .update(|window, cx| match previous_result_id.as_deref() {
+                    Some(previous_result_id) => {
+                        provider.semantic_tokens_full_delta(&text, previous_result_id, window, cx)
+                    }
+                    None => {
+                        let task = provider.semantic_tokens_full(&text, window, cx);
+                        cx.spawn(async move |_| {
+                            Ok(SemanticTokensFullDeltaResult::Tokens(task.await?))
+                        })
+                    }
+                })
+                .ok();
+
+            if let Some(task) = task_result {
+                let result = task.await;
+                let _ = input_state.update(cx, |input_state, cx| {
+                    let lsp = &mut input_state.lsp;
+                    match result {
+                        Ok(SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                            lsp.semantic_tokens = tokens;
+                        }
+                        Ok(SemanticTokensFullDeltaResult::TokensDelta(delta)) => {
+                            apply_semantic_tokens_edits(&mut lsp.semantic_tokens.data, delta.edits);
+                            lsp.semantic_tokens.result_id = delta.result_id;
+                        }
+                        Ok(SemanticTokensFullDeltaResult::PartialTokensDelta { edits }) => {
+                            apply_semantic_tokens_edits(&mut lsp.semantic_tokens.data, edits);
+                        }
+                        Err(_) => {
+                            // Request the full tokens next time.
+                            lsp.semantic_tokens = SemanticTokens::default();
+                            return;
+                        }
+                    }
+
+                    // Keep the shifted tokens if the text has been changed,
+                    // until the tokens of the current text arrive.
+                    if input_state.text != text {
+                        return;
+                    }
+
+                    let tokens = decode_semantic_tokens(
+                        &text,
+                        &lsp.semantic_tokens.data,
+                        &provider.legend(),
+                    );
+                    if tokens != lsp.semantic_token_ranges {
+                        lsp.semantic_token_ranges = tokens;
+                        cx.notify();
+                    }
+                });
+            }
+        });
+    }
+
+    /// Shift the semantic tokens after the text changed,
+    /// the tokens intersecting with the replaced `range` are removed.
+    pub(crate) fn shift_semantic_tokens(&mut self, range: &Range<usize>, new_len: usize) {
+        shift_semantic_token_ranges(&mut self.semantic_token_ranges, range, new_len);
+    }
+
+    /// Get the semantic token styles in the byte range, sorted by the start offset.
+    pub(crate) fn semantic_token_styles(
+        &self,
+        range: &Range<usize>,
+        theme: &HighlightTheme,
+    ) -> Vec<(Range<usize>, HighlightStyle)> {
+        let start_ix = self
+            .semantic_token_ranges
+            .partition_point(|token| token.range.end <= range.start);
+
+        self.semantic_token_ranges[start_ix..]
+            .iter()
+            .take_while(|token| token.range.start < range.end)
+            .filter_map(|token| Some((token.range.clone(), token.style(theme)?)))
+            .collect()
+    }
+}
+
+/// Returns the highlight name in the [`HighlightTheme`] of the semantic token.
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokenTypes
+fn semantic_token_highlight_name(token_type: &str, modifiers: &[&str]) -> Option<&'static str> {
+    let name = match token_type {
+        "namespace" | "type" | "class" | "enum" | "interface" | "struct" | "typeParameter"
+        | "builtinType" | "typeAlias" => "type",
+        "enumMember" => "variant",
+        "parameter" | "variable" => {
+            if modifiers
+                .iter()
+                .any(|modifier| matches!(*modifier, "readonly" | "constant"))
+            {
+                "constant"
+            } else if modifiers.contains(&"defaultLibrary") {
+                "variable.special"
+            } else {
+                "variable"
+            }
+        }
+        "selfKeyword" | "selfTypeKeyword" => "variable.special",
+        "property" | "event" => "property",
+        "function" | "method" | "macro" => "function",
+        "decorator" | "attribute" => "attribute",
+        "keyword" | "modifier" => "keyword",
+        "comment" => {
+            if modifiers.contains(&"documentation") {
+                "comment.doc"
+            } else {
+                "comment"
+            }
+        }
+        "string" => "string",
+        "number" => "number",
+        "boolean" => "boolean",
+        "regexp" => "string.regex",
+        "operator" => "operator",
+        "label" | "lifetime" => "label",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Decode the relative positions of the semantic tokens to the byte ranges of the text.
+fn decode_semantic_tokens(
+    text: &Rope,
+    tokens: &[SemanticToken],
+    legend: &SemanticTokensLegend,
+) -> Vec<SemanticTokenRange> {
+    let mut line = 0;
+    let mut character = 0;
+    let mut ranges = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if token.delta_line > 0 {
+            line += token.delta_line;
+            character = token.delta_start;
+        } else {
+            character += token.delta_start;
+        }
+
+        let Some(token_type) = legend.token_types.get(token.token_type as usize) else {
+            continue;
+        };
+        let modifiers = legend
+            .token_modifiers
+            .iter()
+            .take(32)
+            .enumerate()
+            .filter(|(ix, _)| token.token_modifiers_bitset & (1 << ix) != 0)
+            .map(|(_, modifier)| modifier.as_str())
+            .collect::<Vec<_>>();
+
+        let token = SemanticTokenRange {
+            range: text.position_to_offset(&Position::new(line, character))
+                ..text.position_to_offset(&Position::new(line, character + token.length)),
+            name: semantic_token_highlight_name(token_type.as_str(), &modifiers),
+            mutable: modifiers.contains(&"mutable"),
+            deprecated: modifiers.contains(&"deprecated"),
+        };
+        if token.range.is_empty() || (token.name.is_none() && !token.mutable && !token.deprecated) {
+            continue;
+        }
+        ranges.push(token);
+    }
+    ranges
+}
+
+/// Apply the [`SemanticTokensEdit`]s of the delta response to the previous tokens.
+///
+/// The `start` and `delete_count` of the edit are the indexes of the integers, each token has 5 integers.
+fn apply_semantic_tokens_edits(
+    tokens: &mut Vec<SemanticToken>,
+    mut edits: Vec<SemanticTokensEdit>,
+) {
+    // Apply from the last edit, so the indexes of the previous edits are not changed.
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    for edit in edits {
+        let start = (edit.start as usize / 5).min(tokens.len());
+        let end = (start + edit.delete_count as usize / 5).min(tokens.len());
+        tokens.splice(start..end, edit.data.unwrap_or_default());
+    }
+}
+
+fn shift_semantic_token_ranges(
+    tokens: &mut Vec<SemanticTokenRange>,
+    range: &Range<usize>,
+    new_len: usize,
+) {
+    tokens.retain_mut(|token| {
+        if token.range.end <= range.start {
+            true
+        } else if token.range.start >= range.end {
+            token.range = token.range.start - range.end + range.start + new_len
+                ..token.range.end - range.end + range.start + new_len;
+            true
+        } else {
+            false
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{
+        SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+        SemanticTokensLegend,
+    };
+    use ropey::Rope;
+
+    use super::{
+        SemanticTokenRange, apply_semantic_tokens_edits, decode_semantic_tokens,
+        shift_semantic_token_ranges,
+    };
+
+    fn token(delta_line: u32, delta_start: u32, length: u32, token_type: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_semantic_tokens() {
+        let text = Rope::from("let mut a = Foo::Bar;\nlet b = a;");
+        let legend = SemanticTokensLegend {
+            token_types: vec![SemanticTokenType::VARIABLE, SemanticTokenType::ENUM_MEMBER],
+            token_modifiers: vec![
+                SemanticTokenModifier::DECLARATION,
+                SemanticTokenModifier::new("mutable"),
+            ],
+        };
+        let mut tokens = vec![
+            token(0, 8, 1, 0),
+            token(0, 9, 3, 1),
+            token(1, 4, 1, 0),
+            token(0, 4, 1, 0),
+            // Unknown token type.
+            token(0, 1, 1, 5),
+        ];
+        tokens[0].token_modifiers_bitset = 0b11;
+        tokens[3].token_modifiers_bitset = 0b10;
+
+        let ranges = decode_semantic_tokens(&text, &tokens, &legend);
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|token| (token.range.clone(), token.name, token.mutable))
+                .collect::<Vec<_>>(),
+            vec![
+                (8..9, Some("variable"), true),
+                (17..20, Some("variant"), false),
+                (26..27, Some("variable"), false),
+                (30..31, Some("variable"), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_semantic_tokens_edits() {
+        let mut tokens = vec![token(0, 0, 1, 0), token(0, 2, 1, 0), token(1, 0, 1, 0)];
+        apply_semantic_tokens_edits(
+            &mut tokens,
+            vec![
+                SemanticTokensEdit {
+                    start: 5,
+                    delete_count: 5,
+                    data: Some(vec![token(0, 3, 2, 1)]),
+                },
+                SemanticTokensEdit {
+                    start: 15,
+                    delete_count: 0,
+                    data: Some(vec![token(2, 0, 1, 0)]),
+                },
+                SemanticTokensEdit {
+                    start: 0,
+                    delete_count: 5,
+                    data: None,
+                },
+            ],
+        );
+        assert_eq!(
+            tokens,
+            vec![token(0, 3, 2, 1), token(1, 0, 1, 0), token(2, 0, 1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_shift_semantic_token_ranges() {
+        let token = |range: std::ops::Range<usize>| SemanticTokenRange {
+            range,
+            name: Some("variable"),
+            mutable: false,
+            deprecated: false,
+        };
+        let mut tokens = vec![token(0..3), token(4..7), token(10..12)];
+
+        // Insert 2 bytes at 3.
+        shift_semantic_token_ranges(&mut tokens, &(3..3), 2);
+        assert_eq!(tokens, vec![token(0..3), token(6..9), token(12..14)]);
+
+        // Replace 7..10 (intersects with 6..9) with 1 byte.
+        shift_semantic_token_ranges(&mut tokens, &(7..10), 1);
+        assert_eq!(tokens, vec![token(0..3), token(10..12)]);
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use gpui::{App, Context, SharedString, Task, Window};
+use lsp_types::{SignatureHelp, SignatureHelpContext, SignatureHelpTriggerKind};
+use ropey::Rope;
+
+use crate::input::{InputState, popovers::SignatureHelpPopover};
+
+/// Signature help provider
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_signatureHelp
+pub trait SignatureHelpProvider {
+    /// textDocument/signatureHelp
+    ///
+    /// - The `offset` is in bytes of current cursor.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_signatureHelp
+    fn signature_help(
+        &self,
+        _text: &Rope,
+        _offset: usize,
+        _context: SignatureHelpContext,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Task<Result<Option<SignatureHelp>>>;
+
+    /// The characters that trigger the signature help automatically.
+    ///
+    /// Default: `(` and `,`
+    fn trigger_characters(&self) -> Vec<SharedString> {
+        vec!["(".into(), ",".into()]
+    }
+
+    /// The characters that re-trigger the signature help when it's already showing.
+    ///
+    /// Default: empty, the signature help will always be updated on the text changes when it's showing.
+    fn retrigger_characters(&self) -> Vec<SharedString> {
+        vec![]
+    }
+}
+
+impl InputState {
+    /// Request the signature help on the text changes.
+    pub(crate) fn handle_signature_help_trigger(
+        &mut self,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.mode.is_code_editor() {
+            return;
+        }
+
+        let Some(provider) = self.lsp.signature_help_provider.clone() else {
+            return;
+        };
+
+        let active_signature_help = self
+            .signature_help_popover
+            .as_ref()
+            .map(|popover| popover.read(cx).signature_help.clone());
+        let is_retrigger = active_signature_help.is_some();
+
+        let mut trigger_character = provider
+            .trigger_characters()
+            .into_iter()
+            .find(|c| !c.is_empty() && new_text.ends_with(c.as_ref()));
+        if trigger_character.is_none() && is_retrigger {
+            trigger_character = provider
+                .retrigger_characters()
+                .into_iter()
+                .find(|c| !c.is_empty() && new_text.ends_with(c.as_ref()));
+        }
+
+        let trigger_kind = if trigger_character.is_some() {
+            SignatureHelpTriggerKind::TRIGGER_CHARACTER
+        } else if is_retrigger {
+            SignatureHelpTriggerKind::CONTENT_CHANGE
+        } else {
+            return;
+        };
+
+        let context = SignatureHelpContext {
+            trigger_kind,
+            trigger_character: trigger_character.map(|c| c.to_string()),
+            is_retrigger,
+            active_signature_help,
+        };
+
+        let offset = self.cursor();
+        let task = provider.signature_help(&self.text, offset, context, window, cx);
+        self.lsp._signature_help_task = cx.spawn_in(window, async move |editor, cx| {
+            let signature_help = task.await?;
+
+            editor.update(cx, |editor, cx| {
+                match signature_help.filter(|help| !help.signatures.is_empty()) {
+                    Some(signature_help) => match editor.signature_help_popover.as_ref() {
+                        Some(popover) => popover.update(cx, |popover, cx| {
+                            popover.update(offset, signature_help, cx);
+                        }),
+                        None => {
+                            editor.signature_help_popover = Some(SignatureHelpPopover::new(
+                                cx.entity(),
+                                offset,
+                                signature_help,
+                                cx,
+                            ));
+                        }
+                    },
+                    None => editor.signature_help_popover = None,
+                }
+                cx.notify();
+            })
+        });
+    }
+
+    /// Select the previous or next signature (overload), return true if handled.
+    pub(crate) fn cycle_signature_help(&mut self, delta: isize, cx: &mut Context<Self>) -> bool {
+        let Some(popover) = self.signature_help_popover.as_ref() else {
+            return false;
+        };
+
+        popover.update(cx, |popover, cx| popover.cycle(delta, cx))
+    }
+
+    /// Hide the signature help, return true if it was showing.
+    pub(crate) fn hide_signature_help(&mut self, cx: &mut Context<Self>) -> bool {
+        if self.signature_help_popover.take().is_none() {
+            return false;
+        }
+
+        self.lsp._signature_help_task = Task::ready(Ok(()));
+        cx.notify();
+        true
+    }
+}
@@ -0,0 +1,563 @@
+use std::{cell::Cell, rc::Rc};
+
+use gpui::{
+    App, Bounds, ContentMask, Context, CursorStyle, Element, ElementId, Entity, GlobalElementId,
+    Hitbox, HitboxBehavior, Hsla, InspectorElementId, IntoElement, LayoutId, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, ScrollWheelEvent, Style, Window, fill,
+    point, px, relative, size,
+};
+
+use crate::{
+    ActiveTheme as _, RopeExt as _,
+    input::{InputState, mode::InputMode},
+};
+
+/// The height of a display line in the minimap.
+const MINIMAP_LINE_HEIGHT: Pixels = px(2.);
+/// The width of a character in the minimap.
+const MINIMAP_CHAR_WIDTH: Pixels = px(1.);
+/// The width of the diagnostic marker at the right edge of the minimap.
+const MINIMAP_MARKER_WIDTH: Pixels = px(3.);
+
+impl InputMode {
+    #[inline]
+    pub(super) fn has_minimap(&self) -> bool {
+        match self {
+            InputMode::CodeEditor {
+                minimap,
+                multi_line,
+                ..
+            } => *minimap && *multi_line,
+            _ => false,
+        }
+    }
+}
+
+impl InputState {
+    /// Set whether to show the minimap in code editor mode, default is false.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn minimap(mut self, minimap: bool) -> Self {
+        debug_assert!(self.mode.is_code_editor() && self.mode.is_multi_line());
+        if let InputMode::CodeEditor { minimap: m, .. } = &mut self.mode {
+            *m = minimap;
+        }
+        self
+    }
+
+    /// Set minimap in code editor mode.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn set_minimap(&mut self, minimap: bool, _: &mut Window, cx: &mut Context<Self>) {
+        debug_assert!(self.mode.is_code_editor());
+        if let InputMode::CodeEditor { minimap: m, .. } = &mut self.mode {
+            *m = minimap;
+        }
+        cx.notify();
+    }
+
+    /// Set the width of the minimap, default is 100px.
+    pub fn minimap_width(mut self, width: impl Into<Pixels>) -> Self {
+        self.minimap_width = width.into();
+        self
+    }
+
+    /// Set the width of the minimap.
+    pub fn set_minimap_width(
+        &mut self,
+        width: impl Into<Pixels>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.minimap_width = width.into();
+        cx.notify();
+    }
+}
+
+/// The scroll metrics of the minimap, all values are in the minimap pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MinimapMetrics {
+    /// The scroll top of the minimap content.
+    scroll_top: Pixels,
+    /// The top of the slider, relative to the minimap bounds.
+    slider_top: Pixels,
+    slider_height: Pixels,
+    /// The max top of the slider.
+    track: Pixels,
+    /// The max scroll offset of the editor.
+    max_scroll: Pixels,
+}
+
+impl MinimapMetrics {
+    /// - `scroll_y`: The scroll offset (positive) of the editor.
+    /// - `viewport_height`: The height of the editor viewport.
+    /// - `total_rows`: The number of the display lines (with soft wrap, without folded).
+    /// - `height`: The height of the minimap.
+    fn new(
+        scroll_y: Pixels,
+        max_scroll: Pixels,
+        viewport_height: Pixels,
+        line_height: Pixels,
+        total_rows: usize,
+        height: Pixels,
+    ) -> Self {
+        let scale = MINIMAP_LINE_HEIGHT / line_height;
+        let content_height = MINIMAP_LINE_HEIGHT * total_rows as f32;
+        let slider_height = (viewport_height * scale).min(height);
+        let fraction = if max_scroll > px(0.) {
+            (scroll_y / max_scroll).clamp(0., 1.)
+        } else {
+            0.
+        };
+        let track = (content_height.min(height) - slider_height).max(px(0.));
+
+        Self {
+            scroll_top: (content_height - height).max(px(0.)) * fraction,
+            slider_top: track * fraction,
+            slider_height,
+            track,
+            max_scroll: max_scroll.max(px(0.)),
+        }
+    }
+
+    /// Returns the scroll offset (positive) of the editor to place the slider at the `slider_top`.
+    fn scroll_y_for_slider_top(&self, slider_top: Pixels) -> Pixels {
+        if self.track <= px(0.) {
+            return px(0.);
+        }
+
+        self.max_scroll * (slider_top / self.track).clamp(0., 1.)
+    }
+}
+
+/// The drag offset of the mouse in the slider, None if not dragging.
+#[derive(Default, Clone)]
+struct MinimapDragState(Rc<Cell<Option<Pixels>>>);
+
+/// A scaled-down overview of the code editor, rendered at the right side of the editor.
+pub(super) struct MinimapElement {
+    state: Entity<InputState>,
+}
+
+impl MinimapElement {
+    pub(super) fn new(state: Entity<InputState>) -> Self {
+        Self { state }
+    }
+
+    /// Returns the (display row, column) of the `offset` in the minimap, the column is counted in chars.
+    fn display_position(state: &InputState, offset: usize, tab_size: usize) -> (usize, usize) {
+        let display_point = state.text_wrapper.offset_to_display_point(offset);
+        let start = offset.saturating_sub(display_point.column);
+        let column = state
+            .text
+            .slice(start..offset)
+            .chars()
+            .map(|c| if c == '\t' { tab_size } else { 1 })
+            .sum();
+
+        (display_point.row, column)
+    }
+
+    /// Layout the blocks of the text, search matches and diagnostic markers of the visible rows.
+    fn layout_blocks(
+        state: &InputState,
+        bounds: &Bounds<Pixels>,
+        metrics: &MinimapMetrics,
+        cx: &App,
+    ) -> Vec<(Bounds<Pixels>, Hsla)> {
+        let first_row = (metrics.scroll_top / MINIMAP_LINE_HEIGHT).floor() as usize;
+        let last_row =
+            ((metrics.scroll_top + bounds.size.height) / MINIMAP_LINE_HEIGHT).ceil() as usize;
+        let tab_size = state.mode.tab_size().tab_size;
+        let default_color = cx.theme().foreground.opacity(0.6);
+        let row_origin = |display_row: usize, column: usize| {
+            point(
+                bounds.origin.x + MINIMAP_CHAR_WIDTH * column as f32,
+                bounds.origin.y + MINIMAP_LINE_HEIGHT * display_row as f32 - metrics.scroll_top,
+            )
+        };
+
+        let (highlighter, diagnostics) = match &state.mode {
+            InputMode::CodeEditor {
+                highlighter,
+                diagnostics,
+                ..
+            } => (highlighter.borrow(), diagnostics),
+            _ => return vec![],
+        };
+
+        let mut blocks = vec![];
+        let mut display_row = 0;
+        for (row, line) in state.text_wrapper.lines.iter().enumerate() {
+            if display_row >= last_row {
+                break;
+            }
+            if line.folded {
+                continue;
+            }
+            if display_row + line.lines_len() <= first_row {
+                display_row += line.lines_len();
+                continue;
+            }
+
+            let line_start = state.text.line_start_offset(row);
+            let line_text = state.text.slice_line(row);
+            let styles = highlighter
+                .as_ref()
+                .map(|highlighter| {
+                    highlighter.styles(
+                        &(line_start..line_start + line_text.len()),
+                        &cx.theme().highlight_theme,
+                    )
+                })
+                .unwrap_or_default();
+
+            let mut style_ix = 0;
+            let mut offset = 0;
+            let mut chars = line_text.chars();
+            for range in line.wrapped_lines.iter() {
+                let mut column = 0;
+                // The run of the chars with the same color: (start column, end column, color)
+                let mut run: Option<(usize, usize, Hsla)> = None;
+                let mut flush = |run: &mut Option<(usize, usize, Hsla)>| {
+                    if let Some((start, end, color)) = run.take() {
+                        blocks.push((
+                            Bounds::new(
+                                row_origin(display_row, start),
+                                size(
+                                    MINIMAP_CHAR_WIDTH * (end - start) as f32,
+                                    MINIMAP_LINE_HEIGHT,
+                                ),
+                            ),
+                            color,
+                        ));
+                    }
+                };
+
+                while offset < range.end {
+                    let Some(c) = chars.next() else {
+                        break;
+                    };
+                    let char_offset = line_start + offset;
+                    offset += c.len_utf8();
+
+                    if c.is_whitespace() {
+                        flush(&mut run);
+                        column += if c == '\t' { tab_size } else { 1 };
+                        continue;
+                    }
+
+                    while style_ix < styles.len() && styles[style_ix].0.end <= char_offset {
+                        style_ix += 1;
+                    }
+                    let color = styles
+                        .get(style_ix)
+                        .filter(|(range, _)| range.start <= char_offset)
+                        .and_then(|(_, style)| style.color)
+                        .map(|color| color.opacity(0.6))
+                        .unwrap_or(default_color);
+
+                    match run.as_mut() {
+                        Some((_, end, run_color)) if *end == column && *run_color == color => {
+                            *end += 1;
+                        }
+                        _ => {
+                            flush(&mut run);
+                            run = Some((column, column + 1, color));
+                        }
+                    }
+                    column += 1;
+                }
+                flush(&mut run);
+                display_row += 1;
+            }
+        }
+
+        let visible_rows = first_row..last_row;
+
+        // Search matches
+        if let Some((ranges, current_match_ix)) = state.search_panel.as_ref().and_then(|panel| {
+            panel
+                .read(cx)
+                .matcher()
+                .map(|matcher| (matcher.matched_ranges.clone(), matcher.current_match_ix))
+        }) {
+            for (ix, range) in ranges.iter().enumerate() {
+                let (row, start) = Self::display_position(state, range.start, tab_size);
+                if !visible_rows.contains(&row) {
+                    continue;
+                }
+
+                let width = state.text.slice(range.clone()).chars().count().max(1);
+                let color = if ix == current_match_ix {
+                    cx.theme().selection
+                } else {
+                    cx.theme().selection.opacity(0.6)
+                };
+                blocks.push((
+                    Bounds::new(
+                        row_origin(row, start),
+                        size(MINIMAP_CHAR_WIDTH * width as f32, MINIMAP_LINE_HEIGHT),
+                    ),
+                    color,
+                ));
+            }
+        }
+
+        // Diagnostic markers, the less severe is painted first to be covered by the more severe.
+        let mut entries = diagnostics.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.severity.level());
+        for entry in entries {
+            let (row, _) = Self::display_position(state, entry.range.start, tab_size);
+            if !visible_rows.contains(&row) {
+                continue;
+            }
+
+            blocks.push((
+                Bounds::new(
+                    point(bounds.right() - MINIMAP_MARKER_WIDTH, row_origin(row, 0).y),
+                    size(MINIMAP_MARKER_WIDTH, MINIMAP_LINE_HEIGHT),
+                ),
+                entry.severity.fg(cx),
+            ));
+        }
+
+        blocks
+    }
+}
+
+pub(super) struct MinimapPrepaintState {
+    hitbox: Hitbox,
+    metrics: Option<MinimapMetrics>,
+    blocks: Vec<(Bounds<Pixels>, Hsla)>,
+    line_height: Pixels,
+}
+
+impl IntoElement for MinimapElement {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Element for MinimapElement {
+    type RequestLayoutState = ();
+    type PrepaintState = MinimapPrepaintState;
+
+    fn id(&self) -> Option<ElementId> {
+        Some("minimap".into())
+    }
+
+    fn source_location(&self) -> Option<&'static std::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.size.width = relative(1.).into();
+        style.size.height = relative(1.).into();
+
+        (window.request_layout(style, None, cx), ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        let hitbox = window.insert_hitbox(bounds, HitboxBehavior::Normal);
+        let state = self.state.read(cx);
+
+        let Some(line_height) = state
+            .last_layout
+            .as_ref()
+            .map(|last_layout| last_layout.line_height)
+            .filter(|line_height| *line_height > px(0.))
+        else {
+            return MinimapPrepaintState {
+                hitbox,
+                metrics: None,
+                blocks: vec![],
+                line_height: px(0.),
+            };
+        };
+
+        let viewport_height = state.input_bounds.size.height;
+        let metrics = MinimapMetrics::new(
+            -state.scroll_handle.offset().y,
+            state.scroll_size.height - viewport_height,
+            viewport_height,
+            line_height,
+            state.text_wrapper.len(),
+            bounds.size.height,
+        );
+        let blocks = Self::layout_blocks(state, &bounds, &metrics, cx);
+
+        MinimapPrepaintState {
+            hitbox,
+            metrics: Some(metrics),
+            blocks,
+            line_height,
+        }
+    }
+
+    fn paint(
+        &mut self,
+        _: Option<&GlobalElementId>,
+        _: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _: &mut Self::RequestLayoutState,
+        prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let Some(metrics) = prepaint.metrics else {
+            return;
+        };
+
+        let drag_state = window
+            .use_state(cx, |_, _| MinimapDragState::default())
+            .read(cx)
+            .clone();
+        let slider_bounds = Bounds::new(
+            point(bounds.origin.x, bounds.origin.y + metrics.slider_top),
+            size(bounds.size.width, metrics.slider_height),
+        );
+        let slider_color = if drag_state.0.get().is_some() {
+            cx.theme().scrollbar_thumb.opacity(0.5)
+        } else {
+            cx.theme().scrollbar_thumb.opacity(0.3)
+        };
+
+        window.set_cursor_style(CursorStyle::default(), &prepaint.hitbox);
+        window.with_content_mask(Some(ContentMask { bounds }), |window| {
+            for (block_bounds, color) in prepaint.blocks.drain(..) {
+                window.paint_quad(fill(block_bounds, color));
+            }
+            window.paint_quad(fill(slider_bounds, slider_color));
+        });
+
+        let scroll_to_slider_top = {
+            let state = self.state.clone();
+            move |slider_top: Pixels, cx: &mut App| {
+                let scroll_y = metrics.scroll_y_for_slider_top(slider_top);
+                state.update(cx, |state, cx| {
+                    let offset = state.scroll_handle.offset();
+                    state.update_scroll_offset(Some(point(offset.x, -scroll_y)), cx);
+                });
+            }
+        };
+
+        window.on_mouse_event({
+            let drag_state = drag_state.clone();
+            let scroll_to_slider_top = scroll_to_slider_top.clone();
+            move |event: &MouseDownEvent, phase, _, cx| {
+                if !phase.bubble()
+                    || event.button != MouseButton::Left
+                    || !bounds.contains(&event.position)
+                {
+                    return;
+                }
+                cx.stop_propagation();
+
+                // Keep the mouse position in the slider when dragging,
+                // or jump to make the slider center at the mouse position.
+                let drag_offset = if slider_bounds.contains(&event.position) {
+                    event.position.y - slider_bounds.origin.y
+                } else {
+                    metrics.slider_height / 2.
+                };
+                drag_state.0.set(Some(drag_offset));
+                scroll_to_slider_top(event.position.y - bounds.origin.y - drag_offset, cx);
+            }
+        });
+
+        window.on_mouse_event({
+            let drag_state = drag_state.clone();
+            move |event: &MouseMoveEvent, _, _, cx| {
+                let Some(drag_offset) = drag_state.0.get() else {
+                    return;
+                };
+                if !event.dragging() {
+                    return;
+                }
+                cx.stop_propagation();
+
+                scroll_to_slider_top(event.position.y - bounds.origin.y - drag_offset, cx);
+            }
+        });
+
+        window.on_mouse_event({
+            let state = self.state.clone();
+            move |_: &MouseUpEvent, phase, _, cx| {
+                if phase.bubble() && drag_state.0.take().is_some() {
+                    state.update(cx, |_, cx| cx.notify());
+                }
+            }
+        });
+
+        window.on_mouse_event({
+            let state = self.state.clone();
+            let line_height = prepaint.line_height;
+            move |event: &ScrollWheelEvent, phase, _, cx| {
+                if !phase.bubble() || !bounds.contains(&event.position) {
+                    return;
+                }
+                cx.stop_propagation();
+
+                let delta = event.delta.pixel_delta(line_height);
+                state.update(cx, |state, cx| {
+                    let offset = state.scroll_handle.offset();
+                    state.update_scroll_offset(Some(point(offset.x, offset.y + delta.y)), cx);
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::px;
+
+    use super::MinimapMetrics;
+
+    #[test]
+    fn test_minimap_metrics() {
+        // 100 rows with 20px line height, the minimap content is 200px, fits in the 400px minimap.
+        let metrics = MinimapMetrics::new(px(0.), px(1600.), px(400.), px(20.), 100, px(400.));
+        assert_eq!(metrics.scroll_top, px(0.));
+        assert_eq!(metrics.slider_top, px(0.));
+        assert_eq!(metrics.slider_height, px(40.));
+        assert_eq!(metrics.track, px(160.));
+
+        let metrics = MinimapMetrics::new(px(800.), px(1600.), px(400.), px(20.), 100, px(400.));
+        assert_eq!(metrics.scroll_top, px(0.));
+        assert_eq!(metrics.slider_top, px(80.));
+        assert_eq!(metrics.scroll_y_for_slider_top(px(80.)), px(800.));
+        assert_eq!(metrics.scroll_y_for_slider_top(px(-10.)), px(0.));
+        assert_eq!(metrics.scroll_y_for_slider_top(px(500.)), px(1600.));
+
+        // 1000 rows, the minimap content is 2000px, scroll the minimap with the editor.
+        let metrics =
+            MinimapMetrics::new(px(19600.), px(19600.), px(400.), px(20.), 1000, px(400.));
+        assert_eq!(metrics.scroll_top, px(1600.));
+        assert_eq!(metrics.slider_top, px(360.));
+        assert_eq!(metrics.slider_height, px(40.));
+
+        // No scroll
+        let metrics = MinimapMetrics::new(px(0.), px(0.), px(400.), px(20.), 10, px(400.));
+        assert_eq!(metrics.slider_top, px(0.));
+        assert_eq!(metrics.scroll_y_for_slider_top(px(100.)), px(0.));
+    }
+}
@@ -1,32 +1,53 @@
 mod blink_cursor;
 mod change;
 mod clear_button;
+mod column_selection;
+mod comment;
 mod cursor;
+mod diagnostics;
+pub(crate) mod diff;
+mod edit;
 mod element;
+mod folding;
 mod indent;
 mod input;
+mod large_file;
 mod lsp;
 mod mask_pattern;
+mod minimap;
 mod mode;
 mod movement;
+mod multi_cursor;
 mod number_input;
 mod otp_input;
+mod outline;
 pub(crate) mod popovers;
+mod problem_list;
 mod rope_ext;
 mod search;
 mod selection;
+mod snippet;
 mod state;
+mod sticky_scroll;
 mod text_wrapper;
+mod vim;
 
 pub(crate) use clear_button::*;
 pub use cursor::*;
+pub use diff::{DiffHunk, DiffHunkStatus};
+pub use edit::InputEdit;
+pub use folding::FoldRange;
 pub use indent::TabSize;
 pub use input::*;
 pub use lsp::*;
 pub use mask_pattern::MaskPattern;
 pub use number_input::{NumberInput, NumberInputEvent, StepAction};
 pub use otp_input::*;
+pub use outline::Outline;
+pub use problem_list::ProblemList;
+pub use snippet::{SnippetRegistry, UserSnippet};
 pub use state::*;
+pub use vim::VimMode;
 
 pub use lsp_types::Position;
 pub use rope_ext::*;
@@ -33,6 +33,12 @@ pub(crate) enum InputMode {
         line_number: bool,
         language: SharedString,
         indent_guides: bool,
+        /// Enable code folding
+        folding: bool,
+        /// Show the minimap
+        minimap: bool,
+        /// The max depth of the sticky scroll headers, 0 means disabled.
+        sticky_scroll: usize,
         highlighter: Rc<RefCell<Option<SyntaxHighlighter>>>,
         diagnostics: DiagnosticSet,
     },
@@ -65,6 +71,9 @@ impl InputMode {
             highlighter: Rc::new(RefCell::new(None)),
             line_number: true,
             indent_guides: true,
+            folding: true,
+            minimap: false,
+            sticky_scroll: 0,
             diagnostics: DiagnosticSet::new(&Rope::new()),
         }
     }
@@ -188,12 +197,17 @@ impl InputMode {
         }
     }
 
+    /// Update the highlighter for the text in `selected_range` replaced by the `new_text`.
+    ///
+    /// If `background` is true, only the edit is applied to the last parsed tree,
+    /// the text should be parsed by the [`crate::highlighter::BackgroundParser`].
     pub(super) fn update_highlighter(
         &mut self,
         selected_range: &Range<usize>,
         text: &Rope,
         new_text: &str,
         force: bool,
+        background: bool,
         cx: &mut App,
     ) {
         match &self {
@@ -240,12 +254,24 @@ impl InputMode {
                     new_end_position: new_end_pos,
                 };
 
-                highlighter.update(Some(edit), text);
+                if background {
+                    highlighter.edit(&edit, text);
+                } else {
+                    highlighter.update(Some(edit), text);
+                }
             }
             _ => {}
         }
     }
 
+    /// Returns the language of the [`InputMode::CodeEditor`] mode.
+    pub(super) fn language(&self) -> Option<&SharedString> {
+        match self {
+            InputMode::CodeEditor { language, .. } => Some(language),
+            _ => None,
+        }
+    }
+
     #[allow(unused)]
     pub(super) fn diagnostics(&self) -> Option<&DiagnosticSet> {
         match self {
@@ -260,6 +286,12 @@ impl InputMode {
             _ => None,
         }
     }
+
+    /// Returns true if the gutter has the diagnostic markers, only when there are diagnostics.
+    pub(super) fn has_diagnostic_markers(&self) -> bool {
+        self.diagnostics()
+            .is_some_and(|diagnostics| !diagnostics.is_empty())
+    }
 }
 
 #[cfg(test)]
@@ -279,6 +311,9 @@ mod tests {
         assert_eq!(mode.is_single_line(), false);
         assert_eq!(mode.line_number(), true);
         assert_eq!(mode.has_indent_guides(), true);
+        assert_eq!(mode.has_folding(), true);
+        assert_eq!(mode.has_minimap(), false);
+        assert_eq!(mode.sticky_scroll(), 0);
         assert_eq!(mode.max_rows(), usize::MAX);
         assert_eq!(mode.min_rows(), 1);
 
@@ -286,6 +321,9 @@ mod tests {
             multi_line: false,
             line_number: true,
             indent_guides: true,
+            folding: true,
+            minimap: true,
+            sticky_scroll: 5,
             rows: 0,
             tab: Default::default(),
             language: "rust".into(),
@@ -297,6 +335,9 @@ mod tests {
         assert_eq!(mode.is_single_line(), true);
         assert_eq!(mode.line_number(), false);
         assert_eq!(mode.has_indent_guides(), false);
+        assert_eq!(mode.has_folding(), false);
+        assert_eq!(mode.has_minimap(), false);
+        assert_eq!(mode.sticky_scroll(), 0);
         assert_eq!(mode.max_rows(), 1);
         assert_eq!(mode.min_rows(), 1);
     }
@@ -312,6 +353,7 @@ mod tests {
         assert_eq!(mode.is_multi_line(), true);
         assert_eq!(mode.is_single_line(), false);
         assert_eq!(mode.line_number(), false);
+        assert_eq!(mode.has_folding(), false);
         assert_eq!(mode.rows(), 5);
         assert_eq!(mode.max_rows(), usize::MAX);
         assert_eq!(mode.min_rows(), 1);
@@ -46,7 +46,10 @@ impl InputState {
         cx: &mut Context<Self>,
     ) {
         let offset = self.snap_offset_outside_inline_badge(offset.clamp(0, self.text.len()));
+        let offset = self.clip_offset_to_folds(offset, offset > self.cursor());
         self.selected_range = (offset..offset).into();
+        self.column_selection = None;
+        self.normalize_extra_selections();
         self.scroll_to(offset, direction, cx);
         self.pause_blink_cursor(cx);
         self.update_preferred_column();
@@ -118,6 +121,13 @@ impl InputState {
 
     pub(super) fn left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
         self.pause_blink_cursor(cx);
+        self.move_extra_cursors(|this, selection| {
+            if selection.is_empty() {
+                this.previous_boundary(selection.end)
+            } else {
+                selection.start
+            }
+        });
         if self.selected_range.is_empty() {
             self.move_to(self.previous_boundary(self.cursor()), None, cx);
         } else {
@@ -127,6 +137,13 @@ impl InputState {
 
     pub(super) fn right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
         self.pause_blink_cursor(cx);
+        self.move_extra_cursors(|this, selection| {
+            if selection.is_empty() {
+                this.next_boundary(selection.end)
+            } else {
+                selection.end
+            }
+        });
         if self.selected_range.is_empty() {
             self.move_to(self.next_boundary(self.selected_range.end), None, cx);
         } else {
@@ -138,11 +155,21 @@ impl InputState {
         if self.handle_action_for_context_menu(Box::new(action.clone()), window, cx) {
             return;
         }
+        if self.cycle_signature_help(-1, cx) {
+            return;
+        }
+        if self.select_reference(-1, window, cx) {
+            return;
+        }
 
         if self.mode.is_single_line() {
             return;
         }
 
+        self.move_extra_cursors(|this, selection| {
+            this.offset_for_vertical_move(selection.start, -1)
+                .unwrap_or(0)
+        });
         if !self.selected_range.is_empty() {
             self.move_to(
                 self.previous_boundary(self.selected_range.start.saturating_sub(1)),
@@ -158,11 +185,21 @@ impl InputState {
         if self.handle_action_for_context_menu(Box::new(action.clone()), window, cx) {
             return;
         }
+        if self.cycle_signature_help(1, cx) {
+            return;
+        }
+        if self.select_reference(1, window, cx) {
+            return;
+        }
 
         if self.mode.is_single_line() {
             return;
         }
 
+        self.move_extra_cursors(|this, selection| {
+            this.offset_for_vertical_move(selection.end, 1)
+                .unwrap_or(this.text.len())
+        });
         if !self.selected_range.is_empty() {
             self.move_to(
                 self.next_boundary(self.selected_range.end.saturating_sub(1)),
@@ -185,6 +222,7 @@ impl InputState {
         };
 
         let display_lines = (self.input_bounds.size.height / last_layout.line_height) as isize;
+        self.clear_extra_cursors(cx);
         self.move_vertical(-display_lines, window, cx);
     }
 
@@ -203,17 +241,26 @@ impl InputState {
         };
 
         let display_lines = (self.input_bounds.size.height / last_layout.line_height) as isize;
+        self.clear_extra_cursors(cx);
         self.move_vertical(display_lines, window, cx);
     }
 
     pub(super) fn home(&mut self, _: &MoveHome, _: &mut Window, cx: &mut Context<Self>) {
         self.pause_blink_cursor(cx);
+        self.move_extra_cursors(|this, selection| {
+            let row = this.text.offset_to_point(selection.end).row;
+            this.text.line_start_offset(row)
+        });
         let offset = self.start_of_line();
         self.move_to(offset, Some(MoveDirection::Up), cx);
     }
 
     pub(super) fn end(&mut self, _: &MoveEnd, _: &mut Window, cx: &mut Context<Self>) {
         self.pause_blink_cursor(cx);
+        self.move_extra_cursors(|this, selection| {
+            let row = this.text.offset_to_point(selection.end).row;
+            this.text.line_end_offset(row)
+        });
         let offset = self.end_of_line();
         self.move_to(offset, Some(MoveDirection::Down), cx);
     }
@@ -224,10 +271,12 @@ impl InputState {
         _: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        self.clear_extra_cursors(cx);
         self.move_to(0, None, cx);
     }
 
     pub(super) fn move_to_end(&mut self, _: &MoveToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.clear_extra_cursors(cx);
         self.move_to(self.text.len(), None, cx);
     }
 
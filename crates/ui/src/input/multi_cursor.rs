@@ -0,0 +1,624 @@
+use std::ops::Range;
+
+use gpui::{ClipboardItem, Context, Window};
+use ropey::Rope;
+use sum_tree::Bias;
+
+use crate::input::{
+    AddCursorAbove, AddCursorBelow, InputState, RopeExt as _, SelectNextOccurrence, Selection,
+};
+
+impl InputState {
+    /// Returns true if there are more than one cursor in the input.
+    pub fn has_multiple_cursors(&self) -> bool {
+        !self.extra_selections.is_empty()
+    }
+
+    /// Returns all the selections (include the primary selection), sorted by offset.
+    ///
+    /// The overlapped selections are merged.
+    pub fn selections(&self) -> Vec<Selection> {
+        let mut selections = self.extra_selections.clone();
+        selections.push(self.selected_range);
+        merge_selections(selections)
+    }
+
+    /// Remove all the extra cursors, only keep the primary cursor.
+    pub fn clear_extra_cursors(&mut self, cx: &mut Context<Self>) {
+        self.column_selection = None;
+        if self.extra_selections.is_empty() {
+            return;
+        }
+
+        self.extra_selections.clear();
+        cx.notify();
+    }
+
+    /// Add a new cursor at the given offset, the new cursor will be the primary cursor.
+    pub(super) fn add_cursor(&mut self, offset: usize, cx: &mut Context<Self>) {
+        if !self.mode.is_multi_line() {
+            return;
+        }
+
+        let offset = self.snap_offset_outside_inline_badge(offset.min(self.text.len()));
+        // Alt-click on an existing cursor to remove it.
+        if let Some(ix) = self
+            .extra_selections
+            .iter()
+            .position(|selection| selection.start <= offset && offset <= selection.end)
+        {
+            self.extra_selections.remove(ix);
+            cx.notify();
+            return;
+        }
+
+        self.extra_selections.push(self.selected_range);
+        self.selected_range = (offset..offset).into();
+        self.selection_reversed = false;
+        self.selected_word_range = None;
+        self.column_selection = None;
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        self.pause_blink_cursor(cx);
+        cx.notify();
+    }
+
+    /// Sort and merge the extra selections, and remove the ones overlapped with the primary selection.
+    pub(super) fn normalize_extra_selections(&mut self) {
+        let primary = self.selected_range;
+        self.extra_selections = merge_selections(std::mem::take(&mut self.extra_selections))
+            .into_iter()
+            .filter(|selection| !selections_overlap(selection, &primary))
+            .collect();
+    }
+
+    pub(super) fn add_cursor_above(
+        &mut self,
+        _: &AddCursorAbove,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.add_cursor_vertical(-1, cx);
+    }
+
+    pub(super) fn add_cursor_below(
+        &mut self,
+        _: &AddCursorBelow,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.add_cursor_vertical(1, cx);
+    }
+
+    /// Add a cursor above (or below) the top most (or bottom most) cursor, keep the same column.
+    fn add_cursor_vertical(&mut self, direction: isize, cx: &mut Context<Self>) {
+        if !self.mode.is_multi_line() {
+            return;
+        }
+
+        let selections = self.selections();
+        let edge = if direction < 0 {
+            selections.first()
+        } else {
+            selections.last()
+        };
+        let Some(edge) = edge else {
+            return;
+        };
+
+        let offset = if direction < 0 { edge.start } else { edge.end };
+        let Some(new_offset) = self.offset_for_vertical_move(offset, direction) else {
+            return;
+        };
+
+        self.add_cursor(new_offset, cx);
+        self.scroll_to(new_offset, None, cx);
+    }
+
+    /// Select the next occurrence of the current selection, and add it as a new cursor.
+    ///
+    /// If the selection is empty, select the word under the cursor first.
+    pub(super) fn select_next_occurrence(
+        &mut self,
+        _: &SelectNextOccurrence,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.selected_range.is_empty() {
+            let Some(range) = self.text.word_range(self.cursor()) else {
+                return;
+            };
+
+            self.selected_range = range.into();
+            self.selection_reversed = false;
+            cx.notify();
+            return;
+        }
+
+        if !self.mode.is_multi_line() {
+            return;
+        }
+
+        let query = self.text.slice(self.selected_range).to_string();
+        let text = self.text.to_string();
+        let mut selections = self.selections();
+        // Skip the occurrences in the folded lines, the cursors are not placed in them.
+        let range = loop {
+            let Some(range) =
+                find_next_occurrence(&text, &query, self.selected_range.end, &selections)
+            else {
+                return;
+            };
+            if self.clip_offset_to_folds(range.start, true) == range.start
+                && self.clip_offset_to_folds(range.end, true) == range.end
+            {
+                break range;
+            }
+            selections.push(range.into());
+        };
+
+        self.extra_selections.push(self.selected_range);
+        self.selected_range = range.into();
+        self.selection_reversed = false;
+        self.normalize_extra_selections();
+        self.scroll_to(self.selected_range.end, None, cx);
+        cx.notify();
+    }
+
+    /// Returns the offset that moved `lines` rows from the `offset`, keep the same char column.
+    ///
+    /// The offset in the folded lines is moved out of the folds, same as the primary cursor.
+    ///
+    /// Returns `None` if the target row is out of the text.
+    pub(super) fn offset_for_vertical_move(&self, offset: usize, lines: isize) -> Option<usize> {
+        let point = self.text.offset_to_point(offset);
+        let row = point.row.checked_add_signed(lines)?;
+        if row >= self.text.lines_len() {
+            return None;
+        }
+
+        let line_start = self.text.line_start_offset(point.row);
+        let column = self.text.slice(line_start..offset).chars().count();
+        let new_offset = offset_for_char_column(&self.text, row, column);
+        Some(self.clip_offset_to_folds(new_offset, lines > 0))
+    }
+
+    /// Move all the extra cursors to the offset returned by `f`, this will collapse the selections.
+    pub(super) fn move_extra_cursors(&mut self, f: impl Fn(&Self, Selection) -> usize) {
+        if self.extra_selections.is_empty() {
+            return;
+        }
+
+        let extra_selections = std::mem::take(&mut self.extra_selections);
+        self.extra_selections = extra_selections
+            .into_iter()
+            .map(|selection| {
+                let offset = f(self, selection).min(self.text.len());
+                (offset..offset).into()
+            })
+            .collect();
+        self.normalize_extra_selections();
+    }
+
+    /// Extend the empty extra selections from the cursor to the offset returned by `f`.
+    ///
+    /// This is used to prepare the selections for deleting (e.g.: Backspace, Delete).
+    pub(super) fn select_extra_cursors_to(&mut self, f: impl Fn(&Self, usize) -> usize) {
+        if self.extra_selections.is_empty() {
+            return;
+        }
+
+        let extra_selections = std::mem::take(&mut self.extra_selections);
+        self.extra_selections = extra_selections
+            .into_iter()
+            .map(|selection| {
+                if !selection.is_empty() {
+                    return selection;
+                }
+
+                let offset = f(self, selection.end).min(self.text.len());
+                Selection::new(offset.min(selection.end), offset.max(selection.end))
+            })
+            .collect();
+        self.normalize_extra_selections();
+    }
+
+    /// Shift the extra selections after the text in `range` is replaced by a text with `new_len`.
+    pub(super) fn shift_extra_selections(&mut self, range: &Range<usize>, new_len: usize) {
+        for selection in self.extra_selections.iter_mut() {
+            selection.start = shift_offset(selection.start, range, new_len);
+            selection.end = shift_offset(selection.end, range, new_len);
+        }
+    }
+
+    /// Replace the text of all the selections.
+    ///
+    /// If the `new_texts` length is equal to the selections length, each selection
+    /// will be replaced by the corresponding text, otherwise the first text is used for all.
+    ///
+    /// All the changes are grouped as one undo step.
+    pub(super) fn replace_text_in_selections(
+        &mut self,
+        new_texts: &[String],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let primary = self.selected_range;
+        let selections = self.selections();
+        let primary_ix = selections
+            .iter()
+            .position(|selection| selection.start <= primary.start && primary.end <= selection.end)
+            .unwrap_or(selections.len().saturating_sub(1));
+        let per_selection = new_texts.len() == selections.len();
+
+        let was_silent = self.silent_replace_text;
+        self.silent_replace_text = true;
+        self.extra_selections.clear();
+
+        // Replace from the last selection to the first, so the earlier offsets are not changed.
+        let mut cursors: Vec<usize> = Vec::with_capacity(selections.len());
+        for (ix, selection) in selections.iter().enumerate().rev() {
+            let new_text = if per_selection {
+                new_texts[ix].as_str()
+            } else {
+                new_texts.first().map(|s| s.as_str()).unwrap_or_default()
+            };
+
+            if ix + 1 < selections.len() {
+                self.history.start_grouping();
+            }
+
+            let range: Range<usize> = (*selection).into();
+            self.replace_text_in_range(Some(self.range_to_utf16(&range)), new_text, window, cx);
+            for cursor in cursors.iter_mut() {
+                *cursor = shift_offset(*cursor, &range, new_text.len());
+            }
+            cursors.push(self.cursor());
+        }
+        self.history.end_grouping();
+        self.silent_replace_text = was_silent;
+
+        cursors.reverse();
+        let cursor = cursors.remove(primary_ix.min(cursors.len().saturating_sub(1)));
+        self.selected_range = (cursor..cursor).into();
+        self.selection_reversed = false;
+        self.extra_selections = cursors
+            .into_iter()
+            .map(|offset| (offset..offset).into())
+            .collect();
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        self.scroll_to(cursor, None, cx);
+        cx.notify();
+    }
+
+    /// Copy the text of all the selections, joined by `\n`.
+    pub(super) fn copy_selections(&self, cx: &mut Context<Self>) -> bool {
+        let texts = self
+            .selections()
+            .into_iter()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| self.text.slice(selection).to_string())
+            .collect::<Vec<_>>();
+        if texts.is_empty() {
+            return false;
+        }
+
+        cx.write_to_clipboard(ClipboardItem::new_string(texts.join("\n")));
+        true
+    }
+
+    /// Paste the text to all the selections.
+    ///
+    /// If the lines count of the text is equal to the cursors count,
+    /// each line will be pasted to the corresponding cursor.
+    pub(super) fn paste_to_selections(
+        &mut self,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let lines = new_text.lines().map(String::from).collect::<Vec<_>>();
+        if lines.len() == self.selections().len() {
+            self.replace_text_in_selections(&lines, window, cx);
+        } else {
+            self.replace_text_in_selections(&[new_text.to_string()], window, cx);
+        }
+    }
+}
+
+/// Returns the offset of the given row and char column, the column is clamped to the line length.
+fn offset_for_char_column(text: &Rope, row: usize, column: usize) -> usize {
+    let column_offset: usize = text
+        .slice_line(row)
+        .chars()
+        .take(column)
+        .map(|c| c.len_utf8())
+        .sum();
+    text.clip_offset(text.line_start_offset(row) + column_offset, Bias::Left)
+}
+
+fn selections_overlap(a: &Selection, b: &Selection) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return a.start <= b.end && b.start <= a.end;
+    }
+
+    a.start < b.end && b.start < a.end
+}
+
+/// Sort the selections, and merge the overlapped (or same cursor) selections.
+fn merge_selections(mut selections: Vec<Selection>) -> Vec<Selection> {
+    selections.sort_by_key(|selection| (selection.start, selection.end));
+
+    let mut merged: Vec<Selection> = Vec::with_capacity(selections.len());
+    for selection in selections {
+        if let Some(last) = merged.last_mut() {
+            if selections_overlap(last, &selection) {
+                last.end = last.end.max(selection.end);
+                continue;
+            }
+        }
+
+        merged.push(selection);
+    }
+
+    merged
+}
+
+/// Shift the offset after the text in `range` is replaced by a text with `new_len`.
+pub(super) fn shift_offset(offset: usize, range: &Range<usize>, new_len: usize) -> usize {
+    if offset >= range.end {
+        (offset + new_len).saturating_sub(range.len())
+    } else if offset > range.start {
+        range.start + new_len
+    } else {
+        offset
+    }
+}
+
+/// Find the next occurrence of `query` in the `text` after `offset`, wrap around to the start.
+///
+/// The ranges already in `selections` are skipped.
+fn find_next_occurrence(
+    text: &str,
+    query: &str,
+    offset: usize,
+    selections: &[Selection],
+) -> Option<Range<usize>> {
+    if query.is_empty() {
+        return None;
+    }
+
+    let offset = offset.min(text.len());
+    let is_selected = |range: &Range<usize>| {
+        selections
+            .iter()
+            .any(|s| s.start == range.start && s.end == range.end)
+    };
+
+    text[offset..]
+        .match_indices(query)
+        .map(|(ix, _)| offset + ix)
+        .chain(text.match_indices(query).map(|(ix, _)| ix))
+        .map(|start| start..start + query.len())
+        .find(|range| !is_selected(range))
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{AppContext as _, ClipboardItem, EntityInputHandler as _, TestAppContext};
+    use ropey::Rope;
+
+    use super::{find_next_occurrence, merge_selections, offset_for_char_column, shift_offset};
+    use crate::{
+        history::History,
+        input::{
+            AddCursorBelow, Backspace, FoldRange, InputState, Paste, SelectNextOccurrence,
+            Selection, Undo,
+        },
+    };
+
+    #[test]
+    fn test_merge_selections() {
+        let selections = vec![
+            Selection::new(10, 12),
+            Selection::new(0, 0),
+            Selection::new(11, 15),
+            Selection::new(5, 5),
+            Selection::new(5, 5),
+        ];
+
+        assert_eq!(
+            merge_selections(selections),
+            vec![
+                Selection::new(0, 0),
+                Selection::new(5, 5),
+                Selection::new(10, 15),
+            ]
+        );
+
+        // Adjacent selections are not merged.
+        let selections = vec![Selection::new(0, 3), Selection::new(3, 6)];
+        assert_eq!(
+            merge_selections(selections),
+            vec![Selection::new(0, 3), Selection::new(3, 6)]
+        );
+    }
+
+    #[test]
+    fn test_shift_offset() {
+        // Insert "abc" at 5
+        assert_eq!(shift_offset(2, &(5..5), 3), 2);
+        assert_eq!(shift_offset(5, &(5..5), 3), 8);
+        assert_eq!(shift_offset(10, &(5..5), 3), 13);
+
+        // Delete 5..8
+        assert_eq!(shift_offset(6, &(5..8), 0), 5);
+        assert_eq!(shift_offset(8, &(5..8), 0), 5);
+        assert_eq!(shift_offset(10, &(5..8), 0), 7);
+    }
+
+    #[test]
+    fn test_offset_for_char_column() {
+        let text = Rope::from("hello\n你好\nab");
+        assert_eq!(offset_for_char_column(&text, 0, 3), 3);
+        assert_eq!(offset_for_char_column(&text, 1, 1), 9);
+        assert_eq!(offset_for_char_column(&text, 1, 10), 12);
+        assert_eq!(offset_for_char_column(&text, 2, 10), 15);
+    }
+
+    #[test]
+    fn test_find_next_occurrence() {
+        let text = "foo bar foo baz foo";
+        let selections = vec![Selection::new(4, 7)];
+        assert_eq!(find_next_occurrence(text, "bar", 7, &selections), None);
+
+        let selections = vec![Selection::new(0, 3)];
+        assert_eq!(
+            find_next_occurrence(text, "foo", 3, &selections),
+            Some(8..11)
+        );
+
+        let selections = vec![Selection::new(0, 3), Selection::new(16, 19)];
+        assert_eq!(
+            find_next_occurrence(text, "foo", 19, &selections),
+            Some(8..11)
+        );
+        assert_eq!(find_next_occurrence(text, "", 0, &selections), None);
+    }
+
+    #[gpui::test]
+    fn test_multi_cursor_editing(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("foo\nbar\nbaz")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // A cursor at the end of each line.
+                state.selected_range = (3..3).into();
+                state.add_cursor(7, cx);
+                state.add_cursor(11, cx);
+                assert!(state.has_multiple_cursors());
+
+                state.replace_text_in_range(None, "!", window, cx);
+                assert_eq!(state.value(), "foo!\nbar!\nbaz!");
+                assert_eq!(
+                    state.selections(),
+                    vec![
+                        Selection::new(4, 4),
+                        Selection::new(9, 9),
+                        Selection::new(14, 14)
+                    ]
+                );
+
+                state.backspace(&Backspace, window, cx);
+                assert_eq!(state.value(), "foo\nbar\nbaz");
+                assert_eq!(
+                    state.selections(),
+                    vec![
+                        Selection::new(3, 3),
+                        Selection::new(7, 7),
+                        Selection::new(11, 11)
+                    ]
+                );
+
+                // The lines are pasted to the corresponding cursors.
+                cx.write_to_clipboard(ClipboardItem::new_string("1\n2\n3".into()));
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "foo1\nbar2\nbaz3");
+
+                // Otherwise, the whole text is pasted to each cursor.
+                cx.write_to_clipboard(ClipboardItem::new_string("-".into()));
+                state.paste(&Paste, window, cx);
+                assert_eq!(state.value(), "foo1-\nbar2-\nbaz3-");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_multi_cursor_undo(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("foo\nbar\nbaz")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // Every change is a new undo step without the grouping.
+                state.history = History::new().group_interval(std::time::Duration::ZERO);
+                state.selected_range = (0..0).into();
+                state.replace_text_in_range(None, "> ", window, cx);
+                assert_eq!(state.value(), "> foo\nbar\nbaz");
+
+                state.selected_range = (6..6).into();
+                state.add_cursor(10, cx);
+                state.replace_text_in_range(None, "> ", window, cx);
+                assert_eq!(state.value(), "> foo\n> bar\n> baz");
+
+                // The multi-cursor edit is undone as one step.
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "> foo\nbar\nbaz");
+                state.undo(&Undo, window, cx);
+                assert_eq!(state.value(), "foo\nbar\nbaz");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_multi_cursor_with_folds(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .code_editor("text")
+                    .default_value("fn a\n    foo\n    foo\nend foo\nfoo")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.text_wrapper.prepare_if_need(&state.text.clone(), cx);
+                state.selected_range = (2..2).into();
+                state.fold_at_row(0, cx);
+                assert_eq!(state.folded_ranges(), &[FoldRange::new(0, 2)]);
+
+                // The cursors are moved out of the folded lines.
+                assert_eq!(state.offset_for_vertical_move(2, 1), Some(21));
+                assert_eq!(state.offset_for_vertical_move(23, -1), Some(4));
+                state.add_cursor_below(&AddCursorBelow, window, cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![Selection::new(2, 2), Selection::new(21, 21)]
+                );
+
+                // The occurrences in the folded lines are skipped.
+                state.clear_extra_cursors(cx);
+                state.selected_range = (25..28).into();
+                state.select_next_occurrence(&SelectNextOccurrence, window, cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![Selection::new(25, 28), Selection::new(29, 32)]
+                );
+                state.select_next_occurrence(&SelectNextOccurrence, window, cx);
+                assert_eq!(
+                    state.selections(),
+                    vec![Selection::new(25, 28), Selection::new(29, 32)]
+                );
+            });
+        });
+    }
+}
@@ -0,0 +1,277 @@
+use std::rc::Rc;
+
+use gpui::{Context, Window};
+use lsp_types::{DocumentSymbol, Position};
+
+use crate::{
+    input::{InputState, RopeExt as _, mode::InputMode},
+    tree::TreeItem,
+};
+
+/// The outline of the document, the symbols are nested and sorted by the position.
+///
+/// Use [`Outline::tree_items`] to show it in a [`crate::tree::Tree`],
+/// [`Outline::flatten`] to list the symbols in a picker,
+/// and [`Outline::path_at`] to show the symbols at the cursor in a [`crate::breadcrumb::Breadcrumb`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    symbols: Vec<DocumentSymbol>,
+}
+
+impl Outline {
+    /// Create an outline from the symbols, the flattened symbols are nested by their ranges.
+    pub fn new(symbols: Vec<DocumentSymbol>) -> Self {
+        Self {
+            symbols: nest_symbols(symbols),
+        }
+    }
+
+    /// Returns the top level symbols.
+    pub fn symbols(&self) -> &[DocumentSymbol] {
+        &self.symbols
+    }
+
+    /// Returns true if there is no symbol.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns all the symbols with their depth (0 for the top level), in the document order.
+    pub fn flatten(&self) -> Vec<(usize, &DocumentSymbol)> {
+        fn walk<'a>(
+            symbols: &'a [DocumentSymbol],
+            depth: usize,
+            items: &mut Vec<(usize, &'a DocumentSymbol)>,
+        ) {
+            for symbol in symbols {
+                items.push((depth, symbol));
+                if let Some(children) = symbol.children.as_ref() {
+                    walk(children, depth + 1, items);
+                }
+            }
+        }
+
+        let mut items = vec![];
+        walk(&self.symbols, 0, &mut items);
+        items
+    }
+
+    /// Returns the symbols contain the position, from the outermost to the innermost.
+    pub fn path_at(&self, position: Position) -> Vec<&DocumentSymbol> {
+        let mut path = vec![];
+        let mut symbols = self.symbols.as_slice();
+        while let Some(symbol) = symbols
+            .iter()
+            .rev()
+            .find(|symbol| symbol.range.start <= position && position <= symbol.range.end)
+        {
+            path.push(symbol);
+            symbols = symbol.children.as_deref().unwrap_or_default();
+        }
+        path
+    }
+
+    /// Returns the [`TreeItem`]s of the symbols, all expanded.
+    ///
+    /// The id of the item is the index path of the symbol, e.g.: `0/2`, use [`Outline::symbol`] to get the symbol.
+    pub fn tree_items(&self) -> Vec<TreeItem> {
+        fn items(symbols: &[DocumentSymbol], parent_id: Option<&str>) -> Vec<TreeItem> {
+            symbols
+                .iter()
+                .enumerate()
+                .map(|(ix, symbol)| {
+                    let id = match parent_id {
+                        Some(parent_id) => format!("{}/{}", parent_id, ix),
+                        None => ix.to_string(),
+                    };
+                    let children = symbol
+                        .children
+                        .as_deref()
+                        .map(|children| items(children, Some(&id)))
+                        .unwrap_or_default();
+
+                    TreeItem::new(id, symbol.name.clone())
+                        .expanded(!children.is_empty())
+                        .children(children)
+                })
+                .collect()
+        }
+
+        items(&self.symbols, None)
+    }
+
+    /// Returns the symbol by the id of the [`TreeItem`] from [`Outline::tree_items`].
+    pub fn symbol(&self, id: &str) -> Option<&DocumentSymbol> {
+        let mut symbols = self.symbols.as_slice();
+        let mut symbol = None;
+        for ix in id.split('/') {
+            let found = symbols.get(ix.parse::<usize>().ok()?)?;
+            symbols = found.children.as_deref().unwrap_or_default();
+            symbol = Some(found);
+        }
+        symbol
+    }
+}
+
+impl InputState {
+    /// Returns the outline of the document.
+    ///
+    /// The symbols are from the [`super::DocumentSymbolProvider`] if it has,
+    /// otherwise from the outline query of the language, see [`crate::highlighter::LanguageConfig::outline`].
+    pub fn outline(&mut self) -> Rc<Outline> {
+        if let Some(outline) = self.outline.as_ref() {
+            return outline.clone();
+        }
+
+        let outline = if !self.lsp.document_symbols.is_empty() {
+            Outline::new(self.lsp.document_symbols.clone())
+        } else {
+            match &self.mode {
+                InputMode::CodeEditor { highlighter, .. } => Outline::new(
+                    highlighter
+                        .borrow()
+                        .as_ref()
+                        .map(|highlighter| highlighter.outline_symbols())
+                        .unwrap_or_default(),
+                ),
+                _ => Outline::default(),
+            }
+        };
+
+        let outline = Rc::new(outline);
+        self.outline = Some(outline.clone());
+        outline
+    }
+
+    /// Returns the symbols contain the cursor, from the outermost to the innermost.
+    pub fn symbol_path_at_cursor(&mut self) -> Vec<DocumentSymbol> {
+        let position = self.text.offset_to_position(self.cursor());
+        self.outline()
+            .path_at(position)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Move the cursor to the symbol and select its name.
+    pub fn go_to_symbol(
+        &mut self,
+        symbol: &DocumentSymbol,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let start = self.text.position_to_offset(&symbol.selection_range.start);
+        let end = self.text.position_to_offset(&symbol.selection_range.end);
+        self.unfold_at_row(symbol.selection_range.start.line as usize, cx);
+        self.move_to(start, None, cx);
+        self.select_to(end, cx);
+        self.focus(window, cx);
+    }
+}
+
+/// Nest the symbols by their ranges, the symbol is the child of the last symbol contains it.
+fn nest_symbols(symbols: Vec<DocumentSymbol>) -> Vec<DocumentSymbol> {
+    fn flatten(symbols: Vec<DocumentSymbol>, items: &mut Vec<DocumentSymbol>) {
+        for mut symbol in symbols {
+            let children = symbol.children.take();
+            items.push(symbol);
+            if let Some(children) = children {
+                flatten(children, items);
+            }
+        }
+    }
+
+    fn insert(symbols: &mut Vec<DocumentSymbol>, symbol: DocumentSymbol) {
+        if let Some(parent) = symbols.last_mut() {
+            if parent.range.start <= symbol.range.start && symbol.range.end <= parent.range.end {
+                insert(parent.children.get_or_insert_default(), symbol);
+                return;
+            }
+        }
+        symbols.push(symbol);
+    }
+
+    let mut items = vec![];
+    flatten(symbols, &mut items);
+    items.sort_by(|a, b| {
+        a.range
+            .start
+            .cmp(&b.range.start)
+            .then(b.range.end.cmp(&a.range.end))
+    });
+
+    let mut symbols = vec![];
+    for symbol in items {
+        insert(&mut symbols, symbol);
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{DocumentSymbol, Position, Range, SymbolKind};
+
+    use super::Outline;
+
+    #[allow(deprecated)]
+    fn symbol(name: &str, start: u32, end: u32) -> DocumentSymbol {
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: Range::new(Position::new(start, 0), Position::new(end, 1)),
+            selection_range: Range::new(Position::new(start, 0), Position::new(start, 1)),
+            children: None,
+        }
+    }
+
+    fn names(symbols: Vec<(usize, &DocumentSymbol)>) -> Vec<(usize, &str)> {
+        symbols
+            .into_iter()
+            .map(|(depth, symbol)| (depth, symbol.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_outline() {
+        let outline = Outline::new(vec![
+            symbol("main", 12, 14),
+            symbol("Foo", 0, 10),
+            symbol("bar", 5, 8),
+            symbol("new", 2, 4),
+            symbol("x", 6, 6),
+        ]);
+        assert_eq!(
+            names(outline.flatten()),
+            vec![(0, "Foo"), (1, "new"), (1, "bar"), (2, "x"), (0, "main")]
+        );
+
+        // The nested symbols are kept.
+        let outline = Outline::new(outline.symbols().to_vec());
+        assert_eq!(outline.symbols().len(), 2);
+        assert_eq!(outline.flatten().len(), 5);
+
+        let path = |line, character| {
+            outline
+                .path_at(Position::new(line, character))
+                .into_iter()
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(path(6, 0), vec!["Foo", "bar", "x"]);
+        assert_eq!(path(3, 0), vec!["Foo", "new"]);
+        assert_eq!(path(9, 0), vec!["Foo"]);
+        assert_eq!(path(11, 0), Vec::<&str>::new());
+
+        let items = outline.tree_items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].children.len(), 2);
+        assert_eq!(items[0].children[1].id.as_ref(), "0/1");
+        assert_eq!(outline.symbol("0/1/0").map(|s| s.name.as_str()), Some("x"));
+        assert_eq!(outline.symbol("1").map(|s| s.name.as_str()), Some("main"));
+        assert!(outline.symbol("0/5").is_none());
+        assert!(outline.symbol("").is_none());
+    }
+}
@@ -6,7 +6,7 @@ use gpui::{
     Render, RenderOnce, SharedString, Styled, StyledText, Subscription, Window, deferred, div,
     prelude::FluentBuilder, px, relative,
 };
-use lsp_types::{CompletionItem, CompletionTextEdit};
+use lsp_types::{CompletionItem, CompletionTextEdit, InsertTextFormat};
 
 const MAX_MENU_WIDTH: Pixels = px(320.);
 const MAX_MENU_HEIGHT: Pixels = px(240.);
@@ -176,6 +176,9 @@ pub struct CompletionMenu {
 
     /// The offset of the first character that triggered the completion.
     pub(crate) trigger_start_offset: Option<usize>,
+    /// The items from the completion provider, the user snippets are merged into them
+    /// if the provider is not queried for the typed text.
+    pub(crate) provider_items: Vec<CompletionItem>,
     query: SharedString,
     _subscriptions: Vec<Subscription>,
 }
@@ -219,6 +222,7 @@ impl CompletionMenu {
                 list,
                 open: false,
                 trigger_start_offset: None,
+                provider_items: vec![],
                 query: SharedString::default(),
                 _subscriptions,
             }
@@ -255,12 +259,16 @@ impl CompletionMenu {
                     range = offset..offset;
                 }
 
-                editor.replace_text_in_range_silent(
-                    Some(editor.range_to_utf16(&range)),
-                    &new_text,
-                    window,
-                    cx,
-                );
+                if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+                    editor.insert_snippet_in_range(range, &new_text, window, cx);
+                } else if !editor.replace_snippet_choice(&range, &new_text, window, cx) {
+                    editor.replace_text_in_range_silent(
+                        Some(editor.range_to_utf16(&range)),
+                        &new_text,
+                        window,
+                        cx,
+                    );
+                }
                 editor.completion_inserting = false;
                 // FIXME: Input not get the focus
                 editor.focus(window, cx);
@@ -328,6 +336,7 @@ impl CompletionMenu {
     pub(crate) fn hide(&mut self, cx: &mut Context<Self>) {
         self.open = false;
         self.trigger_start_offset = None;
+        self.provider_items.clear();
         cx.notify();
     }
 
@@ -42,10 +42,14 @@ impl InputState {
         }
 
         let is_enable = !self.disabled;
+        let is_editable = is_enable && !self.read_only;
         let has_goto_definition = is_enable && self.lsp.definition_provider.is_some();
-        let has_code_action = is_enable && !self.lsp.code_action_providers.is_empty();
+        let has_code_action = is_editable && !self.lsp.code_action_providers.is_empty();
+        let has_rename = is_editable && self.lsp.rename_provider.is_some();
+        let has_references = self.lsp.references_provider.is_some();
+        let has_formatting = is_editable && self.lsp.formatting_provider.is_some();
         let is_selected = !self.selected_range.is_empty();
-        let has_paste = is_enable && cx.read_from_clipboard().is_some();
+        let has_paste = is_editable && cx.read_from_clipboard().is_some();
 
         let action_context = self.focus_handle.clone();
         self.mouse_context_menu.update(cx, |this, cx| {
@@ -58,17 +62,32 @@ impl InputState {
                             Box::new(input::GoToDefinition),
                             has_goto_definition,
                         )
+                        .menu_with_enable(
+                            t!("Input.Find All References"),
+                            Box::new(input::FindAllReferences),
+                            has_references,
+                        )
+                        .menu_with_enable(
+                            t!("Input.Rename Symbol"),
+                            Box::new(input::Rename),
+                            has_rename,
+                        )
                         .menu_with_enable(
                             t!("Input.Show Code Actions"),
                             Box::new(input::ToggleCodeActions),
                             has_code_action,
                         )
+                        .menu_with_enable(
+                            t!("Input.Format Document"),
+                            Box::new(input::FormatDocument),
+                            has_formatting,
+                        )
                         .separator()
                     })
                     .menu_with_enable(
                         t!("Input.Cut"),
                         Box::new(input::Cut),
-                        is_enable && is_selected,
+                        is_editable && is_selected,
                     )
                     .menu_with_enable(t!("Input.Copy"), Box::new(input::Copy), is_selected)
                     .menu_with_enable(t!("Input.Paste"), Box::new(input::Paste), has_paste)
@@ -104,6 +104,12 @@ impl Popover {
         }
     }
 
+    /// Set the max width of the popover, default is 500px.
+    pub(crate) fn max_width(mut self, width: Pixels) -> Self {
+        self.width_limit.end = width;
+        self
+    }
+
     /// Get the bounds of the range in the editor, if it is visible.
     fn trigger_bounds(&self, cx: &App) -> Option<Bounds<Pixels>> {
         let editor = self.editor.read(cx);
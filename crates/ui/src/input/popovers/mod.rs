@@ -3,12 +3,18 @@ mod completion_menu;
 mod context_menu;
 mod diagnostic_popover;
 mod hover_popover;
+mod references_popover;
+mod rename_popover;
+mod signature_help_popover;
 
 pub(crate) use code_action_menu::*;
 pub(crate) use completion_menu::*;
 pub(crate) use context_menu::*;
 pub(crate) use diagnostic_popover::*;
 pub(crate) use hover_popover::*;
+pub(crate) use references_popover::*;
+pub(crate) use rename_popover::*;
+pub(crate) use signature_help_popover::*;
 
 use gpui::{
     App, Div, ElementId, Entity, InteractiveElement as _, IntoElement, SharedString, Stateful,
@@ -79,3 +85,12 @@ pub(super) fn editor_popover(id: impl Into<ElementId>, cx: &App) -> Stateful<Div
         .text_xs()
         .p_1()
 }
+
+/// Returns the last path segment of the URI.
+pub(super) fn uri_file_name(uri: &lsp_types::Uri) -> String {
+    let path = uri.path().as_str();
+    path.rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or(path)
+        .to_string()
+}
@@ -0,0 +1,253 @@
+use std::ops::Range;
+
+use gpui::{
+    App, AppContext as _, ClickEvent, Context, Entity, InteractiveElement as _, IntoElement,
+    ParentElement as _, Render, ScrollHandle, SharedString, StatefulInteractiveElement as _,
+    Styled as _, Window, div, prelude::FluentBuilder as _, px,
+};
+use lsp_types::Location;
+use ropey::Rope;
+use rust_i18n::t;
+
+use crate::{
+    ActiveTheme as _, h_flex,
+    input::{
+        Input, InputState, RopeExt as _,
+        popovers::{Popover, uri_file_name},
+    },
+    label::Label,
+    v_flex,
+};
+
+/// A reference location to show in the [`ReferencesPopover`].
+pub(crate) struct ReferenceItem {
+    pub(crate) location: Location,
+    /// Whether the location is in the current document.
+    pub(crate) is_current: bool,
+    /// The text of the document, `None` if can't be previewed.
+    text: Option<Rope>,
+    /// The file name and line number, e.g.: `main.rs:12`.
+    title: SharedString,
+    /// The trimmed line text of the reference.
+    line: SharedString,
+}
+
+impl ReferenceItem {
+    pub(crate) fn new(location: Location, is_current: bool, text: Option<Rope>) -> Self {
+        let row = location.range.start.line as usize;
+        let line = text
+            .as_ref()
+            .filter(|text| row < text.lines_len())
+            .map(|text| text.slice_line(row).to_string().trim().to_string())
+            .unwrap_or_default();
+        let title = format!("{}:{}", uri_file_name(&location.uri), row + 1);
+
+        Self {
+            location,
+            is_current,
+            text,
+            title: title.into(),
+            line: line.into(),
+        }
+    }
+}
+
+/// The peek view of the references, with a read-only code preview and the reference list.
+///
+/// Use `up` and `down` in the editor to select the reference, `enter` to open it.
+pub(crate) struct ReferencesPopover {
+    editor: Entity<InputState>,
+    /// The symbol range byte of the references request.
+    symbol_range: Range<usize>,
+    /// The text version of the editor when the references were requested.
+    pub(crate) version: usize,
+    items: Vec<ReferenceItem>,
+    selected_ix: usize,
+    pub(crate) preview: Entity<InputState>,
+    /// The document URI of the text in the `preview`.
+    preview_uri: Option<lsp_types::Uri>,
+    scroll_handle: ScrollHandle,
+}
+
+impl ReferencesPopover {
+    pub(crate) fn new(
+        editor: Entity<InputState>,
+        symbol_range: Range<usize>,
+        version: usize,
+        items: Vec<ReferenceItem>,
+        language: SharedString,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let preview = cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor(language)
+                .line_number(true)
+                .soft_wrap(false)
+                .read_only(true)
+        });
+
+        cx.new(|cx| {
+            let mut this = Self {
+                editor,
+                symbol_range,
+                version,
+                items,
+                selected_ix: 0,
+                preview,
+                preview_uri: None,
+                scroll_handle: ScrollHandle::new(),
+            };
+            this.select(0, window, cx);
+            this
+        })
+    }
+
+    pub(crate) fn selected_item(&self) -> Option<&ReferenceItem> {
+        self.items.get(self.selected_ix)
+    }
+
+    /// Select the previous or next reference, wrap around at the ends.
+    pub(crate) fn select_delta(
+        &mut self,
+        delta: isize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let ix = (self.selected_ix as isize + delta).rem_euclid(self.items.len() as isize);
+        self.select(ix as usize, window, cx);
+    }
+
+    fn select(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(item) = self.items.get(ix) else {
+            return;
+        };
+        self.selected_ix = ix;
+        self.scroll_handle.scroll_to_item(ix);
+
+        if let Some(text) = item.text.clone() {
+            let uri = item.location.uri.clone();
+            let range = item.location.range;
+            let needs_update = self.preview_uri.as_ref() != Some(&uri);
+            self.preview.update(cx, |preview, cx| {
+                if needs_update {
+                    preview.set_value(text.to_string(), window, cx);
+                }
+
+                let start = preview.text.position_to_offset(&range.start);
+                let end = preview.text.position_to_offset(&range.end);
+                preview.move_to(start, None, cx);
+                preview.select_to(end, cx);
+            });
+            self.preview_uri = Some(uri);
+        }
+
+        cx.notify();
+    }
+
+    fn on_click_item(
+        &mut self,
+        ix: usize,
+        event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select(ix, window, cx);
+
+        // Double click to open the reference.
+        if event.click_count() > 1 {
+            let Some(item) = self.items.get(ix) else {
+                return;
+            };
+            let (location, is_current) = (item.location.clone(), item.is_current);
+            let version = self.version;
+            self.editor.update(cx, |editor, cx| {
+                editor.open_reference(location, is_current, version, window, cx);
+            });
+        }
+    }
+}
+
+impl Render for ReferencesPopover {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let view = cx.entity();
+        let preview = self.preview.clone();
+        let has_preview = self
+            .selected_item()
+            .map_or(false, |item| item.text.is_some());
+        let selected_ix = self.selected_ix;
+        let scroll_handle = self.scroll_handle.clone();
+        let items = self
+            .items
+            .iter()
+            .map(|item| (item.title.clone(), item.line.clone()))
+            .collect::<Vec<_>>();
+
+        Popover::new(
+            "references-popover",
+            self.editor.clone(),
+            self.symbol_range.clone(),
+            move |_, cx| {
+                h_flex()
+                    .w(px(640.))
+                    .h(px(240.))
+                    .gap_1()
+                    .child(
+                        div()
+                            .flex_1()
+                            .h_full()
+                            .overflow_hidden()
+                            .when(has_preview, |this| {
+                                this.child(Input::new(&preview).h_full().appearance(false))
+                            })
+                            .when(!has_preview, |this| {
+                                this.flex().items_center().justify_center().child(
+                                    Label::new(t!("Input.No Preview"))
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                            }),
+                    )
+                    .child(
+                        v_flex()
+                            .id("references-list")
+                            .w(px(220.))
+                            .h_full()
+                            .flex_none()
+                            .border_l_1()
+                            .border_color(cx.theme().border)
+                            .overflow_y_scroll()
+                            .track_scroll(&scroll_handle)
+                            .children(items.iter().enumerate().map(|(ix, (title, line))| {
+                                let view = view.clone();
+                                v_flex()
+                                    .id(ix)
+                                    .px_2()
+                                    .py_0p5()
+                                    .rounded(cx.theme().radius)
+                                    .when(ix == selected_ix, |this| {
+                                        this.bg(cx.theme().accent)
+                                            .text_color(cx.theme().accent_foreground)
+                                    })
+                                    .hover(|this| this.bg(cx.theme().accent.opacity(0.5)))
+                                    .on_click(move |event, window, cx| {
+                                        view.update(cx, |this, cx| {
+                                            this.on_click_item(ix, event, window, cx)
+                                        })
+                                    })
+                                    .child(Label::new(title.clone()))
+                                    .child(
+                                        Label::new(line.clone())
+                                            .text_color(cx.theme().muted_foreground)
+                                            .truncate(),
+                                    )
+                            })),
+                    )
+            },
+        )
+        .max_width(px(640.))
+    }
+}
@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use gpui::{
+    App, AppContext as _, Context, Entity, InteractiveElement as _, IntoElement,
+    ParentElement as _, Render, Styled as _, Subscription, Window, div,
+    prelude::FluentBuilder as _, px,
+};
+use rust_i18n::t;
+
+use crate::{
+    ActiveTheme as _, Sizable as _, h_flex,
+    input::{
+        Enter, Escape, Input, InputEvent, InputState, RenamePreview, SelectAll,
+        popovers::{Popover, uri_file_name},
+    },
+    label::Label,
+    v_flex,
+};
+
+/// The inline input to rename the symbol.
+///
+/// Press `Enter` to preview the edits, and `Enter` again to apply them.
+pub(crate) struct RenamePopover {
+    editor: Entity<InputState>,
+    input: Entity<InputState>,
+    /// The symbol range byte to rename.
+    pub(crate) symbol_range: Range<usize>,
+    /// The rename edits of the current new name.
+    pub(crate) preview: Option<RenamePreview>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl RenamePopover {
+    pub(crate) fn new(
+        editor: Entity<InputState>,
+        symbol_range: Range<usize>,
+        placeholder: &str,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let input = cx.new(|cx| InputState::new(window, cx).default_value(placeholder));
+        input.update(cx, |input, cx| {
+            input.focus(window, cx);
+            input.select_all(&SelectAll, window, cx);
+        });
+
+        cx.new(|cx| {
+            let _subscriptions = vec![cx.subscribe_in(
+                &input,
+                window,
+                |this: &mut Self, _, ev: &InputEvent, window, cx| match ev {
+                    InputEvent::Change => {
+                        // The preview is outdated after the name changed.
+                        if this.preview.take().is_some() {
+                            this.editor.update(cx, |_, cx| cx.notify());
+                            cx.notify();
+                        }
+                    }
+                    InputEvent::Blur => {
+                        this.editor.update(cx, |editor, cx| {
+                            editor.cancel_rename(window, cx);
+                        });
+                    }
+                    _ => {}
+                },
+            )];
+
+            Self {
+                editor,
+                input,
+                symbol_range,
+                preview: None,
+                _subscriptions,
+            }
+        })
+    }
+
+    pub(crate) fn set_preview(&mut self, preview: RenamePreview, cx: &mut Context<Self>) {
+        self.preview = Some(preview);
+        cx.notify();
+    }
+
+    /// Preview the edits of the new name, or apply them if already previewed.
+    fn on_action_enter(&mut self, _: &Enter, window: &mut Window, cx: &mut Context<Self>) {
+        let new_name = self.input.read(cx).value().trim().to_string();
+        if new_name.is_empty() {
+            return;
+        }
+
+        let offset = self.symbol_range.start;
+        let preview = self.preview.clone();
+        self.editor.update(cx, |editor, cx| match preview {
+            Some(preview) => editor.confirm_rename(preview, window, cx),
+            None => editor.request_rename(offset, new_name, window, cx),
+        });
+    }
+
+    fn on_action_escape(&mut self, _: &Escape, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor.update(cx, |editor, cx| {
+            editor.cancel_rename(window, cx);
+        });
+    }
+
+    /// Returns the total edits count and the edits count of each file to preview.
+    fn preview_files(&self, cx: &App) -> Option<(usize, Vec<(String, usize)>)> {
+        let preview = self.preview.as_ref()?;
+
+        let current_name = self
+            .editor
+            .read(cx)
+            .lsp
+            .document_uri
+            .as_ref()
+            .map(uri_file_name)
+            .unwrap_or_else(|| t!("Input.Current File").to_string());
+
+        let mut files = vec![];
+        if !preview.edits.is_empty() {
+            files.push((current_name, preview.edits.len()));
+        }
+        let mut changes = preview
+            .changes
+            .iter()
+            .map(|(uri, edits)| (uri_file_name(uri), edits.len()))
+            .collect::<Vec<_>>();
+        changes.sort();
+        files.extend(changes);
+
+        Some((preview.edits_count(), files))
+    }
+}
+
+fn render_preview(count: usize, files: &[(String, usize)], cx: &App) -> impl IntoElement {
+    v_flex()
+        .pt_1()
+        .gap_0p5()
+        .text_color(cx.theme().muted_foreground)
+        .child(Label::new(format!(
+            "{} ({})",
+            t!("Input.Rename Preview"),
+            count
+        )))
+        .children(files.iter().map(|(name, count)| {
+            h_flex()
+                .gap_2()
+                .justify_between()
+                .child(Label::new(name.clone()).text_color(cx.theme().foreground))
+                .child(Label::new(count.to_string()))
+        }))
+}
+
+impl Render for RenamePopover {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let view = cx.entity();
+        let input = self.input.clone();
+        let preview_files = self.preview_files(cx);
+
+        Popover::new(
+            "rename-popover",
+            self.editor.clone(),
+            self.symbol_range.clone(),
+            move |_, cx| {
+                div()
+                    .w(px(240.))
+                    .on_action({
+                        let view = view.clone();
+                        move |action: &Enter, window, cx| {
+                            view.update(cx, |this, cx| this.on_action_enter(action, window, cx))
+                        }
+                    })
+                    .on_action({
+                        let view = view.clone();
+                        move |action: &Escape, window, cx| {
+                            view.update(cx, |this, cx| this.on_action_escape(action, window, cx))
+                        }
+                    })
+                    .child(Input::new(&input).small())
+                    .when_some(preview_files.as_ref(), |this, (count, files)| {
+                        this.child(render_preview(*count, files, cx))
+                    })
+            },
+        )
+    }
+}
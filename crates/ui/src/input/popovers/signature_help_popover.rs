@@ -0,0 +1,258 @@
+use std::ops::Range;
+
+use gpui::{
+    App, AppContext as _, Context, Entity, FontWeight, HighlightStyle, IntoElement,
+    ParentElement as _, Render, SharedString, Styled as _, StyledText, Window, div,
+    prelude::FluentBuilder as _,
+};
+use lsp_types::{Documentation, ParameterLabel, SignatureHelp, SignatureInformation};
+
+use crate::{
+    ActiveTheme as _, h_flex,
+    input::{
+        InputState,
+        popovers::{Popover, render_markdown},
+    },
+    label::Label,
+    v_flex,
+};
+
+/// The popover to show the parameter hints of the function call.
+pub struct SignatureHelpPopover {
+    editor: Entity<InputState>,
+    /// The cursor offset of the signature help request.
+    offset: usize,
+    pub(crate) signature_help: SignatureHelp,
+    /// The index of the active signature (overload).
+    active_signature: usize,
+}
+
+impl SignatureHelpPopover {
+    pub fn new(
+        editor: Entity<InputState>,
+        offset: usize,
+        signature_help: SignatureHelp,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        cx.new(|cx| {
+            let mut this = Self {
+                editor,
+                offset,
+                signature_help: SignatureHelp {
+                    signatures: vec![],
+                    active_signature: None,
+                    active_parameter: None,
+                },
+                active_signature: 0,
+            };
+            this.update(offset, signature_help, cx);
+            this
+        })
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        offset: usize,
+        signature_help: SignatureHelp,
+        cx: &mut Context<Self>,
+    ) {
+        self.offset = offset;
+        self.active_signature = (signature_help.active_signature.unwrap_or(0) as usize)
+            .min(signature_help.signatures.len().saturating_sub(1));
+        self.signature_help = signature_help;
+        cx.notify();
+    }
+
+    /// Select the previous or next signature, return false if there is only one signature.
+    pub(crate) fn cycle(&mut self, delta: isize, cx: &mut Context<Self>) -> bool {
+        let count = self.signature_help.signatures.len();
+        if count < 2 {
+            return false;
+        }
+
+        self.active_signature =
+            (self.active_signature as isize + delta).rem_euclid(count as isize) as usize;
+        // Keep the selected signature on the next retrigger.
+        self.signature_help.active_signature = Some(self.active_signature as u32);
+        cx.notify();
+        true
+    }
+
+    fn active_parameter_range(&self, signature: &SignatureInformation) -> Option<Range<usize>> {
+        let active_parameter = signature
+            .active_parameter
+            .or(self.signature_help.active_parameter)?;
+        let parameter = signature
+            .parameters
+            .as_ref()?
+            .get(active_parameter as usize)?;
+
+        parameter_range(&signature.label, &parameter.label)
+    }
+}
+
+fn documentation_text(documentation: &Documentation) -> SharedString {
+    match documentation {
+        Documentation::String(s) => s.clone().into(),
+        Documentation::MarkupContent(content) => content.value.clone().into(),
+    }
+}
+
+/// Returns the byte range of the parameter in the signature label.
+fn parameter_range(label: &str, parameter: &ParameterLabel) -> Option<Range<usize>> {
+    match parameter {
+        ParameterLabel::Simple(name) => {
+            if name.is_empty() {
+                return None;
+            }
+
+            // Prefer to match in the parameters part, the function name may contain the same text.
+            let params_start = label.find('(').map(|ix| ix + 1).unwrap_or(0);
+            label[params_start..]
+                .find(name.as_str())
+                .map(|ix| params_start + ix..params_start + ix + name.len())
+        }
+        ParameterLabel::LabelOffsets([start, end]) => {
+            let offset_from_utf16 = |target: u32| {
+                let mut utf16_offset = 0;
+                for (ix, c) in label.char_indices() {
+                    if utf16_offset >= target as usize {
+                        return ix;
+                    }
+                    utf16_offset += c.len_utf16();
+                }
+                label.len()
+            };
+
+            let range = offset_from_utf16(*start)..offset_from_utf16(*end);
+            (!range.is_empty()).then_some(range)
+        }
+    }
+}
+
+impl Render for SignatureHelpPopover {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(signature) = self
+            .signature_help
+            .signatures
+            .get(self.active_signature)
+            .cloned()
+        else {
+            return div().into_any_element();
+        };
+
+        let count = self.signature_help.signatures.len();
+        let active_signature = self.active_signature;
+        let parameter_range = self.active_parameter_range(&signature);
+        let parameter_documentation = signature
+            .active_parameter
+            .or(self.signature_help.active_parameter)
+            .and_then(|ix| signature.parameters.as_ref()?.get(ix as usize))
+            .and_then(|parameter| parameter.documentation.as_ref())
+            .map(documentation_text);
+        let documentation = signature.documentation.as_ref().map(documentation_text);
+        let label: SharedString = signature.label.into();
+
+        Popover::new(
+            "signature-help-popover",
+            self.editor.clone(),
+            self.offset..self.offset,
+            move |window, cx| {
+                let highlights = parameter_range
+                    .clone()
+                    .map(|range| {
+                        vec![(
+                            range,
+                            HighlightStyle {
+                                color: Some(cx.theme().blue),
+                                font_weight: Some(FontWeight::BOLD),
+                                ..Default::default()
+                            },
+                        )]
+                    })
+                    .unwrap_or_default();
+
+                v_flex()
+                    .gap_1()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_start()
+                            .when(count > 1, |this| {
+                                this.child(
+                                    Label::new(format!("{}/{}", active_signature + 1, count))
+                                        .text_color(cx.theme().muted_foreground),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .font_family(cx.theme().mono_font_family.clone())
+                                    .child(
+                                        StyledText::new(label.clone()).with_highlights(highlights),
+                                    ),
+                            ),
+                    )
+                    .when_some(parameter_documentation.clone(), |this, documentation| {
+                        this.child(render_markdown(
+                            "parameter-documentation",
+                            documentation,
+                            window,
+                            cx,
+                        ))
+                    })
+                    .when_some(documentation.clone(), |this, documentation| {
+                        this.child(render_markdown(
+                            "signature-documentation",
+                            documentation,
+                            window,
+                            cx,
+                        ))
+                    })
+            },
+        )
+        .into_any_element()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::ParameterLabel;
+
+    use super::parameter_range;
+
+    #[test]
+    fn test_parameter_range() {
+        let label = "fn add(a: i32, b: i32) -> i32";
+        assert_eq!(
+            parameter_range(label, &ParameterLabel::Simple("a: i32".into())),
+            Some(7..13)
+        );
+        assert_eq!(
+            parameter_range(label, &ParameterLabel::Simple("b: i32".into())),
+            Some(15..21)
+        );
+        // Skip the same text in the function name.
+        assert_eq!(
+            parameter_range("add(add)", &ParameterLabel::Simple("add".into())),
+            Some(4..7)
+        );
+        assert_eq!(
+            parameter_range(label, &ParameterLabel::Simple("c".into())),
+            None
+        );
+
+        assert_eq!(
+            parameter_range(label, &ParameterLabel::LabelOffsets([7, 13])),
+            Some(7..13)
+        );
+        // The offsets are in UTF-16.
+        assert_eq!(
+            parameter_range("fn 你好(a: i32)", &ParameterLabel::LabelOffsets([6, 12])),
+            Some(10..16)
+        );
+        assert_eq!(
+            parameter_range(label, &ParameterLabel::LabelOffsets([7, 7])),
+            None
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use gpui::{
+    App, ElementId, Entity, InteractiveElement as _, IntoElement, ParentElement, RenderOnce,
+    StatefulInteractiveElement as _, StyleRefinement, Styled, Window, div,
+    prelude::FluentBuilder as _,
+};
+use rust_i18n::t;
+
+use crate::{
+    ActiveTheme as _, Icon, StyledExt as _, h_flex,
+    highlighter::{DiagnosticEntry, DiagnosticSeverity},
+    input::InputState,
+    v_flex,
+};
+
+/// A list of the diagnostics of a code editor, grouped by the severity.
+///
+/// Click a diagnostic to move the cursor to it and show the diagnostic popover.
+///
+/// ```ignore
+/// ProblemList::new("problems", &editor)
+/// ```
+#[derive(IntoElement)]
+pub struct ProblemList {
+    id: ElementId,
+    state: Entity<InputState>,
+    style: StyleRefinement,
+}
+
+impl ProblemList {
+    /// Create a problem list for the diagnostics of the [`InputState`].
+    pub fn new(id: impl Into<ElementId>, state: &Entity<InputState>) -> Self {
+        Self {
+            id: id.into(),
+            state: state.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+}
+
+impl Styled for ProblemList {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+fn severity_label(severity: DiagnosticSeverity) -> String {
+    match severity {
+        DiagnosticSeverity::Error => t!("Input.Errors"),
+        DiagnosticSeverity::Warning => t!("Input.Warnings"),
+        DiagnosticSeverity::Info => t!("Input.Infos"),
+        DiagnosticSeverity::Hint => t!("Input.Hints"),
+    }
+    .to_string()
+}
+
+fn severity_id(severity: DiagnosticSeverity) -> &'static str {
+    match severity {
+        DiagnosticSeverity::Error => "error",
+        DiagnosticSeverity::Warning => "warning",
+        DiagnosticSeverity::Info => "info",
+        DiagnosticSeverity::Hint => "hint",
+    }
+}
+
+/// Group the diagnostics by the severity (most severe first), and sort by the position in each group.
+fn group_by_severity(
+    entries: impl Iterator<Item = DiagnosticEntry>,
+) -> Vec<(DiagnosticSeverity, Vec<DiagnosticEntry>)> {
+    let mut groups: Vec<(DiagnosticSeverity, Vec<DiagnosticEntry>)> = [
+        DiagnosticSeverity::Error,
+        DiagnosticSeverity::Warning,
+        DiagnosticSeverity::Info,
+        DiagnosticSeverity::Hint,
+    ]
+    .into_iter()
+    .map(|severity| (severity, vec![]))
+    .collect();
+
+    for entry in entries {
+        if let Some((_, items)) = groups
+            .iter_mut()
+            .find(|(severity, _)| *severity == entry.severity)
+        {
+            items.push(entry);
+        }
+    }
+
+    groups.retain(|(_, items)| !items.is_empty());
+    for (_, items) in groups.iter_mut() {
+        items.sort_by_key(|entry| entry.range.start);
+    }
+    groups
+}
+
+impl RenderOnce for ProblemList {
+    fn render(self, _: &mut Window, cx: &mut App) -> impl IntoElement {
+        let (summary, groups) = match self.state.read(cx).diagnostics() {
+            Some(diagnostics) => (
+                diagnostics.summary(),
+                group_by_severity(diagnostics.iter().cloned()),
+            ),
+            None => (Default::default(), vec![]),
+        };
+
+        v_flex()
+            .id(self.id)
+            .size_full()
+            .overflow_y_scroll()
+            .text_sm()
+            .refine_style(&self.style)
+            .when(groups.is_empty(), |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(t!("Input.No Problems").to_string()),
+                )
+            })
+            .children(groups.into_iter().map(|(severity, entries)| {
+                v_flex()
+                    .child(
+                        h_flex()
+                            .px_2()
+                            .py_1()
+                            .gap_1p5()
+                            .font_semibold()
+                            .child(
+                                Icon::new(severity.icon())
+                                    .size_3p5()
+                                    .text_color(severity.fg(cx)),
+                            )
+                            .child(severity_label(severity))
+                            .child(
+                                div()
+                                    .px_1p5()
+                                    .rounded_full()
+                                    .text_xs()
+                                    .bg(cx.theme().muted)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(summary.count_of(severity).to_string()),
+                            ),
+                    )
+                    .children(entries.into_iter().enumerate().map(|(ix, entry)| {
+                        let state = self.state.clone();
+                        let position = entry.diagnostic.range.start;
+
+                        h_flex()
+                            .id((severity_id(severity), ix))
+                            .pl_7()
+                            .pr_2()
+                            .py_0p5()
+                            .gap_2()
+                            .cursor_pointer()
+                            .hover(|this| this.bg(cx.theme().accent))
+                            .child(div().flex_1().truncate().child(entry.message.clone()))
+                            .when_some(entry.source.clone(), |this, source| {
+                                this.child(
+                                    div()
+                                        .flex_shrink_0()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(source),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .flex_shrink_0()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "[{}:{}]",
+                                        position.line + 1,
+                                        position.character + 1
+                                    )),
+                            )
+                            .on_click(move |_, window, cx| {
+                                state.update(cx, |state, cx| {
+                                    state.go_to_diagnostic_entry(&entry, window, cx);
+                                });
+                            })
+                    }))
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::group_by_severity;
+    use crate::{
+        highlighter::{Diagnostic, DiagnosticSet, DiagnosticSeverity},
+        input::Position,
+    };
+
+    #[test]
+    fn test_group_by_severity() {
+        let text = Rope::from("let a = 1;\nlet b = 2;\nlet c = 3;");
+        let mut diagnostics = DiagnosticSet::new(&text);
+        diagnostics.extend([
+            Diagnostic::new(Position::new(2, 4)..Position::new(2, 5), "c")
+                .with_severity(DiagnosticSeverity::Warning),
+            Diagnostic::new(Position::new(1, 4)..Position::new(1, 5), "b")
+                .with_severity(DiagnosticSeverity::Error),
+            Diagnostic::new(Position::new(0, 4)..Position::new(0, 5), "a")
+                .with_severity(DiagnosticSeverity::Warning),
+        ]);
+
+        let groups = group_by_severity(diagnostics.iter().cloned());
+        let groups = groups
+            .iter()
+            .map(|(severity, entries)| {
+                (
+                    *severity,
+                    entries
+                        .iter()
+                        .map(|entry| entry.message.as_str())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                (DiagnosticSeverity::Error, vec!["b"]),
+                (DiagnosticSeverity::Warning, vec!["a", "c"]),
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use aho_corasick::AhoCorasick;
+use regex::{Regex, RegexBuilder};
 use rust_i18n::t;
 use std::{ops::Range, rc::Rc};
 
 use gpui::{
     App, AppContext as _, Context, Empty, Entity, FocusHandle, Focusable, Half,
-    InteractiveElement as _, IntoElement, KeyBinding, ParentElement as _, Pixels, Render, Styled,
-    Subscription, Window, actions, div, prelude::FluentBuilder as _,
+    InteractiveElement as _, IntoElement, KeyBinding, ParentElement as _, Pixels, Render,
+    SharedString, Styled, Subscription, Window, actions, div, prelude::FluentBuilder as _,
 };
 use ropey::Rope;
 
@@ -15,14 +16,16 @@ use crate::{
     button::{Button, ButtonVariants},
     h_flex,
     input::{
-        Enter, Escape, IndentInline, Input, InputEvent, InputState, RopeExt as _, Search,
-        movement::MoveDirection,
+        Enter, Escape, IndentInline, Input, InputEvent, InputState, MoveDown, MoveUp, RopeExt as _,
+        Search, movement::MoveDirection, selection::is_word_char,
     },
     label::Label,
     v_flex,
 };
 
 const CONTEXT: &'static str = "SearchPanel";
+/// The max number of the queries to keep in the search history.
+const MAX_SEARCH_HISTORY: usize = 50;
 
 actions!(input, [Tab]);
 
@@ -34,10 +37,43 @@ pub(super) fn init(cx: &mut App) {
     )]);
 }
 
+/// The options of the search query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Ignore the case of the query, default is true.
+    pub case_insensitive: bool,
+    /// Only match the whole word, default is false.
+    pub whole_word: bool,
+    /// Treat the query as a regular expression, default is false.
+    pub regex: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: true,
+            whole_word: false,
+            regex: false,
+        }
+    }
+}
+
+/// The compiled search query.
+#[derive(Debug, Clone)]
+pub enum SearchQuery {
+    Text(AhoCorasick),
+    Regex(Regex),
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchMatcher {
     text: Rope,
-    pub query: Option<AhoCorasick>,
+    pub query: Option<SearchQuery>,
+    options: SearchOptions,
+    /// The range to search in, None to search the whole text.
+    search_range: Option<Range<usize>>,
+    /// The error message if the regex query is invalid.
+    pub(super) error: Option<String>,
 
     pub(super) matched_ranges: Rc<Vec<Range<usize>>>,
     pub(super) current_match_ix: usize,
@@ -50,6 +86,9 @@ impl SearchMatcher {
         Self {
             text: "".into(),
             query: None,
+            options: SearchOptions::default(),
+            search_range: None,
+            error: None,
             matched_ranges: Rc::new(Vec::new()),
             current_match_ix: 0,
             replacing: false,
@@ -70,12 +109,59 @@ impl SearchMatcher {
         let mut new_ranges = Vec::new();
         if let Some(query) = &self.query {
             let text = self.text.to_string();
-            // FIXME: Use stream find
-            let matches = query.stream_find_iter(text.as_bytes());
+            let search_range = match &self.search_range {
+                Some(range) => range.start.min(text.len())..range.end.min(text.len()),
+                None => 0..text.len(),
+            };
+            let whole_word = self.options.whole_word;
+
+            match query {
+                SearchQuery::Text(query) => {
+                    // Use the overlapping iterator, so the whole word match is not hidden
+                    // by a previous not whole word match, e.g.: `aa` in `aaa aa`.
+                    for query_match in query.find_overlapping_iter(text.as_bytes()) {
+                        let range = query_match.range();
+                        if !search_range_contains(&search_range, &range)
+                            || (whole_word && !is_whole_word(&text, &range))
+                        {
+                            continue;
+                        }
+                        if new_ranges
+                            .last()
+                            .is_some_and(|last: &Range<usize>| last.end > range.start)
+                        {
+                            continue;
+                        }
+
+                        new_ranges.push(range);
+                    }
+                }
+                SearchQuery::Regex(regex) => {
+                    let mut offset = search_range.start;
+                    while offset <= search_range.end {
+                        // Search in the full text, so the anchors like `$` and `\b` are
+                        // not matched at the end of the search range.
+                        let Some(m) = regex.find_at(&text, offset) else {
+                            break;
+                        };
+                        let range = m.range();
+                        if range.is_empty()
+                            || range.end > search_range.end
+                            || (whole_word && !is_whole_word(&text, &range))
+                        {
+                            // Skip the match and continue from the next char.
+                            offset = range.start
+                                + text[range.start..]
+                                    .chars()
+                                    .next()
+                                    .map_or(1, |c| c.len_utf8());
+                            continue;
+                        }
 
-            for query_match in matches.into_iter() {
-                let query_match = query_match.expect("query match for select all action");
-                new_ranges.push(query_match.range());
+                        offset = range.end;
+                        new_ranges.push(range);
+                    }
+                }
             }
         }
         self.matched_ranges = Rc::new(new_ranges);
@@ -86,20 +172,68 @@ impl SearchMatcher {
     }
 
     /// Update the search query and reset the current match index.
-    pub fn update_query(&mut self, query: &str, case_insensitive: bool) {
+    pub fn update_query(&mut self, query: &str, options: SearchOptions) {
+        self.options = options;
+        self.error = None;
+        self.query = None;
+
         if query.len() > 0 {
-            self.query = Some(
-                AhoCorasick::builder()
-                    .ascii_case_insensitive(case_insensitive)
-                    .build(&[query.to_string()])
-                    .expect("failed to build AhoCorasick query in SearchMatcher"),
-            );
-        } else {
-            self.query = None;
+            if options.regex {
+                match RegexBuilder::new(query)
+                    .case_insensitive(options.case_insensitive)
+                    .multi_line(true)
+                    .build()
+                {
+                    Ok(regex) => self.query = Some(SearchQuery::Regex(regex)),
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+            } else {
+                self.query = Some(SearchQuery::Text(
+                    AhoCorasick::builder()
+                        .ascii_case_insensitive(options.case_insensitive)
+                        .build(&[query.to_string()])
+                        .expect("failed to build AhoCorasick query in SearchMatcher"),
+                ));
+            }
         }
         self.update_matches();
     }
 
+    /// Set the range to search in, None to search the whole text.
+    pub fn set_search_range(&mut self, range: Option<Range<usize>>) {
+        if self.search_range == range {
+            return;
+        }
+
+        self.search_range = range;
+        self.update_matches();
+    }
+
+    /// Returns the replacement text for each of the `ranges`.
+    ///
+    /// For the regex query, the `$1`, `${name}` in the `replacement` will be
+    /// replaced by the captured groups, otherwise the `replacement` is used as it is.
+    fn replacements(&self, ranges: &[Range<usize>], replacement: &str) -> Vec<String> {
+        let Some(SearchQuery::Regex(regex)) = &self.query else {
+            return ranges.iter().map(|_| replacement.to_string()).collect();
+        };
+
+        let text = self.text.to_string();
+        ranges
+            .iter()
+            .map(|range| {
+                let mut new_text = String::new();
+                match regex.captures_at(&text, range.start) {
+                    Some(captures) if captures.get_match().range() == *range => {
+                        captures.expand(replacement, &mut new_text);
+                    }
+                    _ => new_text.push_str(replacement),
+                }
+                new_text
+            })
+            .collect()
+    }
+
     /// Returns the number of matches found.
     #[allow(unused)]
     #[inline]
@@ -129,6 +263,26 @@ impl SearchMatcher {
     }
 }
 
+fn search_range_contains(search_range: &Range<usize>, range: &Range<usize>) -> bool {
+    range.start >= search_range.start && range.end <= search_range.end
+}
+
+/// Returns true if the `range` in the `text` is not a part of a larger word.
+fn is_whole_word(text: &str, range: &Range<usize>) -> bool {
+    let matched = &text[range.clone()];
+    let prev = text[..range.start].chars().next_back();
+    let next = text[range.end..].chars().next();
+    let first = matched.chars().next();
+    let last = matched.chars().next_back();
+
+    let is_boundary = |a: Option<char>, b: Option<char>| match (a, b) {
+        (Some(a), Some(b)) => !(is_word_char(a) && is_word_char(b)),
+        _ => true,
+    };
+
+    is_boundary(prev, first) && is_boundary(last, next)
+}
+
 impl Iterator for SearchMatcher {
     type Item = Range<usize>;
 
@@ -168,10 +322,16 @@ pub(super) struct SearchPanel {
     editor: Entity<InputState>,
     search_input: Entity<InputState>,
     replace_input: Entity<InputState>,
-    case_insensitive: bool,
+    options: SearchOptions,
+    /// Restrict the matches to the selection of the editor.
+    in_selection: bool,
     replace_mode: bool,
     matcher: SearchMatcher,
     input_width: Pixels,
+    /// The search history of this editor, the latest is at the end.
+    history: Vec<SharedString>,
+    /// The index of the history item showing in the search input.
+    history_ix: Option<usize>,
 
     open: bool,
     _subscriptions: Vec<Subscription>,
@@ -230,6 +390,7 @@ impl SearchPanel {
                         // Handle search input changes
                         match ev {
                             InputEvent::Change => {
+                                this.reset_history_ix(cx);
                                 this.update_search_query(cx);
                             }
                             _ => {}
@@ -241,11 +402,14 @@ impl SearchPanel {
                 editor,
                 search_input,
                 replace_input,
-                case_insensitive: true,
+                options: SearchOptions::default(),
+                in_selection: false,
                 replace_mode: false,
                 matcher: SearchMatcher::new(),
                 open: true,
                 input_width: Pixels::ZERO,
+                history: Vec::new(),
+                history_ix: None,
                 _subscriptions,
             }
         })
@@ -264,6 +428,10 @@ impl SearchPanel {
             .clone()
             .focus(window, cx);
 
+        if self.in_selection {
+            self.update_search_range(cx);
+        }
+
         self.search_input.update(cx, |this, cx| {
             if selected_text.len() > 0 {
                 // Set value will emit to update_search_query
@@ -282,8 +450,7 @@ impl SearchPanel {
             .as_ref()
             .map(|l| l.visible_range_offset.clone());
 
-        self.matcher
-            .update_query(query.as_str(), self.case_insensitive);
+        self.matcher.update_query(query.as_str(), self.options);
 
         if let Some(visible_range_offset) = visible_range_offset {
             self.matcher
@@ -292,7 +459,88 @@ impl SearchPanel {
         cx.notify();
     }
 
+    /// Restrict the matches to the selection of the editor if `in_selection` is enabled.
+    fn update_search_range(&mut self, cx: &mut Context<Self>) {
+        let range = if self.in_selection {
+            let selected_range = self.editor.read(cx).selected_range;
+            (!selected_range.is_empty()).then(|| selected_range.start..selected_range.end)
+        } else {
+            None
+        };
+
+        self.matcher.set_search_range(range);
+        cx.notify();
+    }
+
+    fn toggle_in_selection(&mut self, cx: &mut Context<Self>) {
+        self.in_selection = !self.in_selection;
+        self.update_search_range(cx);
+    }
+
+    /// Add the current query to the search history.
+    fn push_history(&mut self, cx: &mut Context<Self>) {
+        let query: SharedString = self.search_input.read(cx).value();
+        if query.is_empty() {
+            return;
+        }
+
+        self.history.retain(|item| item != &query);
+        self.history.push(query);
+        if self.history.len() > MAX_SEARCH_HISTORY {
+            self.history.remove(0);
+        }
+        self.history_ix = None;
+    }
+
+    fn reset_history_ix(&mut self, cx: &mut Context<Self>) {
+        let Some(ix) = self.history_ix else {
+            return;
+        };
+
+        // The query is changed by typing, not by the history navigation.
+        if self.history.get(ix) != Some(&self.search_input.read(cx).value()) {
+            self.history_ix = None;
+        }
+    }
+
+    /// Show the previous (or next) query of the search history in the search input.
+    fn navigate_history(&mut self, direction: isize, window: &mut Window, cx: &mut Context<Self>) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let ix = match self.history_ix {
+            None if direction < 0 => {
+                // Keep the current query in the history, so it can be recalled by the next.
+                let is_empty = self.search_input.read(cx).value().is_empty();
+                self.push_history(cx);
+                if is_empty {
+                    self.history.len() - 1
+                } else {
+                    self.history.len().saturating_sub(2)
+                }
+            }
+            None => return,
+            Some(ix) => {
+                let ix = ix.saturating_add_signed(direction);
+                if ix >= self.history.len() {
+                    return;
+                }
+                ix
+            }
+        };
+
+        let Some(query) = self.history.get(ix).cloned() else {
+            return;
+        };
+        self.history_ix = Some(ix);
+        self.search_input.update(cx, |this, cx| {
+            this.set_value(query, window, cx);
+        });
+    }
+
     pub(super) fn hide(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.push_history(cx);
         self.open = false;
         self.editor.read(cx).focus_handle.clone().focus(window, cx);
         cx.notify();
@@ -314,6 +562,27 @@ impl SearchPanel {
         self.editor.focus_handle(cx).focus(window, cx);
     }
 
+    fn on_action_history_prev(&mut self, _: &MoveUp, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.search_input.read(cx).focus_handle.is_focused(window) {
+            return;
+        }
+
+        self.navigate_history(-1, window, cx);
+    }
+
+    fn on_action_history_next(
+        &mut self,
+        _: &MoveDown,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.search_input.read(cx).focus_handle.is_focused(window) {
+            return;
+        }
+
+        self.navigate_history(1, window, cx);
+    }
+
     fn prev(&mut self, _: &mut Window, cx: &mut Context<Self>) {
         if let Some(range) = self.matcher.next_back() {
             self.editor.update(cx, |state, cx| {
@@ -323,6 +592,7 @@ impl SearchPanel {
     }
 
     fn next(&mut self, _: &mut Window, cx: &mut Context<Self>) {
+        self.push_history(cx);
         if let Some(range) = self.matcher.next() {
             self.editor.update(cx, |state, cx| {
                 state.scroll_to(range.end, Some(MoveDirection::Down), cx);
@@ -348,6 +618,11 @@ impl SearchPanel {
             .cloned()
         {
             let text_state = self.editor.clone();
+            let new_text = self
+                .matcher
+                .replacements(&[range.clone()], new_text.as_str())
+                .remove(0);
+            self.shift_search_range(new_text.len() as isize - range.len() as isize);
 
             let next_range = self.matcher.peek().unwrap_or(range.clone());
             cx.spawn_in(window, async move |_, cx| {
@@ -368,6 +643,14 @@ impl SearchPanel {
         }
     }
 
+    /// Grow (or shrink) the end of the search range by `delta` after replacing in it.
+    fn shift_search_range(&mut self, delta: isize) {
+        if let Some(range) = self.matcher.search_range.clone() {
+            let end = range.end.saturating_add_signed(delta).max(range.start);
+            self.matcher.search_range = Some(range.start..end);
+        }
+    }
+
     fn replace_all(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let new_text = self.replace_input.read(cx).value();
         self.matcher.replacing = true;
@@ -376,13 +659,22 @@ impl SearchPanel {
             return;
         }
 
+        let new_texts = self.matcher.replacements(&ranges, new_text.as_str());
+        self.shift_search_range(
+            new_texts
+                .iter()
+                .zip(ranges.iter())
+                .map(|(new_text, range)| new_text.len() as isize - range.len() as isize)
+                .sum(),
+        );
+
         let editor = self.editor.clone();
         cx.spawn_in(window, async move |_, cx| {
             cx.update(|window, cx| {
                 editor.update(cx, |state, cx| {
                     // Replace from the end to avoid messing up the ranges.
                     let mut rope = state.text.clone();
-                    for range in ranges.iter().rev() {
+                    for (range, new_text) in ranges.iter().zip(new_texts.iter()).rev() {
                         rope.replace(range.clone(), new_text.as_str());
                     }
                     state.replace_text_in_range_silent(
@@ -412,6 +704,7 @@ impl Render for SearchPanel {
         }
 
         let has_matches = self.matcher.len() > 0;
+        let has_error = self.matcher.error.is_some();
 
         v_flex()
             .id("search-panel")
@@ -422,6 +715,8 @@ impl Render for SearchPanel {
             .on_action(cx.listener(Self::on_action_next))
             .on_action(cx.listener(Self::on_action_escape))
             .on_action(cx.listener(Self::on_action_tab))
+            .on_action(cx.listener(Self::on_action_history_prev))
+            .on_action(cx.listener(Self::on_action_history_next))
             .font_family(cx.theme().font_family.clone())
             .items_center()
             .py_2()
@@ -444,17 +739,49 @@ impl Render for SearchPanel {
                                 Input::new(&self.search_input)
                                     .focus_bordered(false)
                                     .suffix(
-                                        Button::new("case-insensitive")
-                                            .selected(!self.case_insensitive)
-                                            .xsmall()
-                                            .compact()
-                                            .ghost()
-                                            .icon(IconName::CaseSensitive)
-                                            .on_click(cx.listener(|this, _, _, cx| {
-                                                this.case_insensitive = !this.case_insensitive;
-                                                this.update_search_query(cx);
-                                                cx.notify();
-                                            })),
+                                        h_flex()
+                                            .gap_0p5()
+                                            .child(
+                                                Button::new("case-insensitive")
+                                                    .selected(!self.options.case_insensitive)
+                                                    .xsmall()
+                                                    .compact()
+                                                    .ghost()
+                                                    .icon(IconName::CaseSensitive)
+                                                    .tooltip(t!("Input.Match Case"))
+                                                    .on_click(cx.listener(|this, _, _, cx| {
+                                                        this.options.case_insensitive =
+                                                            !this.options.case_insensitive;
+                                                        this.update_search_query(cx);
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("whole-word")
+                                                    .selected(self.options.whole_word)
+                                                    .xsmall()
+                                                    .compact()
+                                                    .ghost()
+                                                    .icon(IconName::WholeWord)
+                                                    .tooltip(t!("Input.Match Whole Word"))
+                                                    .on_click(cx.listener(|this, _, _, cx| {
+                                                        this.options.whole_word =
+                                                            !this.options.whole_word;
+                                                        this.update_search_query(cx);
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("regex")
+                                                    .selected(self.options.regex)
+                                                    .xsmall()
+                                                    .compact()
+                                                    .ghost()
+                                                    .icon(IconName::Regex)
+                                                    .tooltip(t!("Input.Use Regular Expression"))
+                                                    .on_click(cx.listener(|this, _, _, cx| {
+                                                        this.options.regex = !this.options.regex;
+                                                        this.update_search_query(cx);
+                                                    })),
+                                            ),
                                     )
                                     .small()
                                     .w_full()
@@ -491,6 +818,17 @@ impl Render for SearchPanel {
                                 cx.notify();
                             })),
                     )
+                    .child(
+                        Button::new("in-selection")
+                            .xsmall()
+                            .ghost()
+                            .icon(IconName::TextSelect)
+                            .tooltip(t!("Input.Find in Selection"))
+                            .selected(self.in_selection)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.toggle_in_selection(cx);
+                            })),
+                    )
                     .child(
                         Button::new("prev")
                             .xsmall()
@@ -516,6 +854,7 @@ impl Render for SearchPanel {
                             .when(!has_matches, |this| {
                                 this.text_color(cx.theme().muted_foreground)
                             })
+                            .when(has_error, |this| this.text_color(cx.theme().danger))
                             .text_left()
                             .min_w_16(),
                     )
@@ -574,7 +913,7 @@ mod tests {
     fn test_search() {
         let mut matcher = SearchMatcher::new();
         matcher.update(&Rope::from("Hello 世界 this is a Is test string."));
-        matcher.update_query("Is", true);
+        matcher.update_query("Is", SearchOptions::default());
 
         assert_eq!(matcher.len(), 3);
         let mut matches = matcher.clone();
@@ -592,7 +931,13 @@ mod tests {
         assert_eq!(matches.current_match_ix, 0);
         assert_eq!(matches.next_back(), Some(23..25));
 
-        matcher.update_query("IS", false);
+        matcher.update_query(
+            "IS",
+            SearchOptions {
+                case_insensitive: false,
+                ..Default::default()
+            },
+        );
         assert_eq!(matcher.len(), 0);
         assert_eq!(matcher.next(), None);
         assert_eq!(matcher.next_back(), None);
@@ -602,7 +947,7 @@ mod tests {
     fn test_search_label() {
         let mut matcher = SearchMatcher::new();
         matcher.update(&Rope::from("Hello 世界 this is a Is test string."));
-        matcher.update_query("Is", true);
+        matcher.update_query("Is", SearchOptions::default());
         assert_eq!(matcher.label(), "1/3");
         matcher.next();
         assert_eq!(matcher.label(), "2/3");
@@ -611,7 +956,13 @@ mod tests {
         matcher.next();
         assert_eq!(matcher.label(), "1/3");
 
-        matcher.update_query("IS", false);
+        matcher.update_query(
+            "IS",
+            SearchOptions {
+                case_insensitive: false,
+                ..Default::default()
+            },
+        );
         assert_eq!(matcher.label(), "0/0");
     }
 
@@ -637,4 +988,108 @@ mod tests {
         matcher.update_cursor_by_offset(31);
         assert_eq!(matcher.current_match_ix, 2);
     }
+
+    #[test]
+    fn test_search_whole_word() {
+        let mut matcher = SearchMatcher::new();
+        matcher.update(&Rope::from("aaa aa foo_aa aa.b aa"));
+        let options = SearchOptions {
+            whole_word: true,
+            ..Default::default()
+        };
+        matcher.update_query("aa", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![4..6, 14..16, 19..21]);
+
+        matcher.update_query("a", options);
+        assert_eq!(matcher.len(), 0);
+
+        // Non-word chars in the query are always at the boundary.
+        matcher.update_query(".b", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![16..18]);
+    }
+
+    #[test]
+    fn test_search_regex() {
+        let mut matcher = SearchMatcher::new();
+        matcher.update(&Rope::from("let foo = 1;\nlet bar = 22;"));
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+
+        matcher.update_query(r"(\w+) = (\d+)", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![4..11, 17..25]);
+        assert_eq!(
+            matcher.replacements(&matcher.matched_ranges.clone(), "$2 = $1"),
+            vec!["1 = foo".to_string(), "22 = bar".to_string()]
+        );
+
+        matcher.update_query("^LET", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![0..3, 13..16]);
+
+        // Empty matches are ignored.
+        matcher.update_query("x*", options);
+        assert_eq!(matcher.len(), 0);
+
+        matcher.update_query("(foo", options);
+        assert!(matcher.error.is_some());
+        assert_eq!(matcher.len(), 0);
+
+        matcher.update_query(r"\bba", options);
+        assert!(matcher.error.is_none());
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![17..19]);
+    }
+
+    #[test]
+    fn test_search_in_range() {
+        let mut matcher = SearchMatcher::new();
+        matcher.update(&Rope::from("foo bar foo bar foo"));
+        matcher.update_query("foo", SearchOptions::default());
+        assert_eq!(matcher.len(), 3);
+
+        matcher.set_search_range(Some(4..15));
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![8..11]);
+
+        // The match must be fully in the range.
+        matcher.set_search_range(Some(4..10));
+        assert_eq!(matcher.len(), 0);
+
+        matcher.set_search_range(None);
+        assert_eq!(matcher.len(), 3);
+        assert_eq!(
+            matcher.replacements(&matcher.matched_ranges.clone(), "$1"),
+            vec!["$1".to_string(); 3]
+        );
+    }
+
+    #[test]
+    fn test_search_regex_in_range() {
+        let mut matcher = SearchMatcher::new();
+        matcher.update(&Rope::from("let foo = 1;\nlet bar = 22;"));
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+
+        // The end of the range is not the end of the line.
+        matcher.set_search_range(Some(0..6));
+        matcher.update_query(r"\w+$", options);
+        assert_eq!(matcher.len(), 0);
+        matcher.update_query(r"fo\b", options);
+        assert_eq!(matcher.len(), 0);
+
+        matcher.set_search_range(Some(4..25));
+        matcher.update_query(r"\d+;$", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![10..12]);
+        matcher.update_query(r"(\w+) = (\d+)", options);
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![4..11, 17..25]);
+        assert_eq!(
+            matcher.replacements(&matcher.matched_ranges.clone(), "$2 = $1"),
+            vec!["1 = foo".to_string(), "22 = bar".to_string()]
+        );
+
+        // The match crossing the end of the range is dropped.
+        matcher.set_search_range(Some(4..24));
+        assert_eq!(matcher.matched_ranges.as_ref(), &vec![4..11]);
+    }
 }
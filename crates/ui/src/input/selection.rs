@@ -3,8 +3,12 @@ use std::{char, ops::Range};
 use gpui::{Context, Window};
 use ropey::Rope;
 use sum_tree::Bias;
+use tree_sitter::Node;
 
-use crate::{RopeExt as _, input::InputState};
+use crate::{
+    RopeExt as _,
+    input::{ExpandSelection, InputState, ShrinkSelection, mode::InputMode},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CharType {
@@ -19,7 +23,7 @@ enum CharType {
 }
 
 /// Implementation based on <https://github.com/zed-industries/zed/blob/main/crates/gpui/src/text_system/line_wrapper.rs>
-fn is_word_char(c: char) -> bool {
+pub(super) fn is_word_char(c: char) -> bool {
     matches!(c, '_' ) ||
     // ASCII alphanumeric characters, for English, numbers: `Hello123`, etc.
     c.is_ascii_alphanumeric() ||
@@ -88,6 +92,125 @@ impl InputState {
         self.selected_word_range = None;
         cx.notify()
     }
+
+    /// Expand the selection to the next enclosing syntax node.
+    ///
+    /// Without the syntax tree, the selection is expanded to the word, line and the whole text.
+    pub(super) fn expand_selection(
+        &mut self,
+        _: &ExpandSelection,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let current = self.selected_range;
+        // The selection has been changed by others, start a new stack.
+        if self.selection_stack.last() != Some(&current) {
+            self.selection_stack = vec![current];
+        }
+
+        let Some(range) = self.larger_selection_range(&current.into()) else {
+            return;
+        };
+
+        self.selected_range = range.into();
+        self.selection_reversed = false;
+        self.selected_word_range = None;
+        self.selection_stack.push(self.selected_range);
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        cx.notify();
+    }
+
+    /// Shrink the selection to the previous range before [`Self::expand_selection`].
+    pub(super) fn shrink_selection(
+        &mut self,
+        _: &ShrinkSelection,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.selection_stack.len() < 2
+            || self.selection_stack.last() != Some(&self.selected_range)
+        {
+            self.selection_stack.clear();
+            return;
+        }
+
+        self.selection_stack.pop();
+        if let Some(range) = self.selection_stack.last() {
+            self.selected_range = *range;
+            self.selection_reversed = false;
+            self.update_preferred_column();
+            cx.notify();
+        }
+    }
+
+    fn larger_selection_range(&self, range: &Range<usize>) -> Option<Range<usize>> {
+        if let InputMode::CodeEditor { highlighter, .. } = &self.mode {
+            let highlighter = highlighter.borrow();
+            if let Some(tree) = highlighter
+                .as_ref()
+                .and_then(|highlighter| highlighter.tree())
+            {
+                return enclosing_syntax_range(tree.root_node(), &self.text, range);
+            }
+        }
+
+        TextSelector::larger_range(&self.text, range)
+    }
+}
+
+/// Returns true if the `outer` range contains the `inner` range, and is larger than it.
+fn is_larger_range(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end && outer.len() > inner.len()
+}
+
+/// Returns the smallest syntax node range that is larger than the `range`.
+///
+/// The content inside the brackets or quotes is a step before the node,
+/// e.g.: `a` -> `a, b` -> `(a, b)`, `abc` -> `"abc"`.
+fn enclosing_syntax_range(root: Node, text: &Rope, range: &Range<usize>) -> Option<Range<usize>> {
+    let mut node = root.descendant_for_byte_range(range.start, range.end)?;
+    loop {
+        if let Some(inner) = inner_range(node, text).filter(|inner| is_larger_range(inner, range)) {
+            return Some(inner);
+        }
+        if is_larger_range(&node.byte_range(), range) {
+            return Some(node.byte_range());
+        }
+
+        node = node.parent()?;
+    }
+}
+
+/// Returns the range between the first and last children if they are paired brackets or quotes,
+/// the whitespaces around the content are trimmed.
+fn inner_range(node: Node, text: &Rope) -> Option<Range<usize>> {
+    const PAIRS: [(&str, &str); 7] = [
+        ("(", ")"),
+        ("[", "]"),
+        ("{", "}"),
+        ("<", ">"),
+        ("\"", "\""),
+        ("'", "'"),
+        ("`", "`"),
+    ];
+
+    let mut cursor = node.walk();
+    let children = node.children(&mut cursor).collect::<Vec<_>>();
+    let (first, last) = (children.first()?, children.last()?);
+    if children.len() < 3
+        || first.is_named()
+        || last.is_named()
+        || !PAIRS.contains(&(first.kind(), last.kind()))
+    {
+        return None;
+    }
+
+    let (start, end) = (first.end_byte(), last.start_byte());
+    let content = text.slice(start..end).to_string();
+    let start = start + content.len() - content.trim_start().len();
+    let end = end - (content.len() - content.trim_end().len());
+    (start < end).then_some(start..end)
 }
 
 struct TextSelector;
@@ -141,6 +264,18 @@ impl TextSelector {
 
         Some(start..end)
     }
+
+    /// Returns the word, line or the whole text range that is larger than the `range`.
+    pub fn larger_range(text: &Rope, range: &Range<usize>) -> Option<Range<usize>> {
+        [
+            Self::word_range(text, range.start),
+            Some(Self::line_range(text, range.start)),
+            Some(0..text.len()),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|candidate| is_larger_range(candidate, range))
+    }
 }
 
 #[cfg(test)]
@@ -244,4 +379,49 @@ mod tests {
             assert_eq!(actual, expected, "line {}, column {}", line, column);
         }
     }
+
+    #[test]
+    fn test_larger_range() {
+        let rope = Rope::from("hello world\nfoo");
+        let expand = |range: Range<usize>| {
+            TextSelector::larger_range(&rope, &range).map(|r| rope.slice(r).to_string())
+        };
+
+        assert_eq!(expand(1..1), Some("hello".into()));
+        assert_eq!(expand(0..5), Some("hello world".into()));
+        assert_eq!(expand(0..11), Some("hello world\nfoo".into()));
+        assert_eq!(expand(0..15), None);
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_enclosing_syntax_range() {
+        use crate::highlighter::SyntaxHighlighter;
+
+        let text = Rope::from("fn main() {\n    foo(\"abc\", 1);\n}");
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &text);
+        let root = highlighter.tree().unwrap().root_node();
+
+        let mut range = 22..22;
+        let mut steps = vec![];
+        while let Some(new_range) = enclosing_syntax_range(root, &text, &range) {
+            steps.push(text.slice(new_range.clone()).to_string());
+            range = new_range;
+        }
+
+        assert_eq!(
+            steps,
+            vec![
+                "abc",
+                "\"abc\"",
+                "\"abc\", 1",
+                "(\"abc\", 1)",
+                "foo(\"abc\", 1)",
+                "foo(\"abc\", 1);",
+                "{\n    foo(\"abc\", 1);\n}",
+                "fn main() {\n    foo(\"abc\", 1);\n}",
+            ]
+        );
+    }
 }
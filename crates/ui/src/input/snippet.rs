@@ -0,0 +1,1128 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use gpui::{App, Context, SharedString, Window};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, InsertTextFormat, TextEdit,
+};
+
+use crate::{
+    highlighter::Language,
+    input::{
+        InputState, RopeExt as _, Selection,
+        multi_cursor::shift_offset,
+        popovers::{CompletionMenu, ContextMenu},
+    },
+};
+
+/// A user snippet of a language, see [`SnippetRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnippet {
+    /// The word to show the snippet in the completion menu, e.g.: `fn`.
+    pub prefix: SharedString,
+    /// The snippet in the LSP snippet syntax, e.g.: `fn ${1:name}($2) {\n\t$0\n}`.
+    pub body: SharedString,
+    /// The description to show in the completion menu.
+    pub description: Option<SharedString>,
+}
+
+impl UserSnippet {
+    pub fn new(prefix: impl Into<SharedString>, body: impl Into<SharedString>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            body: body.into(),
+            description: None,
+        }
+    }
+
+    /// Set the description to show in the completion menu.
+    pub fn description(mut self, description: impl Into<SharedString>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// Registry for the user snippets of the languages.
+///
+/// The snippets of the language of the code editor are shown in the completion menu
+/// when the word before the cursor is a prefix of them.
+pub struct SnippetRegistry {
+    snippets: Mutex<HashMap<SharedString, Vec<UserSnippet>>>,
+}
+
+impl SnippetRegistry {
+    /// Returns the singleton instance of the `SnippetRegistry`.
+    pub fn singleton() -> &'static LazyLock<SnippetRegistry> {
+        static INSTANCE: LazyLock<SnippetRegistry> = LazyLock::new(|| SnippetRegistry {
+            snippets: Mutex::new(HashMap::new()),
+        });
+        &INSTANCE
+    }
+
+    /// Registers the snippets of the language, they are appended to the existing snippets.
+    pub fn register(&self, language: &str, snippets: impl IntoIterator<Item = UserSnippet>) {
+        self.snippets
+            .lock()
+            .unwrap()
+            .entry(language.to_string().into())
+            .or_default()
+            .extend(snippets);
+    }
+
+    /// Removes all the snippets of the language.
+    pub fn clear(&self, language: &str) {
+        self.snippets.lock().unwrap().remove(language);
+    }
+
+    /// Returns the snippets of the language.
+    pub fn snippets(&self, language: &str) -> Vec<UserSnippet> {
+        // Same as the `LanguageRegistry`, fallback to the built-in language to support short names.
+        let snippets = self.snippets.lock().unwrap();
+        snippets
+            .get(language)
+            .or_else(|| snippets.get(Language::from_str(language).name()))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SnippetPart {
+    Text(String),
+    /// `$1`, `${1:placeholder}` or `${1|one,two|}`.
+    TabStop {
+        index: usize,
+        placeholder: Vec<SnippetPart>,
+        choices: Vec<String>,
+    },
+    /// `$NAME` or `${NAME:default}`, the transform (`${NAME/regex/format/}`) is ignored.
+    Variable {
+        name: String,
+        default: Option<Vec<SnippetPart>>,
+    },
+}
+
+/// A snippet in the LSP snippet syntax.
+///
+/// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#snippet_syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snippet {
+    parts: Vec<SnippetPart>,
+}
+
+/// A tab stop of the expanded snippet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TabStop {
+    /// The number of the tab stop, `0` is the final cursor position.
+    index: usize,
+    /// The ranges of the placeholders, more than one if the tab stop is mirrored.
+    ranges: Vec<Range<usize>>,
+    /// The choices of the placeholder, e.g.: `${1|one,two|}`.
+    choices: Vec<String>,
+}
+
+/// The text and tab stops of the expanded snippet.
+///
+/// The tab stops are in the order to jump, the last one is always the final tab stop (`$0`).
+#[derive(Debug, PartialEq, Eq)]
+struct ExpandedSnippet {
+    text: String,
+    tabstops: Vec<TabStop>,
+}
+
+impl Snippet {
+    fn parse(source: &str) -> Self {
+        let mut parser = SnippetParser { source, pos: 0 };
+        Self {
+            parts: parser.parse_parts(None),
+        }
+    }
+
+    /// Expand the snippet to the text to insert.
+    ///
+    /// - `indent`: The indent of the line to insert, it is added after each new line.
+    /// - `tab`: The text to replace the `\t` in the snippet.
+    /// - `resolve`: Returns the value of a variable, `None` if the variable is unknown.
+    fn expand(
+        &self,
+        indent: &str,
+        tab: &str,
+        resolve: impl Fn(&str) -> Option<String>,
+    ) -> ExpandedSnippet {
+        let mut placeholders = HashMap::new();
+        collect_placeholders(&self.parts, &mut placeholders);
+
+        let mut expander = Expander {
+            indent,
+            tab,
+            resolve,
+            placeholders,
+            text: String::new(),
+            tabstops: BTreeMap::new(),
+            visiting: vec![],
+        };
+        expander.render(&self.parts);
+
+        let len = expander.text.len();
+        let mut tabstops = expander.tabstops.into_values().collect::<Vec<_>>();
+        let final_tabstop = if tabstops.first().is_some_and(|tabstop| tabstop.index == 0) {
+            tabstops.remove(0)
+        } else {
+            TabStop {
+                index: 0,
+                ranges: vec![len..len],
+                choices: vec![],
+            }
+        };
+        tabstops.push(final_tabstop);
+
+        ExpandedSnippet {
+            text: expander.text,
+            tabstops,
+        }
+    }
+}
+
+struct SnippetParser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl SnippetParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parse the parts until the `end` char (not consumed) or the end of the source.
+    fn parse_parts(&mut self, end: Option<char>) -> Vec<SnippetPart> {
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            if Some(c) == end {
+                break;
+            }
+
+            let start = self.pos;
+            self.bump();
+            match c {
+                '\\' => match self.peek() {
+                    Some(next @ ('$' | '}' | '\\')) => {
+                        self.bump();
+                        text.push(next);
+                    }
+                    _ => text.push(c),
+                },
+                '$' => match self.parse_dollar() {
+                    Some(part) => {
+                        if !text.is_empty() {
+                            parts.push(SnippetPart::Text(std::mem::take(&mut text)));
+                        }
+                        parts.push(part);
+                    }
+                    // Not a valid tab stop or variable, keep the `$` as text.
+                    None => {
+                        self.pos = start + 1;
+                        text.push(c);
+                    }
+                },
+                _ => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(SnippetPart::Text(text));
+        }
+        parts
+    }
+
+    /// Parse a tab stop or variable after the `$`.
+    fn parse_dollar(&mut self) -> Option<SnippetPart> {
+        if let Some(index) = self.parse_int() {
+            return Some(SnippetPart::TabStop {
+                index,
+                placeholder: vec![],
+                choices: vec![],
+            });
+        }
+        if let Some(name) = self.parse_name() {
+            return Some(SnippetPart::Variable {
+                name,
+                default: None,
+            });
+        }
+        if !self.eat('{') {
+            return None;
+        }
+
+        if let Some(index) = self.parse_int() {
+            let mut placeholder = vec![];
+            let mut choices = vec![];
+            if self.eat(':') {
+                placeholder = self.parse_parts(Some('}'));
+            } else if self.eat('|') {
+                choices = self.parse_choices()?;
+                if let Some(choice) = choices.first() {
+                    placeholder = vec![SnippetPart::Text(choice.clone())];
+                }
+            }
+
+            return self.eat('}').then_some(SnippetPart::TabStop {
+                index,
+                placeholder,
+                choices,
+            });
+        }
+
+        let name = self.parse_name()?;
+        let mut default = None;
+        if self.eat(':') {
+            default = Some(self.parse_parts(Some('}')));
+        } else if self.eat('/') {
+            self.skip_transform()?;
+        }
+
+        self.eat('}')
+            .then_some(SnippetPart::Variable { name, default })
+    }
+
+    fn parse_int(&mut self) -> Option<usize> {
+        let len = self.source[self.pos..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let value = self.source[self.pos..self.pos + len].parse().ok()?;
+        self.pos += len;
+        Some(value)
+    }
+
+    fn parse_name(&mut self) -> Option<String> {
+        let rest = &self.source[self.pos..];
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+
+        let len = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .count();
+        self.pos += len;
+        Some(rest[..len].to_string())
+    }
+
+    /// Parse the choices after the `|` until the closing `|`.
+    fn parse_choices(&mut self) -> Option<Vec<String>> {
+        let mut choices = vec![];
+        let mut choice = String::new();
+        loop {
+            match self.bump()? {
+                '\\' => match self.peek() {
+                    Some(next @ (',' | '|' | '\\')) => {
+                        self.bump();
+                        choice.push(next);
+                    }
+                    _ => choice.push('\\'),
+                },
+                ',' => choices.push(std::mem::take(&mut choice)),
+                '|' => {
+                    choices.push(choice);
+                    return Some(choices);
+                }
+                c => choice.push(c),
+            }
+        }
+    }
+
+    /// Skip the `regex/format/options` of a variable transform until the closing `}`.
+    fn skip_transform(&mut self) -> Option<()> {
+        let mut slashes = 0;
+        let mut depth = 0;
+        while slashes < 2 {
+            match self.bump()? {
+                '\\' => {
+                    self.bump();
+                }
+                '$' if self.peek() == Some('{') => {
+                    self.bump();
+                    depth += 1;
+                }
+                '}' if depth > 0 => depth -= 1,
+                '/' if depth == 0 => slashes += 1,
+                _ => {}
+            }
+        }
+
+        while self.peek()? != '}' {
+            self.bump();
+        }
+        Some(())
+    }
+}
+
+/// Collect the first placeholder of each tab stop, to fill the mirrored tab stops.
+fn collect_placeholders<'a>(
+    parts: &'a [SnippetPart],
+    placeholders: &mut HashMap<usize, &'a [SnippetPart]>,
+) {
+    for part in parts {
+        match part {
+            SnippetPart::TabStop {
+                index, placeholder, ..
+            } => {
+                if !placeholder.is_empty() {
+                    placeholders.entry(*index).or_insert(placeholder);
+                }
+                collect_placeholders(placeholder, placeholders);
+            }
+            SnippetPart::Variable {
+                default: Some(default),
+                ..
+            } => collect_placeholders(default, placeholders),
+            _ => {}
+        }
+    }
+}
+
+struct Expander<'a, F> {
+    indent: &'a str,
+    tab: &'a str,
+    resolve: F,
+    placeholders: HashMap<usize, &'a [SnippetPart]>,
+    text: String,
+    tabstops: BTreeMap<usize, TabStop>,
+    /// The tab stops being rendered, to avoid the infinite recursion of `${1:$1}`.
+    visiting: Vec<usize>,
+}
+
+impl<'a, F: Fn(&str) -> Option<String>> Expander<'a, F> {
+    fn push_text(&mut self, text: &str, expand_tabs: bool) {
+        for c in text.chars() {
+            match c {
+                '\n' => {
+                    self.text.push(c);
+                    self.text.push_str(self.indent);
+                }
+                '\t' if expand_tabs => self.text.push_str(self.tab),
+                _ => self.text.push(c),
+            }
+        }
+    }
+
+    fn render(&mut self, parts: &'a [SnippetPart]) {
+        for part in parts {
+            match part {
+                SnippetPart::Text(text) => self.push_text(text, true),
+                SnippetPart::Variable { name, default } => match ((self.resolve)(name), default) {
+                    (Some(value), Some(default)) if value.is_empty() => self.render(default),
+                    (Some(value), _) => self.push_text(&value, false),
+                    (None, Some(default)) => self.render(default),
+                    // The unknown variable is inserted as its name.
+                    (None, None) => self.push_text(name, false),
+                },
+                SnippetPart::TabStop {
+                    index,
+                    placeholder,
+                    choices,
+                } => {
+                    let start = self.text.len();
+                    if !self.visiting.contains(index) {
+                        let placeholder = if placeholder.is_empty() {
+                            self.placeholders.get(index).copied().unwrap_or_default()
+                        } else {
+                            placeholder.as_slice()
+                        };
+                        self.visiting.push(*index);
+                        self.render(placeholder);
+                        self.visiting.pop();
+                    }
+
+                    let tabstop = self.tabstops.entry(*index).or_insert_with(|| TabStop {
+                        index: *index,
+                        ranges: vec![],
+                        choices: vec![],
+                    });
+                    tabstop.ranges.push(start..self.text.len());
+                    tabstop.ranges.sort_by_key(|range| range.start);
+                    if tabstop.choices.is_empty() {
+                        tabstop.choices = choices.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The tab stops of the inserted snippet, press `tab` and `shift-tab` to jump between them.
+pub(super) struct SnippetSession {
+    tabstops: Vec<TabStop>,
+    /// The index of the active tab stop in `tabstops`.
+    active: usize,
+}
+
+impl SnippetSession {
+    /// Returns true if the offset is in the range of the snippet.
+    fn contains(&self, offset: usize) -> bool {
+        let ranges = || {
+            self.tabstops
+                .iter()
+                .flat_map(|tabstop| tabstop.ranges.iter())
+        };
+        let start = ranges().map(|range| range.start).min();
+        let end = ranges().map(|range| range.end).max();
+        matches!((start, end), (Some(start), Some(end)) if start <= offset && offset <= end)
+    }
+}
+
+/// Shift the range of a tab stop after the text in `edit` is replaced by a text with `new_len`.
+///
+/// If `grow` is true, the edit inside the range (include the boundaries) changes the range,
+/// so the text typed in the active placeholder is still a part of it.
+fn shift_range(
+    range: &Range<usize>,
+    edit: &Range<usize>,
+    new_len: usize,
+    grow: bool,
+) -> Range<usize> {
+    if grow && range.start <= edit.start && edit.end <= range.end {
+        return range.start..range.end + new_len - edit.len();
+    }
+
+    let start = shift_offset(range.start, edit, new_len);
+    let end = if edit.start >= range.end {
+        range.end
+    } else {
+        shift_offset(range.end, edit, new_len)
+    };
+    start..end.max(start)
+}
+
+impl InputState {
+    /// Insert a snippet in the LSP snippet syntax to replace the selected text.
+    ///
+    /// e.g.: `fn ${1:name}($2) {\n\t$0\n}`
+    ///
+    /// - The first tab stop is selected, press `tab` and `shift-tab` to jump between the tab stops.
+    /// - The mirrored tab stops (e.g.: `${1:foo} = $1`) are selected with multiple cursors to edit together.
+    /// - The variables (e.g.: `$TM_SELECTED_TEXT`, `$CLIPBOARD`, `$CURRENT_YEAR`) are resolved.
+    pub fn insert_snippet(&mut self, snippet: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_range.into();
+        self.insert_snippet_in_range(range, snippet, window, cx);
+    }
+
+    pub(crate) fn insert_snippet_in_range(
+        &mut self,
+        range: Range<usize>,
+        snippet: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.disabled || self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let row = self.text.offset_to_point(range.start).row;
+        let line_start = self.text.line_start_offset(row);
+        let indent = self
+            .text
+            .slice(line_start..range.start)
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect::<String>();
+        let tab = self.mode.tab_size().to_string();
+        let app: &App = cx;
+        let expanded = Snippet::parse(snippet).expand(&indent, &tab, |name| {
+            self.snippet_variable(name, &range, app)
+        });
+
+        let old_len = self.text.len();
+        self.snippet_session = None;
+        self.extra_selections.clear();
+        self.replace_text_in_range_silent(
+            Some(self.range_to_utf16(&range)),
+            &expanded.text,
+            window,
+            cx,
+        );
+        // The text is rejected (e.g.: by the validation of the single line input).
+        if self.text.len() + range.len() != old_len + expanded.text.len() {
+            return;
+        }
+
+        let tabstops = expanded
+            .tabstops
+            .into_iter()
+            .map(|mut tabstop| {
+                for stop_range in tabstop.ranges.iter_mut() {
+                    *stop_range = range.start + stop_range.start..range.start + stop_range.end;
+                }
+                tabstop
+            })
+            .collect();
+        self.snippet_session = Some(SnippetSession {
+            tabstops,
+            active: 0,
+        });
+        self.select_tabstop(0, window, cx);
+    }
+
+    /// Select the tab stop at `ix`, the snippet session is ended at the final tab stop.
+    fn select_tabstop(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(session) = self.snippet_session.as_mut() else {
+            return;
+        };
+        let Some(tabstop) = session.tabstops.get(ix).cloned() else {
+            return;
+        };
+        session.active = ix;
+        if ix + 1 == session.tabstops.len() {
+            self.snippet_session = None;
+        }
+
+        let Some(first) = tabstop.ranges.first().cloned() else {
+            return;
+        };
+        self.selected_range = first.clone().into();
+        self.selection_reversed = false;
+        self.selected_word_range = None;
+        self.column_selection = None;
+        self.extra_selections = tabstop.ranges[1..]
+            .iter()
+            .map(|range| Selection::from(range.clone()))
+            .collect();
+        self.normalize_extra_selections();
+        self.update_preferred_column();
+        self.scroll_to(first.end, None, cx);
+        if !tabstop.choices.is_empty() {
+            self.show_snippet_choices(first, &tabstop.choices, window, cx);
+        }
+        cx.notify();
+    }
+
+    /// Jump to the next tab stop of the snippet, returns false if there is no snippet session.
+    ///
+    /// The snippet session is ended if the cursor is moved out of the snippet.
+    pub(super) fn next_tabstop(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(session) = self.snippet_session.as_ref() else {
+            return false;
+        };
+        if !session.contains(self.cursor()) {
+            self.snippet_session = None;
+            return false;
+        }
+
+        let ix = session.active + 1;
+        self.select_tabstop(ix, window, cx);
+        true
+    }
+
+    /// Jump to the previous tab stop of the snippet, returns false if there is no snippet session.
+    pub(super) fn prev_tabstop(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(session) = self.snippet_session.as_ref() else {
+            return false;
+        };
+        if !session.contains(self.cursor()) {
+            self.snippet_session = None;
+            return false;
+        }
+
+        let ix = session.active.saturating_sub(1);
+        self.select_tabstop(ix, window, cx);
+        true
+    }
+
+    /// Shift the tab stops after the text in `range` is replaced by a text with `new_len`.
+    pub(super) fn shift_snippet_session(&mut self, range: &Range<usize>, new_len: usize) {
+        let Some(session) = self.snippet_session.as_mut() else {
+            return;
+        };
+
+        for (ix, tabstop) in session.tabstops.iter_mut().enumerate() {
+            let grow = ix == session.active;
+            for stop_range in tabstop.ranges.iter_mut() {
+                *stop_range = shift_range(stop_range, range, new_len, grow);
+            }
+        }
+    }
+
+    fn show_snippet_choices(
+        &mut self,
+        range: Range<usize>,
+        choices: &[String],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let items = choices
+            .iter()
+            .map(|choice| CompletionItem {
+                label: choice.clone(),
+                kind: Some(CompletionItemKind::VALUE),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let menu = match self.context_menu.as_ref() {
+            Some(ContextMenu::Completion(menu)) => menu.clone(),
+            _ => {
+                let menu = CompletionMenu::new(cx.entity(), window, cx);
+                self.context_menu = Some(ContextMenu::Completion(menu.clone()));
+                menu
+            }
+        };
+
+        menu.update(cx, |menu, cx| {
+            menu.hide(cx);
+            menu.update_query(range.start, "");
+            menu.show(range.end, items, window, cx);
+        });
+    }
+
+    /// Replace the active tab stop (include the mirrors) with the choice selected in the completion menu.
+    ///
+    /// Returns false if the `range` is not the active tab stop with choices.
+    pub(crate) fn replace_snippet_choice(
+        &mut self,
+        range: &Range<usize>,
+        choice: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(tabstop) = self
+            .snippet_session
+            .as_ref()
+            .and_then(|session| session.tabstops.get(session.active))
+        else {
+            return false;
+        };
+        if tabstop.choices.is_empty() || tabstop.ranges.first() != Some(range) {
+            return false;
+        }
+
+        let ranges = tabstop.ranges.clone();
+        self.selected_range = range.clone().into();
+        self.extra_selections = ranges[1..]
+            .iter()
+            .map(|range| Selection::from(range.clone()))
+            .collect();
+        self.replace_text_in_selections(&[choice.to_string()], window, cx);
+        true
+    }
+
+    /// Returns the completion items of the user snippets that match the word before the `offset`.
+    pub(crate) fn snippet_completions(&self, offset: usize) -> Vec<CompletionItem> {
+        let Some(language) = self.mode.language() else {
+            return vec![];
+        };
+
+        let row = self.text.offset_to_point(offset).row;
+        let line_start = self.text.line_start_offset(row);
+        let word_len: usize = self
+            .text
+            .slice(line_start..offset)
+            .to_string()
+            .chars()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .map(char::len_utf8)
+            .sum();
+        if word_len == 0 {
+            return vec![];
+        }
+
+        let start = offset - word_len;
+        let word = self.text.slice(start..offset).to_string();
+        let range = lsp_types::Range::new(
+            self.text.offset_to_position(start),
+            self.text.offset_to_position(offset),
+        );
+
+        SnippetRegistry::singleton()
+            .snippets(language)
+            .into_iter()
+            .filter(|snippet| snippet.prefix.starts_with(&word))
+            .map(|snippet| CompletionItem {
+                label: snippet.prefix.to_string(),
+                kind: Some(CompletionItemKind::SNIPPET),
+                detail: snippet
+                    .description
+                    .map(|description| description.to_string()),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                    range,
+                    snippet.body.to_string(),
+                ))),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Returns the value of the snippet variable, `None` if the variable is unknown.
+    ///
+    /// https://code.visualstudio.com/docs/editing/userdefinedsnippets#_variables
+    fn snippet_variable(&self, name: &str, range: &Range<usize>, cx: &App) -> Option<String> {
+        let row = self.text.offset_to_point(range.start).row;
+        let now = chrono::Local::now;
+        let random = || uuid::Uuid::new_v4().as_u128();
+
+        let value = match name {
+            "TM_SELECTED_TEXT" => self.text.slice(range.clone()).to_string(),
+            "TM_CURRENT_LINE" => self.text.slice_line(row).to_string(),
+            "TM_CURRENT_WORD" => self.text.word_at(range.start),
+            "TM_LINE_INDEX" => row.to_string(),
+            "TM_LINE_NUMBER" => (row + 1).to_string(),
+            "TM_FILENAME" | "TM_FILENAME_BASE" | "TM_DIRECTORY" | "TM_FILEPATH" => {
+                let uri = self.lsp.document_uri.as_ref()?;
+                let path = Path::new(uri.path().as_str());
+                let value = match name {
+                    "TM_FILENAME" => path.file_name(),
+                    "TM_FILENAME_BASE" => path.file_stem(),
+                    "TM_DIRECTORY" => path.parent().map(|parent| parent.as_os_str()),
+                    _ => Some(path.as_os_str()),
+                };
+                value?.to_string_lossy().to_string()
+            }
+            "CLIPBOARD" => cx.read_from_clipboard()?.text()?,
+            "CURRENT_YEAR" => now().format("%Y").to_string(),
+            "CURRENT_YEAR_SHORT" => now().format("%y").to_string(),
+            "CURRENT_MONTH" => now().format("%m").to_string(),
+            "CURRENT_MONTH_NAME" => now().format("%B").to_string(),
+            "CURRENT_MONTH_NAME_SHORT" => now().format("%b").to_string(),
+            "CURRENT_DATE" => now().format("%d").to_string(),
+            "CURRENT_DAY_NAME" => now().format("%A").to_string(),
+            "CURRENT_DAY_NAME_SHORT" => now().format("%a").to_string(),
+            "CURRENT_HOUR" => now().format("%H").to_string(),
+            "CURRENT_MINUTE" => now().format("%M").to_string(),
+            "CURRENT_SECOND" => now().format("%S").to_string(),
+            "CURRENT_SECONDS_UNIX" => now().timestamp().to_string(),
+            "RANDOM" => format!("{:06}", random() % 1_000_000),
+            "RANDOM_HEX" => format!("{:06x}", random() & 0xff_ffff),
+            "UUID" => uuid::Uuid::new_v4().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{AppContext as _, EntityInputHandler as _, TestAppContext};
+
+    use super::{ExpandedSnippet, Snippet, SnippetPart, TabStop, shift_range};
+    use crate::input::{IndentInline, InputState, OutdentInline, Selection};
+
+    fn text(s: &str) -> SnippetPart {
+        SnippetPart::Text(s.to_string())
+    }
+
+    fn tabstop(index: usize, ranges: Vec<std::ops::Range<usize>>) -> TabStop {
+        TabStop {
+            index,
+            ranges,
+            choices: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let snippet = Snippet::parse("fn ${1:name}($2) {\n\t$0\n}");
+        assert_eq!(
+            snippet.parts,
+            vec![
+                text("fn "),
+                SnippetPart::TabStop {
+                    index: 1,
+                    placeholder: vec![text("name")],
+                    choices: vec![],
+                },
+                text("("),
+                SnippetPart::TabStop {
+                    index: 2,
+                    placeholder: vec![],
+                    choices: vec![],
+                },
+                text(") {\n\t"),
+                SnippetPart::TabStop {
+                    index: 0,
+                    placeholder: vec![],
+                    choices: vec![],
+                },
+                text("\n}"),
+            ]
+        );
+
+        let snippet = Snippet::parse("${1|one,t\\,wo|} ${TM_FILENAME:${2:file}} $FOO");
+        assert_eq!(
+            snippet.parts,
+            vec![
+                SnippetPart::TabStop {
+                    index: 1,
+                    placeholder: vec![text("one")],
+                    choices: vec!["one".into(), "t,wo".into()],
+                },
+                text(" "),
+                SnippetPart::Variable {
+                    name: "TM_FILENAME".into(),
+                    default: Some(vec![SnippetPart::TabStop {
+                        index: 2,
+                        placeholder: vec![text("file")],
+                        choices: vec![],
+                    }]),
+                },
+                text(" "),
+                SnippetPart::Variable {
+                    name: "FOO".into(),
+                    default: None,
+                },
+            ]
+        );
+
+        // The escaped and invalid `$` are kept as text.
+        assert_eq!(
+            Snippet::parse("\\$1 $ ${ \\} ${1:a").parts,
+            vec![text("$1 $ ${ } ${1:a")]
+        );
+        // The transform is ignored.
+        assert_eq!(
+            Snippet::parse("${TM_FILENAME/(.*)/${1:/upcase}/g}").parts,
+            vec![SnippetPart::Variable {
+                name: "TM_FILENAME".into(),
+                default: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let resolve = |name: &str| match name {
+            "TM_SELECTED_TEXT" => Some("foo\nbar".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+
+        let snippet = Snippet::parse("fn ${1:name}() {\n\t$0\n}");
+        assert_eq!(
+            snippet.expand("  ", "    ", resolve),
+            ExpandedSnippet {
+                text: "fn name() {\n      \n  }".into(),
+                tabstops: vec![tabstop(1, vec![3..7]), tabstop(0, vec![18..18])],
+            }
+        );
+
+        // Mirrored tab stops, and the implicit final tab stop.
+        let snippet = Snippet::parse("let ${1:a} = $2; $1");
+        assert_eq!(
+            snippet.expand("", "\t", resolve),
+            ExpandedSnippet {
+                text: "let a = ; a".into(),
+                tabstops: vec![
+                    tabstop(1, vec![4..5, 10..11]),
+                    tabstop(2, vec![8..8]),
+                    tabstop(0, vec![11..11]),
+                ],
+            }
+        );
+
+        // Variables, the default is used if the variable is unknown or empty.
+        let snippet = Snippet::parse("$TM_SELECTED_TEXT ${EMPTY:x} ${UNKNOWN:y} $UNKNOWN");
+        assert_eq!(
+            snippet.expand("  ", "\t", resolve).text,
+            "foo\n  bar x y UNKNOWN"
+        );
+
+        // The recursive placeholder is not expanded.
+        let snippet = Snippet::parse("${1:a$1}");
+        assert_eq!(snippet.expand("", "\t", resolve).text, "a");
+    }
+
+    #[test]
+    fn test_shift_range() {
+        // Typing in the active placeholder.
+        assert_eq!(shift_range(&(5..8), &(5..8), 1, true), 5..6);
+        assert_eq!(shift_range(&(5..5), &(5..5), 2, true), 5..7);
+        assert_eq!(shift_range(&(5..8), &(8..8), 2, true), 5..10);
+
+        // The other tab stops.
+        assert_eq!(shift_range(&(5..8), &(8..8), 2, false), 5..8);
+        assert_eq!(shift_range(&(5..8), &(5..5), 2, false), 7..10);
+        assert_eq!(shift_range(&(5..5), &(5..5), 2, false), 7..7);
+        assert_eq!(shift_range(&(5..8), &(1..2), 0, false), 4..7);
+        assert_eq!(shift_range(&(5..8), &(3..6), 0, false), 3..5);
+    }
+
+    #[gpui::test]
+    fn test_insert_snippet(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state =
+            cx.update(|window, cx| cx.new(|cx| InputState::new(window, cx).multi_line(true)));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.insert_snippet("fn ${1:name}($2) {$0}", window, cx);
+                assert_eq!(state.value(), "fn name() {}");
+                assert_eq!(state.selected_range, (3..7).into());
+                assert!(state.snippet_session.is_some());
+
+                // Jump between the tab stops.
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (8..8).into());
+                state.outdent_inline(&OutdentInline, window, cx);
+                assert_eq!(state.selected_range, (3..7).into());
+                state.outdent_inline(&OutdentInline, window, cx);
+                assert_eq!(state.selected_range, (3..7).into());
+
+                // The typed text is a part of the tab stop, the next tab stops are shifted.
+                state.replace_text_in_range(None, "foo", window, cx);
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (7..7).into());
+                state.replace_text_in_range(None, "x", window, cx);
+                state.outdent_inline(&OutdentInline, window, cx);
+                assert_eq!(state.selected_range, (3..6).into());
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (7..8).into());
+
+                // The session is ended at the final tab stop.
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.value(), "fn foo(x) {}");
+                assert_eq!(state.selected_range, (11..11).into());
+                assert!(state.snippet_session.is_none());
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_snippet_mirrors(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state =
+            cx.update(|window, cx| cx.new(|cx| InputState::new(window, cx).multi_line(true)));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.insert_snippet("let ${1:a} = $2; $1", window, cx);
+                assert_eq!(state.value(), "let a = ; a");
+                assert_eq!(state.selected_range, (4..5).into());
+                assert_eq!(state.extra_selections, vec![Selection::from(10..11)]);
+
+                // The mirrors are edited together, and still grow with the typed text.
+                state.replace_text_in_range(None, "foo", window, cx);
+                assert_eq!(state.value(), "let foo = ; foo");
+                state.replace_text_in_range(None, "o", window, cx);
+                assert_eq!(state.value(), "let fooo = ; fooo");
+
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (11..11).into());
+                assert!(state.extra_selections.is_empty());
+                state.outdent_inline(&OutdentInline, window, cx);
+                assert_eq!(state.selected_range, (4..8).into());
+                assert_eq!(state.extra_selections, vec![Selection::from(13..17)]);
+
+                state.indent_inline(&IndentInline, window, cx);
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (17..17).into());
+                assert!(state.snippet_session.is_none());
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_snippet_session_end(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .multi_line(true)
+                    .default_value("x = ")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.selected_range = (4..4).into();
+                state.insert_snippet("(${1:a}, $2)", window, cx);
+                assert_eq!(state.value(), "x = (a, )");
+                assert_eq!(state.selected_range, (5..6).into());
+
+                // The session is ended when the cursor is moved out of the snippet.
+                state.selected_range = (0..0).into();
+                assert!(!state.next_tabstop(window, cx));
+                assert!(state.snippet_session.is_none());
+
+                // The snippet without tab stops is ended at once.
+                state.selected_range = (0..0).into();
+                state.insert_snippet("let ", window, cx);
+                assert_eq!(state.value(), "let x = (a, )");
+                assert_eq!(state.selected_range, (4..4).into());
+                assert!(state.snippet_session.is_none());
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_replace_snippet_choice(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state =
+            cx.update(|window, cx| cx.new(|cx| InputState::new(window, cx).multi_line(true)));
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.insert_snippet("${1|one,two|} $1 $2", window, cx);
+                assert_eq!(state.value(), "one one ");
+                assert_eq!(state.selected_range, (0..3).into());
+
+                // Only the active tab stop with choices is replaced.
+                assert!(!state.replace_snippet_choice(&(4..7), "two", window, cx));
+                assert!(state.replace_snippet_choice(&(0..3), "two", window, cx));
+                assert_eq!(state.value(), "two two ");
+
+                state.indent_inline(&IndentInline, window, cx);
+                assert_eq!(state.selected_range, (8..8).into());
+                assert!(!state.replace_snippet_choice(&(8..8), "two", window, cx));
+                assert_eq!(state.value(), "two two ");
+            });
+        });
+    }
+
+    #[gpui::test]
+    fn test_insert_snippet_rejected(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .validate(|text, _| !text.contains('!'))
+                    .default_value("hello")
+            })
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                state.selected_range = (5..5).into();
+                state.insert_snippet(" ${1:world}!", window, cx);
+                assert_eq!(state.value(), "hello");
+                assert_eq!(state.selected_range, (5..5).into());
+                assert!(state.snippet_session.is_none());
+
+                state.insert_snippet(" ${1:world}", window, cx);
+                assert_eq!(state.value(), "hello world");
+                assert_eq!(state.selected_range, (6..11).into());
+                assert!(state.snippet_session.is_some());
+            });
+        });
+    }
+}
@@ -14,6 +14,7 @@ use gpui::{
 use gpui::{Half, TextAlign};
 use ropey::{Rope, RopeSlice};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::ops::Range;
 use std::rc::Rc;
 use sum_tree::Bias;
@@ -26,13 +27,20 @@ use super::{
 use crate::Size;
 use crate::actions::{SelectDown, SelectLeft, SelectRight, SelectUp};
 use crate::input::blink_cursor::CURSOR_WIDTH;
+use crate::input::column_selection::ColumnSelection;
 use crate::input::movement::MoveDirection;
 use crate::input::{
-    HoverDefinition, Lsp, Position,
+    FoldRange, HoverDefinition, InputEdit, Lsp, Outline, Position,
+    diff::DiffBase,
     element::RIGHT_MARGIN,
-    popovers::{ContextMenu, DiagnosticPopover, HoverPopover, MouseContextMenu},
+    popovers::{
+        ContextMenu, DiagnosticPopover, HoverPopover, MouseContextMenu, ReferencesPopover,
+        RenamePopover, SignatureHelpPopover,
+    },
     search::{self, SearchPanel},
+    snippet::SnippetSession,
     text_wrapper::LineLayout,
+    vim::{self, VimMode, VimState},
 };
 use crate::input::{InlineCompletion, RopeExt as _, Selection};
 use crate::{Root, history::History};
@@ -56,6 +64,7 @@ actions!(
         DeleteToNextWordEnd,
         Indent,
         Outdent,
+        ToggleComment,
         IndentInline,
         OutdentInline,
         MoveUp,
@@ -89,12 +98,41 @@ actions!(
         ToggleCodeActions,
         Search,
         GoToDefinition,
+        AddCursorAbove,
+        AddCursorBelow,
+        SelectNextOccurrence,
+        ExpandSelection,
+        ShrinkSelection,
+        SelectColumnUp,
+        SelectColumnDown,
+        SelectColumnLeft,
+        SelectColumnRight,
+        Fold,
+        Unfold,
+        FoldAll,
+        UnfoldAll,
+        Rename,
+        FormatDocument,
+        FormatSelection,
+        FindAllReferences,
+        GoToNextDiagnostic,
+        GoToPrevDiagnostic,
+        GoToNextHunk,
+        GoToPrevHunk,
+        RevertHunk,
+        ToggleHunkDiff,
     ]
 );
 
 #[derive(Clone)]
 pub enum InputEvent {
     Change,
+    /// Emitted for each edit applied to the text, with the replaced range and the inserted text.
+    ///
+    /// Use this instead of diffing the whole text on [`InputEvent::Change`], e.g.: to sync the text to others.
+    Edit {
+        edit: InputEdit,
+    },
     InlineBadgeClick {
         id: uuid::Uuid,
     },
@@ -103,11 +141,29 @@ pub enum InputEvent {
     },
     Focus,
     Blur,
+    /// Emitted when an edit is attempted in the read-only mode, e.g.: to show "file is read-only".
+    ///
+    /// See also [`InputState::read_only`].
+    ReadOnlyEdit,
     /// Emitted when images are pasted from clipboard.
     /// Consumer should handle storing/displaying the images.
     PasteImages {
         images: Vec<gpui::Image>,
     },
+    /// Emitted when the Vim mode is changed, to show the mode in a status bar.
+    VimModeChange {
+        mode: VimMode,
+    },
+    /// Emitted when a rename returns edits for other documents (not the [`Lsp::document_uri`]),
+    /// the host should apply them to the other open buffers.
+    WorkspaceEdit {
+        changes: HashMap<lsp_types::Uri, Vec<lsp_types::TextEdit>>,
+    },
+    /// Emitted when a reference in other document (not the [`Lsp::document_uri`]) is opened from the references peek view,
+    /// the host should open the document and move to the location.
+    OpenLocation {
+        location: lsp_types::Location,
+    },
 }
 
 #[derive(Clone)]
@@ -220,6 +276,10 @@ pub(crate) fn init(cx: &mut App) {
         KeyBinding::new("cmd-[", Outdent, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-[", Outdent, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-/", ToggleComment, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-/", ToggleComment, Some(CONTEXT)),
         KeyBinding::new("shift-left", SelectLeft, Some(CONTEXT)),
         KeyBinding::new("shift-right", SelectRight, Some(CONTEXT)),
         KeyBinding::new("shift-up", SelectUp, Some(CONTEXT)),
@@ -302,10 +362,76 @@ pub(crate) fn init(cx: &mut App) {
         KeyBinding::new("cmd-f", Search, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-f", Search, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-up", AddCursorAbove, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-alt-up", AddCursorAbove, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-down", AddCursorBelow, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-alt-down", AddCursorBelow, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-d", SelectNextOccurrence, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-d", SelectNextOccurrence, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("ctrl-shift-cmd-right", ExpandSelection, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("alt-up", ExpandSelection, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("ctrl-shift-cmd-left", ShrinkSelection, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("alt-down", ShrinkSelection, Some(CONTEXT)),
+        KeyBinding::new("shift-alt-up", SelectColumnUp, Some(CONTEXT)),
+        KeyBinding::new("shift-alt-down", SelectColumnDown, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-alt-left", SelectColumnLeft, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("shift-alt-left", SelectColumnLeft, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-alt-right", SelectColumnRight, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("shift-alt-right", SelectColumnRight, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-[", Fold, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-[", Fold, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-]", Unfold, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-]", Unfold, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k cmd-0", FoldAll, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k ctrl-0", FoldAll, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k cmd-j", UnfoldAll, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k ctrl-j", UnfoldAll, Some(CONTEXT)),
+        KeyBinding::new("f2", Rename, Some(CONTEXT)),
+        KeyBinding::new("shift-alt-f", FormatDocument, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k cmd-f", FormatSelection, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k ctrl-f", FormatSelection, Some(CONTEXT)),
+        KeyBinding::new("shift-f12", FindAllReferences, Some(CONTEXT)),
+        KeyBinding::new("f8", GoToNextDiagnostic, Some(CONTEXT)),
+        KeyBinding::new("shift-f8", GoToPrevDiagnostic, Some(CONTEXT)),
+        KeyBinding::new("alt-f5", GoToNextHunk, Some(CONTEXT)),
+        KeyBinding::new("shift-alt-f5", GoToPrevHunk, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-z", RevertHunk, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-alt-z", RevertHunk, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-'", ToggleHunkDiff, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-'", ToggleHunkDiff, Some(CONTEXT)),
     ]);
 
     search::init(cx);
     number_input::init(cx);
+    vim::init(cx);
 }
 
 /// Whitespace indicators for rendering spaces and tabs.
@@ -339,6 +465,8 @@ pub(super) struct LastLayout {
     pub(super) text_align: TextAlign,
     /// The content width of the text layout.
     pub(super) content_width: Pixels,
+    /// The width of the space character, used to calculate the column out of the line end.
+    pub(super) space_width: Pixels,
 }
 
 impl LastLayout {
@@ -379,8 +507,28 @@ pub struct InputState {
     /// - "Hello 世界💝" = 16
     /// - "💝" = 4
     pub(super) selected_range: Selection,
+    /// The extra selections for multiple cursors, the cursor is at the end of each selection.
+    ///
+    /// The `selected_range` is always the primary (newest) selection.
+    pub(super) extra_selections: Vec<Selection>,
+    /// The selections before [`InputState::expand_selection`], the last one is the current selection.
+    pub(super) selection_stack: Vec<Selection>,
+    /// The rectangular selection, the selections of each row are kept in `extra_selections`.
+    pub(super) column_selection: Option<ColumnSelection>,
+    /// The active snippet session to jump between the tab stops.
+    pub(super) snippet_session: Option<SnippetSession>,
+    /// The last copied text of the column selection, to paste it as a block.
+    pub(super) column_clipboard: Option<SharedString>,
+    /// The folded ranges, sorted by the start row.
+    pub(super) folded_ranges: Vec<FoldRange>,
+    /// The cached foldable ranges, None means need to recompute.
+    pub(super) foldable_ranges: Option<Rc<Vec<FoldRange>>>,
+    /// The cached outline, None means need to recompute.
+    pub(super) outline: Option<Rc<Outline>>,
     pub(super) search_panel: Option<Entity<SearchPanel>>,
     pub(super) searchable: bool,
+    /// The Vim modal editing state, None if the Vim mode is not enabled.
+    pub(super) vim: Option<VimState>,
     /// Range for save the selected word, use to keep word range when drag move.
     pub(super) selected_word_range: Option<Selection>,
     pub(super) selection_reversed: bool,
@@ -396,9 +544,13 @@ pub struct InputState {
     pub(super) selecting: bool,
     pub(super) size: Size,
     pub(super) disabled: bool,
+    /// The text can't be edited, but can be selected, copied and navigated.
+    pub(super) read_only: bool,
     pub(super) masked: bool,
     pub(super) clean_on_escape: bool,
     pub(super) soft_wrap: bool,
+    /// The width of the minimap, only for code editor with minimap enabled.
+    pub(super) minimap_width: Pixels,
     pub(super) pattern: Option<regex::Regex>,
     pub(super) validate: Option<Box<dyn Fn(&str, &mut Context<Self>) -> bool + 'static>>,
     pub(crate) scroll_handle: ScrollHandle,
@@ -413,13 +565,21 @@ pub struct InputState {
     pub(super) placeholder: SharedString,
 
     /// Popover
-    diagnostic_popover: Option<Entity<DiagnosticPopover>>,
+    pub(super) diagnostic_popover: Option<Entity<DiagnosticPopover>>,
     /// Completion/CodeAction context menu
     pub(super) context_menu: Option<ContextMenu>,
     pub(super) mouse_context_menu: Entity<MouseContextMenu>,
     /// A flag to indicate if we are currently inserting a completion item.
     pub(super) completion_inserting: bool,
     pub(super) hover_popover: Option<Entity<HoverPopover>>,
+    /// The inline rename input, anchored at the symbol to rename.
+    pub(super) rename_popover: Option<Entity<RenamePopover>>,
+    /// The parameter hints of the function call at the cursor.
+    pub(super) signature_help_popover: Option<Entity<SignatureHelpPopover>>,
+    /// The peek view of the references of the symbol.
+    pub(super) references_popover: Option<Entity<ReferencesPopover>>,
+    /// The diff base text to compare with, see [`InputState::set_diff_base`].
+    pub(super) diff_base: Option<DiffBase>,
     /// The LSP definitions locations for "Go to Definition" feature.
     pub(super) hover_definition: HoverDefinition,
     pub(super) inline_badges: Vec<InlineBadge>,
@@ -432,6 +592,12 @@ pub struct InputState {
     _pending_update: bool,
     /// A flag to indicate if we should ignore the next completion event.
     pub(super) silent_replace_text: bool,
+    /// The version of the text, increased by 1 for each edit.
+    pub(super) version: usize,
+    /// The origin of the remote edits being applied, see [`InputState::apply_remote_edits`].
+    pub(super) edit_origin: Option<SharedString>,
+    /// The undo steps of the remote edits by the origin, see [`InputState::undo_remote_edits`].
+    pub(super) remote_undos: HashMap<SharedString, Vec<Vec<Change>>>,
 
     /// To remember the horizontal column (x-coordinate) of the cursor position for keep column for move up/down.
     ///
@@ -441,6 +607,8 @@ pub struct InputState {
     _subscriptions: Vec<Subscription>,
 
     pub(super) _context_menu_task: Task<Result<()>>,
+    /// The task to parse the large text in the background.
+    pub(super) _parse_task: Task<()>,
     pub(super) inline_completion: InlineCompletion,
 }
 
@@ -483,17 +651,28 @@ impl InputState {
             blink_cursor,
             history,
             selected_range: Selection::default(),
+            extra_selections: Vec::new(),
+            selection_stack: Vec::new(),
+            column_selection: None,
+            snippet_session: None,
+            column_clipboard: None,
+            folded_ranges: Vec::new(),
+            foldable_ranges: None,
+            outline: None,
             search_panel: None,
             searchable: false,
+            vim: None,
             selected_word_range: None,
             selection_reversed: false,
             ime_marked_range: None,
             input_bounds: Bounds::default(),
             selecting: false,
             disabled: false,
+            read_only: false,
             masked: false,
             clean_on_escape: false,
             soft_wrap: true,
+            minimap_width: px(100.),
             loading: false,
             pattern: None,
             validate: None,
@@ -515,12 +694,20 @@ impl InputState {
             mouse_context_menu,
             completion_inserting: false,
             hover_popover: None,
+            rename_popover: None,
+            signature_help_popover: None,
+            references_popover: None,
+            diff_base: None,
             hover_definition: HoverDefinition::default(),
             inline_badges: Vec::new(),
             silent_replace_text: false,
+            version: 0,
+            edit_origin: None,
+            remote_undos: HashMap::new(),
             size: Size::default(),
             _subscriptions,
             _context_menu_task: Task::ready(Ok(())),
+            _parse_task: Task::ready(()),
             _pending_update: false,
             inline_completion: InlineCompletion::default(),
         }
@@ -550,6 +737,8 @@ impl InputState {
     /// - height: 100%
     /// - multi_line: true
     /// - indent_guides: true
+    /// - minimap: false
+    /// - sticky_scroll: 0
     ///
     /// If `highlighter` is None, will use the default highlighter.
     ///
@@ -560,7 +749,7 @@ impl InputState {
     /// - Syntax Highlighting
     /// - Auto Indent
     /// - Line Number
-    /// - Large Text support, up to 50K lines.
+    /// - Large Text support, the large file mode is used for the text larger than [`Self::large_file_size`].
     pub fn code_editor(mut self, language: impl Into<SharedString>) -> Self {
         let language: SharedString = language.into();
         self.mode = InputMode::code_editor(language);
@@ -638,6 +827,8 @@ impl InputState {
             }
             _ => {}
         }
+        // The parsing of the old highlighter may time out and disable the new one.
+        self._parse_task = Task::ready(());
         cx.notify();
     }
 
@@ -648,6 +839,7 @@ impl InputState {
             }
             _ => {}
         }
+        self._parse_task = Task::ready(());
         cx.notify();
     }
 
@@ -693,7 +885,9 @@ impl InputState {
         for (line_index, line) in last_layout.lines.iter().enumerate() {
             let local_offset = offset.saturating_sub(prev_lines_offset);
             if let Some(pos) = line.position_for_index(local_offset, last_layout) {
-                let sub_line_index = (pos.y / line_height) as usize;
+                // The block rows above the line are not sub-lines.
+                let sub_line_index =
+                    ((pos.y - line.block_height(line_height)) / line_height) as usize;
                 let adjusted_pos = point(pos.x + last_layout.line_number_width, pos.y + y_offset);
                 return (line_index, sub_line_index, Some(adjusted_pos));
             }
@@ -715,10 +909,17 @@ impl InputState {
     ) {
         self.history.ignore = true;
         let was_disabled = self.disabled;
+        let was_read_only = self.read_only;
         self.disabled = false;
+        self.read_only = false;
         self.replace_text(value, window, cx);
         self.disabled = was_disabled;
+        self.read_only = was_read_only;
         self.history.ignore = false;
+        self.extra_selections.clear();
+        self.snippet_session = None;
+        self.folded_ranges.clear();
+        self.text_wrapper.set_folded_ranges(&[]);
 
         // Ensure cursor to start when set text
         if self.mode.is_single_line() {
@@ -802,6 +1003,37 @@ impl InputState {
         self
     }
 
+    /// Set with read-only mode, default is false.
+    ///
+    /// In the read-only mode, all the edits (typing, paste, IME, undo, LSP edits, etc.) are blocked
+    /// and the [`InputEvent::ReadOnlyEdit`] is emitted, the cursor movement, selection, copy,
+    /// search, hover, go to definition and folding are still working.
+    ///
+    /// The [`Self::set_value`] and [`Self::apply_remote_edits`] can still change the text.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Set the read-only mode, see [`Self::read_only`].
+    pub fn set_read_only(&mut self, read_only: bool, _: &mut Window, cx: &mut Context<Self>) {
+        self.read_only = read_only;
+        cx.notify();
+    }
+
+    /// Returns true if the input is in the read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns true if the edit is blocked by the read-only mode, and emits the [`InputEvent::ReadOnlyEdit`].
+    pub(super) fn block_read_only_edit(&mut self, cx: &mut Context<Self>) -> bool {
+        if self.read_only {
+            cx.emit(InputEvent::ReadOnlyEdit);
+        }
+        self.read_only
+    }
+
     /// Set with password masked state.
     ///
     /// Only for [`InputMode::SingleLine`] mode.
@@ -1060,6 +1292,8 @@ impl InputState {
     }
 
     pub(super) fn select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.extra_selections.clear();
+        self.column_selection = None;
         self.selected_range = (0..self.text.len()).into();
         cx.notify();
     }
@@ -1244,17 +1478,25 @@ impl InputState {
     }
 
     pub(super) fn backspace(&mut self, _: &Backspace, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
         if self.selected_range.is_empty() {
             self.select_to(self.previous_boundary(self.cursor()), cx)
         }
+        self.select_extra_cursors_to(|this, offset| this.previous_boundary(offset));
         self.replace_text_in_range(None, "", window, cx);
         self.pause_blink_cursor(cx);
     }
 
     pub(super) fn delete(&mut self, _: &Delete, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
         if self.selected_range.is_empty() {
             self.select_to(self.next_boundary(self.cursor()), cx)
         }
+        self.select_extra_cursors_to(|this, offset| this.next_boundary(offset));
         self.replace_text_in_range(None, "", window, cx);
         self.pause_blink_cursor(cx);
     }
@@ -1357,6 +1599,9 @@ impl InputState {
         if self.handle_action_for_context_menu(Box::new(action.clone()), window, cx) {
             return;
         }
+        if self.confirm_reference(window, cx) {
+            return;
+        }
 
         // Clear inline completion on enter (user chose not to accept it)
         if self.has_inline_completion() {
@@ -1364,16 +1609,23 @@ impl InputState {
         }
 
         if self.mode.is_multi_line() {
-            // Get current line indent
-            let indent = if self.mode.is_code_editor() {
-                self.indent_of_next_line()
-            } else {
-                "".to_string()
-            };
+            if self.block_read_only_edit(cx) {
+                return;
+            }
 
             // Add newline and indent
-            let new_line_text = format!("\n{}", indent);
+            let start = self.selected_range.start;
+            let version = self.version;
+            let (new_line_text, cursor_offset) = self.new_line_text();
             self.replace_text_in_range_silent(None, &new_line_text, window, cx);
+            // The text is not changed if the edit is rejected.
+            if self.version != version {
+                if cursor_offset < new_line_text.len() {
+                    let cursor = start + cursor_offset;
+                    self.selected_range = (cursor..cursor).into();
+                }
+                self.handle_on_type_formatting(&new_line_text, window, cx);
+            }
             self.pause_blink_cursor(cx);
         } else {
             // Single line input, just emit the event (e.g.: In a dialog to confirm).
@@ -1406,6 +1658,27 @@ impl InputState {
             self.unmark_text(window, cx);
         }
 
+        if self.hide_signature_help(cx) {
+            return;
+        }
+
+        if self.hide_references(cx) {
+            return;
+        }
+
+        if self.collapse_diff_hunk(cx) {
+            return;
+        }
+
+        if self.vim_escape(window, cx) {
+            return;
+        }
+
+        if self.snippet_session.take().is_some() || self.has_multiple_cursors() {
+            self.clear_extra_cursors(cx);
+            return;
+        }
+
         if self.clean_on_escape {
             return self.clean(window, cx);
         }
@@ -1421,6 +1694,8 @@ impl InputState {
     ) {
         // Clear inline completion on any mouse interaction
         self.clear_inline_completion(cx);
+        self.signature_help_popover = None;
+        self.references_popover = None;
 
         // If there have IME marked range and is empty (Means pressed Esc to abort IME typing)
         // Clear the marked range.
@@ -1440,12 +1715,43 @@ impl InputState {
             return;
         }
 
+        if event.button == MouseButton::Left && self.on_mouse_down_fold_marker(event, cx) {
+            self.selecting = false;
+            return;
+        }
+
+        if event.button == MouseButton::Left && self.on_mouse_down_diff_marker(event, cx) {
+            self.selecting = false;
+            return;
+        }
+
         let offset = self.index_for_mouse_position(event.position);
 
         if self.handle_click_hover_definition(event, offset, window, cx) {
             return;
         }
 
+        if event.button == MouseButton::Left {
+            // Alt-click to add a cursor, and Alt-drag to select column.
+            if event.modifiers.alt && event.click_count == 1 && self.mode.is_multi_line() {
+                self.add_cursor(offset, cx);
+                self.start_column_selection(offset);
+                return;
+            }
+
+            self.clear_extra_cursors(cx);
+        }
+
+        // Middle-drag to select column.
+        if event.button == MouseButton::Middle {
+            if self.mode.is_multi_line() {
+                self.clear_extra_cursors(cx);
+                self.move_to(offset, None, cx);
+                self.start_column_selection(offset);
+            }
+            return;
+        }
+
         // Triple click to select line
         if event.button == MouseButton::Left && event.click_count >= 3 {
             self.select_line(offset, window, cx);
@@ -1482,6 +1788,13 @@ impl InputState {
         }
         self.selecting = false;
         self.selected_word_range = None;
+        // A plain Alt-click (without drag) only adds a cursor, it is not a column selection.
+        if self
+            .column_selection
+            .is_some_and(|selection| selection.anchor == selection.head)
+        {
+            self.column_selection = None;
+        }
     }
 
     pub(super) fn on_mouse_move(
@@ -1599,7 +1912,9 @@ impl InputState {
         let Some(bounds) = self.last_bounds.as_ref() else {
             let display_point = self.text_wrapper.offset_to_display_point(offset);
             let line_height = last_layout.line_height;
-            let row_y = display_point.row as f32 * line_height;
+            let row = self.text.offset_to_point(offset).row;
+            let row_y =
+                (display_point.row + self.text_wrapper.block_rows_above(row)) as f32 * line_height;
             self.deferred_scroll_offset = Some(point(px(0.), -row_y));
             cx.notify();
             return;
@@ -1613,7 +1928,8 @@ impl InputState {
         let row = point.row;
 
         let display_point = self.text_wrapper.offset_to_display_point(offset);
-        let row_offset_y = display_point.row as f32 * line_height;
+        let row_offset_y =
+            (display_point.row + self.text_wrapper.block_rows_above(row)) as f32 * line_height;
 
         // Apart from left alignment, just leave enough space for the cursor size on the right side.
         let safety_margin = if last_layout.text_align == TextAlign::Left {
@@ -1676,6 +1992,13 @@ impl InputState {
     }
 
     pub(super) fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        if self.has_multiple_cursors() {
+            if !self.copy_column_selection(cx) {
+                self.copy_selections(cx);
+            }
+            return;
+        }
+
         if self.selected_range.is_empty() {
             return;
         }
@@ -1685,6 +2008,17 @@ impl InputState {
     }
 
     pub(super) fn cut(&mut self, _: &Cut, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
+        if self.has_multiple_cursors() {
+            if self.copy_column_selection(cx) || self.copy_selections(cx) {
+                self.replace_text_in_selections(&["".to_string()], window, cx);
+            }
+            return;
+        }
+
         if self.selected_range.is_empty() {
             return;
         }
@@ -1696,6 +2030,10 @@ impl InputState {
     }
 
     pub(super) fn paste(&mut self, _: &Paste, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
         let Some(clipboard) = cx.read_from_clipboard() else {
             return;
         };
@@ -1724,7 +2062,23 @@ impl InputState {
             new_text = new_text.replace('\n', "");
         }
 
-        self.replace_text_in_range_silent(None, &new_text, window, cx);
+        if self.has_multiple_cursors() {
+            self.paste_to_selections(&new_text, window, cx);
+            return;
+        }
+
+        if self.paste_column_block(&new_text, window, cx) {
+            return;
+        }
+
+        // Reindent the pasted lines by the indents query of the language.
+        let mut range = None;
+        if let Some((reindent_range, reindented_text)) = self.reindent_pasted_text(&new_text) {
+            range = Some(self.range_to_utf16(&reindent_range));
+            new_text = reindented_text;
+        }
+
+        self.replace_text_in_range_silent(range, &new_text, window, cx);
         self.scroll_to(self.cursor(), None, cx);
     }
 
@@ -1743,6 +2097,10 @@ impl InputState {
     }
 
     pub(super) fn undo(&mut self, _: &Undo, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
         self.history.ignore = true;
         if let Some(changes) = self.history.undo() {
             for change in changes {
@@ -1754,6 +2112,10 @@ impl InputState {
     }
 
     pub(super) fn redo(&mut self, _: &Redo, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
         self.history.ignore = true;
         if let Some(changes) = self.history.redo() {
             for change in changes {
@@ -1886,8 +2248,10 @@ impl InputState {
     /// Ensure the offset use self.next_boundary or self.previous_boundary to get the correct offset.
     pub(crate) fn select_to(&mut self, offset: usize, cx: &mut Context<Self>) {
         self.clear_inline_completion(cx);
+        self.column_selection = None;
 
         let offset = self.snap_offset_outside_inline_badge(offset.clamp(0, self.text.len()));
+        let offset = self.clip_offset_to_folds(offset, offset > self.cursor());
         if self.selection_reversed {
             self.selected_range.start = offset
         } else {
@@ -2086,6 +2450,18 @@ impl InputState {
 
         self.hover_popover = None;
         self.diagnostic_popover = None;
+        self.signature_help_popover = None;
+        // Keep the references peek view when the preview is focused.
+        if !self.references_popover.as_ref().map_or(false, |popover| {
+            popover
+                .read(cx)
+                .preview
+                .read(cx)
+                .focus_handle
+                .is_focused(window)
+        }) {
+            self.references_popover = None;
+        }
         self.context_menu = None;
         self.clear_inline_completion(cx);
         self.blink_cursor.update(cx, |cursor, cx| {
@@ -2142,6 +2518,10 @@ impl InputState {
             return;
         }
 
+        if self.on_drag_column_selection(event, cx) {
+            return;
+        }
+
         let offset = self.index_for_mouse_position(event.position);
         self.select_to(offset, cx);
     }
@@ -2348,6 +2728,71 @@ impl InputState {
         self.replace_text_in_range(range_utf16, new_text, window, cx);
         self.silent_replace_text = false;
     }
+
+    /// Replace the text in the ranges of the `edits` (sorted and not overlapped ranges of the current text)
+    /// as one undo step, without changing the selection.
+    ///
+    /// The edits are applied from the last one, then the text wrapper, folds, diff, highlighter,
+    /// search and the language server are updated once for all the edits.
+    pub(crate) fn replace_texts_in_ranges(
+        &mut self,
+        edits: &[(Range<usize>, String)],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (Some((first, _)), Some((last, _))) = (edits.first(), edits.last()) else {
+            return;
+        };
+        if self.disabled || self.block_read_only_edit(cx) {
+            return;
+        }
+
+        let old_text = self.text.clone();
+        self.history.break_grouping();
+        self.history.start_grouping();
+        for (range, new_text) in edits.iter().rev() {
+            let text = self.text.clone();
+            self.text.replace(range.clone(), new_text);
+
+            let delta = new_text.len() as isize - range.len() as isize;
+            self.remove_inline_badges_intersecting(range);
+            self.shift_inline_badges_after(range.end, delta);
+            self.shift_extra_selections(range, new_text.len());
+            self.shift_snippet_session(range, new_text.len());
+            self.shift_remote_undos(range, new_text.len());
+            self.lsp.shift_semantic_tokens(range, new_text.len());
+            self.push_history(&text, range, new_text);
+            self.emit_edit(&text, range, new_text, cx);
+        }
+        self.history.end_grouping();
+        self.history.break_grouping();
+        self.column_selection = None;
+        self.ime_marked_range.take();
+
+        // The range covers all the edits, the edits in it are replaced by the `new_text`.
+        let range = first.start..last.end;
+        let new_len = (range.len() + self.text.len()).saturating_sub(old_text.len());
+        let new_text = self
+            .text
+            .slice(range.start..range.start + new_len)
+            .to_string();
+
+        if let Some(diagnostics) = self.mode.diagnostics_mut() {
+            diagnostics.reset(&self.text)
+        }
+        self.text_wrapper
+            .update(&self.text, &range, &Rope::from(new_text.as_str()), cx);
+        self.update_folds_for_edit(&old_text, &range, &new_text);
+        self.update_diff_for_edit(&old_text, &range, &new_text);
+        self.update_highlighter(&range, &new_text, true, cx);
+        self.lsp.did_change_edits(&old_text, edits, &self.text, cx);
+        self.lsp.update(&self.text, window, cx);
+        self.update_search(cx);
+        self.mode.update_auto_grow(&self.text_wrapper);
+
+        cx.emit(InputEvent::Change);
+        cx.notify();
+    }
 }
 
 impl EntityInputHandler for InputState {
@@ -2403,6 +2848,23 @@ impl EntityInputHandler for InputState {
             return;
         }
 
+        // The typed text is a command in the Vim normal and visual modes, the commands to move
+        // are still allowed in the read-only mode, the commands to edit are blocked by themselves.
+        let is_vim_command = !self.silent_replace_text
+            && matches!(self.vim_mode(), Some(mode) if mode != VimMode::Insert);
+        if !is_vim_command && self.block_read_only_edit(cx) {
+            return;
+        }
+        if is_vim_command && self.handle_vim_input(new_text, window, cx) {
+            return;
+        }
+
+        // Typing with multiple cursors, apply the change to all the selections.
+        if range_utf16.is_none() && self.ime_marked_range.is_none() && self.has_multiple_cursors() {
+            self.replace_text_in_selections(&[new_text.to_string()], window, cx);
+            return;
+        }
+
         self.pause_blink_cursor(cx);
 
         let range = range_utf16
@@ -2419,6 +2881,7 @@ impl EntityInputHandler for InputState {
         self.text.replace(range.clone(), new_text);
 
         let mut new_offset = (range.start + new_text.len()).min(self.text.len());
+        let mut masked = false;
 
         if self.mode.is_single_line() {
             let pending_text = self.text.to_string();
@@ -2434,12 +2897,17 @@ impl EntityInputHandler for InputState {
                 let new_text_len =
                     (new_text.len() + mask_text.len()).saturating_sub(pending_text.len());
                 new_offset = (range.start + new_text_len).min(mask_text.len());
+                masked = true;
             }
         }
 
         let delta = new_text.len() as isize - range.len() as isize;
         self.remove_inline_badges_intersecting(&range);
         self.shift_inline_badges_after(range.end, delta);
+        self.shift_extra_selections(&range, new_text.len());
+        self.shift_snippet_session(&range, new_text.len());
+        self.shift_remote_undos(&range, new_text.len());
+        self.column_selection = None;
 
         self.push_history(&old_text, &range, &new_text);
         self.history.end_grouping();
@@ -2448,9 +2916,20 @@ impl EntityInputHandler for InputState {
         }
         self.text_wrapper
             .update(&self.text, &range, &Rope::from(new_text), cx);
-        self.mode
-            .update_highlighter(&range, &self.text, &new_text, true, cx);
+        self.update_folds_for_edit(&old_text, &range, new_text);
+        self.update_diff_for_edit(&old_text, &range, new_text);
+        self.update_highlighter(&range, &new_text, true, cx);
+        self.lsp.shift_semantic_tokens(&range, new_text.len());
+        self.lsp
+            .did_change(&old_text, &range, new_text, &self.text, cx);
         self.lsp.update(&self.text, window, cx);
+        if masked {
+            // The whole text is reformatted by the mask pattern.
+            let text = self.text.to_string();
+            self.emit_edit(&old_text, &(0..old_text.len()), &text, cx);
+        } else {
+            self.emit_edit(&old_text, &range, new_text, cx);
+        }
         self.selected_range = (new_offset..new_offset).into();
         self.ime_marked_range.take();
         self.update_preferred_column();
@@ -2472,7 +2951,7 @@ impl EntityInputHandler for InputState {
                     bounds.size.height = new_height;
                 }
                 self.input_bounds.size.height = new_height;
-                let content_height = self.text_wrapper.len() as f32 * line_height;
+                let content_height = self.text_wrapper.height(line_height);
                 self.scroll_size.height = content_height.max(new_height);
             }
         }
@@ -2481,6 +2960,9 @@ impl EntityInputHandler for InputState {
 
         if !self.silent_replace_text {
             self.handle_completion_trigger(&range, &new_text, window, cx);
+            self.handle_signature_help_trigger(&new_text, window, cx);
+            self.handle_on_type_formatting(&new_text, window, cx);
+            self.auto_outdent(&new_text, window, cx);
         }
         cx.emit(InputEvent::Change);
         cx.notify();
@@ -2499,6 +2981,15 @@ impl EntityInputHandler for InputState {
             return;
         }
 
+        // No IME composing in the Vim normal and visual modes.
+        if matches!(self.vim_mode(), Some(mode) if mode != VimMode::Insert) {
+            return;
+        }
+
+        if self.block_read_only_edit(cx) {
+            return;
+        }
+
         self.lsp.reset();
 
         let range = range_utf16
@@ -2527,9 +3018,14 @@ impl EntityInputHandler for InputState {
         }
         self.text_wrapper
             .update(&self.text, &range, &Rope::from(new_text), cx);
-        self.mode
-            .update_highlighter(&range, &self.text, &new_text, true, cx);
+        self.update_folds_for_edit(&old_text, &range, new_text);
+        self.update_diff_for_edit(&old_text, &range, new_text);
+        self.update_highlighter(&range, &new_text, true, cx);
+        self.lsp.shift_semantic_tokens(&range, new_text.len());
+        self.lsp
+            .did_change(&old_text, &range, new_text, &self.text, cx);
         self.lsp.update(&self.text, window, cx);
+        self.emit_edit(&old_text, &range, new_text, cx);
         if new_text.is_empty() {
             // Cancel selection, when cancel IME input.
             self.selected_range = (range.start..range.start).into();
@@ -2549,6 +3045,8 @@ impl EntityInputHandler for InputState {
         let delta = new_text.len() as isize - range.len() as isize;
         self.remove_inline_badges_intersecting(&range);
         self.shift_inline_badges_after(range.end, delta);
+        self.shift_snippet_session(&range, new_text.len());
+        self.shift_remote_undos(&range, new_text.len());
         self.push_history(&old_text, &range, new_text);
         cx.notify();
     }
@@ -2638,9 +3136,10 @@ impl Focusable for InputState {
 impl Render for InputState {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         if self._pending_update {
-            self.mode
-                .update_highlighter(&(0..0), &self.text, "", false, cx);
+            self.update_highlighter(&(0..0), "", false, cx);
             self.lsp.update(&self.text, window, cx);
+            self.foldable_ranges = None;
+            self.outline = None;
             self._pending_update = false;
         }
 
@@ -2651,8 +3150,96 @@ impl Render for InputState {
             .flex_grow()
             .overflow_x_hidden()
             .child(TextElement::new(cx.entity().clone()).placeholder(self.placeholder.clone()))
+            .children(self.render_sticky_scroll(window, cx))
             .children(self.diagnostic_popover.clone())
             .children(self.context_menu.as_ref().map(|menu| menu.render()))
             .children(self.hover_popover.clone())
+            .children(self.signature_help_popover.clone())
+            .children(self.rename_popover.clone())
+            .children(self.references_popover.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use gpui::{AppContext as _, ClipboardItem, EntityInputHandler as _, TestAppContext};
+
+    use super::{
+        Copy, Cut, Enter, FormatDocument, InputEvent, InputState, MoveToEnd, Paste, Redo,
+        SelectAll, Undo,
+    };
+
+    #[gpui::test]
+    fn test_read_only(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .code_editor("text")
+                    .default_value("hello world")
+            })
+        });
+        let blocked = Rc::new(Cell::new(0));
+        cx.update(|_, cx| {
+            let blocked = blocked.clone();
+            cx.subscribe(&state, move |_, event: &InputEvent, _| {
+                if matches!(event, InputEvent::ReadOnlyEdit) {
+                    blocked.set(blocked.get() + 1);
+                }
+            })
+            .detach();
+        });
+
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                // A change to undo.
+                state.selected_range = (11..11).into();
+                state.replace_text_in_range(None, "!", window, cx);
+                state.set_read_only(true, window, cx);
+                assert!(state.is_read_only());
+
+                state.selected_range = (0..5).into();
+                cx.write_to_clipboard(ClipboardItem::new_string("bye".into()));
+
+                state.replace_text_in_range(None, "a", window, cx);
+                state.paste(&Paste, window, cx);
+                state.cut(&Cut, window, cx);
+                state.enter(&Enter { secondary: false }, window, cx);
+                state.undo(&Undo, window, cx);
+                state.redo(&Redo, window, cx);
+                state.replace_and_mark_text_in_range(None, "a", None, window, cx);
+                state.on_action_format_document(&FormatDocument, window, cx);
+                state.start_rename(window, cx);
+                state.insert_snippet("${1:a}$0", window, cx);
+                assert_eq!(state.value(), "hello world!");
+                assert_eq!(state.ime_marked_range, None);
+                assert!(state.rename_popover.is_none());
+                assert!(state.snippet_session.is_none());
+                // The cut is blocked before writing to the clipboard.
+                assert_eq!(
+                    cx.read_from_clipboard().and_then(|item| item.text()),
+                    Some("bye".into())
+                );
+
+                // The selection, copy and cursor movement still work.
+                state.copy(&Copy, window, cx);
+                assert_eq!(
+                    cx.read_from_clipboard().and_then(|item| item.text()),
+                    Some("hello".into())
+                );
+                state.select_all(&SelectAll, window, cx);
+                assert_eq!(state.selected_range, (0..12).into());
+                state.move_to_end(&MoveToEnd, window, cx);
+                assert_eq!(state.cursor(), 12);
+
+                // The `set_value` still replaces the text.
+                state.set_value("bye", window, cx);
+                assert_eq!(state.value(), "bye");
+                assert!(state.is_read_only());
+            });
+        });
+        assert_eq!(blocked.get(), 10);
     }
 }
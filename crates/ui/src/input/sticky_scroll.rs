@@ -0,0 +1,278 @@
+use gpui::{
+    Context, InteractiveElement as _, IntoElement, MouseButton, ParentElement as _, Styled as _,
+    StyledText, Window, div, point, prelude::FluentBuilder as _, px,
+};
+use tree_sitter::Tree;
+
+use crate::{
+    ActiveTheme as _, h_flex,
+    input::{
+        InputState, RopeExt as _,
+        element::{DIAGNOSTIC_MARKER_WIDTH, line_number_len},
+        mode::InputMode,
+        text_wrapper::DisplayPoint,
+    },
+    v_flex,
+};
+
+impl InputMode {
+    /// Returns the max depth of the sticky scroll headers, 0 means disabled.
+    #[inline]
+    pub(super) fn sticky_scroll(&self) -> usize {
+        match self {
+            InputMode::CodeEditor {
+                sticky_scroll,
+                multi_line,
+                ..
+            } if *multi_line => *sticky_scroll,
+            _ => 0,
+        }
+    }
+}
+
+impl InputState {
+    /// Set the max depth of the sticky scroll headers in code editor mode, default is 0 (disabled).
+    ///
+    /// The header lines of the enclosing scopes (e.g.: functions, impl blocks) of the first visible line
+    /// are pinned at the top of the viewport, click the header to jump there.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn sticky_scroll(mut self, max_depth: usize) -> Self {
+        debug_assert!(self.mode.is_code_editor() && self.mode.is_multi_line());
+        if let InputMode::CodeEditor { sticky_scroll, .. } = &mut self.mode {
+            *sticky_scroll = max_depth;
+        }
+        self
+    }
+
+    /// Set the max depth of the sticky scroll headers in code editor mode, 0 to disable.
+    ///
+    /// Only for [`InputMode::CodeEditor`] mode.
+    pub fn set_sticky_scroll(&mut self, max_depth: usize, _: &mut Window, cx: &mut Context<Self>) {
+        debug_assert!(self.mode.is_code_editor());
+        if let InputMode::CodeEditor { sticky_scroll, .. } = &mut self.mode {
+            *sticky_scroll = max_depth;
+        }
+        cx.notify();
+    }
+
+    /// Returns the rows of the sticky scroll headers, from the outermost to the innermost.
+    pub(super) fn sticky_scroll_rows(&self) -> Vec<usize> {
+        let max_depth = self.mode.sticky_scroll();
+        if max_depth == 0 {
+            return vec![];
+        }
+        let Some(line_height) = self
+            .last_layout
+            .as_ref()
+            .map(|last_layout| last_layout.line_height)
+            .filter(|line_height| *line_height > px(0.))
+        else {
+            return vec![];
+        };
+        let InputMode::CodeEditor { highlighter, .. } = &self.mode else {
+            return vec![];
+        };
+        let highlighter = highlighter.borrow();
+        let Some(tree) = highlighter
+            .as_ref()
+            .and_then(|highlighter| highlighter.tree())
+        else {
+            return vec![];
+        };
+
+        let scopes_at = |display_row: usize| {
+            let row = self
+                .text_wrapper
+                .display_point_to_point(DisplayPoint::new(display_row, 0, 0))
+                .row;
+            let mut rows = enclosing_scope_rows(tree, row);
+            rows.truncate(max_depth);
+            rows
+        };
+
+        let top = (-self.scroll_handle.offset().y / line_height).floor() as usize;
+        let rows = scopes_at(top);
+        if rows.is_empty() {
+            return rows;
+        }
+
+        // The headers cover the first lines of the viewport, so use the scopes of the line below them.
+        scopes_at(top + rows.len())
+    }
+
+    /// Move the cursor to the header row, and scroll it to the below of its outer headers.
+    ///
+    /// - `depth`: The index of the header in the sticky scroll headers.
+    fn go_to_sticky_scroll_row(
+        &mut self,
+        row: usize,
+        depth: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(line_height) = self
+            .last_layout
+            .as_ref()
+            .map(|last_layout| last_layout.line_height)
+        else {
+            return;
+        };
+
+        self.move_to(self.text.line_start_offset(row), None, cx);
+        let display_row = (self.text_wrapper.cumulative_at(row)
+            + self.text_wrapper.block_rows_above(row))
+        .saturating_sub(depth);
+        let offset = self.scroll_handle.offset();
+        self.update_scroll_offset(Some(point(offset.x, -line_height * display_row as f32)), cx);
+        self.focus(window, cx);
+    }
+
+    pub(super) fn render_sticky_scroll(
+        &self,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<impl IntoElement> {
+        let rows = self.sticky_scroll_rows();
+        if rows.is_empty() {
+            return None;
+        }
+        let last_layout = self.last_layout.as_ref()?;
+        let line_height = last_layout.line_height;
+        let line_number_width = last_layout.line_number_width;
+        let line_number_len = line_number_len(self.text.lines_len());
+        let line_number_left = if self.mode.has_diagnostic_markers() {
+            DIAGNOSTIC_MARKER_WIDTH
+        } else {
+            px(0.)
+        };
+        let scroll_x = self.scroll_handle.offset().x;
+        let highlighter = match &self.mode {
+            InputMode::CodeEditor { highlighter, .. } => Some(highlighter.borrow()),
+            _ => None,
+        };
+
+        Some(
+            v_flex()
+                .id("sticky-scroll")
+                .absolute()
+                .top_0()
+                .left_0()
+                .right_0()
+                .overflow_hidden()
+                .bg(cx.theme().editor_background())
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .children(rows.into_iter().enumerate().map(|(depth, row)| {
+                    let line_start = self.text.line_start_offset(row);
+                    let line = self.text.slice_line(row).to_string();
+                    let line_end = line_start + line.len();
+                    let styles = highlighter
+                        .as_ref()
+                        .and_then(|highlighter| highlighter.as_ref())
+                        .map(|highlighter| {
+                            highlighter.styles(&(line_start..line_end), &cx.theme().highlight_theme)
+                        })
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(range, style)| {
+                            let start = range.start.max(line_start) - line_start;
+                            let end = range.end.min(line_end).saturating_sub(line_start);
+                            (start < end).then_some((start..end, style))
+                        })
+                        .collect::<Vec<_>>();
+
+                    h_flex()
+                        .id(("sticky-scroll-row", row))
+                        .h(line_height)
+                        .w_full()
+                        .overflow_hidden()
+                        .whitespace_nowrap()
+                        .cursor_pointer()
+                        .hover(|this| this.bg(cx.theme().accent.opacity(0.5)))
+                        .when(line_number_width > px(0.), |this| {
+                            this.child(
+                                div()
+                                    .flex_shrink_0()
+                                    .w(line_number_width)
+                                    .pl(line_number_left)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("{:>width$}", row + 1, width = line_number_len)),
+                            )
+                        })
+                        .child(
+                            div().flex_1().overflow_hidden().child(
+                                div()
+                                    .ml(scroll_x)
+                                    .child(StyledText::new(line).with_highlights(styles)),
+                            ),
+                        )
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, window, cx| {
+                                cx.stop_propagation();
+                                this.go_to_sticky_scroll_row(row, depth, window, cx);
+                            }),
+                        )
+                })),
+        )
+    }
+}
+
+/// Returns the start rows of the multi-line named nodes contain the `row` and start before it,
+/// from the outermost to the innermost.
+///
+/// The nodes start at the same row are merged into one.
+fn enclosing_scope_rows(tree: &Tree, row: usize) -> Vec<usize> {
+    let mut rows: Vec<usize> = vec![];
+    let mut node = tree.root_node();
+    loop {
+        let mut cursor = node.walk();
+        let Some(child) = node.named_children(&mut cursor).find(|child| {
+            let start_row = child.start_position().row;
+            let mut end_row = child.end_position().row;
+            if child.end_position().column == 0 {
+                // The node ends with a `\n`.
+                end_row = end_row.saturating_sub(1);
+            }
+
+            start_row < row && row <= end_row
+        }) else {
+            break;
+        };
+
+        let start_row = child.start_position().row;
+        if rows.last() != Some(&start_row) {
+            rows.push(start_row);
+        }
+        node = child;
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "tree-sitter-languages")]
+    fn test_enclosing_scope_rows() {
+        use super::enclosing_scope_rows;
+        use crate::highlighter::SyntaxHighlighter;
+        use ropey::Rope;
+
+        let text = Rope::from(
+            "struct Foo;\n\nimpl Foo {\n    fn foo() {\n        let a = 1;\n        let b = 2;\n    }\n}\n",
+        );
+        let mut highlighter = SyntaxHighlighter::new("rust");
+        highlighter.update(None, &text);
+        let tree = highlighter.tree().unwrap();
+
+        assert_eq!(enclosing_scope_rows(tree, 0), Vec::<usize>::new());
+        assert_eq!(enclosing_scope_rows(tree, 2), Vec::<usize>::new());
+        assert_eq!(enclosing_scope_rows(tree, 3), vec![2]);
+        assert_eq!(enclosing_scope_rows(tree, 4), vec![2, 3]);
+        assert_eq!(enclosing_scope_rows(tree, 6), vec![2, 3]);
+        assert_eq!(enclosing_scope_rows(tree, 7), vec![2]);
+        assert_eq!(enclosing_scope_rows(tree, 8), Vec::<usize>::new());
+    }
+}
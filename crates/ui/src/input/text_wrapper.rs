@@ -6,35 +6,54 @@ use gpui::{
 use ropey::Rope;
 use smallvec::SmallVec;
 
-use crate::input::{LastLayout, RopeExt, WhitespaceIndicators};
+use crate::input::{
+    FoldRange, LastLayout, RopeExt, WhitespaceIndicators, large_file::LARGE_FILE_SIZE,
+};
 
 /// A line with soft wrapped lines info.
 #[derive(Debug, Clone)]
 pub(super) struct LineItem {
-    /// The original line text, without end `\n`.
-    line: Rope,
+    /// The bytes length of the line, without end `\n`.
+    len: usize,
     /// The soft wrapped lines relative byte range (0..line.len) of this line (Include first line).
     ///
     /// Not contains the line end `\n`.
     pub(super) wrapped_lines: Vec<Range<usize>>,
+    /// Whether this line is hidden by a fold.
+    pub(super) folded: bool,
+    /// Whether the soft wrap of this line is not computed yet in the large file mode,
+    /// see [`TextWrapper::wrap_rows`].
+    pub(super) pending_wrap: bool,
+    /// The rows of the block displayed above this line, see [`TextWrapper::set_block_rows`].
+    pub(super) block_rows: usize,
 }
 
 impl LineItem {
     /// Get the bytes length of this line.
     #[inline]
     pub(super) fn len(&self) -> usize {
-        self.line.len()
+        self.len
     }
 
     /// Get number of soft wrapped lines of this line (include the first line).
+    ///
+    /// Returns 0 if the line is hidden by a fold.
     #[inline]
     pub(super) fn lines_len(&self) -> usize {
+        if self.folded {
+            return 0;
+        }
+
         self.wrapped_lines.len()
     }
 
-    /// Get the height of this line item with given line height.
+    /// Get the height of this line item with given line height, including the block rows above it.
     pub(super) fn height(&self, line_height: Pixels) -> Pixels {
-        self.lines_len() as f32 * line_height
+        if self.folded {
+            return px(0.);
+        }
+
+        (self.lines_len() + self.block_rows) as f32 * line_height
     }
 }
 
@@ -64,6 +83,12 @@ pub(super) struct TextWrapper {
     /// Prefix sum of wrapped line counts for O(1) lookup.
     /// `cumulative_wrapped_lines[i]` = total wrapped lines in `lines[0..i]`.
     cumulative_wrapped_lines: Vec<usize>,
+    /// The folded ranges, the lines in the ranges (except the start line) are hidden.
+    folded_ranges: Vec<FoldRange>,
+    /// The (row, rows count) of the block displayed above the row, see [`Self::set_block_rows`].
+    block_rows: Option<(usize, usize)>,
+    /// The text size in bytes to compute the soft wrap lazily, see [`Self::wrap_rows`].
+    pub(super) large_file_size: usize,
 
     _initialized: bool,
 }
@@ -80,6 +105,9 @@ impl TextWrapper {
             longest_row: LongestRow::default(),
             lines: Vec::new(),
             cumulative_wrapped_lines: Vec::new(),
+            folded_ranges: Vec::new(),
+            block_rows: None,
+            large_file_size: LARGE_FILE_SIZE,
             _initialized: false,
         }
     }
@@ -105,7 +133,7 @@ impl TextWrapper {
     /// Get the line item by row index.
     #[inline]
     pub(super) fn line(&self, row: usize) -> Option<&LineItem> {
-        self.lines.iter().skip(row).next()
+        self.lines.get(row)
     }
 
     pub(super) fn set_wrap_width(&mut self, wrap_width: Option<Pixels>, cx: &mut App) {
@@ -175,6 +203,10 @@ impl TextWrapper {
     ) where
         F: FnMut(&str, Pixels) -> Vec<gpui::Boundary>,
     {
+        // The lines are moved, the block is set to the same row again after the update.
+        let block_rows = self.block_rows;
+        self.set_block_rows(None);
+
         // Remove the old changed lines.
         let start_row = self.text.offset_to_point(range.start).row;
         let start_row = start_row.min(self.lines.len().saturating_sub(1));
@@ -191,47 +223,40 @@ impl TextWrapper {
 
         // To add the new lines.
         let new_start_row = changed_text.offset_to_point(range.start).row;
-        let new_start_offset = changed_text.line_start_offset(new_start_row);
         let new_end_row = changed_text
             .offset_to_point(range.start + new_text.len())
             .row;
-        let new_end_offset = changed_text.line_end_offset(new_end_row);
-        let new_range = new_start_offset..new_end_offset;
 
         let mut new_lines = vec![];
         let wrap_width = self.wrap_width;
-
-        // line not contains `\n`.
-        for (ix, line) in Rope::from(changed_text.slice(new_range))
-            .iter_lines()
-            .enumerate()
-        {
-            let line_str = line.to_string();
-            let mut wrapped_lines = vec![];
-            let mut prev_boundary_ix = 0;
-
-            if line_str.len() > longest_row_len {
-                longest_row_ix = new_start_row + ix;
-                longest_row_len = line_str.len();
+        // In the large file mode, only the visible lines are wrapped by `wrap_rows`.
+        let lazy = changed_text.len() >= self.large_file_size;
+
+        for row in new_start_row..=new_end_row {
+            // line not contains `\n`.
+            let line = changed_text.slice_line(row);
+            let len = line.len();
+            if len > longest_row_len {
+                longest_row_ix = row;
+                longest_row_len = len;
             }
 
-            // If wrap_width is Pixels::MAX, skip wrapping to disable word wrap
-            if let Some(wrap_width) = wrap_width {
-                // Here only have wrapped line, if there is no wrap meet, the `line_wraps` result will empty.
-                for boundary in wrap_line(&line_str, wrap_width) {
-                    wrapped_lines.push(prev_boundary_ix..boundary.ix);
-                    prev_boundary_ix = boundary.ix;
+            // If wrap_width is None, skip wrapping to disable word wrap
+            let (wrapped_lines, pending_wrap) = match wrap_width {
+                Some(_) if lazy => (vec![0..len], true),
+                Some(wrap_width) => {
+                    let line_str = line.to_string();
+                    (wrapped_ranges(len, wrap_line(&line_str, wrap_width)), false)
                 }
-            }
-
-            // Reset of the line
-            if !line_str[prev_boundary_ix..].is_empty() || prev_boundary_ix == 0 {
-                wrapped_lines.push(prev_boundary_ix..line.len());
-            }
+                None => (vec![0..len], false),
+            };
 
             new_lines.push(LineItem {
-                line: Rope::from(line),
+                len,
                 wrapped_lines,
+                folded: false,
+                pending_wrap,
+                block_rows: 0,
             });
         }
 
@@ -242,8 +267,68 @@ impl TextWrapper {
         }
 
         self.text = changed_text.clone();
+        self.update_cumulative_wrapped_lines();
+        self.set_block_rows(block_rows);
+
+        self.longest_row = LongestRow {
+            row: longest_row_ix,
+            len: longest_row_len,
+        }
+    }
+
+    /// Compute the soft wrap of the lines in `rows` that are pending in the large file mode.
+    ///
+    /// Returns true if any line is wrapped, then the wrapped lines are changed.
+    pub(super) fn wrap_rows(&mut self, rows: Range<usize>, cx: &mut App) -> bool {
+        let rows = rows.start.min(self.lines.len())..rows.end.min(self.lines.len());
+        if !self.lines[rows.clone()]
+            .iter()
+            .any(|line| line.pending_wrap)
+        {
+            return false;
+        }
+
+        let mut line_wrapper = cx
+            .text_system()
+            .line_wrapper(self.font.clone(), self.font_size);
+        self._wrap_rows(rows, &mut |line_str, wrap_width| {
+            line_wrapper
+                .wrap_line(&[LineFragment::text(line_str)], wrap_width)
+                .collect()
+        })
+    }
 
-        // Rebuild prefix sum for O(1) cumulative line count lookups
+    fn _wrap_rows<F>(&mut self, rows: Range<usize>, wrap_line: &mut F) -> bool
+    where
+        F: FnMut(&str, Pixels) -> Vec<gpui::Boundary>,
+    {
+        let Some(wrap_width) = self.wrap_width else {
+            return false;
+        };
+
+        let mut changed = false;
+        for row in rows {
+            let Some(line) = self.lines.get_mut(row) else {
+                break;
+            };
+            if !line.pending_wrap {
+                continue;
+            }
+
+            let line_str = self.text.slice_line(row).to_string();
+            line.wrapped_lines = wrapped_ranges(line.len, wrap_line(&line_str, wrap_width));
+            line.pending_wrap = false;
+            changed = true;
+        }
+
+        if changed {
+            self.update_cumulative_wrapped_lines();
+        }
+        changed
+    }
+
+    /// Rebuild prefix sum for O(1) cumulative line count lookups
+    fn update_cumulative_wrapped_lines(&mut self) {
         self.cumulative_wrapped_lines.clear();
         self.cumulative_wrapped_lines.reserve(self.lines.len());
         let mut cumulative = 0;
@@ -252,10 +337,73 @@ impl TextWrapper {
             cumulative += line.lines_len();
         }
         self.soft_lines = cumulative;
+    }
 
-        self.longest_row = LongestRow {
-            row: longest_row_ix,
-            len: longest_row_len,
+    /// Hide the lines of the given folded ranges, other lines will be visible.
+    pub(super) fn set_folded_ranges(&mut self, folded_ranges: &[FoldRange]) {
+        self.folded_ranges = folded_ranges.to_vec();
+        for line in self.lines.iter_mut() {
+            line.folded = false;
+        }
+        for range in folded_ranges {
+            let end_row = range.end_row.min(self.lines.len().saturating_sub(1));
+            for row in range.start_row + 1..=end_row {
+                self.lines[row].folded = true;
+            }
+        }
+
+        self.update_cumulative_wrapped_lines();
+    }
+
+    /// Display a block of `rows` above the `row`, e.g.: the deleted lines of an expanded diff hunk.
+    ///
+    /// The block rows are only counted in the height of the lines, not in the display points.
+    /// The `row` can be the rows count of the text to display the block after the last line.
+    pub(super) fn set_block_rows(&mut self, block_rows: Option<(usize, usize)>) {
+        if let Some((row, _)) = self.block_rows {
+            if let Some(line) = self.lines.get_mut(row) {
+                line.block_rows = 0;
+            }
+        }
+
+        self.block_rows = block_rows;
+        if let Some((row, rows)) = block_rows {
+            if let Some(line) = self.lines.get_mut(row) {
+                line.block_rows = rows;
+            }
+        }
+    }
+
+    /// Returns the block rows count above the line of the `row` (include the block of the `row`).
+    pub(super) fn block_rows_above(&self, row: usize) -> usize {
+        match self.block_rows {
+            Some((block_row, rows)) if block_row <= row && !self.is_row_folded(block_row) => rows,
+            _ => 0,
+        }
+    }
+
+    /// Returns the total height of all lines, including the block rows.
+    pub(super) fn height(&self, line_height: Pixels) -> Pixels {
+        (self.soft_lines + self.block_rows_above(self.lines.len())) as f32 * line_height
+    }
+
+    /// Returns true if the row is hidden by a fold.
+    #[inline]
+    pub(super) fn is_row_folded(&self, row: usize) -> bool {
+        self.lines.get(row).map_or(false, |line| line.folded)
+    }
+
+    /// Returns the nearest visible row (not hidden by a fold) of the given row.
+    ///
+    /// The `forward` is true to find the next visible row, otherwise the previous one,
+    /// returns None if not found.
+    pub(super) fn visible_row(&self, row: usize, forward: bool) -> Option<usize> {
+        if forward {
+            (row..self.lines.len()).find(|&row| !self.is_row_folded(row))
+        } else {
+            (0..=row.min(self.lines.len().saturating_sub(1)))
+                .rev()
+                .find(|&row| !self.is_row_folded(row))
         }
     }
 
@@ -264,13 +412,25 @@ impl TextWrapper {
     /// If the `text` is the same as the current text, do nothing.
     fn update_all(&mut self, text: &Rope, cx: &mut App) {
         self.update(text, &(0..text.len()), &text, cx);
+
+        // All lines are recreated, hide the folded lines again.
+        if !self.folded_ranges.is_empty() {
+            let folded_ranges = std::mem::take(&mut self.folded_ranges);
+            self.set_folded_ranges(&folded_ranges);
+        }
     }
 
     /// Return display point (with soft wrap) from the given byte offset in the text.
     ///
     /// Panics if the `offset` is out of bounds.
     pub(crate) fn offset_to_display_point(&self, offset: usize) -> DisplayPoint {
-        let row = self.text.offset_to_point(offset).row;
+        let mut offset = offset;
+        let mut row = self.text.offset_to_point(offset).row;
+        // The folded line is not displayed, use the end of the fold start line.
+        if self.is_row_folded(row) {
+            row = self.visible_row(row, false).unwrap_or(0);
+            offset = self.text.line_end_offset(row);
+        }
         let start = self.text.line_start_offset(row);
         let line = &self.lines[row];
 
@@ -301,16 +461,23 @@ impl TextWrapper {
             return self.text.len();
         }
 
-        // Binary search for the logical line containing this wrapped row
-        let row = match self.cumulative_wrapped_lines.binary_search(&point.row) {
-            Ok(ix) => ix,
-            Err(ix) => ix.saturating_sub(1),
-        };
+        // Binary search for the logical line containing this wrapped row,
+        // the folded lines have same cumulative value, so find the last one.
+        let row = self
+            .cumulative_wrapped_lines
+            .partition_point(|&cumulative| cumulative <= point.row)
+            .saturating_sub(1);
 
         if row >= self.lines.len() {
             return self.text.len();
         }
 
+        // Only happens when the last lines are folded, use the end of the fold start line.
+        if self.is_row_folded(row) {
+            let row = self.visible_row(row, false).unwrap_or(0);
+            return self.text.line_end_offset(row);
+        }
+
         let line = &self.lines[row];
         let wrapped_row = self.cumulative_at(row);
         let local_row = point.row.saturating_sub(wrapped_row);
@@ -334,6 +501,26 @@ impl TextWrapper {
     }
 }
 
+/// Returns the soft wrapped ranges of a line by the wrap `boundaries`.
+fn wrapped_ranges(
+    len: usize,
+    boundaries: impl IntoIterator<Item = gpui::Boundary>,
+) -> Vec<Range<usize>> {
+    let mut wrapped_lines = vec![];
+    let mut prev_boundary_ix = 0;
+    // Here only have wrapped line, if there is no wrap meet, the `boundaries` will be empty.
+    for boundary in boundaries {
+        wrapped_lines.push(prev_boundary_ix..boundary.ix);
+        prev_boundary_ix = boundary.ix;
+    }
+
+    // Reset of the line
+    if prev_boundary_ix < len || prev_boundary_ix == 0 {
+        wrapped_lines.push(prev_boundary_ix..len);
+    }
+    wrapped_lines
+}
+
 /// The actually display point in the text.
 ///
 /// This is usually used to describe the
@@ -365,11 +552,19 @@ pub(crate) struct LineLayout {
     /// Total bytes length of this line.
     len: usize,
     /// The soft wrapped lines of this line (Include the first line).
+    ///
+    /// The shaped text includes the inlay hints, see `inlays`.
     pub(crate) wrapped_lines: SmallVec<[ShapedLine; 1]>,
+    /// The inlay hints of each soft wrapped line, (local byte index in the text, label bytes length).
+    ///
+    /// The inlay hints are zero-width in the text, only the layout needs to skip them.
+    pub(crate) inlays: SmallVec<[Vec<(usize, usize)>; 1]>,
     pub(crate) longest_width: Pixels,
     pub(crate) whitespace_indicators: Option<WhitespaceIndicators>,
     /// Whitespace indicators: (line_index, x_position, is_tab)
     pub(crate) whitespace_chars: Vec<(usize, Pixels, bool)>,
+    /// The rows of the block displayed above this line, the text starts after them.
+    pub(crate) block_rows: usize,
 }
 
 impl LineLayout {
@@ -378,18 +573,45 @@ impl LineLayout {
             len: 0,
             longest_width: px(0.),
             wrapped_lines: SmallVec::new(),
+            inlays: SmallVec::new(),
             whitespace_chars: Vec::new(),
             whitespace_indicators: None,
+            block_rows: 0,
         }
     }
 
+    /// Create a layout for the line hidden by a fold, only keeps the bytes length of the line.
+    pub(crate) fn folded(len: usize) -> Self {
+        Self { len, ..Self::new() }
+    }
+
     pub(crate) fn lines(mut self, wrapped_lines: SmallVec<[ShapedLine; 1]>) -> Self {
         self.set_wrapped_lines(wrapped_lines);
         self
     }
 
+    /// Set the rows of the block above this line, see [`TextWrapper::set_block_rows`].
+    pub(crate) fn set_block_rows(&mut self, block_rows: usize) {
+        self.block_rows = block_rows;
+    }
+
+    /// Get the height of the block above this line.
+    #[inline]
+    pub(crate) fn block_height(&self, line_height: Pixels) -> Pixels {
+        self.block_rows as f32 * line_height
+    }
+
+    /// Set the inlay hints of the soft wrapped lines, must be called before `set_wrapped_lines`.
+    pub(crate) fn set_inlays(&mut self, inlays: SmallVec<[Vec<(usize, usize)>; 1]>) {
+        self.inlays = inlays;
+    }
+
     pub(crate) fn set_wrapped_lines(&mut self, wrapped_lines: SmallVec<[ShapedLine; 1]>) {
-        self.len = wrapped_lines.iter().map(|l| l.len).sum();
+        self.len = wrapped_lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| self.text_len(i, l.len))
+            .sum();
         let width = wrapped_lines
             .iter()
             .map(|l| l.width)
@@ -404,6 +626,21 @@ impl LineLayout {
         self.len
     }
 
+    #[inline]
+    fn inlays_at(&self, i: usize) -> &[(usize, usize)] {
+        self.inlays
+            .get(i)
+            .map(|inlays| inlays.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the text bytes length of the soft wrapped line, without the inlay hints.
+    #[inline]
+    fn text_len(&self, i: usize, display_len: usize) -> usize {
+        let inlays_len: usize = self.inlays_at(i).iter().map(|(_, len)| len).sum();
+        display_len.saturating_sub(inlays_len)
+    }
+
     /// Get the position (x, y) for the given index in this line layout.
     ///
     /// - The `offset` is a local byte index in this line layout.
@@ -414,17 +651,19 @@ impl LineLayout {
         last_layout: &LastLayout,
     ) -> Option<Point<Pixels>> {
         let mut acc_len = 0;
-        let mut offset_y = px(0.);
+        let mut offset_y = self.block_height(last_layout.line_height);
 
         let x_offset = last_layout.alignment_offset(self.longest_width);
 
         for (i, line) in self.wrapped_lines.iter().enumerate() {
             let is_last = i + 1 == self.wrapped_lines.len();
-            let line_len = if is_last { line.len + 1 } else { line.len };
+            let text_len = self.text_len(i, line.len);
+            let line_len = if is_last { text_len + 1 } else { text_len };
 
             let range = acc_len..(acc_len + line_len);
             if range.contains(&offset) {
-                let x = line.x_for_index(offset.saturating_sub(acc_len)) + x_offset;
+                let ix = display_index(self.inlays_at(i), offset.saturating_sub(acc_len));
+                let x = line.x_for_index(ix) + x_offset;
                 return Some(point(x, offset_y));
             }
             acc_len += line_len;
@@ -450,9 +689,9 @@ impl LineLayout {
                     ix = ix.saturating_sub(c_len);
                 }
 
-                return acc_len + ix;
+                return acc_len + text_index(self.inlays_at(i), ix);
             }
-            acc_len += line.text.len();
+            acc_len += self.text_len(i, line.text.len());
         }
 
         acc_len
@@ -468,7 +707,9 @@ impl LineLayout {
         last_layout: &LastLayout,
     ) -> Option<usize> {
         let mut offset = 0;
-        let mut line_top = px(0.);
+        let mut line_top = self.block_height(last_layout.line_height);
+        // The position in the block is moved to the first line.
+        let pos = point(pos.x, pos.y.max(line_top));
         let x_offset = last_layout.alignment_offset(self.longest_width);
         for (i, line) in self.wrapped_lines.iter().enumerate() {
             let is_last = i + 1 == self.wrapped_lines.len();
@@ -480,10 +721,10 @@ impl LineLayout {
                     let c_len = line.text.chars().last().map(|c| c.len_utf8()).unwrap_or(0);
                     ix = ix.saturating_sub(c_len);
                 }
-                return Some(offset + ix);
+                return Some(offset + text_index(self.inlays_at(i), ix));
             }
 
-            offset += line.text.len();
+            offset += self.text_len(i, line.text.len());
             line_top = line_bottom;
         }
 
@@ -496,16 +737,16 @@ impl LineLayout {
         last_layout: &LastLayout,
     ) -> Option<usize> {
         let mut offset = 0;
-        let mut line_top = px(0.);
+        let mut line_top = self.block_height(last_layout.line_height);
         let x_offset = last_layout.alignment_offset(self.longest_width);
-        for line in self.wrapped_lines.iter() {
+        for (i, line) in self.wrapped_lines.iter().enumerate() {
             let line_bottom = line_top + last_layout.line_height;
             if pos.y >= line_top && pos.y < line_bottom {
                 let ix = line.index_for_x(pos.x - x_offset)?;
-                return Some(offset + ix);
+                return Some(offset + text_index(self.inlays_at(i), ix));
             }
 
-            offset += line.text.len();
+            offset += self.text_len(i, line.text.len());
             line_top = line_bottom;
         }
 
@@ -513,7 +754,10 @@ impl LineLayout {
     }
 
     pub(super) fn size(&self, line_height: Pixels) -> Size<Pixels> {
-        size(self.longest_width, self.wrapped_lines.len() * line_height)
+        size(
+            self.longest_width,
+            (self.wrapped_lines.len() + self.block_rows) * line_height,
+        )
     }
 
     pub(super) fn paint(
@@ -525,6 +769,7 @@ impl LineLayout {
         window: &mut Window,
         cx: &mut App,
     ) {
+        let pos = pos + point(px(0.), self.block_height(line_height));
         for (ix, line) in self.wrapped_lines.iter().enumerate() {
             _ = line.paint(
                 pos + point(px(0.), ix * line_height),
@@ -556,6 +801,36 @@ impl LineLayout {
     }
 }
 
+/// Convert the byte index in the text to the index in the display text with inlay hints.
+///
+/// The inlay hint at the index is after the index.
+fn display_index(inlays: &[(usize, usize)], ix: usize) -> usize {
+    ix + inlays
+        .iter()
+        .take_while(|(inlay_ix, _)| *inlay_ix < ix)
+        .map(|(_, len)| len)
+        .sum::<usize>()
+}
+
+/// Convert the index in the display text with inlay hints to the byte index in the text.
+///
+/// The index inside an inlay hint is moved to the position of the inlay hint.
+fn text_index(inlays: &[(usize, usize)], display_ix: usize) -> usize {
+    let mut delta = 0;
+    for (inlay_ix, len) in inlays {
+        let start = inlay_ix + delta;
+        if display_ix <= start {
+            break;
+        }
+        if display_ix < start + len {
+            return *inlay_ix;
+        }
+        delta += len;
+    }
+
+    display_ix - delta
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -758,6 +1033,43 @@ mod tests {
         assert_eq!(wrapper.lines.len(), 2);
     }
 
+    #[test]
+    fn test_wrap_rows() {
+        let font = gpui::Font {
+            family: "Arial".into(),
+            weight: FontWeight::default(),
+            style: FontStyle::Normal,
+            features: FontFeatures::default(),
+            fallbacks: None,
+        };
+
+        // Wrap every 10 bytes.
+        fn fake_wrap_line(line: &str, _wrap_width: Pixels) -> Vec<Boundary> {
+            (10..line.len())
+                .step_by(10)
+                .map(|ix| Boundary { ix, next_indent: 0 })
+                .collect()
+        }
+
+        let mut wrapper = TextWrapper::new(font, px(14.), Some(px(100.)));
+        wrapper.large_file_size = 0;
+        let text = Rope::from("This is first line.\nThis is second line.\nShort");
+        wrapper._update(&text, &(0..text.len()), &text, &mut fake_wrap_line);
+        assert_eq!(wrapper.len(), 3);
+        assert!(wrapper.lines.iter().all(|line| line.pending_wrap));
+        assert_eq!(wrapper.lines[1].wrapped_lines, vec![0..20]);
+
+        assert!(wrapper._wrap_rows(1..3, &mut fake_wrap_line));
+        assert!(wrapper.lines[0].pending_wrap);
+        assert!(!wrapper.lines[1].pending_wrap);
+        assert_eq!(wrapper.lines[1].wrapped_lines, vec![0..10, 10..20]);
+        assert_eq!(wrapper.lines[2].wrapped_lines, vec![0..5]);
+        assert_eq!(wrapper.len(), 4);
+        assert_eq!(wrapper.cumulative_at(2), 3);
+
+        assert!(!wrapper._wrap_rows(1..3, &mut fake_wrap_line));
+    }
+
     #[test]
     fn test_line_layout() {
         let mut line_layout = LineLayout::new();
@@ -787,23 +1099,35 @@ mod tests {
         wrapper.lines = vec![
             // range: 0..15
             LineItem {
-                line: Rope::from("Hello, 世界!\r"),
+                len: "Hello, 世界!\r".len(),
                 wrapped_lines: vec![0..15],
+                folded: false,
+                pending_wrap: false,
+                block_rows: 0,
             },
             // range: 16..36
             LineItem {
-                line: Rope::from("This is second line."),
+                len: "This is second line.".len(),
                 wrapped_lines: vec![0..10, 10..20],
+                folded: false,
+                pending_wrap: false,
+                block_rows: 0,
             },
             // range: 37..56
             LineItem {
-                line: Rope::from("This is third line."),
+                len: "This is third line.".len(),
                 wrapped_lines: vec![0..9, 9..15, 15..20],
+                folded: false,
+                pending_wrap: false,
+                block_rows: 0,
             },
             // range: 57..79
             LineItem {
-                line: Rope::from("这里是第 4 行。"),
+                len: "这里是第 4 行。".len(),
                 wrapped_lines: vec![0..22],
+                folded: false,
+                pending_wrap: false,
+                block_rows: 0,
             },
         ];
 
@@ -870,4 +1194,130 @@ mod tests {
             15
         );
     }
+
+    #[test]
+    fn test_folded_lines() {
+        let font = gpui::Font {
+            family: "Arial".into(),
+            weight: FontWeight::default(),
+            style: FontStyle::Normal,
+            features: FontFeatures::default(),
+            fallbacks: None,
+        };
+
+        let mut wrapper = TextWrapper::new(font, px(14.), None);
+        let text = Rope::from("fn main() {\n    foo();\n    bar();\n}\nbaz");
+
+        fn fake_wrap_line(_line: &str, _wrap_width: Pixels) -> Vec<gpui::Boundary> {
+            vec![]
+        }
+
+        wrapper._update(&text, &(0..0), &text, &mut fake_wrap_line);
+        assert_eq!(wrapper.len(), 5);
+
+        wrapper.set_folded_ranges(&[FoldRange::new(0, 2)]);
+        assert_eq!(wrapper.len(), 3);
+        assert_eq!(wrapper.is_row_folded(0), false);
+        assert_eq!(wrapper.is_row_folded(1), true);
+        assert_eq!(wrapper.is_row_folded(2), true);
+        assert_eq!(wrapper.is_row_folded(3), false);
+        assert_eq!(wrapper.visible_row(1, true), Some(3));
+        assert_eq!(wrapper.visible_row(2, false), Some(0));
+
+        // The offset in the folded lines is displayed at the end of the fold start line.
+        assert_eq!(
+            wrapper.offset_to_display_point(16),
+            DisplayPoint::new(0, 0, 11)
+        );
+        assert_eq!(
+            wrapper.offset_to_display_point(34),
+            DisplayPoint::new(1, 0, 0)
+        );
+        assert_eq!(
+            wrapper.display_point_to_offset(DisplayPoint::new(1, 0, 0)),
+            34
+        );
+        assert_eq!(
+            wrapper.display_point_to_offset(DisplayPoint::new(2, 0, 2)),
+            38
+        );
+
+        wrapper.set_folded_ranges(&[]);
+        assert_eq!(wrapper.len(), 5);
+        assert_eq!(wrapper.is_row_folded(1), false);
+    }
+
+    #[test]
+    fn test_block_rows() {
+        let font = gpui::Font {
+            family: "Arial".into(),
+            weight: FontWeight::default(),
+            style: FontStyle::Normal,
+            features: FontFeatures::default(),
+            fallbacks: None,
+        };
+
+        let mut wrapper = TextWrapper::new(font, px(14.), None);
+        let text = Rope::from("a\nb\nc");
+
+        fn fake_wrap_line(_line: &str, _wrap_width: Pixels) -> Vec<gpui::Boundary> {
+            vec![]
+        }
+
+        wrapper._update(&text, &(0..0), &text, &mut fake_wrap_line);
+        wrapper.set_block_rows(Some((1, 2)));
+        assert_eq!(wrapper.len(), 3);
+        assert_eq!(wrapper.height(px(10.)), px(50.));
+        assert_eq!(wrapper.lines[1].height(px(10.)), px(30.));
+        assert_eq!(wrapper.block_rows_above(0), 0);
+        assert_eq!(wrapper.block_rows_above(1), 2);
+        assert_eq!(wrapper.block_rows_above(2), 2);
+        // The display points are not changed.
+        assert_eq!(
+            wrapper.offset_to_display_point(2),
+            DisplayPoint::new(1, 0, 0)
+        );
+
+        // Keep the block after the text is changed.
+        let text = Rope::from("a\nb\nc\nd");
+        wrapper._update(&text, &(5..5), &Rope::from("\nd"), &mut fake_wrap_line);
+        assert_eq!(wrapper.lines[1].block_rows, 2);
+        assert_eq!(wrapper.height(px(10.)), px(60.));
+
+        // The block after the last line.
+        wrapper.set_block_rows(Some((4, 1)));
+        assert_eq!(wrapper.lines[1].block_rows, 0);
+        assert_eq!(wrapper.block_rows_above(3), 0);
+        assert_eq!(wrapper.height(px(10.)), px(50.));
+
+        wrapper.set_block_rows(None);
+        assert_eq!(wrapper.height(px(10.)), px(40.));
+    }
+
+    #[test]
+    fn test_inlay_index() {
+        // "let a = foo(1);" with "a: i32" and "x: " hints
+        // "let a: i32 = foo(x: 1);"
+        let inlays = vec![(5, 5), (12, 3)];
+        assert_eq!(display_index(&inlays, 0), 0);
+        assert_eq!(display_index(&inlays, 5), 5);
+        assert_eq!(display_index(&inlays, 6), 11);
+        assert_eq!(display_index(&inlays, 12), 17);
+        assert_eq!(display_index(&inlays, 13), 21);
+        assert_eq!(display_index(&[], 3), 3);
+
+        assert_eq!(text_index(&inlays, 0), 0);
+        assert_eq!(text_index(&inlays, 5), 5);
+        // Inside the inlay hint
+        assert_eq!(text_index(&inlays, 7), 5);
+        assert_eq!(text_index(&inlays, 10), 5);
+        assert_eq!(text_index(&inlays, 11), 6);
+        assert_eq!(text_index(&inlays, 17), 12);
+        assert_eq!(text_index(&inlays, 19), 12);
+        assert_eq!(text_index(&inlays, 21), 13);
+
+        for ix in 0..15 {
+            assert_eq!(text_index(&inlays, display_index(&inlays, ix)), ix);
+        }
+    }
 }
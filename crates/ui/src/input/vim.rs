@@ -0,0 +1,1746 @@
+use std::{collections::HashMap, fmt, ops::Range};
+
+use gpui::{
+    App, ClipboardItem, Context, EntityInputHandler as _, KeyBinding, KeyContext, NoAction, Window,
+};
+use ropey::Rope;
+
+use crate::{
+    history::HistoryItem as _,
+    input::{
+        Escape, InputEvent, InputState, MoveDown, MoveLeft, Position, Redo, RopeExt as _, Undo,
+        selection::is_word_char,
+    },
+};
+
+/// The key context for the Vim normal and visual modes.
+const CONTEXT: &str = "Input && VimControl";
+
+pub(super) fn init(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("ctrl-[", Escape, Some(CONTEXT)),
+        KeyBinding::new("ctrl-[", Escape, Some("Input && vim_mode == insert")),
+        KeyBinding::new("ctrl-r", Redo, Some(CONTEXT)),
+        KeyBinding::new("backspace", MoveLeft, Some(CONTEXT)),
+        KeyBinding::new("enter", MoveDown, Some(CONTEXT)),
+        KeyBinding::new("tab", NoAction, Some(CONTEXT)),
+        KeyBinding::new("shift-tab", NoAction, Some(CONTEXT)),
+        KeyBinding::new("delete", NoAction, Some(CONTEXT)),
+    ]);
+}
+
+/// The mode of the Vim modal editing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+impl VimMode {
+    /// Returns the label of the mode to show in the status bar, e.g.: `NORMAL`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Visual => "VISUAL",
+            Self::VisualLine => "VISUAL LINE",
+        }
+    }
+
+    /// Returns true if the mode is [`VimMode::Visual`] or [`VimMode::VisualLine`].
+    pub fn is_visual(&self) -> bool {
+        matches!(self, Self::Visual | Self::VisualLine)
+    }
+
+    fn key_context(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Insert => "insert",
+            Self::Visual => "visual",
+            Self::VisualLine => "visual_line",
+        }
+    }
+}
+
+impl fmt::Display for VimMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`
+    NextWordStart,
+    /// `e`
+    NextWordEnd,
+    /// `b`
+    PrevWordStart,
+    /// `0`
+    LineStart,
+    /// `^`
+    FirstNonBlank,
+    /// `$`
+    LineEnd,
+    /// `gg`
+    FirstLine,
+    /// `G`
+    LastLine,
+    /// `f`, `t`, `F`, `T`
+    FindChar {
+        ch: char,
+        forward: bool,
+        till: bool,
+    },
+    /// `%`
+    MatchingBracket,
+}
+
+impl Motion {
+    fn is_linewise(&self) -> bool {
+        matches!(
+            self,
+            Self::Up | Self::Down | Self::FirstLine | Self::LastLine
+        )
+    }
+
+    fn is_inclusive(&self) -> bool {
+        matches!(
+            self,
+            Self::NextWordEnd
+                | Self::LineEnd
+                | Self::MatchingBracket
+                | Self::FindChar { forward: true, .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsertPosition {
+    /// `i`
+    Before,
+    /// `a`
+    After,
+    /// `I`
+    LineStart,
+    /// `A`
+    LineEnd,
+    /// `o`
+    LineBelow,
+    /// `O`
+    LineAbove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VimAction {
+    Move(Motion),
+    /// The operator with a motion, e.g.: `dw`.
+    Operate(Operator, Motion),
+    /// The operator on the lines, e.g.: `dd`.
+    OperateLines(Operator),
+    /// The operator on the visual selection.
+    OperateSelection(Operator),
+    Insert(InsertPosition),
+    Paste {
+        before: bool,
+    },
+    ReplaceChar(char),
+    Undo,
+    Repeat,
+    ToggleVisual {
+        line: bool,
+    },
+}
+
+impl VimAction {
+    /// Returns true if the action changes the text, it is blocked in the read-only mode.
+    fn is_edit(&self) -> bool {
+        match self {
+            Self::Operate(op, _) | Self::OperateLines(op) | Self::OperateSelection(op) => {
+                *op != Operator::Yank
+            }
+            Self::Insert(_)
+            | Self::Paste { .. }
+            | Self::ReplaceChar(_)
+            | Self::Undo
+            | Self::Repeat => true,
+            Self::Move(_) | Self::ToggleVisual { .. } => false,
+        }
+    }
+
+    /// Returns true if the action can be repeated by `.`.
+    fn is_repeatable(&self) -> bool {
+        match self {
+            Self::Operate(op, _) | Self::OperateLines(op) => *op != Operator::Yank,
+            Self::Insert(_) | Self::Paste { .. } | Self::ReplaceChar(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VimCommand {
+    register: Option<char>,
+    count: Option<usize>,
+    action: VimAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseResult {
+    /// Waiting for more keys, e.g.: `d`, `2f`.
+    Pending,
+    Invalid,
+    Command(VimCommand),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Register {
+    text: String,
+    /// The text is yanked by lines, it will be pasted as the lines.
+    linewise: bool,
+}
+
+/// The last change to repeat by `.`, with the text typed in the insert mode.
+#[derive(Debug, Clone)]
+struct LastChange {
+    command: VimCommand,
+    inserted: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct VimState {
+    mode: VimMode,
+    /// The typed keys of the incomplete command, e.g.: `2d` is waiting for a motion.
+    pending: String,
+    registers: HashMap<char, Register>,
+    /// The offset of the visual selection start.
+    visual_anchor: usize,
+    /// The offset of the cursor in the visual mode.
+    visual_head: usize,
+    /// The column to keep for moving up and down.
+    goal_column: Option<u32>,
+    last_change: Option<LastChange>,
+    /// The command entered the insert mode, and the offset where the insert mode started.
+    recording: Option<(VimCommand, usize)>,
+    /// The history version before the command entered the insert mode,
+    /// the changes of the command and the insert mode are undone as one step.
+    insert_history_version: Option<usize>,
+}
+
+impl InputState {
+    /// Enable the Vim modal editing, default: false.
+    ///
+    /// The input starts in the [`VimMode::Normal`] mode.
+    pub fn vim(mut self, enabled: bool) -> Self {
+        self.vim = enabled.then(VimState::default);
+        self
+    }
+
+    /// Set the Vim modal editing enabled or not.
+    pub fn set_vim(&mut self, enabled: bool, _: &mut Window, cx: &mut Context<Self>) {
+        if enabled == self.vim.is_some() {
+            return;
+        }
+
+        self.vim = enabled.then(VimState::default);
+        if enabled {
+            self.vim_clamp_cursor(cx);
+            cx.emit(InputEvent::VimModeChange {
+                mode: VimMode::Normal,
+            });
+        }
+        cx.notify();
+    }
+
+    /// Returns the current Vim mode, None if the Vim mode is not enabled.
+    pub fn vim_mode(&self) -> Option<VimMode> {
+        self.vim.as_ref().map(|vim| vim.mode)
+    }
+
+    /// Returns the key context of the input, the Vim mode is included.
+    pub(super) fn key_context(&self) -> KeyContext {
+        let mut context = KeyContext::default();
+        context.add(super::CONTEXT);
+        if let Some(vim) = &self.vim {
+            context.set("vim_mode", vim.mode.key_context());
+            if vim.mode != VimMode::Insert {
+                context.add("VimControl");
+            }
+        }
+        context
+    }
+
+    /// Returns true if the cursor should be painted as a block.
+    pub(super) fn vim_block_cursor(&self) -> bool {
+        self.vim_mode() == Some(VimMode::Normal)
+    }
+
+    fn set_vim_mode_inner(&mut self, mode: VimMode, cx: &mut Context<Self>) {
+        let Some(vim) = self.vim.as_mut() else {
+            return;
+        };
+        vim.pending.clear();
+        if vim.mode == mode {
+            return;
+        }
+
+        vim.mode = mode;
+        cx.emit(InputEvent::VimModeChange { mode });
+        cx.notify();
+    }
+
+    /// Handle the typed text in the Vim normal and visual modes.
+    ///
+    /// Returns false if the text should be inserted, e.g.: In the insert mode.
+    pub(super) fn handle_vim_input(
+        &mut self,
+        text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if !matches!(self.vim_mode(), Some(mode) if mode != VimMode::Insert) {
+            return false;
+        }
+
+        for (ix, c) in text.char_indices() {
+            let Some(vim) = self.vim.as_mut() else {
+                break;
+            };
+
+            // The command (e.g.: `i`) has entered the insert mode, insert the rest text.
+            if vim.mode == VimMode::Insert {
+                self.replace_text_in_range(None, &text[ix..], window, cx);
+                break;
+            }
+
+            vim.pending.push(c);
+            match parse_command(&vim.pending, vim.mode.is_visual()) {
+                ParseResult::Pending => {}
+                ParseResult::Invalid => vim.pending.clear(),
+                ParseResult::Command(command) => {
+                    vim.pending.clear();
+                    self.execute_vim_command(command, window, cx);
+                }
+            }
+        }
+
+        self.pause_blink_cursor(cx);
+        cx.notify();
+        true
+    }
+
+    /// Handle the escape key in the Vim mode, returns true if handled.
+    pub(super) fn vim_escape(&mut self, _: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(vim) = self.vim.as_mut() else {
+            return false;
+        };
+
+        match vim.mode {
+            VimMode::Insert => {
+                if let Some(version) = vim.insert_history_version.take() {
+                    for change in self.history.undos_mut() {
+                        if change.version() > version {
+                            change.set_version(version + 1);
+                        }
+                    }
+                }
+
+                let Some(vim) = self.vim.as_mut() else {
+                    return false;
+                };
+                if let Some((command, start)) = vim.recording.take() {
+                    let cursor = self.cursor();
+                    let inserted = (cursor >= start)
+                        .then(|| self.text.slice(start..cursor).to_string())
+                        .unwrap_or_default();
+                    if let Some(vim) = self.vim.as_mut() {
+                        vim.last_change = Some(LastChange {
+                            command,
+                            inserted: Some(inserted),
+                        });
+                    }
+                }
+
+                self.set_vim_mode_inner(VimMode::Normal, cx);
+                let cursor = self.cursor();
+                let row = self.text.offset_to_point(cursor).row;
+                if cursor > self.text.line_start_offset(row) {
+                    self.move_to(self.previous_boundary(cursor), None, cx);
+                }
+                self.vim_clamp_cursor(cx);
+                true
+            }
+            VimMode::Visual | VimMode::VisualLine => {
+                let head = vim.visual_head;
+                self.set_vim_mode_inner(VimMode::Normal, cx);
+                self.move_to(head, None, cx);
+                self.vim_clamp_cursor(cx);
+                true
+            }
+            VimMode::Normal => {
+                if vim.pending.is_empty() {
+                    return false;
+                }
+                vim.pending.clear();
+                cx.notify();
+                true
+            }
+        }
+    }
+
+    /// Keep the cursor on a char in the normal mode, it can not be after the last char of the line.
+    fn vim_clamp_cursor(&mut self, cx: &mut Context<Self>) {
+        if self.vim_mode() != Some(VimMode::Normal) {
+            return;
+        }
+
+        let cursor = self.cursor();
+        let row = self.text.offset_to_point(cursor).row;
+        let line_start = self.text.line_start_offset(row);
+        if cursor > line_start && cursor >= self.text.line_end_offset(row) {
+            self.move_to(self.previous_boundary(cursor), None, cx);
+        }
+    }
+
+    fn execute_vim_command(
+        &mut self,
+        command: VimCommand,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(mode) = self.vim_mode() else {
+            return;
+        };
+
+        if command.action.is_edit() {
+            if self.block_read_only_edit(cx) {
+                return;
+            }
+            // Each command is an undo step.
+            self.history.break_grouping();
+        }
+        let history_version = self.history.version();
+
+        let count = command.count.unwrap_or(1);
+        let register = command.register;
+        if !matches!(command.action, VimAction::Move(Motion::Up | Motion::Down)) {
+            if let Some(vim) = self.vim.as_mut() {
+                vim.goal_column = None;
+            }
+        }
+
+        match command.action {
+            VimAction::Move(motion) => {
+                let from = self.vim_head();
+                let Some(target) = self.vim_motion_target(motion, command.count, from) else {
+                    return;
+                };
+
+                if mode.is_visual() {
+                    self.vim_select_visual(target, cx);
+                } else {
+                    self.move_to(target, None, cx);
+                }
+            }
+            VimAction::Operate(op, motion) => {
+                let from = self.cursor();
+                // `cw` is the same as `ce`, if the cursor is in a word.
+                let motion = match self.text.char_at(from) {
+                    Some(c)
+                        if op == Operator::Change
+                            && motion == Motion::NextWordStart
+                            && !c.is_whitespace() =>
+                    {
+                        Motion::NextWordEnd
+                    }
+                    _ => motion,
+                };
+
+                let Some(target) = self.vim_motion_target(motion, command.count, from) else {
+                    return;
+                };
+                if motion.is_linewise() {
+                    let start_row = self.text.offset_to_point(from.min(target)).row;
+                    let end_row = self.text.offset_to_point(from.max(target)).row;
+                    self.vim_operate_lines(op, start_row, end_row, register, window, cx);
+                } else {
+                    let mut range = from.min(target)..from.max(target);
+                    if motion.is_inclusive() && range.end < self.text.len() {
+                        range.end = self.next_boundary(range.end);
+                    }
+                    if motion == Motion::NextWordStart {
+                        range.end = exclusive_motion_end(&self.text, range.clone());
+                    }
+                    if range.is_empty() && op != Operator::Change {
+                        return;
+                    }
+                    self.vim_operate(op, range, register, window, cx);
+                }
+            }
+            VimAction::OperateLines(op) => {
+                let row = self.text.offset_to_point(self.cursor()).row;
+                let end_row = (row + count - 1).min(self.text.lines_len().saturating_sub(1));
+                self.vim_operate_lines(op, row, end_row, register, window, cx);
+            }
+            VimAction::OperateSelection(op) => {
+                let range = self.selected_range.start..self.selected_range.end;
+                if mode == VimMode::VisualLine {
+                    let start_row = self.text.offset_to_point(range.start).row;
+                    let end_row = self.text.offset_to_point(range.end).row;
+                    self.set_vim_mode_inner(VimMode::Normal, cx);
+                    self.vim_operate_lines(op, start_row, end_row, register, window, cx);
+                } else {
+                    self.set_vim_mode_inner(VimMode::Normal, cx);
+                    self.vim_operate(op, range, register, window, cx);
+                }
+            }
+            VimAction::Insert(position) => self.vim_insert(position, window, cx),
+            VimAction::Paste { before } => {
+                let Some(register) = self.vim_register(register, cx) else {
+                    return;
+                };
+
+                if mode.is_visual() {
+                    let range = self.selected_range.start..self.selected_range.end;
+                    self.set_vim_mode_inner(VimMode::Normal, cx);
+                    self.vim_replace(range.clone(), &register.text, window, cx);
+                    self.move_to(range.start, None, cx);
+                } else {
+                    self.vim_paste(&register, count, before, window, cx);
+                }
+            }
+            VimAction::ReplaceChar(ch) => {
+                let cursor = self.cursor();
+                let row = self.text.offset_to_point(cursor).row;
+                let line_end = self.text.line_end_offset(row);
+                let mut end = cursor;
+                for _ in 0..count {
+                    if end >= line_end {
+                        return;
+                    }
+                    end = self.next_boundary(end);
+                }
+
+                self.vim_replace(cursor..end, &ch.to_string().repeat(count), window, cx);
+                let cursor = cursor + ch.len_utf8() * count;
+                self.move_to(self.previous_boundary(cursor), None, cx);
+            }
+            VimAction::Undo => {
+                self.undo(&Undo, window, cx);
+                let cursor = self.cursor();
+                self.move_to(cursor, None, cx);
+            }
+            VimAction::Repeat => {
+                let Some(last_change) = self.vim.as_ref().and_then(|vim| vim.last_change.clone())
+                else {
+                    return;
+                };
+
+                let mut repeat_command = last_change.command;
+                if command.count.is_some() {
+                    repeat_command.count = command.count;
+                }
+                self.execute_vim_command(repeat_command, window, cx);
+                if let Some(inserted) = last_change.inserted {
+                    self.replace_text_in_range_silent(None, &inserted, window, cx);
+                    self.vim_escape(window, cx);
+                }
+                return;
+            }
+            VimAction::ToggleVisual { line } => {
+                let new_mode = if line {
+                    VimMode::VisualLine
+                } else {
+                    VimMode::Visual
+                };
+
+                if mode == new_mode {
+                    let head = self.vim_head();
+                    self.set_vim_mode_inner(VimMode::Normal, cx);
+                    self.move_to(head, None, cx);
+                } else {
+                    let head = self.vim_head();
+                    if let Some(vim) = self.vim.as_mut() {
+                        if !mode.is_visual() {
+                            vim.visual_anchor = head;
+                        }
+                    }
+                    self.set_vim_mode_inner(new_mode, cx);
+                    self.vim_select_visual(head, cx);
+                }
+            }
+        }
+
+        let Some(vim) = self.vim.as_mut() else {
+            return;
+        };
+        if vim.mode == VimMode::Insert {
+            vim.insert_history_version = Some(history_version);
+        }
+        if command.action.is_repeatable() && !mode.is_visual() {
+            if vim.mode == VimMode::Insert {
+                vim.recording = Some((command, self.selected_range.end));
+            } else {
+                vim.last_change = Some(LastChange {
+                    command,
+                    inserted: None,
+                });
+            }
+        }
+
+        self.vim_clamp_cursor(cx);
+    }
+
+    /// Returns the offset of the cursor, in visual mode it is the moving end of the selection.
+    fn vim_head(&self) -> usize {
+        match &self.vim {
+            Some(vim) if vim.mode.is_visual() => vim.visual_head,
+            _ => self.cursor(),
+        }
+    }
+
+    /// Returns the target offset of the motion from the offset, None if the motion failed.
+    fn vim_motion_target(
+        &mut self,
+        motion: Motion,
+        count: Option<usize>,
+        from: usize,
+    ) -> Option<usize> {
+        let times = count.unwrap_or(1);
+        let point = self.text.offset_to_point(from);
+        let last_row = self.text.lines_len().saturating_sub(1);
+
+        let target = match motion {
+            Motion::Left => {
+                let line_start = self.text.line_start_offset(point.row);
+                let mut offset = from;
+                for _ in 0..times {
+                    if offset <= line_start {
+                        break;
+                    }
+                    offset = self.previous_boundary(offset);
+                }
+                offset
+            }
+            Motion::Right => {
+                let line_end = self.text.line_end_offset(point.row);
+                let mut offset = from;
+                for _ in 0..times {
+                    if offset >= line_end {
+                        break;
+                    }
+                    offset = self.next_boundary(offset);
+                }
+                offset
+            }
+            Motion::Up | Motion::Down => {
+                let row = if motion == Motion::Up {
+                    point.row.saturating_sub(times)
+                } else {
+                    (point.row + times).min(last_row)
+                };
+                let column = self.text.offset_to_position(from).character;
+                let column = match self.vim.as_mut() {
+                    Some(vim) => *vim.goal_column.get_or_insert(column),
+                    None => column,
+                };
+                self.text
+                    .position_to_offset(&Position::new(row as u32, column))
+            }
+            Motion::NextWordStart => {
+                (0..times).fold(from, |offset, _| next_word_start(&self.text, offset))
+            }
+            Motion::NextWordEnd => {
+                (0..times).fold(from, |offset, _| next_word_end(&self.text, offset))
+            }
+            Motion::PrevWordStart => {
+                (0..times).fold(from, |offset, _| prev_word_start(&self.text, offset))
+            }
+            Motion::LineStart => self.text.line_start_offset(point.row),
+            Motion::FirstNonBlank => first_non_blank(&self.text, point.row),
+            Motion::LineEnd => self
+                .text
+                .line_end_offset((point.row + times - 1).min(last_row)),
+            Motion::FirstLine | Motion::LastLine => {
+                let row = match count {
+                    Some(count) => count.saturating_sub(1).min(last_row),
+                    None if motion == Motion::FirstLine => 0,
+                    None => last_row,
+                };
+                first_non_blank(&self.text, row)
+            }
+            Motion::FindChar { ch, forward, till } => {
+                find_char(&self.text, from, ch, forward, till, times)?
+            }
+            Motion::MatchingBracket => matching_bracket(&self.text, from)?,
+        };
+
+        Some(target)
+    }
+
+    /// Update the visual selection to the `head`.
+    fn vim_select_visual(&mut self, head: usize, cx: &mut Context<Self>) {
+        let Some(vim) = self.vim.as_mut() else {
+            return;
+        };
+        vim.visual_head = head;
+        let anchor = vim.visual_anchor;
+        let mode = vim.mode;
+
+        let (start, end) = (anchor.min(head), anchor.max(head));
+        let range = if mode == VimMode::VisualLine {
+            let start_row = self.text.offset_to_point(start).row;
+            let end_row = self.text.offset_to_point(end).row;
+            self.text.line_start_offset(start_row)..self.text.line_end_offset(end_row)
+        } else if end < self.text.len() {
+            // The char under the cursor is included in the visual selection.
+            start..self.next_boundary(end)
+        } else {
+            start..end
+        };
+
+        self.selected_range = range.into();
+        self.selection_reversed = head < anchor;
+        self.extra_selections.clear();
+        self.column_selection = None;
+        self.scroll_to(head, None, cx);
+        cx.notify();
+    }
+
+    /// Apply the operator to the charwise range.
+    fn vim_operate(
+        &mut self,
+        op: Operator,
+        range: Range<usize>,
+        register: Option<char>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let text = self.text.slice(range.clone()).to_string();
+        match op {
+            Operator::Delete | Operator::Change => {
+                self.vim_yank(register, text, false, false, cx);
+                self.vim_replace(range.clone(), "", window, cx);
+                self.move_to(range.start, None, cx);
+                if op == Operator::Change {
+                    self.set_vim_mode_inner(VimMode::Insert, cx);
+                }
+            }
+            Operator::Yank => {
+                self.vim_yank(register, text, false, true, cx);
+                self.move_to(range.start, None, cx);
+            }
+            Operator::Indent | Operator::Outdent => {
+                let start_row = self.text.offset_to_point(range.start).row;
+                let end_row = self.text.offset_to_point(range.end).row;
+                self.vim_operate_lines(op, start_row, end_row, register, window, cx);
+            }
+        }
+    }
+
+    /// Apply the operator to the lines from the `start_row` to the `end_row` (inclusive).
+    fn vim_operate_lines(
+        &mut self,
+        op: Operator,
+        start_row: usize,
+        end_row: usize,
+        register: Option<char>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let start = self.text.line_start_offset(start_row);
+        let end = self.text.line_end_offset(end_row);
+        let text = format!("{}\n", self.text.slice(start..end));
+
+        match op {
+            Operator::Delete => {
+                self.vim_yank(register, text, true, false, cx);
+                self.vim_replace(line_range(&self.text, start_row, end_row), "", window, cx);
+                let row = start_row.min(self.text.lines_len().saturating_sub(1));
+                self.move_to(first_non_blank(&self.text, row), None, cx);
+            }
+            Operator::Change => {
+                self.vim_yank(register, text, true, false, cx);
+                // Keep the indent of the first line.
+                let indent = first_non_blank(&self.text, start_row) - start;
+                let indent = self.text.slice(start..start + indent).to_string();
+                self.vim_replace(start..end, &indent, window, cx);
+                self.move_to(start + indent.len(), None, cx);
+                self.set_vim_mode_inner(VimMode::Insert, cx);
+            }
+            Operator::Yank => {
+                self.vim_yank(register, text, true, true, cx);
+                if self.text.offset_to_point(self.cursor()).row > start_row {
+                    self.move_to(first_non_blank(&self.text, start_row), None, cx);
+                }
+            }
+            Operator::Indent | Operator::Outdent => {
+                self.selected_range = (start..end).into();
+                if op == Operator::Indent {
+                    self.indent(true, window, cx);
+                } else {
+                    self.outdent(true, window, cx);
+                }
+                self.move_to(first_non_blank(&self.text, start_row), None, cx);
+            }
+        }
+    }
+
+    fn vim_insert(
+        &mut self,
+        position: InsertPosition,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let cursor = self.cursor();
+        let row = self.text.offset_to_point(cursor).row;
+        let line_start = self.text.line_start_offset(row);
+        let line_end = self.text.line_end_offset(row);
+        let indent = self
+            .text
+            .slice(line_start..first_non_blank(&self.text, row))
+            .to_string();
+
+        match position {
+            InsertPosition::Before => {}
+            InsertPosition::After => {
+                if cursor < line_end {
+                    self.move_to(self.next_boundary(cursor), None, cx);
+                }
+            }
+            InsertPosition::LineStart => {
+                self.move_to(first_non_blank(&self.text, row), None, cx);
+            }
+            InsertPosition::LineEnd => self.move_to(line_end, None, cx),
+            InsertPosition::LineBelow => {
+                self.vim_replace(line_end..line_end, &format!("\n{}", indent), window, cx);
+                self.move_to(line_end + 1 + indent.len(), None, cx);
+            }
+            InsertPosition::LineAbove => {
+                self.vim_replace(line_start..line_start, &format!("{}\n", indent), window, cx);
+                self.move_to(line_start + indent.len(), None, cx);
+            }
+        }
+
+        self.set_vim_mode_inner(VimMode::Insert, cx);
+    }
+
+    fn vim_paste(
+        &mut self,
+        register: &Register,
+        count: usize,
+        before: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let text = register.text.repeat(count);
+        if text.is_empty() {
+            return;
+        }
+
+        let cursor = self.cursor();
+        let row = self.text.offset_to_point(cursor).row;
+        if register.linewise {
+            let text = if text.ends_with('\n') {
+                text
+            } else {
+                format!("{}\n", text)
+            };
+
+            if before {
+                let offset = self.text.line_start_offset(row);
+                self.vim_replace(offset..offset, &text, window, cx);
+                self.move_to(first_non_blank(&self.text, row), None, cx);
+            } else {
+                let line_end = self.text.line_end_offset(row);
+                if line_end >= self.text.len() {
+                    // The last line has no `\n`, move the `\n` to the front.
+                    let text = format!("\n{}", text.trim_end_matches('\n'));
+                    self.vim_replace(line_end..line_end, &text, window, cx);
+                } else {
+                    self.vim_replace(line_end + 1..line_end + 1, &text, window, cx);
+                }
+                self.move_to(first_non_blank(&self.text, row + 1), None, cx);
+            }
+        } else {
+            let offset = if !before && cursor < self.text.line_end_offset(row) {
+                self.next_boundary(cursor)
+            } else {
+                cursor
+            };
+            self.vim_replace(offset..offset, &text, window, cx);
+            // Place the cursor on the last char of the pasted text.
+            self.move_to(self.previous_boundary(offset + text.len()), None, cx);
+        }
+    }
+
+    /// Replace the text in the range, bypass the Vim input handling.
+    fn vim_replace(
+        &mut self,
+        range: Range<usize>,
+        new_text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let range_utf16 = self.range_to_utf16(&range);
+        self.replace_text_in_range_silent(Some(range_utf16), new_text, window, cx);
+    }
+
+    /// Store the text in the register.
+    ///
+    /// - The unnamed register `"` always keeps the last yanked or deleted text.
+    /// - The register `0` keeps the last yanked text.
+    /// - The registers `+` and `*` are the system clipboard.
+    /// - The register `_` is the black hole, the text is discarded.
+    fn vim_yank(
+        &mut self,
+        register: Option<char>,
+        text: String,
+        linewise: bool,
+        is_yank: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(vim) = self.vim.as_mut() else {
+            return;
+        };
+
+        let value = Register { text, linewise };
+        match register {
+            Some('_') => return,
+            Some('+' | '*') => cx.write_to_clipboard(ClipboardItem::new_string(value.text.clone())),
+            Some(name) if name.is_ascii_uppercase() => {
+                // Uppercase register appends to the lowercase one.
+                let item = vim.registers.entry(name.to_ascii_lowercase()).or_default();
+                item.text.push_str(&value.text);
+                item.linewise |= value.linewise;
+            }
+            Some(name) => {
+                vim.registers.insert(name, value.clone());
+            }
+            None if is_yank => {
+                vim.registers.insert('0', value.clone());
+            }
+            None => {}
+        }
+
+        vim.registers.insert('"', value);
+    }
+
+    /// Read the register, the unnamed register `"` is used if `register` is None.
+    fn vim_register(&self, register: Option<char>, cx: &mut Context<Self>) -> Option<Register> {
+        match register.unwrap_or('"') {
+            '+' | '*' => {
+                let text = cx.read_from_clipboard()?.text()?;
+                Some(Register {
+                    linewise: text.ends_with('\n'),
+                    text,
+                })
+            }
+            name => self
+                .vim
+                .as_ref()?
+                .registers
+                .get(&name.to_ascii_lowercase())
+                .cloned(),
+        }
+    }
+}
+
+fn is_register(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '"' | '+' | '*' | '_')
+}
+
+/// Parse the count (not start with `0`) from the keys.
+fn parse_count(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<usize> {
+    let mut count: Option<usize> = None;
+    while let Some(&c) = chars.peek() {
+        match (count, c.to_digit(10)) {
+            (None, Some(0)) | (_, None) => break,
+            (_, Some(digit)) => {
+                count = Some(
+                    count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit as usize),
+                );
+                chars.next();
+            }
+        }
+    }
+    count
+}
+
+/// Parse the motion start with `c`, returns Ok(None) if waiting for more keys.
+fn parse_motion(c: char, chars: &mut impl Iterator<Item = char>) -> Result<Option<Motion>, ()> {
+    let motion = match c {
+        'h' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'k' => Motion::Up,
+        'j' => Motion::Down,
+        'w' => Motion::NextWordStart,
+        'e' => Motion::NextWordEnd,
+        'b' => Motion::PrevWordStart,
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        'G' => Motion::LastLine,
+        '%' => Motion::MatchingBracket,
+        'g' => match chars.next() {
+            Some('g') => Motion::FirstLine,
+            Some(_) => return Err(()),
+            None => return Ok(None),
+        },
+        'f' | 't' | 'F' | 'T' => {
+            let Some(ch) = chars.next() else {
+                return Ok(None);
+            };
+            Motion::FindChar {
+                ch,
+                forward: c == 'f' || c == 't',
+                till: c == 't' || c == 'T',
+            }
+        }
+        _ => return Err(()),
+    };
+
+    Ok(Some(motion))
+}
+
+/// Parse the typed keys to a [`VimCommand`].
+///
+/// The keys are: `["register][count]action`, the action is a motion, an operator with
+/// a motion (The count can be before the motion), or a command like `p`, `i`, `.`.
+fn parse_command(keys: &str, visual: bool) -> ParseResult {
+    let mut chars = keys.chars().peekable();
+
+    let mut register = None;
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        match chars.next() {
+            Some(c) if is_register(c) => register = Some(c),
+            Some(_) => return ParseResult::Invalid,
+            None => return ParseResult::Pending,
+        }
+    }
+
+    let mut count = parse_count(&mut chars);
+    let Some(c) = chars.next() else {
+        return ParseResult::Pending;
+    };
+
+    let operator = match c {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        '>' => Some(Operator::Indent),
+        '<' => Some(Operator::Outdent),
+        _ => None,
+    };
+
+    let action = if let Some(op) = operator {
+        if visual {
+            VimAction::OperateSelection(op)
+        } else {
+            if let Some(motion_count) = parse_count(&mut chars) {
+                count = Some(count.unwrap_or(1).saturating_mul(motion_count));
+            }
+            match chars.next() {
+                None => return ParseResult::Pending,
+                Some(next) if next == c => VimAction::OperateLines(op),
+                Some(next) => match parse_motion(next, &mut chars) {
+                    Ok(Some(motion)) => VimAction::Operate(op, motion),
+                    Ok(None) => return ParseResult::Pending,
+                    Err(_) => return ParseResult::Invalid,
+                },
+            }
+        }
+    } else {
+        match c {
+            'x' | 'X' if visual => VimAction::OperateSelection(Operator::Delete),
+            's' | 'S' if visual => VimAction::OperateSelection(Operator::Change),
+            'Y' if visual => VimAction::OperateSelection(Operator::Yank),
+            'x' => VimAction::Operate(Operator::Delete, Motion::Right),
+            'X' => VimAction::Operate(Operator::Delete, Motion::Left),
+            's' => VimAction::Operate(Operator::Change, Motion::Right),
+            'S' => VimAction::OperateLines(Operator::Change),
+            'D' => VimAction::Operate(Operator::Delete, Motion::LineEnd),
+            'C' => VimAction::Operate(Operator::Change, Motion::LineEnd),
+            'Y' => VimAction::OperateLines(Operator::Yank),
+            'i' if !visual => VimAction::Insert(InsertPosition::Before),
+            'a' if !visual => VimAction::Insert(InsertPosition::After),
+            'I' if !visual => VimAction::Insert(InsertPosition::LineStart),
+            'A' if !visual => VimAction::Insert(InsertPosition::LineEnd),
+            'o' if !visual => VimAction::Insert(InsertPosition::LineBelow),
+            'O' if !visual => VimAction::Insert(InsertPosition::LineAbove),
+            'p' => VimAction::Paste { before: false },
+            'P' => VimAction::Paste { before: true },
+            'r' if !visual => match chars.next() {
+                Some(ch) => VimAction::ReplaceChar(ch),
+                None => return ParseResult::Pending,
+            },
+            'u' => VimAction::Undo,
+            '.' if !visual => VimAction::Repeat,
+            'v' => VimAction::ToggleVisual { line: false },
+            'V' => VimAction::ToggleVisual { line: true },
+            _ => match parse_motion(c, &mut chars) {
+                Ok(Some(motion)) => VimAction::Move(motion),
+                Ok(None) => return ParseResult::Pending,
+                Err(_) => return ParseResult::Invalid,
+            },
+        }
+    };
+
+    if chars.next().is_some() {
+        return ParseResult::Invalid;
+    }
+
+    ParseResult::Command(VimCommand {
+        register,
+        count,
+        action,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharKind {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+impl From<char> for CharKind {
+    fn from(c: char) -> Self {
+        if c.is_whitespace() {
+            Self::Blank
+        } else if is_word_char(c) {
+            Self::Word
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+/// Returns the start offset of the next word (`w`).
+fn next_word_start(text: &Rope, offset: usize) -> usize {
+    let mut offset = offset;
+    let mut chars = text.chars_at(offset).peekable();
+    let Some(&first) = chars.peek() else {
+        return offset;
+    };
+
+    let kind = CharKind::from(first);
+    if kind != CharKind::Blank {
+        while let Some(&c) = chars.peek() {
+            if CharKind::from(c) != kind {
+                break;
+            }
+            offset += c.len_utf8();
+            chars.next();
+        }
+    }
+
+    while let Some(&c) = chars.peek() {
+        if CharKind::from(c) != CharKind::Blank {
+            break;
+        }
+        offset += c.len_utf8();
+        chars.next();
+    }
+
+    offset
+}
+
+/// Returns the offset of the last char of the current or next word (`e`).
+fn next_word_end(text: &Rope, offset: usize) -> usize {
+    let mut chars = text.chars_at(offset).peekable();
+    let Some(first) = chars.next() else {
+        return offset;
+    };
+
+    let mut offset = offset + first.len_utf8();
+    while let Some(&c) = chars.peek() {
+        if CharKind::from(c) != CharKind::Blank {
+            break;
+        }
+        offset += c.len_utf8();
+        chars.next();
+    }
+
+    let Some(&c) = chars.peek() else {
+        return offset;
+    };
+    let kind = CharKind::from(c);
+    let mut end = offset;
+    while let Some(&c) = chars.peek() {
+        if CharKind::from(c) != kind {
+            break;
+        }
+        end = offset;
+        offset += c.len_utf8();
+        chars.next();
+    }
+
+    end
+}
+
+/// Returns the start offset of the current or previous word (`b`).
+fn prev_word_start(text: &Rope, offset: usize) -> usize {
+    let mut offset = offset;
+    let mut chars = text.chars_at(offset).reversed().peekable();
+    while let Some(&c) = chars.peek() {
+        if CharKind::from(c) != CharKind::Blank {
+            break;
+        }
+        offset -= c.len_utf8();
+        chars.next();
+    }
+
+    let Some(&c) = chars.peek() else {
+        return offset;
+    };
+    let kind = CharKind::from(c);
+    while let Some(&c) = chars.peek() {
+        if CharKind::from(c) != kind {
+            break;
+        }
+        offset -= c.len_utf8();
+        chars.next();
+    }
+
+    offset
+}
+
+/// The `w` motion with an operator (e.g.: `dw`) stops at the end of the line,
+/// the end of the last word becomes the end of the range, not the first word in the next line.
+fn exclusive_motion_end(text: &Rope, range: Range<usize>) -> usize {
+    let start_row = text.offset_to_point(range.start).row;
+    let end_row = text.offset_to_point(range.end).row;
+    if end_row > start_row {
+        return text.line_end_offset(end_row - 1).max(range.start);
+    }
+
+    range.end
+}
+
+/// Returns the offset of the first non-blank char of the line.
+fn first_non_blank(text: &Rope, row: usize) -> usize {
+    let line_start = text.line_start_offset(row);
+    let indent = text
+        .slice_line(row)
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .count();
+    line_start + indent
+}
+
+/// Returns the range of the lines, includes the `\n` after the lines,
+/// or the `\n` before the lines if they are the last lines.
+fn line_range(text: &Rope, start_row: usize, end_row: usize) -> Range<usize> {
+    let start = text.line_start_offset(start_row);
+    let end = text.line_end_offset(end_row);
+    if end < text.len() {
+        start..end + 1
+    } else if start > 0 {
+        start - 1..end
+    } else {
+        start..end
+    }
+}
+
+/// Find the `count`th `ch` in the line of the `offset` (`f`, `t`, `F`, `T`).
+fn find_char(
+    text: &Rope,
+    offset: usize,
+    ch: char,
+    forward: bool,
+    till: bool,
+    count: usize,
+) -> Option<usize> {
+    let row = text.offset_to_point(offset).row;
+    let mut found = 0;
+
+    if forward {
+        let line_end = text.line_end_offset(row);
+        let mut chars = text.chars_at(offset);
+        let first = chars.next()?;
+        let mut prev_offset = offset;
+        let mut pos = offset + first.len_utf8();
+        for c in chars {
+            if pos >= line_end {
+                break;
+            }
+            if c == ch {
+                found += 1;
+                if found == count {
+                    return Some(if till { prev_offset } else { pos });
+                }
+            }
+            prev_offset = pos;
+            pos += c.len_utf8();
+        }
+    } else {
+        let line_start = text.line_start_offset(row);
+        let mut pos = offset;
+        for c in text.chars_at(offset).reversed() {
+            if pos <= line_start {
+                break;
+            }
+            pos -= c.len_utf8();
+            if c == ch {
+                found += 1;
+                if found == count {
+                    return Some(if till { pos + c.len_utf8() } else { pos });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Find the bracket under (or after) the cursor in the line, and returns the offset of the matching one (`%`).
+fn matching_bracket(text: &Rope, offset: usize) -> Option<usize> {
+    let row = text.offset_to_point(offset).row;
+    let line_end = text.line_end_offset(row);
+
+    let mut pos = offset;
+    let mut bracket = None;
+    for c in text.chars_at(offset) {
+        if pos >= line_end {
+            break;
+        }
+        if matches!(c, '(' | ')' | '[' | ']' | '{' | '}') {
+            bracket = Some(c);
+            break;
+        }
+        pos += c.len_utf8();
+    }
+
+    let (open, close, forward) = match bracket? {
+        '(' => ('(', ')', true),
+        ')' => ('(', ')', false),
+        '[' => ('[', ']', true),
+        ']' => ('[', ']', false),
+        '{' => ('{', '}', true),
+        _ => ('{', '}', false),
+    };
+
+    let mut depth = 0usize;
+    if forward {
+        for c in text.chars_at(pos) {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            pos += c.len_utf8();
+        }
+    } else {
+        // The bracket is 1 byte.
+        pos += 1;
+        for c in text.chars_at(pos).reversed() {
+            pos -= c.len_utf8();
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::Duration,
+    };
+
+    use gpui::{AppContext as _, Entity, EntityInputHandler as _};
+    use ropey::Rope;
+
+    use super::*;
+    use crate::history::History;
+
+    fn command(count: Option<usize>, action: VimAction) -> ParseResult {
+        ParseResult::Command(VimCommand {
+            register: None,
+            count,
+            action,
+        })
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("w", false),
+            command(None, VimAction::Move(Motion::NextWordStart))
+        );
+        assert_eq!(
+            parse_command("0", false),
+            command(None, VimAction::Move(Motion::LineStart))
+        );
+        assert_eq!(
+            parse_command("10j", false),
+            command(Some(10), VimAction::Move(Motion::Down))
+        );
+        assert_eq!(parse_command("g", false), ParseResult::Pending);
+        assert_eq!(
+            parse_command("gg", false),
+            command(None, VimAction::Move(Motion::FirstLine))
+        );
+        assert_eq!(parse_command("gx", false), ParseResult::Invalid);
+        assert_eq!(parse_command("f", false), ParseResult::Pending);
+        assert_eq!(
+            parse_command("2t;", false),
+            command(
+                Some(2),
+                VimAction::Move(Motion::FindChar {
+                    ch: ';',
+                    forward: true,
+                    till: true
+                })
+            )
+        );
+
+        assert_eq!(parse_command("2d", false), ParseResult::Pending);
+        assert_eq!(parse_command("2d3", false), ParseResult::Pending);
+        assert_eq!(
+            parse_command("2d3w", false),
+            command(
+                Some(6),
+                VimAction::Operate(Operator::Delete, Motion::NextWordStart)
+            )
+        );
+        assert_eq!(
+            parse_command("dd", false),
+            command(None, VimAction::OperateLines(Operator::Delete))
+        );
+        assert_eq!(
+            parse_command(">>", false),
+            command(None, VimAction::OperateLines(Operator::Indent))
+        );
+        assert_eq!(parse_command("dc", false), ParseResult::Invalid);
+        assert_eq!(
+            parse_command("cw", false),
+            command(
+                None,
+                VimAction::Operate(Operator::Change, Motion::NextWordStart)
+            )
+        );
+        assert_eq!(
+            parse_command("d", true),
+            command(None, VimAction::OperateSelection(Operator::Delete))
+        );
+
+        assert_eq!(parse_command("\"", false), ParseResult::Pending);
+        assert_eq!(parse_command("\"a", false), ParseResult::Pending);
+        assert_eq!(
+            parse_command("\"a2yy", false),
+            ParseResult::Command(VimCommand {
+                register: Some('a'),
+                count: Some(2),
+                action: VimAction::OperateLines(Operator::Yank),
+            })
+        );
+        assert_eq!(parse_command("\"!", false), ParseResult::Invalid);
+
+        assert_eq!(parse_command("r", false), ParseResult::Pending);
+        assert_eq!(
+            parse_command("rx", false),
+            command(None, VimAction::ReplaceChar('x'))
+        );
+        assert_eq!(
+            parse_command("3.", false),
+            command(Some(3), VimAction::Repeat)
+        );
+        assert_eq!(parse_command("i", true), ParseResult::Invalid);
+        assert_eq!(parse_command("z", false), ParseResult::Invalid);
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let text = Rope::from("foo.bar  baz\n  qux");
+        assert_eq!(next_word_start(&text, 0), 3);
+        assert_eq!(next_word_start(&text, 3), 4);
+        assert_eq!(next_word_start(&text, 4), 9);
+        assert_eq!(next_word_start(&text, 9), 15);
+        assert_eq!(next_word_start(&text, 15), 18);
+
+        assert_eq!(next_word_end(&text, 0), 2);
+        assert_eq!(next_word_end(&text, 2), 3);
+        assert_eq!(next_word_end(&text, 4), 6);
+        assert_eq!(next_word_end(&text, 6), 11);
+
+        assert_eq!(prev_word_start(&text, 15), 9);
+        assert_eq!(prev_word_start(&text, 10), 9);
+        assert_eq!(prev_word_start(&text, 9), 4);
+        assert_eq!(prev_word_start(&text, 4), 3);
+        assert_eq!(prev_word_start(&text, 3), 0);
+        assert_eq!(prev_word_start(&text, 0), 0);
+
+        // `dw` on the last word of the line does not delete the `\n`.
+        assert_eq!(exclusive_motion_end(&text, 9..15), 12);
+        assert_eq!(exclusive_motion_end(&text, 0..3), 3);
+    }
+
+    #[test]
+    fn test_find_char() {
+        let text = Rope::from("a(b, c), d\ne, f");
+        assert_eq!(find_char(&text, 0, ',', true, false, 1), Some(3));
+        assert_eq!(find_char(&text, 0, ',', true, true, 1), Some(2));
+        assert_eq!(find_char(&text, 0, ',', true, false, 2), Some(7));
+        assert_eq!(find_char(&text, 0, ',', true, false, 3), None);
+        assert_eq!(find_char(&text, 9, 'b', false, false, 1), Some(2));
+        assert_eq!(find_char(&text, 9, 'b', false, true, 1), Some(3));
+        assert_eq!(find_char(&text, 12, 'd', false, false, 1), None);
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let text = Rope::from("fn a(b: [u8; 2]) {\n    (1)\n}");
+        assert_eq!(matching_bracket(&text, 0), Some(15));
+        assert_eq!(matching_bracket(&text, 15), Some(4));
+        assert_eq!(matching_bracket(&text, 8), Some(14));
+        assert_eq!(matching_bracket(&text, 16), Some(27));
+        assert_eq!(matching_bracket(&text, 27), Some(17));
+        assert_eq!(matching_bracket(&text, 28), None);
+    }
+
+    #[test]
+    fn test_line_range() {
+        let text = Rope::from("  foo\nbar\nbaz");
+        assert_eq!(first_non_blank(&text, 0), 2);
+        assert_eq!(first_non_blank(&text, 1), 6);
+        assert_eq!(line_range(&text, 0, 0), 0..6);
+        assert_eq!(line_range(&text, 1, 2), 5..13);
+        assert_eq!(line_range(&text, 0, 2), 0..13);
+    }
+
+    fn vim_state(
+        text: &str,
+        cx: &mut gpui::TestAppContext,
+    ) -> (Entity<InputState>, &mut gpui::VisualTestContext) {
+        let cx = cx.add_empty_window();
+        let state = cx.update(|window, cx| {
+            cx.new(|cx| {
+                let mut state = InputState::new(window, cx)
+                    .multi_line(true)
+                    .vim(true)
+                    .default_value(text);
+                // Every change is a new undo step without the grouping.
+                state.history = History::new().group_interval(Duration::ZERO);
+                state
+            })
+        });
+        (state, cx)
+    }
+
+    /// Type the `keys` in the Vim mode, `<esc>` for the escape key.
+    fn type_keys(state: &Entity<InputState>, keys: &str, cx: &mut gpui::VisualTestContext) {
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| {
+                for (ix, keys) in keys.split("<esc>").enumerate() {
+                    if ix > 0 {
+                        state.vim_escape(window, cx);
+                    }
+                    // Type the keys one by one, like the key events.
+                    for c in keys.chars() {
+                        state.replace_text_in_range(None, &c.to_string(), window, cx);
+                    }
+                }
+            });
+        });
+    }
+
+    fn assert_state(
+        state: &Entity<InputState>,
+        text: &str,
+        cursor: usize,
+        cx: &mut gpui::VisualTestContext,
+    ) {
+        state.read_with(cx, |state, _| {
+            assert_eq!(state.value(), text);
+            assert_eq!(state.cursor(), cursor);
+        });
+    }
+
+    #[gpui::test]
+    fn test_vim_counted_operators(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("one two three four\na\nb\nc", cx);
+
+        type_keys(&state, "3dw", cx);
+        assert_state(&state, "four\na\nb\nc", 0, cx);
+
+        type_keys(&state, "j2dd", cx);
+        assert_state(&state, "four\nc", 5, cx);
+
+        // `cw` changes to the end of the word, the same as `ce`.
+        type_keys(&state, "ggcwfive<esc>", cx);
+        assert_state(&state, "five\nc", 3, cx);
+        state.read_with(cx, |state, _| {
+            assert_eq!(state.vim_mode(), Some(VimMode::Normal));
+        });
+    }
+
+    #[gpui::test]
+    fn test_vim_linewise_paste(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("a\nb\nc", cx);
+
+        type_keys(&state, "yyp", cx);
+        assert_state(&state, "a\na\nb\nc", 2, cx);
+
+        type_keys(&state, "GP", cx);
+        assert_state(&state, "a\na\nb\na\nc", 6, cx);
+
+        // Paste after the last line.
+        type_keys(&state, "Gp", cx);
+        assert_state(&state, "a\na\nb\na\nc\na", 10, cx);
+    }
+
+    #[gpui::test]
+    fn test_vim_named_registers(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("foo bar", cx);
+
+        type_keys(&state, "\"ayww\"bdw", cx);
+        state.read_with(cx, |state, _| {
+            let registers = &state.vim.as_ref().unwrap().registers;
+            assert_eq!(registers[&'a'].text, "foo ");
+            assert_eq!(registers[&'b'].text, "bar");
+            assert_eq!(registers[&'"'].text, "bar");
+            // The named yank is not kept in the register `0`.
+            assert!(!registers.contains_key(&'0'));
+        });
+        assert_state(&state, "foo ", 3, cx);
+
+        type_keys(&state, "\"ap", cx);
+        assert_state(&state, "foo foo ", 7, cx);
+    }
+
+    #[gpui::test]
+    fn test_vim_repeat(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("one two three", cx);
+
+        // Repeat the operator.
+        type_keys(&state, "dw.", cx);
+        assert_state(&state, "three", 0, cx);
+
+        // Repeat the insert with the typed text.
+        type_keys(&state, "ix<esc>.", cx);
+        assert_state(&state, "xxthree", 0, cx);
+    }
+
+    #[gpui::test]
+    fn test_vim_undo_insert(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("foo", cx);
+
+        // The whole insert session is undone as one step.
+        type_keys(&state, "Abar<esc>", cx);
+        assert_state(&state, "foobar", 5, cx);
+        type_keys(&state, "u", cx);
+        state.read_with(cx, |state, _| assert_eq!(state.value(), "foo"));
+
+        // Include the change of the command entered the insert mode.
+        type_keys(&state, "0cwxyz<esc>", cx);
+        state.read_with(cx, |state, _| assert_eq!(state.value(), "xyz"));
+        type_keys(&state, "u", cx);
+        state.read_with(cx, |state, _| assert_eq!(state.value(), "foo"));
+
+        // Each command in the normal mode is an undo step.
+        type_keys(&state, "0xx", cx);
+        state.read_with(cx, |state, _| assert_eq!(state.value(), "o"));
+        type_keys(&state, "u", cx);
+        state.read_with(cx, |state, _| assert_eq!(state.value(), "oo"));
+    }
+
+    #[gpui::test]
+    fn test_vim_mode_change(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("foo", cx);
+        let modes = Rc::new(RefCell::new(vec![]));
+        cx.update(|_, cx| {
+            let modes = modes.clone();
+            cx.subscribe(&state, move |_, event: &InputEvent, _| {
+                if let InputEvent::VimModeChange { mode } = event {
+                    modes.borrow_mut().push(*mode);
+                }
+            })
+            .detach();
+        });
+
+        type_keys(&state, "i<esc>v<esc>VV", cx);
+        assert_eq!(
+            *modes.borrow(),
+            vec![
+                VimMode::Insert,
+                VimMode::Normal,
+                VimMode::Visual,
+                VimMode::Normal,
+                VimMode::VisualLine,
+                VimMode::Normal,
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn test_vim_read_only(cx: &mut gpui::TestAppContext) {
+        let (state, cx) = vim_state("foo bar\nbaz", cx);
+        let blocked = Rc::new(Cell::new(0));
+        cx.update(|window, cx| {
+            state.update(cx, |state, cx| state.set_read_only(true, window, cx));
+
+            let blocked = blocked.clone();
+            cx.subscribe(&state, move |_, event: &InputEvent, _| {
+                if matches!(event, InputEvent::ReadOnlyEdit) {
+                    blocked.set(blocked.get() + 1);
+                }
+            })
+            .detach();
+        });
+
+        // The commands to edit are blocked, the insert mode is not entered.
+        let commands = ["i", "a", "o", "cw", "cc", "dd", "x", "p", "rx", "u", "."];
+        for keys in commands {
+            type_keys(&state, keys, cx);
+            state.read_with(cx, |state, _| {
+                assert_eq!(state.value(), "foo bar\nbaz", "{}", keys);
+                assert_eq!(state.vim_mode(), Some(VimMode::Normal), "{}", keys);
+            });
+        }
+        assert_eq!(blocked.get(), commands.len());
+
+        // The commands to move, select and yank still work.
+        type_keys(&state, "wj", cx);
+        assert_state(&state, "foo bar\nbaz", 10, cx);
+        type_keys(&state, "yyvb", cx);
+        state.read_with(cx, |state, _| {
+            assert_eq!(state.vim_mode(), Some(VimMode::Visual));
+            assert_eq!(state.selected_range, (8..11).into());
+        });
+        assert_eq!(blocked.get(), commands.len());
+    }
+}
@@ -35,6 +35,7 @@ pub mod collapsible;
 pub mod color_picker;
 pub mod description_list;
 pub mod dialog;
+pub mod diff_view;
 pub mod divider;
 pub mod dock;
 pub mod form;
@@ -114,6 +115,7 @@ pub fn init(cx: &mut App) {
     input::init(cx);
     list::init(cx);
     dialog::init(cx);
+    diff_view::init(cx);
     popover::init(cx);
     menu::init(cx);
     table::init(cx);
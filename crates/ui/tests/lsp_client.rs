@@ -0,0 +1,319 @@
+use std::{
+    path::Path,
+    process::Command,
+    str::FromStr as _,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use gpui::{AppContext as _, EntityInputHandler as _, TestAppContext};
+use gpui_component::input::{InputState, LanguageServer};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidOpenTextDocumentParams, HoverContents, HoverParams,
+    HoverProviderCapability, Position, PublishDiagnosticsParams, Range,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+    VersionedTextDocumentIdentifier,
+    notification::{DidChangeTextDocument, DidOpenTextDocument, PublishDiagnostics},
+    request::HoverRequest,
+};
+use smol::{
+    channel::{Receiver, RecvError},
+    future::FutureExt as _,
+};
+
+async fn recv<T>(rx: &Receiver<T>) -> T {
+    rx.recv()
+        .or(async {
+            smol::Timer::after(Duration::from_secs(5)).await;
+            Err(RecvError)
+        })
+        .await
+        .expect("timeout")
+}
+
+/// Returns the command of the `fake_lsp_server` example, it is built with the tests by `cargo test`.
+fn fake_lsp_server() -> Command {
+    // target/debug/deps/lsp_client-* -> target/debug/examples/fake_lsp_server
+    let path = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(Path::parent)
+        .unwrap()
+        .join("examples")
+        .join(format!("fake_lsp_server{}", std::env::consts::EXE_SUFFIX));
+    assert!(
+        path.exists(),
+        "{} is not found, run `cargo test -p gpui-component --features lsp-client` to build it",
+        path.display()
+    );
+    Command::new(path)
+}
+
+#[test]
+fn test_language_server() {
+    smol::block_on(async {
+        let server = LanguageServer::start("fake-lsp-server", fake_lsp_server()).unwrap();
+
+        let result = server.initialize(None, None).await.unwrap();
+        assert_eq!(
+            result.capabilities.text_document_sync,
+            Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL
+            ))
+        );
+        assert_eq!(
+            server.capabilities().hover_provider,
+            Some(HoverProviderCapability::Simple(true))
+        );
+
+        let (tx, rx) = smol::channel::unbounded::<PublishDiagnosticsParams>();
+        let _subscription = server.on_notification::<PublishDiagnostics>(move |params| {
+            _ = tx.try_send(params);
+        });
+
+        let uri = Uri::from_str("file:///main.rs").unwrap();
+        server
+            .notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "rust".into(),
+                    0,
+                    "fn main() {\n    // TODO: 你好\n}".into(),
+                ),
+            })
+            .unwrap();
+
+        let params = recv(&rx).await;
+        assert_eq!(params.uri, uri);
+        assert_eq!(params.version, Some(0));
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(
+            params.diagnostics[0].range,
+            Range::new(Position::new(1, 7), Position::new(1, 11))
+        );
+
+        // Replace `TODO` with `Hello`.
+        server
+            .notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 1),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: Some(Range::new(Position::new(1, 7), Position::new(1, 11))),
+                    range_length: None,
+                    text: "Hello".into(),
+                }],
+            })
+            .unwrap();
+
+        let params = recv(&rx).await;
+        assert_eq!(params.version, Some(1));
+        assert!(params.diagnostics.is_empty());
+
+        let hover = server
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(1, 9),
+                ),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        match hover.contents {
+            HoverContents::Markup(content) => assert_eq!(content.value, "Hello"),
+            contents => panic!("unexpected hover contents: {:?}", contents),
+        }
+
+        server.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn test_unsubscribe_in_notification_handler() {
+    smol::block_on(async {
+        let server = LanguageServer::start("fake-lsp-server", fake_lsp_server()).unwrap();
+        server.initialize(None, None).await.unwrap();
+
+        // The handler drops its own subscription at the first notification.
+        let (tx, rx) = smol::channel::unbounded::<Option<i32>>();
+        let subscription = Arc::new(Mutex::new(None));
+        *subscription.lock().unwrap() = Some(server.on_notification::<PublishDiagnostics>({
+            let subscription = subscription.clone();
+            move |params| {
+                subscription.lock().unwrap().take();
+                _ = tx.try_send(params.version);
+            }
+        }));
+
+        let (all_tx, all_rx) = smol::channel::unbounded::<Option<i32>>();
+        let _subscription = server.on_notification::<PublishDiagnostics>(move |params| {
+            _ = all_tx.try_send(params.version);
+        });
+
+        let uri = Uri::from_str("file:///main.rs").unwrap();
+        server
+            .notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri.clone(), "rust".into(), 0, "".into()),
+            })
+            .unwrap();
+        assert_eq!(recv(&rx).await, Some(0));
+        assert_eq!(recv(&all_rx).await, Some(0));
+
+        server
+            .notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 1),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "// TODO".into(),
+                }],
+            })
+            .unwrap();
+        assert_eq!(recv(&all_rx).await, Some(1));
+
+        // The response is read after the notification, all the handlers have been called.
+        server
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(0, 4),
+                ),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+
+        server.shutdown().await.unwrap();
+    });
+}
+
+#[gpui::test]
+fn test_open_document(cx: &mut TestAppContext) {
+    let server = LanguageServer::start("fake-lsp-server", fake_lsp_server()).unwrap();
+    smol::block_on(server.initialize(None, None)).unwrap();
+
+    let (tx, rx) = smol::channel::unbounded::<PublishDiagnosticsParams>();
+    let _subscription = server.on_notification::<PublishDiagnostics>(move |params| {
+        _ = tx.try_send(params);
+    });
+
+    let cx = cx.add_empty_window();
+    let editor = cx.update(|window, cx| {
+        cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor("rust")
+                .default_value("fn main() {\n    // TODO: 你好\n}")
+        })
+    });
+
+    let uri = Uri::from_str("file:///main.rs").unwrap();
+    let lsp = cx.update(|_, cx| server.open_document(uri.clone(), "rust", &editor, cx));
+    assert_eq!(lsp.document_uri, Some(uri.clone()));
+    assert!(lsp.document_sync_provider.is_some());
+    assert!(lsp.completion_provider.is_some());
+    assert!(lsp.hover_provider.is_some());
+    assert!(lsp.definition_provider.is_some());
+    assert_eq!(lsp.code_action_providers.len(), 1);
+    assert!(lsp.document_color_provider.is_some());
+
+    // The `textDocument/didOpen` with the text of the editor.
+    let params = smol::block_on(recv(&rx));
+    assert_eq!(params.uri, uri);
+    assert_eq!(params.version, Some(0));
+    assert_eq!(params.diagnostics.len(), 1);
+    assert_eq!(
+        params.diagnostics[0].range,
+        Range::new(Position::new(1, 7), Position::new(1, 11))
+    );
+
+    // Replace `TODO` with `Hello` in the editor, the `textDocument/didChange` is sent.
+    cx.update(|window, cx| {
+        editor.update(cx, |editor, cx| {
+            editor.lsp = lsp;
+            editor.replace_text_in_range(Some(19..23), "Hello", window, cx);
+        });
+    });
+
+    let params = smol::block_on(recv(&rx));
+    assert_eq!(params.uri, uri);
+    assert_eq!(params.version, Some(1));
+    assert!(params.diagnostics.is_empty());
+
+    // The text of the server is synced with the editor.
+    let hover = smol::block_on(server.request::<HoverRequest>(HoverParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            Position::new(1, 9),
+        ),
+        work_done_progress_params: Default::default(),
+    }))
+    .unwrap()
+    .unwrap();
+    match hover.contents {
+        HoverContents::Markup(content) => assert_eq!(content.value, "Hello"),
+        contents => panic!("unexpected hover contents: {:?}", contents),
+    }
+
+    smol::block_on(server.shutdown()).unwrap();
+}
+
+#[gpui::test]
+fn test_open_document_utf16(cx: &mut TestAppContext) {
+    let server = LanguageServer::start("fake-lsp-server", fake_lsp_server()).unwrap();
+    smol::block_on(server.initialize(None, None)).unwrap();
+
+    let (tx, rx) = smol::channel::unbounded::<PublishDiagnosticsParams>();
+    let _subscription = server.on_notification::<PublishDiagnostics>(move |params| {
+        _ = tx.try_send(params);
+    });
+
+    let cx = cx.add_empty_window();
+    let editor = cx.update(|window, cx| {
+        cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor("rust")
+                .default_value("fn main() {\n    // 😀 TODO\n}")
+        })
+    });
+
+    let uri = Uri::from_str("file:///main.rs").unwrap();
+    let lsp = cx.update(|_, cx| server.open_document(uri.clone(), "rust", &editor, cx));
+
+    // The emoji is 2 code units in UTF-16.
+    let params = smol::block_on(recv(&rx));
+    assert_eq!(params.version, Some(0));
+    assert_eq!(
+        params.diagnostics[0].range,
+        Range::new(Position::new(1, 10), Position::new(1, 14))
+    );
+
+    // Replace `TODO` with `Hello`, the range of the change is encoded in UTF-16.
+    cx.update(|window, cx| {
+        editor.update(cx, |editor, cx| {
+            editor.lsp = lsp;
+            editor.replace_text_in_range(Some(22..26), "Hello", window, cx);
+        });
+    });
+
+    let params = smol::block_on(recv(&rx));
+    assert_eq!(params.version, Some(1));
+    assert!(params.diagnostics.is_empty());
+
+    let hover = smol::block_on(server.request::<HoverRequest>(HoverParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            Position::new(1, 12),
+        ),
+        work_done_progress_params: Default::default(),
+    }))
+    .unwrap()
+    .unwrap();
+    match hover.contents {
+        HoverContents::Markup(content) => assert_eq!(content.value, "Hello"),
+        contents => panic!("unexpected hover contents: {:?}", contents),
+    }
+
+    smol::block_on(server.shutdown()).unwrap();
+}